@@ -0,0 +1,291 @@
+use crate::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{bit_length, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+
+#[cfg(test)]
+pub mod tests;
+
+/// Array of assigned values with an assigned length, at most the fixed maximum length of the array.
+///
+/// The values from `len` on are padding. They are arbitrary in arrays loaded with [ArrayChip::load_var_len], and
+/// zero in arrays returned by [ArrayChip].
+#[derive(Clone, Debug)]
+pub struct VarLenArray<F: ScalarField> {
+    /// The values of the array, including the padding.
+    pub values: Vec<AssignedValue<F>>,
+    /// The length of the array, at most `values.len()`.
+    pub len: AssignedValue<F>,
+}
+
+impl<F: ScalarField> VarLenArray<F> {
+    /// Returns the maximum length of the array, fixed at keygen.
+    pub fn max_len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+/// Chip for arrays of fixed maximum length with an assigned length, see [VarLenArray].
+///
+/// Operations selecting at a variable index use [GateInstructions::idx_to_indicator] and
+/// [GateInstructions::select_by_indicator]: an indicator of an index among `n` costs O(`n`) cells, and a selection
+/// with it costs `3 * n + 1` cells. Costs are given for arrays of maximum length `n`.
+#[derive(Clone, Debug)]
+pub struct ArrayChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+}
+
+impl<'a, F: ScalarField> ArrayChip<'a, F> {
+    /// Construct an ArrayChip.
+    pub fn new(range: &'a RangeChip<F>) -> Self {
+        Self { range }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Returns a [VarLenArray] of the first `len` elements of `values`, constraining `len <= values.len()`.
+    ///
+    /// Costs a range check of `len`.
+    pub fn load_var_len(
+        &self,
+        ctx: &mut Context<F>,
+        values: Vec<AssignedValue<F>>,
+        len: AssignedValue<F>,
+    ) -> VarLenArray<F> {
+        self.range.check_less_than_safe(ctx, len, values.len() as u64 + 1);
+        VarLenArray { values, len }
+    }
+
+    /// Constrains and returns the indicator of `idx` among `0..len`, constraining `idx < len`.
+    ///
+    /// Costs O(`len`) cells.
+    pub fn indicator(
+        &self,
+        ctx: &mut Context<F>,
+        idx: AssignedValue<F>,
+        len: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        let indicator = gate.idx_to_indicator(ctx, idx, len);
+        // the indicator is all zeros if `idx >= len`
+        let sum = gate.sum(ctx, indicator.iter().copied());
+        gate.assert_is_const(ctx, &sum, &F::one());
+        indicator
+    }
+
+    /// Constrains and returns the mask of the prefix of length `len` among `max_len` elements, i.e. `mask[i] == 1` if
+    /// `i < len`, otherwise 0. Constrains `len <= max_len`.
+    ///
+    /// Costs O(`max_len`) cells.
+    pub fn prefix_mask(
+        &self,
+        ctx: &mut Context<F>,
+        len: AssignedValue<F>,
+        max_len: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        let indicator = self.indicator(ctx, len, max_len + 1);
+        let mut mask = Vec::with_capacity(max_len);
+        let mut prev = Constant(F::one());
+        for ind in &indicator[..max_len] {
+            let bit = gate.sub(ctx, prev, *ind);
+            mask.push(bit);
+            prev = Existing(bit);
+        }
+        mask
+    }
+
+    /// Returns the values of `array`, with the padding set to zero.
+    ///
+    /// Costs O(`n`) cells.
+    pub fn masked_values(
+        &self,
+        ctx: &mut Context<F>,
+        array: &VarLenArray<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        let mask = self.prefix_mask(ctx, array.len, array.max_len());
+        array.values.iter().zip(mask).map(|(value, bit)| gate.mul(ctx, *value, bit)).collect()
+    }
+
+    /// Constrains and returns `values` shifted left by `shift`, i.e. `out[i] = values[i + shift]`, padded with zeros.
+    /// Constrains `shift <= values.len()`.
+    ///
+    /// Costs about `3 * n^2 / 2` cells.
+    pub fn shift_left(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        shift: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let indicator = self.indicator(ctx, shift, values.len() + 1);
+        self.select_shifted_left(ctx, values, &indicator, values.len())
+    }
+
+    /// Constrains and returns `values` shifted right by `shift`, i.e. `out[i] = values[i - shift]`, padded with zeros
+    /// and truncated to `values.len()` elements. Constrains `shift <= values.len()`.
+    ///
+    /// Costs about `3 * n^2 / 2` cells.
+    pub fn shift_right(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        shift: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let indicator = self.indicator(ctx, shift, values.len() + 1);
+        self.select_shifted_right(ctx, values, &indicator, values.len())
+    }
+
+    /// Constrains and returns the subarray of `len` elements of `array` from `start`, with maximum length
+    /// `max_sub_len`. Constrains `start + len <= array.len` and `len <= max_sub_len`.
+    ///
+    /// Costs about `3 * max_sub_len * n` cells.
+    pub fn subarray(
+        &self,
+        ctx: &mut Context<F>,
+        array: &VarLenArray<F>,
+        start: AssignedValue<F>,
+        len: AssignedValue<F>,
+        max_sub_len: usize,
+    ) -> VarLenArray<F> {
+        let gate = self.gate();
+        let max_len = array.max_len();
+        let indicator = self.indicator(ctx, start, max_len + 1);
+        // `start <= max_len` by the indicator, and bounding `len` as well prevents `start + len` from wrapping around
+        let mask = self.prefix_mask(ctx, len, max_sub_len);
+        let end = gate.add(ctx, start, len);
+        let bound = gate.add(ctx, array.len, Constant(F::one()));
+        let bits = bit_length((max_len + max_sub_len) as u64 + 1);
+        self.range.check_less_than(ctx, end, bound, bits);
+
+        let shifted = self.select_shifted_left(ctx, &array.values, &indicator, max_sub_len);
+        let values =
+            shifted.into_iter().zip(mask).map(|(value, bit)| gate.mul(ctx, value, bit)).collect();
+        VarLenArray { values, len }
+    }
+
+    /// Constrains and returns the concatenation of `a` and `b`, with maximum length `a.max_len() + b.max_len()`.
+    ///
+    /// Costs about `3 * a.max_len() * (a.max_len() + b.max_len())` cells.
+    pub fn concat(
+        &self,
+        ctx: &mut Context<F>,
+        a: &VarLenArray<F>,
+        b: &VarLenArray<F>,
+    ) -> VarLenArray<F> {
+        let gate = self.gate();
+        let max_len = a.max_len() + b.max_len();
+        let a_values = self.masked_values(ctx, a);
+        let b_values = self.masked_values(ctx, b);
+        // `b` starts at one of the offsets `0, ..., a.max_len()`
+        let indicator = self.indicator(ctx, a.len, a.max_len() + 1);
+        let shifted = self.select_shifted_right(ctx, &b_values, &indicator, max_len);
+        let values = shifted
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| match a_values.get(i) {
+                Some(a_value) => gate.add(ctx, *a_value, value),
+                None => value,
+            })
+            .collect();
+        let len = gate.add(ctx, a.len, b.len);
+        VarLenArray { values, len }
+    }
+
+    /// Returns 1 if one of the first `array.len` elements of `array` is `value`, otherwise 0.
+    ///
+    /// Costs O(`n`) cells.
+    pub fn contains(
+        &self,
+        ctx: &mut Context<F>,
+        array: &VarLenArray<F>,
+        value: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let mask = self.prefix_mask(ctx, array.len, array.max_len());
+        let is_equal =
+            array.values.iter().map(|x| gate.is_equal(ctx, *x, value)).collect::<Vec<_>>();
+        // the number of occurrences of `value`, at most `n`
+        let count = gate.inner_product(ctx, mask, is_equal.into_iter().map(Existing));
+        let is_zero = gate.is_zero(ctx, count);
+        gate.not(ctx, is_zero)
+    }
+
+    /// Returns 1 if `a` and `b` have the same length and elements, otherwise 0. Padding is ignored.
+    ///
+    /// Costs O(`a.max_len() + b.max_len()`) cells.
+    pub fn is_equal(
+        &self,
+        ctx: &mut Context<F>,
+        a: &VarLenArray<F>,
+        b: &VarLenArray<F>,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let a_values = self.masked_values(ctx, a);
+        let b_values = self.masked_values(ctx, b);
+        let max_len = a.max_len().max(b.max_len());
+        let mut checks = (0..max_len)
+            .map(|i| match (a_values.get(i), b_values.get(i)) {
+                (Some(x), Some(y)) => gate.is_equal(ctx, *x, *y),
+                (Some(x), None) | (None, Some(x)) => gate.is_zero(ctx, *x),
+                (None, None) => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        checks.push(gate.is_equal(ctx, a.len, b.len));
+        let num_checks = checks.len();
+        let sum = gate.sum(ctx, checks);
+        gate.is_equal(ctx, sum, Constant(F::from(num_checks as u64)))
+    }
+
+    /// Returns `out_len` values with `out[i] = sum_j indicator[j] * values[i + j]`, where out of range values are 0.
+    fn select_shifted_left(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        indicator: &[AssignedValue<F>],
+        out_len: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        (0..out_len)
+            .map(|i| {
+                let window =
+                    values.iter().skip(i).take(indicator.len()).copied().collect::<Vec<_>>();
+                if window.is_empty() {
+                    return ctx.load_zero();
+                }
+                let len = window.len();
+                gate.select_by_indicator(ctx, window, indicator[..len].iter().copied())
+            })
+            .collect()
+    }
+
+    /// Returns `out_len` values with `out[i] = sum_j indicator[j] * values[i - j]`, where out of range values are 0.
+    fn select_shifted_right(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        indicator: &[AssignedValue<F>],
+        out_len: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        (0..out_len)
+            .map(|i| {
+                let window = (0..indicator.len().min(i + 1)).map(|j| {
+                    values.get(i - j).map_or(Constant(F::zero()), |value| Existing(*value))
+                });
+                let len = indicator.len().min(i + 1);
+                gate.select_by_indicator(ctx, window, indicator[..len].iter().copied())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+use crate::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 10;
+const LOOKUP_BITS: usize = 8;
+
+/// Runs `f` and returns its output values and whether the circuit is satisfied.
+fn run(
+    f: impl FnOnce(&mut Context<Fr>, &ArrayChip<Fr>) -> Vec<AssignedValue<Fr>>,
+) -> (Vec<u64>, bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = ArrayChip::new(&range);
+    let out = f(builder.main(0), &chip);
+    let out = out.iter().map(|x| x.value().get_lower_32() as u64).collect();
+    (out, mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok())
+}
+
+fn load(ctx: &mut Context<Fr>, values: &[u64]) -> Vec<AssignedValue<Fr>> {
+    ctx.assign_witnesses(values.iter().map(|v| Fr::from(*v)))
+}
+
+fn load_var_len(
+    ctx: &mut Context<Fr>,
+    chip: &ArrayChip<Fr>,
+    values: &[u64],
+    len: u64,
+) -> VarLenArray<Fr> {
+    let values = load(ctx, values);
+    let len = ctx.load_witness(Fr::from(len));
+    chip.load_var_len(ctx, values, len)
+}
+
+#[test_case(0 => (vec![0, 0, 0, 0], true); "empty")]
+#[test_case(3 => (vec![1, 1, 1, 0], true); "prefix")]
+#[test_case(4 => (vec![1, 1, 1, 1], true); "full")]
+#[test_case(5 => (vec![1, 1, 1, 1], false); "too long")]
+fn test_prefix_mask(len: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let len = ctx.load_witness(Fr::from(len));
+        chip.prefix_mask(ctx, len, 4)
+    })
+}
+
+#[test_case(0 => (vec![1, 2, 3, 4, 5], true); "shift_left() by 0")]
+#[test_case(2 => (vec![3, 4, 5, 0, 0], true); "shift_left() by 2")]
+#[test_case(5 => (vec![0, 0, 0, 0, 0], true); "shift_left() by length")]
+#[test_case(6 => (vec![0, 0, 0, 0, 0], false); "shift_left() too far")]
+fn test_shift_left(shift: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let values = load(ctx, &[1, 2, 3, 4, 5]);
+        let shift = ctx.load_witness(Fr::from(shift));
+        chip.shift_left(ctx, &values, shift)
+    })
+}
+
+#[test_case(0 => (vec![1, 2, 3, 4, 5], true); "shift_right() by 0")]
+#[test_case(2 => (vec![0, 0, 1, 2, 3], true); "shift_right() by 2")]
+#[test_case(5 => (vec![0, 0, 0, 0, 0], true); "shift_right() by length")]
+#[test_case(6 => (vec![0, 0, 0, 0, 0], false); "shift_right() too far")]
+fn test_shift_right(shift: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let values = load(ctx, &[1, 2, 3, 4, 5]);
+        let shift = ctx.load_witness(Fr::from(shift));
+        chip.shift_right(ctx, &values, shift)
+    })
+}
+
+#[test_case(1, 3 => (vec![2, 3, 4, 0, 3], true); "middle")]
+#[test_case(0, 0 => (vec![0, 0, 0, 0, 0], true); "empty")]
+#[test_case(1, 4 => (vec![2, 3, 4, 5, 4], true); "until the end")]
+#[test_case(2, 4 => (vec![3, 4, 5, 9, 4], false); "past the end")]
+#[test_case(0, 5 => (vec![1, 2, 3, 4, 5], false); "too long")]
+fn test_subarray(start: u64, len: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        // the array is padded with 9
+        let array = load_var_len(ctx, chip, &[1, 2, 3, 4, 5, 9], 5);
+        let [start, len] = [start, len].map(|x| ctx.load_witness(Fr::from(x)));
+        let sub = chip.subarray(ctx, &array, start, len, 4);
+        [sub.values, vec![sub.len]].concat()
+    })
+}
+
+#[test_case(2, 3 => (vec![1, 2, 7, 8, 9, 0, 0, 5], true); "both partial")]
+#[test_case(0, 4 => (vec![7, 8, 9, 6, 0, 0, 0, 4], true); "first empty")]
+#[test_case(3, 0 => (vec![1, 2, 3, 0, 0, 0, 0, 3], true); "second empty")]
+#[test_case(3, 4 => (vec![1, 2, 3, 7, 8, 9, 6, 7], true); "both full")]
+fn test_concat(a_len: u64, b_len: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let a = load_var_len(ctx, chip, &[1, 2, 3], a_len);
+        let b = load_var_len(ctx, chip, &[7, 8, 9, 6], b_len);
+        let out = chip.concat(ctx, &a, &b);
+        [out.values, vec![out.len]].concat()
+    })
+}
+
+#[test_case(3 => (vec![1], true); "first")]
+#[test_case(5 => (vec![1], true); "last")]
+#[test_case(6 => (vec![0], true); "in padding")]
+#[test_case(7 => (vec![0], true); "absent")]
+fn test_contains(value: u64) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let array = load_var_len(ctx, chip, &[3, 4, 5, 6], 3);
+        let value = ctx.load_witness(Fr::from(value));
+        vec![chip.contains(ctx, &array, value)]
+    })
+}
+
+#[test_case((&[1, 2, 3], 2), (&[1, 2, 9, 9], 2) => (vec![1], true); "different padding")]
+#[test_case((&[1, 2, 3], 3), (&[1, 2, 3, 9], 3) => (vec![1], true); "different max lengths")]
+#[test_case((&[1, 2, 3], 0), (&[], 0) => (vec![1], true); "empty")]
+#[test_case((&[1, 2, 3], 3), (&[1, 2, 3, 9], 4) => (vec![0], true); "different lengths")]
+#[test_case((&[1, 2, 3], 2), (&[1, 0, 3], 2) => (vec![0], true); "different elements")]
+#[test_case((&[0, 0, 0], 2), (&[0, 0, 0], 3) => (vec![0], true); "different lengths of zeros")]
+fn test_is_equal(a: (&[u64], u64), b: (&[u64], u64)) -> (Vec<u64>, bool) {
+    run(|ctx, chip| {
+        let a = load_var_len(ctx, chip, a.0, a.1);
+        let b = load_var_len(ctx, chip, b.0, b.1);
+        vec![chip.is_equal(ctx, &a, &b)]
+    })
+}
+
+#[test]
+fn test_load_var_len_too_long() {
+    let (_, verified) = run(|ctx, chip| load_var_len(ctx, chip, &[1, 2], 3).values);
+    assert!(!verified);
+}
@@ -0,0 +1,382 @@
+use crate::{
+    array::ArrayChip,
+    gates::{
+        builder::GateThreadBuilder, range::LookupTableId, GateChip, GateInstructions, RangeChip,
+        RangeInstructions,
+    },
+    safe_types::{SafeType, SafeUint8},
+    utils::{bit_length, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+
+#[cfg(test)]
+pub mod tests;
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_CHARS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEX_CHARS: &[u8; 16] = b"0123456789abcdef";
+const PAD: u8 = b'=';
+
+/// Alphabets of the character table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Alphabet {
+    Base64 = 0,
+    Base64Url = 1,
+    /// Hex digits in either case, for decoding.
+    Hex = 2,
+    /// Lowercase hex digits, for encoding.
+    HexLower = 3,
+}
+
+impl Alphabet {
+    const ALL: [Alphabet; 4] = [Self::Base64, Self::Base64Url, Self::Hex, Self::HexLower];
+
+    /// Returns the `(character, value)` pairs of the alphabet.
+    fn entries(self) -> Vec<(u8, u64)> {
+        let chars: &[u8] = match self {
+            Self::Base64 => BASE64_CHARS,
+            Self::Base64Url => BASE64URL_CHARS,
+            Self::Hex | Self::HexLower => HEX_CHARS,
+        };
+        let mut entries = chars.iter().enumerate().map(|(v, c)| (*c, v as u64)).collect::<Vec<_>>();
+        if self == Self::Hex {
+            entries.extend(b"ABCDEF".iter().enumerate().map(|(v, c)| (*c, v as u64 + 10)));
+        }
+        entries
+    }
+
+    fn value(self, char: u8) -> Option<u64> {
+        self.entries().into_iter().find(|(c, _)| *c == char).map(|(_, v)| v)
+    }
+
+    fn char(self, value: u64) -> u8 {
+        self.entries()[value as usize].0
+    }
+}
+
+/// Bytes of fixed maximum length with an assigned length, as returned by [EncodingChip]. The bytes from `len` on are
+/// zero.
+#[derive(Clone, Debug)]
+pub struct VarLenBytes<F: ScalarField>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// The bytes, including the padding.
+    pub bytes: Vec<SafeUint8<F>>,
+    /// The number of bytes, at most `bytes.len()`.
+    pub len: AssignedValue<F>,
+}
+
+/// Chip decoding and encoding base64 ([RFC 4648](https://www.rfc-editor.org/rfc/rfc4648)) and hex strings.
+///
+/// The alphabets are registered as a lookup table of rows `(alphabet, character, value)`, so each character costs a
+/// lookup. The character table has 166 rows.
+///
+/// Decoding constrains the input to be a valid and canonical encoding: the circuit is not satisfied otherwise. In
+/// particular the unused bits of the last base64 character must be zero, so each byte string has a unique encoding.
+#[derive(Clone, Debug)]
+pub struct EncodingChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+    table: LookupTableId,
+}
+
+impl<'a, F: ScalarField> EncodingChip<'a, F> {
+    /// Construct an EncodingChip, registering the character table in `builder`.
+    ///
+    /// Tables must be registered in the same order for keygen and proving.
+    pub fn new(range: &'a RangeChip<F>, builder: &mut GateThreadBuilder<F>) -> Self {
+        let rows = Alphabet::ALL.into_iter().flat_map(|alphabet| {
+            alphabet
+                .entries()
+                .into_iter()
+                .map(move |(c, v)| [alphabet as u64, c as u64, v].map(F::from))
+        });
+        let table = builder.register_tuple_lookup_table(rows);
+        Self { range, table }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+}
+
+impl<'a, F: ScalarField> EncodingChip<'a, F>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// Constrains and returns the bytes encoded by the first `len` characters of `chars` in padded base64 with the
+    /// standard alphabet. Constrains `len <= chars.len()`, `len` to be a multiple of 4, and at most the last two
+    /// characters to be `=`. The decoded bytes have maximum length `3 * chars.len() / 4`.
+    ///
+    /// Assumes `chars.len()` is a multiple of 4.
+    ///
+    /// Costs a lookup and O(1) cells per character, plus three byte range checks per 4 characters.
+    pub fn base64_decode(
+        &self,
+        ctx: &mut Context<F>,
+        chars: &[SafeUint8<F>],
+        len: AssignedValue<F>,
+    ) -> VarLenBytes<F> {
+        assert_eq!(chars.len() % 4, 0, "padded base64 has a length multiple of 4");
+        self.decode_base64(ctx, chars, len, Alphabet::Base64, true)
+    }
+
+    /// Constrains and returns the bytes encoded by the first `len` characters of `chars` in unpadded base64 with the
+    /// URL and filename safe alphabet, as used by JWT. Constrains `len <= chars.len()` and `len % 4 != 1`. The decoded
+    /// bytes have maximum length `3 * ceil(chars.len() / 4)`.
+    ///
+    /// Costs a lookup and O(1) cells per character, plus three byte range checks per 4 characters.
+    pub fn base64url_decode(
+        &self,
+        ctx: &mut Context<F>,
+        chars: &[SafeUint8<F>],
+        len: AssignedValue<F>,
+    ) -> VarLenBytes<F> {
+        self.decode_base64(ctx, chars, len, Alphabet::Base64Url, false)
+    }
+
+    /// Constrains and returns the bytes encoded by the first `len` characters of `chars` in hex, with digits in either
+    /// case. Constrains `len <= chars.len()` and `len` to be even. The decoded bytes have maximum length
+    /// `ceil(chars.len() / 2)`.
+    ///
+    /// Costs a lookup and O(1) cells per character.
+    pub fn hex_decode(
+        &self,
+        ctx: &mut Context<F>,
+        chars: &[SafeUint8<F>],
+        len: AssignedValue<F>,
+    ) -> VarLenBytes<F> {
+        let gate = self.gate();
+        let mask = ArrayChip::new(self.range).prefix_mask(ctx, len, chars.len());
+        let (half_len, parity) = self.range.div_mod(ctx, len, 2u64, bit_length(chars.len() as u64));
+        gate.assert_is_const(ctx, &parity, &F::zero());
+
+        // the digits from `len` on are zero
+        let alphabet = ctx.load_constant(F::from(Alphabet::Hex as u64));
+        let digits = chars
+            .iter()
+            .zip(mask)
+            .map(|(c, in_range)| {
+                self.lookup_value(ctx, Alphabet::Hex, alphabet, c.value()[0], in_range)
+            })
+            .collect::<Vec<_>>();
+        // the digits are less than 16 by the lookups, so the bytes are less than 256
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                let low = pair.get(1).map_or(Constant(F::zero()), |low| Existing(*low));
+                SafeType::new(vec![gate.mul_add(ctx, pair[0], Constant(F::from(16)), low)])
+            })
+            .collect();
+        VarLenBytes { bytes, len: half_len }
+    }
+
+    /// Returns the characters of the padded base64 encoding of `bytes` with the standard alphabet.
+    ///
+    /// Costs a lookup and O(1) cells per character.
+    pub fn base64_encode(&self, ctx: &mut Context<F>, bytes: &[SafeUint8<F>]) -> Vec<SafeUint8<F>> {
+        self.encode_base64(ctx, bytes, Alphabet::Base64, true)
+    }
+
+    /// Returns the characters of the unpadded base64 encoding of `bytes` with the URL and filename safe alphabet.
+    ///
+    /// Costs a lookup and O(1) cells per character.
+    pub fn base64url_encode(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[SafeUint8<F>],
+    ) -> Vec<SafeUint8<F>> {
+        self.encode_base64(ctx, bytes, Alphabet::Base64Url, false)
+    }
+
+    /// Returns the characters of the hex encoding of `bytes` with lowercase digits.
+    ///
+    /// Costs a lookup and O(1) cells per character.
+    pub fn hex_encode(&self, ctx: &mut Context<F>, bytes: &[SafeUint8<F>]) -> Vec<SafeUint8<F>> {
+        let gate = self.gate();
+        let alphabet = ctx.load_constant(F::from(Alphabet::HexLower as u64));
+        let mut chars = Vec::with_capacity(2 * bytes.len());
+        for byte in bytes {
+            let byte = byte.value()[0];
+            let value = byte.value().get_lower_32();
+            // the digits are less than 16 by the lookups of their characters
+            let [high, low] =
+                [value >> 4, value & 0xf].map(|x| ctx.load_witness(F::from(x as u64)));
+            let sum = gate.mul_add(ctx, high, Constant(F::from(16)), low);
+            ctx.constrain_equal(&sum, &byte);
+            for digit in [high, low] {
+                chars.push(self.lookup_char(ctx, Alphabet::HexLower, alphabet, digit));
+            }
+        }
+        chars
+    }
+
+    fn decode_base64(
+        &self,
+        ctx: &mut Context<F>,
+        chars: &[SafeUint8<F>],
+        len: AssignedValue<F>,
+        alphabet: Alphabet,
+        padded: bool,
+    ) -> VarLenBytes<F> {
+        let gate = self.gate();
+        let array = ArrayChip::new(self.range);
+        let max_len = chars.len();
+        let chars = chars.iter().map(|c| c.value()[0]).collect::<Vec<_>>();
+        let mask = array.prefix_mask(ctx, len, max_len);
+        let (quot, rem) = self.range.div_mod(ctx, len, 4u64, bit_length(max_len as u64));
+
+        // the characters decoded to a sextet are those in range, except padding
+        let (enabled, out_len) = if padded {
+            gate.assert_is_const(ctx, &rem, &F::zero());
+            let is_pad = chars
+                .iter()
+                .zip(&mask)
+                .map(|(c, in_range)| {
+                    let is_pad = gate.is_equal(ctx, *c, Constant(F::from(PAD as u64)));
+                    gate.mul(ctx, is_pad, *in_range)
+                })
+                .collect::<Vec<_>>();
+            let end = array.indicator(ctx, len, max_len + 1);
+            let last =
+                gate.inner_product(ctx, is_pad.clone(), end[1..].iter().map(|x| Existing(*x)));
+            let second_last =
+                gate.inner_product(ctx, is_pad.clone(), end.iter().skip(2).map(|x| Existing(*x)));
+            // every `=` is one of the last two characters, and the second to last is `=` only if the last is
+            let num_pad = gate.sum(ctx, is_pad.iter().copied());
+            let pad_at_end = gate.add(ctx, last, second_last);
+            ctx.constrain_equal(&num_pad, &pad_at_end);
+            let invalid = gate.mul_not(ctx, last, second_last);
+            gate.assert_is_const(ctx, &invalid, &F::zero());
+
+            let enabled = mask
+                .iter()
+                .zip(is_pad)
+                .map(|(bit, pad)| gate.sub(ctx, *bit, pad))
+                .collect::<Vec<_>>();
+            let full_len = gate.mul(ctx, quot, Constant(F::from(3)));
+            (enabled, gate.sub(ctx, full_len, num_pad))
+        } else {
+            // a single character in the last group does not encode a byte
+            let is_one = gate.is_equal(ctx, rem, Constant(F::one()));
+            gate.assert_is_const(ctx, &is_one, &F::zero());
+            // a last group of 2 or 3 characters encodes 1 or 2 bytes
+            let is_zero = gate.is_zero(ctx, rem);
+            let extra = gate.sub(ctx, rem, Constant(F::one()));
+            let extra = gate.add(ctx, extra, is_zero);
+            (mask, gate.mul_add(ctx, quot, Constant(F::from(3)), extra))
+        };
+
+        // the sextets of padding and of the characters from `len` on are zero
+        let alphabet_id = ctx.load_constant(F::from(alphabet as u64));
+        let sextets = chars
+            .iter()
+            .zip(enabled)
+            .map(|(c, on)| self.lookup_value(ctx, alphabet, alphabet_id, *c, on))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::with_capacity(3 * ((max_len + 3) / 4));
+        for group in sextets.chunks(4) {
+            let weights = [18, 12, 6, 0].map(|shift| Constant(F::from(1 << shift)));
+            let value = gate.inner_product(ctx, group.iter().copied(), weights);
+            let value_u32 = value.value().get_lower_32();
+            let group_bytes = [16, 8, 0]
+                .map(|shift| ctx.load_witness(F::from(((value_u32 >> shift) & 0xff) as u64)));
+            for byte in &group_bytes {
+                self.range.range_check(ctx, *byte, 8);
+            }
+            let weights = [16, 8, 0].map(|shift| Constant(F::from(1 << shift)));
+            let sum = gate.inner_product(ctx, group_bytes, weights);
+            ctx.constrain_equal(&sum, &value);
+            bytes.extend(group_bytes);
+        }
+
+        // the unused bits of the last character must be zero for the encoding to be canonical
+        let out_mask = array.prefix_mask(ctx, out_len, bytes.len());
+        for (byte, bit) in bytes.iter().zip(out_mask) {
+            let padding = gate.mul_not(ctx, bit, *byte);
+            gate.assert_is_const(ctx, &padding, &F::zero());
+        }
+        let bytes = bytes.into_iter().map(|byte| SafeType::new(vec![byte])).collect();
+        VarLenBytes { bytes, len: out_len }
+    }
+
+    fn encode_base64(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[SafeUint8<F>],
+        alphabet: Alphabet,
+        padded: bool,
+    ) -> Vec<SafeUint8<F>> {
+        let gate = self.gate();
+        let alphabet_id = ctx.load_constant(F::from(alphabet as u64));
+        let mut chars = Vec::with_capacity(4 * ((bytes.len() + 2) / 3));
+        for group in bytes.chunks(3) {
+            let weights = [16, 8, 0].map(|shift| Constant(F::from(1 << shift)));
+            let value = gate.inner_product(ctx, group.iter().map(|byte| byte.value()[0]), weights);
+            let value_u32 = value.value().get_lower_32();
+            let sextets = [18, 12, 6, 0]
+                .map(|shift| ctx.load_witness(F::from(((value_u32 >> shift) & 0x3f) as u64)));
+            let weights = [18, 12, 6, 0].map(|shift| Constant(F::from(1 << shift)));
+            let sum = gate.inner_product(ctx, sextets, weights);
+            ctx.constrain_equal(&sum, &value);
+            // a group of `n` bytes is encoded by `n + 1` characters, whose sextets are less than 64 by the lookups,
+            // and the remaining sextets are zero
+            let (used, unused) = sextets.split_at(group.len() + 1);
+            for sextet in used {
+                chars.push(self.lookup_char(ctx, alphabet, alphabet_id, *sextet));
+            }
+            for sextet in unused {
+                gate.assert_is_const(ctx, sextet, &F::zero());
+                if padded {
+                    chars.push(SafeType::new(vec![ctx.load_constant(F::from(PAD as u64))]));
+                }
+            }
+        }
+        chars
+    }
+
+    /// Constrains and returns the value of `char` in `alphabet`, whose id is assigned in `alphabet_id`, if `enabled`,
+    /// otherwise 0.
+    fn lookup_value(
+        &self,
+        ctx: &mut Context<F>,
+        alphabet: Alphabet,
+        alphabet_id: AssignedValue<F>,
+        char: AssignedValue<F>,
+        enabled: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let value = if *enabled.value() == F::one() {
+            alphabet.value(char.value().get_lower_32() as u8).unwrap_or(0)
+        } else {
+            0
+        };
+        let value = ctx.load_witness(F::from(value));
+        // the character of value 0 is looked up if not enabled, which forces the value to zero
+        let zero_char = Constant(F::from(alphabet.char(0) as u64));
+        let char = self.gate().select(ctx, char, zero_char, enabled);
+        ctx.lookup_tuple(self.table, [alphabet_id, char, value]);
+        value
+    }
+
+    /// Constrains and returns the character of `value` in `alphabet`, whose id is assigned in `alphabet_id`.
+    fn lookup_char(
+        &self,
+        ctx: &mut Context<F>,
+        alphabet: Alphabet,
+        alphabet_id: AssignedValue<F>,
+        value: AssignedValue<F>,
+    ) -> SafeUint8<F> {
+        let char = alphabet.char(value.value().get_lower_32() as u64);
+        let char = ctx.load_witness(F::from(char as u64));
+        ctx.lookup_tuple(self.table, [alphabet_id, char, value]);
+        SafeType::new(vec![char])
+    }
+}
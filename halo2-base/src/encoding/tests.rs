@@ -0,0 +1,126 @@
+use super::*;
+use crate::{
+    gates::builder::RangeCircuitBuilder, halo2_proofs::halo2curves::bn256::Fr,
+    safe_types::SafeTypeChip, utils::testing::mock_verify,
+};
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 10;
+const LOOKUP_BITS: usize = 8;
+
+/// Runs `f` on `input` loaded as bytes and returns the bytes of its output, truncated to `len` if any, and whether the
+/// circuit is satisfied.
+fn run(
+    input: &[u8],
+    f: impl FnOnce(
+        &mut Context<Fr>,
+        &EncodingChip<Fr>,
+        &[SafeUint8<Fr>],
+    ) -> (Vec<SafeUint8<Fr>>, Option<AssignedValue<Fr>>),
+) -> (Vec<u8>, bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = EncodingChip::new(&range, &mut builder);
+    let ctx = builder.main(0);
+    let safe = SafeTypeChip::new(&range);
+    let bytes = input
+        .iter()
+        .map(|byte| {
+            let byte = ctx.load_witness(Fr::from(*byte as u64));
+            safe.raw_bytes_to(ctx, vec![byte])
+        })
+        .collect::<Vec<SafeUint8<Fr>>>();
+    let (out, len) = f(ctx, &chip, &bytes);
+    let len = len.map_or(out.len(), |len| len.value().get_lower_32() as usize);
+    let out = out[..len].iter().map(|byte| byte.value()[0].value().get_lower_32() as u8).collect();
+    (out, mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok())
+}
+
+/// Decodes the first `len` characters of `input`, padded with `#` to `max_len` characters.
+fn decode(
+    input: &str,
+    len: usize,
+    max_len: usize,
+    f: impl FnOnce(
+        &EncodingChip<Fr>,
+        &mut Context<Fr>,
+        &[SafeUint8<Fr>],
+        AssignedValue<Fr>,
+    ) -> VarLenBytes<Fr>,
+) -> (Vec<u8>, bool) {
+    let mut input = input.as_bytes().to_vec();
+    input.resize(max_len, b'#');
+    run(&input, |ctx, chip, chars| {
+        let len = ctx.load_witness(Fr::from(len as u64));
+        let out = f(chip, ctx, chars, len);
+        (out.bytes, Some(out.len))
+    })
+}
+
+#[test_case("", 0 => (b"".to_vec(), true); "empty")]
+#[test_case("aGVsbG8=", 8 => (b"hello".to_vec(), true); "one pad")]
+#[test_case("aGk=", 4 => (b"hi".to_vec(), true); "one pad short")]
+#[test_case("aA==", 4 => (b"h".to_vec(), true); "two pads")]
+#[test_case("aGVsbG8h", 8 => (b"hello!".to_vec(), true); "no pad")]
+#[test_case("+/+/", 4 => (vec![0xfb, 0xff, 0xbf], true); "symbols")]
+#[test_case("aGVsbG8hZm9v", 8 => (b"hello!".to_vec(), true); "prefix")]
+#[test_case("aGVsbG8", 7 => matches (_, false); "length not multiple of 4")]
+#[test_case("aGVsbG=h", 8 => matches (_, false); "pad before the end")]
+#[test_case("aGk=aGk=", 8 => matches (_, false); "pad in the middle")]
+#[test_case("a===", 4 => matches (_, false); "three pads")]
+#[test_case("aB==", 4 => matches (_, false); "non-canonical")]
+#[test_case("aGVs-G8h", 8 => matches (_, false); "url character")]
+fn test_base64_decode(input: &str, len: usize) -> (Vec<u8>, bool) {
+    decode(input, len, 12, |chip, ctx, chars, len| chip.base64_decode(ctx, chars, len))
+}
+
+#[test_case("", 0 => (b"".to_vec(), true); "empty")]
+#[test_case("aGVsbG8", 7 => (b"hello".to_vec(), true); "three in last group")]
+#[test_case("aGk", 3 => (b"hi".to_vec(), true); "three short")]
+#[test_case("aA", 2 => (b"h".to_vec(), true); "two in last group")]
+#[test_case("-_-_", 4 => (vec![0xfb, 0xff, 0xbf], true); "symbols")]
+#[test_case("eyJhbGciOiJIUzI1NiJ9", 20 => (br#"{"alg":"HS256"}"#.to_vec(), true); "jwt header")]
+#[test_case("aGVsbG8h", 5 => matches (_, false); "one in last group")]
+#[test_case("aGk=", 4 => matches (_, false); "padded")]
+#[test_case("+/+/", 4 => matches (_, false); "standard character")]
+#[test_case("aGl", 3 => matches (_, false); "non-canonical")]
+fn test_base64url_decode(input: &str, len: usize) -> (Vec<u8>, bool) {
+    decode(input, len, 22, |chip, ctx, chars, len| chip.base64url_decode(ctx, chars, len))
+}
+
+#[test_case("", 0 => (b"".to_vec(), true); "empty")]
+#[test_case("00ff7f", 6 => (vec![0x00, 0xff, 0x7f], true); "lowercase")]
+#[test_case("DeadBEEF", 8 => (vec![0xde, 0xad, 0xbe, 0xef], true); "mixed case")]
+#[test_case("abcd", 2 => (vec![0xab], true); "prefix")]
+#[test_case("abc", 3 => matches (_, false); "odd length")]
+#[test_case("0g", 2 => matches (_, false); "invalid digit")]
+#[test_case("abcd", 9 => matches (_, false); "too long")]
+fn test_hex_decode(input: &str, len: usize) -> (Vec<u8>, bool) {
+    decode(input, len, 8, |chip, ctx, chars, len| chip.hex_decode(ctx, chars, len))
+}
+
+#[test_case(b"" => (b"".to_vec(), true); "empty")]
+#[test_case(b"h" => (b"aA==".to_vec(), true); "two pads")]
+#[test_case(b"hi" => (b"aGk=".to_vec(), true); "one pad")]
+#[test_case(b"hello!" => (b"aGVsbG8h".to_vec(), true); "no pad")]
+#[test_case(&[0xfb, 0xff, 0xbf] => (b"+/+/".to_vec(), true); "symbols")]
+fn test_base64_encode(input: &[u8]) -> (Vec<u8>, bool) {
+    run(input, |ctx, chip, bytes| (chip.base64_encode(ctx, bytes), None))
+}
+
+#[test_case(b"h" => (b"aA".to_vec(), true); "two in last group")]
+#[test_case(b"hello" => (b"aGVsbG8".to_vec(), true); "three in last group")]
+#[test_case(&[0xfb, 0xff, 0xbf] => (b"-_-_".to_vec(), true); "symbols")]
+fn test_base64url_encode(input: &[u8]) -> (Vec<u8>, bool) {
+    run(input, |ctx, chip, bytes| (chip.base64url_encode(ctx, bytes), None))
+}
+
+#[test]
+fn test_hex_encode() {
+    let (out, verified) =
+        run(&[0x00, 0xab, 0x7f, 0xff], |ctx, chip, bytes| (chip.hex_encode(ctx, bytes), None));
+    assert!(verified);
+    assert_eq!(out, b"00ab7fff");
+}
@@ -0,0 +1,414 @@
+use crate::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{bigint_to_fe, bit_length, fe_to_bigint, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, ToPrimitive, Zero};
+use std::f64::consts::{LN_2, LOG2_E};
+
+#[cfg(test)]
+pub mod tests;
+
+/// The number of guard bits of the internal precision of the approximations, on top of `frac_bits` and the bit
+/// length of `int_bits + frac_bits`.
+const GUARD_BITS: usize = 8;
+
+/// Chip for signed fixed-point numbers in Q-format, with `int_bits` integer bits and `frac_bits` fractional bits.
+///
+/// A fixed-point number `x` is represented by the field element of the integer `round(x * 2^frac_bits)`, i.e.
+/// `p - |round(x * 2^frac_bits)|` for negative `x`, and the representable numbers are the multiples of the unit in the
+/// last place `ulp = 2^-frac_bits` in `[-2^int_bits, 2^int_bits)`. All methods assume their inputs are representable
+/// and constrain their outputs to be, so arithmetic overflows make the circuit unsatisfiable rather than wrap.
+///
+/// Additions and subtractions are exact, and multiplications and divisions round to the nearest representable number.
+/// The approximations of `exp`, `ln`, `sqrt` and `sigmoid` document their errors, in `ulp`, with respect to the exact
+/// function of the represented input. They are computed with `GUARD_BITS + bit_length(int_bits + frac_bits)` extra
+/// fractional bits, from the Taylor series of `2^x` and `atanh` after range reduction.
+#[derive(Clone, Debug)]
+pub struct FixedPointChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+    int_bits: usize,
+    frac_bits: usize,
+}
+
+impl<'a, F: ScalarField> FixedPointChip<'a, F> {
+    /// Construct a FixedPointChip for numbers with `int_bits` integer bits and `frac_bits` fractional bits.
+    ///
+    /// Assumes `int_bits >= 1`, `1 <= frac_bits <= 32`, so that the constants of the approximations computed with
+    /// `f64` are precise enough, and `2 * (int_bits + frac_bits) + 4 < F::CAPACITY`, so that products do not wrap.
+    pub fn new(range: &'a RangeChip<F>, int_bits: usize, frac_bits: usize) -> Self {
+        assert!(int_bits >= 1);
+        assert!((1..=32).contains(&frac_bits));
+        assert!(2 * (int_bits + frac_bits) + 4 < F::CAPACITY as usize);
+        Self { range, int_bits, frac_bits }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Returns the number of integer bits.
+    pub fn int_bits(&self) -> usize {
+        self.int_bits
+    }
+
+    /// Returns the number of fractional bits.
+    pub fn frac_bits(&self) -> usize {
+        self.frac_bits
+    }
+
+    /// The number of bits of the absolute values of representations.
+    fn bits(&self) -> usize {
+        self.int_bits + self.frac_bits
+    }
+
+    /// The number of fractional bits of the internal precision of the approximations.
+    fn precision(&self) -> usize {
+        self.frac_bits + GUARD_BITS + bit_length(self.bits() as u64)
+    }
+
+    /// Returns the representation of the number nearest to `x`, which may not be representable if `x` is too large.
+    pub fn quantize(&self, x: f64) -> F {
+        let value = (x * 2f64.powi(self.frac_bits as i32)).round() as i128;
+        bigint_to_fe(&BigInt::from(value))
+    }
+
+    /// Returns the number represented by `a`.
+    pub fn dequantize(&self, a: &F) -> f64 {
+        fe_to_bigint(a).to_f64().unwrap() / 2f64.powi(self.frac_bits as i32)
+    }
+
+    /// Constrains `a` to represent a number in `[-2^int_bits, 2^int_bits)`.
+    pub fn check_range(&self, ctx: &mut Context<F>, a: AssignedValue<F>) {
+        let shifted = self.gate().add(ctx, a, Constant(self.gate().pow_of_two()[self.bits()]));
+        self.range.range_check(ctx, shifted, self.bits() + 1);
+    }
+
+    /// Witnesses the number nearest to `x` and constrains it to be representable.
+    pub fn load_witness(&self, ctx: &mut Context<F>, x: f64) -> AssignedValue<F> {
+        let a = ctx.load_witness(self.quantize(x));
+        self.check_range(ctx, a);
+        a
+    }
+
+    /// Loads the number nearest to `x` as a constant.
+    ///
+    /// Assumes it is representable.
+    pub fn load_constant(&self, ctx: &mut Context<F>, x: f64) -> AssignedValue<F> {
+        ctx.load_constant(self.quantize(x))
+    }
+
+    /// Constrains and returns `a + b`, constraining it to be representable.
+    pub fn add(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let sum = self.gate().add(ctx, a, b);
+        self.check_range(ctx, sum);
+        sum
+    }
+
+    /// Constrains and returns `a - b`, constraining it to be representable.
+    pub fn sub(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let diff = self.gate().sub(ctx, a, b);
+        self.check_range(ctx, diff);
+        diff
+    }
+
+    /// Constrains and returns `-a`, constraining it to be representable, i.e. `a != -2^int_bits`.
+    pub fn neg(&self, ctx: &mut Context<F>, a: AssignedValue<F>) -> AssignedValue<F> {
+        let neg = self.gate().neg(ctx, a);
+        self.check_range(ctx, neg);
+        neg
+    }
+
+    /// Constrains and returns `a * b` rounded to the nearest representable number, with ties rounded up.
+    ///
+    /// Constrains the result to be representable. The error is at most `ulp / 2`. Costs a `div_mod` by
+    /// `2^frac_bits`.
+    pub fn mul(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let prod = self.gate().mul(ctx, a, b);
+        let out = self.shr_round(ctx, prod, self.frac_bits, 2 * self.bits() + 1);
+        self.check_range(ctx, out);
+        out
+    }
+
+    /// Constrains and returns `a / b` rounded to the nearest representable number, with ties rounded away from zero.
+    ///
+    /// Constrains `b != 0` and the result to be representable. The error is at most `ulp / 2`. Costs a `div_mod_var`
+    /// of the absolute values.
+    pub fn div(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let (gate, bits) = (self.gate(), self.bits());
+        let (sign_a, abs_a) = self.sign_abs(ctx, a);
+        let (sign_b, abs_b) = self.sign_abs(ctx, b);
+        let b_is_zero = gate.is_zero(ctx, abs_b);
+        gate.assert_is_const(ctx, &b_is_zero, &F::zero());
+        // round(|a| * 2^frac_bits / |b|) = floor((|a| * 2^(frac_bits + 1) + |b|) / (2 * |b|))
+        let num = gate.mul_add(ctx, abs_a, Constant(gate.pow_of_two()[self.frac_bits + 1]), abs_b);
+        // adding `b_is_zero` keeps the witness generation from dividing by zero
+        let den = gate.mul_add(ctx, abs_b, Constant(F::from(2)), b_is_zero);
+        let (abs_out, _) =
+            self.range.div_mod_var(ctx, num, den, bits + self.frac_bits + 2, bits + 2);
+        let sign = gate.xor(ctx, sign_a, sign_b);
+        let neg_out = gate.neg(ctx, abs_out);
+        let out = gate.select(ctx, neg_out, abs_out, sign);
+        self.check_range(ctx, out);
+        out
+    }
+
+    /// Constrains and returns whether `a < b`.
+    pub fn is_less_than(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let offset = Constant(self.gate().pow_of_two()[self.bits()]);
+        let a = self.gate().add(ctx, a, offset);
+        let b = self.gate().add(ctx, b, offset);
+        self.range.is_less_than(ctx, a, b, self.bits() + 1)
+    }
+
+    /// Constrains and returns `sqrt(a)` rounded down to a representable number.
+    ///
+    /// Constrains `a >= 0`. The error is less than `ulp`. Costs an `isqrt` of `a * 2^frac_bits`.
+    pub fn sqrt(&self, ctx: &mut Context<F>, a: AssignedValue<F>) -> AssignedValue<F> {
+        // sqrt(a / 2^frac_bits) * 2^frac_bits = sqrt(a * 2^frac_bits)
+        self.range.range_check(ctx, a, self.bits());
+        let scaled = self.gate().mul(ctx, a, Constant(self.gate().pow_of_two()[self.frac_bits]));
+        self.range.isqrt(ctx, scaled, self.bits() + self.frac_bits)
+    }
+
+    /// Constrains and returns an approximation of `e^a`.
+    ///
+    /// Constrains `e^a < 2^int_bits`, up to the error. The error is at most `ulp * (1 + e^a) / 2`, and numbers with
+    /// `e^a < ulp / 2` may round to 0. Writes `e^a = 2^k * 2^f` with `k = floor(a * log2(e))`, approximates `2^f`
+    /// for `f` in `[0, 1)` by a polynomial and selects `2^k` from the powers of two with exponents in
+    /// `[-frac_bits - 2, int_bits)`.
+    pub fn exp(&self, ctx: &mut Context<F>, a: AssignedValue<F>) -> AssignedValue<F> {
+        let (gate, range) = (self.gate(), self.range);
+        let (int_bits, frac_bits, prec) = (self.int_bits, self.frac_bits, self.precision());
+
+        // y = a * log2(e) with `prec` fractional bits, |y| < 2^(int_bits + 1)
+        let log2_e = self.quantize_prec(LOG2_E, prec);
+        let y = gate.mul(ctx, a, Constant(log2_e));
+        let y = self.shr_round(ctx, y, frac_bits, self.bits() + prec + 1);
+        let (k, f) = self.shr_floor(ctx, y, prec, int_bits + prec + 1);
+        let coeffs = exp2_coeffs(prec);
+        let pow_f = self.eval_poly(ctx, f, &coeffs, prec);
+
+        // k is in [-2^(int_bits + 1), 2^(int_bits + 1)), so `k_shifted` is non-negative, and k < int_bits
+        let k_min = gate.pow_of_two()[int_bits + 1];
+        let k_offset = k_min + F::from(frac_bits as u64 + 2);
+        let k_bits = int_bits.max(bit_length(frac_bits as u64 + 2)) + 3;
+        let k_shifted = gate.add(ctx, k, Constant(k_offset));
+        let k_max = Constant(k_offset + F::from(int_bits as u64));
+        range.check_less_than(ctx, k_shifted, k_max, k_bits);
+        // exponents k < -frac_bits - 2 give the same result 0 as k = -frac_bits - 2
+        let k_min = ctx.load_constant(k_min);
+        let k_shifted = range.max(ctx, k_shifted, k_min, k_bits);
+        let idx = gate.sub(ctx, k_shifted, k_min);
+        let num_pows = int_bits + frac_bits + 2;
+        let indicator = gate.idx_to_indicator(ctx, idx, num_pows);
+        let pows = gate.pow_of_two()[..num_pows].iter().map(|pow| Constant(*pow));
+        let pow_k = gate.select_by_indicator(ctx, pows, indicator);
+
+        // 2^f * 2^k * 2^frac_bits = pow_f / 2^prec * pow_k / 2^(frac_bits + 2) * 2^frac_bits
+        let out = gate.mul(ctx, pow_f, pow_k);
+        let out = self.shr_round(ctx, out, prec + 2, prec + num_pows + 2);
+        self.check_range(ctx, out);
+        out
+    }
+
+    /// Constrains and returns an approximation of `ln(a)`.
+    ///
+    /// Constrains `a > 0` and the result to be representable. The error is at most `ulp`. Writes `a = 2^k * u` with
+    /// `u` in `[1, 2)` and computes `k * ln(2) + 2 * atanh((u - 1) / (u + 1))` with the Taylor series of `atanh`.
+    pub fn ln(&self, ctx: &mut Context<F>, a: AssignedValue<F>) -> AssignedValue<F> {
+        let (gate, range) = (self.gate(), self.range);
+        let (bits, frac_bits, prec) = (self.bits(), self.frac_bits, self.precision());
+
+        let a_minus_one = gate.sub(ctx, a, Constant(F::one()));
+        range.range_check(ctx, a_minus_one, bits);
+        // a * 2^(bits + 1 - bit_length(a)) = u * 2^bits
+        let bit_len = range.bit_length(ctx, a, bits + 1);
+        let k = gate.sub(ctx, bit_len, Constant(F::from(frac_bits as u64 + 1)));
+        let idx = gate.sub(ctx, Constant(F::from(bits as u64 + 1)), bit_len);
+        let indicator = gate.idx_to_indicator(ctx, idx, bits + 1);
+        let pows = gate.pow_of_two()[..=bits].iter().map(|pow| Constant(*pow));
+        let pow = gate.select_by_indicator(ctx, pows, indicator);
+        let u = gate.mul(ctx, a, pow);
+        let u = if bits > prec {
+            self.shr_round(ctx, u, bits - prec, bits + 2)
+        } else {
+            gate.mul(ctx, u, Constant(gate.pow_of_two()[prec - bits]))
+        };
+
+        // z = (u - 1) / (u + 1) is in [0, 1/3], rounded as floor((2 * (u - 1) * 2^prec + (u + 1)) / (2 * (u + 1)))
+        let one = gate.pow_of_two()[prec];
+        let u_minus_one = gate.sub(ctx, u, Constant(one));
+        let u_plus_one = gate.add(ctx, u, Constant(one));
+        let num = gate.mul_add(ctx, u_minus_one, Constant(gate.pow_of_two()[prec + 1]), u_plus_one);
+        let den = gate.add(ctx, u_plus_one, u_plus_one);
+        let (z, _) = range.div_mod_var(ctx, num, den, 2 * prec + 3, prec + 3);
+        // ln(u) = 2 * atanh(z) = z * sum_i 2 * (z^2)^i / (2i + 1)
+        let z_sq = gate.mul(ctx, z, z);
+        let z_sq = self.shr_round(ctx, z_sq, prec, 2 * prec + 1);
+        let coeffs = atanh_coeffs(prec);
+        let sum = self.eval_poly(ctx, z_sq, &coeffs, prec);
+        let ln_u = gate.mul(ctx, z, sum);
+        let ln_u = self.shr_round(ctx, ln_u, prec, 2 * prec + 2);
+
+        let ln_2 = self.quantize_prec(LN_2, prec);
+        let out = gate.mul_add(ctx, k, Constant(ln_2), ln_u);
+        let out = self.shr_round(ctx, out, prec - frac_bits, prec + bit_length(bits as u64) + 2);
+        self.check_range(ctx, out);
+        out
+    }
+
+    /// Constrains and returns an approximation of `sigmoid(a) = 1 / (1 + e^-a)`.
+    ///
+    /// The error is at most `2 * ulp`. Computes `1 / (1 + e^-|a|)` with [Self::exp], and its complement for `a < 0`.
+    pub fn sigmoid(&self, ctx: &mut Context<F>, a: AssignedValue<F>) -> AssignedValue<F> {
+        let (gate, frac_bits) = (self.gate(), self.frac_bits);
+        let (sign, abs) = self.sign_abs(ctx, a);
+        let neg_abs = gate.neg(ctx, abs);
+        let exp = self.exp(ctx, neg_abs);
+        // round(2^(2 * frac_bits) / (2^frac_bits + exp)), in [2^(frac_bits - 1), 2^frac_bits]
+        let one = gate.pow_of_two()[frac_bits];
+        let den = gate.add(ctx, exp, Constant(one));
+        let num = gate.add(ctx, den, Constant(gate.pow_of_two()[2 * frac_bits + 1]));
+        let den = gate.add(ctx, den, den);
+        let (out, _) = self.range.div_mod_var(ctx, num, den, 2 * frac_bits + 3, frac_bits + 3);
+        let complement = gate.sub(ctx, Constant(one), out);
+        gate.select(ctx, complement, out, sign)
+    }
+
+    /// Constrains and returns the sign bit and the absolute value of `a`.
+    fn sign_abs(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+    ) -> (AssignedValue<F>, AssignedValue<F>) {
+        let gate = self.gate();
+        let sign = ctx.load_witness(F::from((fe_to_bigint(a.value()) < BigInt::zero()) as u64));
+        gate.assert_bit(ctx, sign);
+        let neg = gate.neg(ctx, a);
+        let abs = gate.select(ctx, neg, a, sign);
+        // with the wrong sign, the absolute value is the negation of a representable number, which is not small
+        self.range.range_check(ctx, abs, self.bits() + 1);
+        (sign, abs)
+    }
+
+    /// Constrains and returns `floor(a / 2^shift)` and `a mod 2^shift`, for signed `a`.
+    ///
+    /// Assumes `0 < shift <= max_bits` and `|a| < 2^max_bits`.
+    fn shr_floor(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: usize,
+        max_bits: usize,
+    ) -> (AssignedValue<F>, AssignedValue<F>) {
+        assert!(0 < shift && shift <= max_bits);
+        let gate = self.gate();
+        // the offset is a multiple of 2^shift which makes `a` non-negative
+        let shifted = gate.add(ctx, a, Constant(gate.pow_of_two()[max_bits]));
+        let (quot, rem) = self.range.div_mod(ctx, shifted, BigUint::one() << shift, max_bits + 1);
+        let quot = gate.sub(ctx, quot, Constant(gate.pow_of_two()[max_bits - shift]));
+        (quot, rem)
+    }
+
+    /// Constrains and returns `round(a / 2^shift)` for signed `a`, with ties rounded up.
+    ///
+    /// Assumes `0 < shift <= max_bits` and `|a| < 2^max_bits`.
+    fn shr_round(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: usize,
+        max_bits: usize,
+    ) -> AssignedValue<F> {
+        let half = self.gate().pow_of_two()[shift - 1];
+        let a = self.gate().add(ctx, a, Constant(half));
+        self.shr_floor(ctx, a, shift, max_bits + 1).0
+    }
+
+    /// Constrains and returns the evaluation at `x` of the polynomial with coefficients `coeffs`, from the constant
+    /// term up, with `prec` fractional bits by Horner's rule.
+    ///
+    /// Assumes `0 <= x < 2`, and the coefficients and the partial evaluations are less than 4 in absolute value.
+    /// Each step rounds, so the error is at most `coeffs.len() * 2^-(prec + 1)`.
+    fn eval_poly(
+        &self,
+        ctx: &mut Context<F>,
+        x: AssignedValue<F>,
+        coeffs: &[F],
+        prec: usize,
+    ) -> AssignedValue<F> {
+        let (last, rest) = coeffs.split_last().unwrap();
+        let mut acc = ctx.load_constant(*last);
+        for coeff in rest.iter().rev() {
+            let prod = self.gate().mul(ctx, acc, x);
+            let prod = self.shr_round(ctx, prod, prec, 2 * prec + 3);
+            acc = self.gate().add(ctx, prod, Constant(*coeff));
+        }
+        acc
+    }
+
+    /// Returns the representation of `x` with `prec` fractional bits.
+    fn quantize_prec(&self, x: f64, prec: usize) -> F {
+        F::from((x * 2f64.powi(prec as i32)).round() as u64)
+    }
+}
+
+/// Returns the coefficients with `prec` fractional bits of the Taylor polynomial of `2^x = e^(x ln 2)` with error
+/// less than `2^-(prec + 2)` on `[0, 1)`.
+fn exp2_coeffs<F: ScalarField>(prec: usize) -> Vec<F> {
+    let bound = 2f64.powi(-(prec as i32) - 2);
+    let mut coeffs = vec![1.0];
+    // the remainder after the term of degree d is at most 2 * ln(2)^(d + 1) / (d + 1)!, twice the next term
+    let mut next = LN_2;
+    while 2.0 * next >= bound {
+        coeffs.push(next);
+        next *= LN_2 / coeffs.len() as f64;
+    }
+    coeffs.into_iter().map(|c| F::from((c * 2f64.powi(prec as i32)).round() as u64)).collect()
+}
+
+/// Returns the coefficients `2 / (2i + 1)` with `prec` fractional bits of the polynomial in `z^2` of the Taylor series
+/// of `2 * atanh(z) / z`, with error less than `2^-(prec + 2)` for `z` in `[0, 1/3]` once multiplied by `z`.
+fn atanh_coeffs<F: ScalarField>(prec: usize) -> Vec<F> {
+    let bound = 2f64.powi(-(prec as i32) - 2);
+    // the remainder after `n` terms is at most 2 * z^(2n + 1) / (2n + 1) / (1 - z^2) <= 9/4 * 3^-(2n + 1) / (2n + 1)
+    let remainder = |n: i32| 2.25 * 3f64.powi(-2 * n - 1) / (2 * n + 1) as f64;
+    let num_terms = (1..).find(|n| remainder(*n) < bound).unwrap() as usize;
+    (0..num_terms)
+        .map(|i| F::from((2.0 / (2 * i + 1) as f64 * 2f64.powi(prec as i32)).round() as u64))
+        .collect()
+}
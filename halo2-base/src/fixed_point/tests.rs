@@ -0,0 +1,133 @@
+use super::*;
+use crate::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use proptest::prelude::*;
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 12;
+const LOOKUP_BITS: usize = 8;
+const INT_BITS: usize = 16;
+const FRAC_BITS: usize = 16;
+const ULP: f64 = 1.0 / (1u64 << FRAC_BITS) as f64;
+
+type FixedPointFn =
+    fn(&mut Context<Fr>, &FixedPointChip<Fr>, &[AssignedValue<Fr>]) -> AssignedValue<Fr>;
+
+/// Runs `f` on the loaded `inputs`, and returns the number represented by its output and whether the circuit is
+/// satisfied.
+fn run(inputs: &[f64], f: FixedPointFn) -> (f64, bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = FixedPointChip::new(&range, INT_BITS, FRAC_BITS);
+    let ctx = builder.main(0);
+    let inputs = inputs.iter().map(|x| chip.load_witness(ctx, *x)).collect::<Vec<_>>();
+    let out = f(ctx, &chip, &inputs);
+    let out = chip.dequantize(out.value());
+    (out, mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok())
+}
+
+prop_compose! {
+    /// A representable number in `[lo, hi)`.
+    fn rand_fixed(lo: f64, hi: f64)(raw in ((lo / ULP) as i64)..((hi / ULP) as i64)) -> f64 {
+        raw as f64 * ULP
+    }
+}
+
+proptest! {
+
+    #[test]
+    fn prop_test_add(a in rand_fixed(-32768.0, 32768.0), b in rand_fixed(-32768.0, 32768.0)) {
+        prop_assume!((-32768.0..32768.0).contains(&(a + b)));
+        let (out, verified) = run(&[a, b], |ctx, chip, x| chip.add(ctx, x[0], x[1]));
+        prop_assert!(verified);
+        prop_assert_eq!(out, a + b);
+    }
+
+    #[test]
+    fn prop_test_sub(a in rand_fixed(-32768.0, 32768.0), b in rand_fixed(-32768.0, 32768.0)) {
+        prop_assume!((-32768.0..32768.0).contains(&(a - b)));
+        let (out, verified) = run(&[a, b], |ctx, chip, x| chip.sub(ctx, x[0], x[1]));
+        prop_assert!(verified);
+        prop_assert_eq!(out, a - b);
+    }
+
+    #[test]
+    fn prop_test_mul(a in rand_fixed(-180.0, 180.0), b in rand_fixed(-180.0, 180.0)) {
+        let (out, verified) = run(&[a, b], |ctx, chip, x| chip.mul(ctx, x[0], x[1]));
+        prop_assert!(verified);
+        prop_assert!((out - a * b).abs() <= ULP / 2.0);
+    }
+
+    #[test]
+    fn prop_test_div(a in rand_fixed(-1000.0, 1000.0), b in rand_fixed(0.5, 100.0), neg in any::<bool>()) {
+        let b = if neg { -b } else { b };
+        let (out, verified) = run(&[a, b], |ctx, chip, x| chip.div(ctx, x[0], x[1]));
+        prop_assert!(verified);
+        prop_assert!((out - a / b).abs() <= ULP / 2.0 + 1e-12);
+    }
+
+    #[test]
+    fn prop_test_is_less_than(a in rand_fixed(-32768.0, 32768.0), b in rand_fixed(-32768.0, 32768.0)) {
+        let (out, verified) = run(&[a, b], |ctx, chip, x| chip.is_less_than(ctx, x[0], x[1]));
+        prop_assert!(verified);
+        prop_assert_eq!(out, if a < b { ULP } else { 0.0 });
+    }
+
+    #[test]
+    fn prop_test_sqrt(a in rand_fixed(0.0, 32768.0)) {
+        let (out, verified) = run(&[a], |ctx, chip, x| chip.sqrt(ctx, x[0]));
+        prop_assert!(verified);
+        let err = a.sqrt() - out;
+        prop_assert!((0.0..ULP).contains(&err));
+    }
+
+    #[test]
+    fn prop_test_exp(a in rand_fixed(-20.0, 11.0)) {
+        let (out, verified) = run(&[a], |ctx, chip, x| chip.exp(ctx, x[0]));
+        prop_assert!(verified);
+        prop_assert!((out - a.exp()).abs() <= ULP * (1.0 + a.exp()) / 2.0);
+    }
+
+    #[test]
+    fn prop_test_ln(a in rand_fixed(ULP, 32768.0)) {
+        let (out, verified) = run(&[a], |ctx, chip, x| chip.ln(ctx, x[0]));
+        prop_assert!(verified);
+        prop_assert!((out - a.ln()).abs() <= ULP);
+    }
+
+    #[test]
+    fn prop_test_sigmoid(a in rand_fixed(-30.0, 30.0)) {
+        let (out, verified) = run(&[a], |ctx, chip, x| chip.sigmoid(ctx, x[0]));
+        prop_assert!(verified);
+        prop_assert!((out - 1.0 / (1.0 + (-a).exp())).abs() <= 2.0 * ULP);
+    }
+}
+
+#[test_case(&[200.0, 400.0], |ctx, chip, x| chip.mul(ctx, x[0], x[1]); "mul() overflow")]
+#[test_case(&[30000.0, 10000.0], |ctx, chip, x| chip.add(ctx, x[0], x[1]); "add() overflow")]
+#[test_case(&[1.0, 0.0], |ctx, chip, x| chip.div(ctx, x[0], x[1]); "div() by zero")]
+#[test_case(&[1000.0, 0.01], |ctx, chip, x| chip.div(ctx, x[0], x[1]); "div() overflow")]
+#[test_case(&[-1.0], |ctx, chip, x| chip.sqrt(ctx, x[0]); "sqrt() negative")]
+#[test_case(&[12.0], |ctx, chip, x| chip.exp(ctx, x[0]); "exp() overflow")]
+#[test_case(&[0.0], |ctx, chip, x| chip.ln(ctx, x[0]); "ln() zero")]
+#[test_case(&[-2.0], |ctx, chip, x| chip.ln(ctx, x[0]); "ln() negative")]
+fn test_fixed_point_neg(inputs: &[f64], f: FixedPointFn) {
+    assert!(!run(inputs, f).1);
+}
+
+#[test]
+fn test_fixed_point_constants() {
+    let (out, verified) = run(&[], |ctx, chip, _| {
+        let half = chip.load_constant(ctx, 0.5);
+        let sqrt_2 = chip.load_constant(ctx, 2f64.sqrt());
+        let prod = chip.mul(ctx, sqrt_2, sqrt_2);
+        chip.sub(ctx, prod, half)
+    });
+    assert!(verified);
+    assert!((out - 1.5).abs() <= ULP);
+}
@@ -1,6 +1,6 @@
 use super::{
     flex_gate::{FlexGateConfig, GateStrategy, MAX_PHASE},
-    range::{RangeConfig, RangeStrategy},
+    range::{CustomLookupConfig, LookupTable, LookupTableId, RangeConfig, RangeStrategy},
 };
 use crate::{
     halo2_proofs::{
@@ -15,6 +15,8 @@ use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
     env::{set_var, var},
+    fmt,
+    sync::Arc,
 };
 
 mod parallelize;
@@ -36,6 +38,21 @@ pub struct KeygenAssignments<F: ScalarField> {
     pub break_points: MultiPhaseThreadBreakPoints,
 }
 
+/// Value of the challenge `gamma` used when it is not known, i.e. during keygen and when auto-configuring.
+const GAMMA_PLACEHOLDER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Witness generation closure run once the challenge `gamma` is known, see [GateThreadBuilder::register_phase1_callback].
+#[derive(Clone)]
+pub struct Phase1Callback<F: ScalarField>(
+    Arc<dyn Fn(&mut GateThreadBuilder<F>, AssignedValue<F>) + Send + Sync>,
+);
+
+impl<F: ScalarField> fmt::Debug for Phase1Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Phase1Callback")
+    }
+}
+
 /// Builds the process for gate threading
 #[derive(Clone, Debug, Default)]
 pub struct GateThreadBuilder<F: ScalarField> {
@@ -47,6 +64,10 @@ pub struct GateThreadBuilder<F: ScalarField> {
     pub witness_gen_only: bool,
     /// The `unknown` flag is used during key generation. If true, during key generation witness [Value]s are replaced with Value::unknown() for safety.
     use_unknown: bool,
+    /// Values of the user-registered fixed lookup tables, indexed by table id.
+    lookup_tables: Vec<LookupTable<F>>,
+    /// Witness generation closures for phase 1, run in registration order once `gamma` is known.
+    phase1_callbacks: Vec<Phase1Callback<F>>,
 }
 
 impl<F: ScalarField> GateThreadBuilder<F> {
@@ -59,7 +80,14 @@ impl<F: ScalarField> GateThreadBuilder<F> {
         let mut threads = [(); MAX_PHASE].map(|_| vec![]);
         // start with a main thread in phase 0
         threads[0].push(Context::new(witness_gen_only, 0));
-        Self { threads, thread_count: 1, witness_gen_only, use_unknown: false }
+        Self {
+            threads,
+            thread_count: 1,
+            witness_gen_only,
+            use_unknown: false,
+            lookup_tables: Vec::new(),
+            phase1_callbacks: Vec::new(),
+        }
     }
 
     /// Creates a new [GateThreadBuilder] with `witness_gen_only` set to false.
@@ -130,11 +158,80 @@ impl<F: ScalarField> GateThreadBuilder<F> {
         self.threads[phase].last_mut().unwrap()
     }
 
+    /// Registers a fixed lookup table with the given `values` and returns its id.
+    ///
+    /// Cells are constrained to be in the table with [Context::lookup].
+    ///
+    /// Tables must be registered in the same order for keygen and proving.
+    pub fn register_lookup_table(&mut self, values: impl IntoIterator<Item = F>) -> LookupTableId {
+        self.register_tuple_lookup_table(values.into_iter().map(|v| [v]))
+    }
+
+    /// Registers a fixed lookup table with `W` columns and the given `rows`, and returns its id.
+    ///
+    /// Tuples of cells are constrained to be rows of the table with [Context::lookup_tuple].
+    ///
+    /// Tables must be registered in the same order for keygen and proving.
+    pub fn register_tuple_lookup_table<const W: usize>(
+        &mut self,
+        rows: impl IntoIterator<Item = [F; W]>,
+    ) -> LookupTableId {
+        let rows = rows.into_iter().map(|row| row.to_vec()).collect();
+        self.lookup_tables.push(LookupTable { width: W, rows });
+        LookupTableId { index: self.lookup_tables.len() - 1, width: W }
+    }
+
+    /// Returns the values of the user-registered lookup tables, indexed by table id.
+    pub fn lookup_tables(&self) -> &[LookupTable<F>] {
+        &self.lookup_tables
+    }
+
+    /// Registers a closure generating the witnesses of phase 1, which receives the challenge `gamma` as an assigned value.
+    ///
+    /// The circuit builders run the closures with [Self::run_phase1] after all cells of phase 0 are assigned, so the
+    /// closures can use `gamma` for random linear combinations of phase 0 values. All threads of phase 1 must be created
+    /// by these closures, e.g. with `builder.main(1)`.
+    ///
+    /// The closures may be run several times and with a placeholder `gamma` (e.g. in [Self::config] and during keygen),
+    /// so the circuit layout they create must not depend on the value of `gamma`.
+    pub fn register_phase1_callback(
+        &mut self,
+        f: impl Fn(&mut Self, AssignedValue<F>) + Send + Sync + 'static,
+    ) {
+        self.phase1_callbacks.push(Phase1Callback(Arc::new(f)));
+    }
+
+    /// Returns true if phase 1 closures were registered with [Self::register_phase1_callback].
+    pub fn has_phase1_callbacks(&self) -> bool {
+        !self.phase1_callbacks.is_empty()
+    }
+
+    /// Loads `gamma` as the first cell of phase 1 and runs the registered phase 1 closures with it, in registration order.
+    ///
+    /// Returns the assigned `gamma`. The circuit builders constrain this cell to equal the challenge of the
+    /// [FlexGateConfig].
+    pub fn run_phase1(&mut self, gamma: F) -> AssignedValue<F> {
+        assert!(self.threads[1].is_empty(), "phase 1 threads must be created by phase 1 callbacks");
+        let gamma = self.new_thread(1).load_witness(gamma);
+        for callback in std::mem::take(&mut self.phase1_callbacks) {
+            (callback.0)(self, gamma);
+        }
+        gamma
+    }
+
     /// Auto-calculates configuration parameters for the circuit
     ///
     /// * `k`: The number of in the circuit (i.e. numeber of rows = 2<sup>k</sup>)
     /// * `minimum_rows`: The minimum number of rows in the circuit that cannot be used for witness assignments and contain random `blinding factors` to ensure zk property, defaults to 0.
+    ///
+    /// If phase 1 callbacks are registered, they are run on a copy of the builder with a placeholder `gamma` to count the cells of phase 1.
     pub fn config(&self, k: usize, minimum_rows: Option<usize>) -> FlexGateConfigParams {
+        if self.has_phase1_callbacks() {
+            // phase 1 cells only exist once the callbacks are run, and their number does not depend on `gamma`
+            let mut builder = self.clone();
+            builder.run_phase1(F::from(GAMMA_PLACEHOLDER));
+            return builder.config(k, minimum_rows);
+        }
         let max_rows = (1 << k) - minimum_rows.unwrap_or(0);
         let total_advice_per_phase = self
             .threads
@@ -158,6 +255,42 @@ impl<F: ScalarField> GateThreadBuilder<F> {
             .map(|count| (count + max_rows - 1) / max_rows)
             .collect::<Vec<_>>();
 
+        let num_tables = self.lookup_tables.len();
+        let total_custom_lookup_advice_per_phase = self
+            .threads
+            .iter()
+            .map(|threads| {
+                let mut total = vec![0; num_tables];
+                for ctx in threads {
+                    assert!(
+                        ctx.cells_to_lookup_custom.len() <= num_tables,
+                        "lookup into an unregistered lookup table"
+                    );
+                    for ((total, cells), table) in
+                        total.iter_mut().zip(&ctx.cells_to_lookup_custom).zip(&self.lookup_tables)
+                    {
+                        assert_eq!(
+                            cells.len() % table.width,
+                            0,
+                            "lookup of a tuple of wrong width"
+                        );
+                        *total += cells.len() / table.width;
+                    }
+                }
+                total
+            })
+            .collect::<Vec<_>>();
+        let num_custom_lookup_advice_per_phase = (0..num_tables)
+            .map(|table_id| {
+                total_custom_lookup_advice_per_phase
+                    .iter()
+                    .map(|totals| (totals[table_id] + max_rows - 1) / max_rows)
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+
+        let custom_lookup_widths = self.lookup_tables.iter().map(|table| table.width).collect();
+
         let total_fixed: usize = HashSet::<F>::from_iter(self.threads.iter().flat_map(|threads| {
             threads.iter().flat_map(|ctx| ctx.constant_equality_constraints.iter().map(|(c, _)| *c))
         }))
@@ -168,6 +301,8 @@ impl<F: ScalarField> GateThreadBuilder<F> {
             strategy: GateStrategy::Vertical,
             num_advice_per_phase,
             num_lookup_advice_per_phase,
+            num_custom_lookup_advice_per_phase,
+            custom_lookup_widths,
             num_fixed,
             k,
         };
@@ -180,6 +315,15 @@ impl<F: ScalarField> GateThreadBuilder<F> {
                         phase, total_advice_per_phase[phase], total_lookup_advice_per_phase[phase],
                     );
                 }
+                for (table_id, total) in
+                    total_custom_lookup_advice_per_phase[phase].iter().enumerate()
+                {
+                    if *total != 0 {
+                        println!(
+                            "Gate Chip | Phase {phase}: {total} lookups in lookup table {table_id}",
+                        );
+                    }
+                }
             }
             println!("Total {total_fixed} fixed cells");
             log::info!("Auto-calculated config params:\n {params:#?}");
@@ -198,6 +342,7 @@ impl<F: ScalarField> GateThreadBuilder<F> {
     /// * `config`: The [FlexGateConfig] of the circuit.
     /// * `lookup_advice`: The lookup advice columns.
     /// * `q_lookup`: The lookup advice selectors.
+    /// * `custom_lookups`: The configurations of the user-registered lookup tables.
     /// * `region`: The [Region] of the circuit.
     /// * `assigned_advices`: The assigned advice cells.
     /// * `assigned_constants`: The assigned fixed cells.
@@ -207,6 +352,7 @@ impl<F: ScalarField> GateThreadBuilder<F> {
         config: &FlexGateConfig<F>,
         lookup_advice: &[Vec<Column<Advice>>],
         q_lookup: &[Option<Selector>],
+        custom_lookups: &[CustomLookupConfig],
         region: &mut Region<F>,
         KeygenAssignments {
             mut assigned_advices,
@@ -307,6 +453,8 @@ impl<F: ScalarField> GateThreadBuilder<F> {
         for (phase, threads) in self.threads.iter().enumerate() {
             let mut lookup_offset = 0;
             let mut lookup_col = 0;
+            // (offset, column group index) for each user-registered lookup table
+            let mut custom_lookup_positions = vec![(0, 0); custom_lookups.len()];
             for ctx in threads {
                 for (left, right) in &ctx.advice_equality_constraints {
                     let (left, _) = assigned_advices[&(left.context_id, left.offset)];
@@ -358,6 +506,47 @@ impl<F: ScalarField> GateThreadBuilder<F> {
                     }
                     lookup_offset += 1;
                 }
+
+                // custom lookups are always copied to the lookup advice columns of their table
+                // each tuple is assigned to a single row of a group of columns
+                for (table_id, cells) in ctx.cells_to_lookup_custom.iter().enumerate() {
+                    let custom = &custom_lookups[table_id];
+                    let (offset, group) = &mut custom_lookup_positions[table_id];
+                    for tuple in cells.chunks(custom.table.len()) {
+                        if *offset >= max_rows {
+                            *offset = 0;
+                            *group += 1;
+                        }
+                        let columns = custom.lookup_advice[phase].get(*group).unwrap_or_else(|| {
+                            panic!("NOT ENOUGH LOOKUP ADVICE COLUMNS FOR LOOKUP TABLE {table_id} IN PHASE {phase}")
+                        });
+                        custom.q_lookup[phase][*group]
+                            .enable(region, *offset)
+                            .expect("enable selector should not fail");
+                        for (advice, &column) in tuple.iter().zip(columns) {
+                            let cell = advice.cell.unwrap();
+                            let (acell, _) = assigned_advices[&(cell.context_id, cell.offset)];
+                            let value = advice.value;
+                            let value =
+                                if use_unknown { Value::unknown() } else { Value::known(value) };
+
+                            #[cfg(feature = "halo2-axiom")]
+                            {
+                                let bcell = region.assign_advice(column, *offset, value);
+                                region.constrain_equal(&acell, bcell.cell());
+                            }
+                            #[cfg(not(feature = "halo2-axiom"))]
+                            {
+                                let bcell = region
+                                    .assign_advice(|| "", column, *offset, || value)
+                                    .expect("assign_advice should not fail")
+                                    .cell();
+                                region.constrain_equal(acell, bcell).unwrap();
+                            }
+                        }
+                        *offset += 1;
+                    }
+                }
             }
         }
         KeygenAssignments { assigned_advices, assigned_constants, break_points }
@@ -375,6 +564,7 @@ impl<F: ScalarField> GateThreadBuilder<F> {
 /// * `threads` - [Vec] threads to assign
 /// * `config` - immutable reference to the configuration of the circuit
 /// * `lookup_advice` - Slice of lookup advice columns
+/// * `custom_lookups` - The configurations of the user-registered lookup tables
 /// * `region` - mutable reference to the region to assign threads to
 /// * `break_points` - the preprocessed break points for the threads
 pub fn assign_threads_in<F: ScalarField>(
@@ -382,6 +572,7 @@ pub fn assign_threads_in<F: ScalarField>(
     threads: Vec<Context<F>>,
     config: &FlexGateConfig<F>,
     lookup_advice: &[Column<Advice>],
+    custom_lookups: &[CustomLookupConfig],
     region: &mut Region<F>,
     break_points: ThreadBreakPoints,
 ) {
@@ -400,6 +591,9 @@ pub fn assign_threads_in<F: ScalarField>(
     let mut lookup_offset = 0;
     let mut lookup_advice = lookup_advice.iter();
     let mut lookup_column = lookup_advice.next();
+
+    // (offset, column group index) for each user-registered lookup table
+    let mut custom_lookup_positions = vec![(0, 0); custom_lookups.len()];
     for ctx in threads {
         // if lookup_column is [None], that means there should be a single advice column and it has lookup enabled, so we don't need to copy to special lookup advice columns
         if lookup_column.is_some() {
@@ -421,6 +615,28 @@ pub fn assign_threads_in<F: ScalarField>(
                 lookup_offset += 1;
             }
         }
+        for (table_id, cells) in ctx.cells_to_lookup_custom.into_iter().enumerate() {
+            let custom = &custom_lookups[table_id];
+            let (offset, group) = &mut custom_lookup_positions[table_id];
+            // each tuple is assigned to a single row of a group of columns
+            for tuple in cells.chunks(custom.table.len()) {
+                if *offset >= config.max_rows {
+                    *offset = 0;
+                    *group += 1;
+                }
+                let columns = custom.lookup_advice[phase].get(*group).unwrap_or_else(|| {
+                    panic!("NOT ENOUGH LOOKUP ADVICE COLUMNS FOR LOOKUP TABLE {table_id} IN PHASE {phase}")
+                });
+                for (advice, &column) in tuple.iter().zip(columns) {
+                    let value = advice.value;
+                    #[cfg(feature = "halo2-axiom")]
+                    region.assign_advice(column, *offset, Value::known(value));
+                    #[cfg(not(feature = "halo2-axiom"))]
+                    region.assign_advice(|| "", column, *offset, || Value::known(value)).unwrap();
+                }
+                *offset += 1;
+            }
+        }
         // Assign advice values to the advice columns in each [Context]
         for advice in ctx.advice {
             #[cfg(feature = "halo2-axiom")]
@@ -456,10 +672,23 @@ pub struct FlexGateConfigParams {
     pub num_advice_per_phase: Vec<usize>,
     /// The number of advice columns that do not have lookup enabled per phase
     pub num_lookup_advice_per_phase: Vec<usize>,
+    /// The number of groups of advice columns for lookups into each user-registered lookup table, per phase
+    #[serde(default)]
+    pub num_custom_lookup_advice_per_phase: Vec<Vec<usize>>,
+    /// The number of columns of each user-registered lookup table
+    #[serde(default)]
+    pub custom_lookup_widths: Vec<usize>,
     /// The number of fixed columns per phase
     pub num_fixed: usize,
 }
 
+/// Returns the value of `gamma` if it is known, and [GAMMA_PLACEHOLDER] otherwise.
+fn known_or_placeholder<F: ScalarField>(gamma: Value<F>) -> F {
+    let mut value = F::from(GAMMA_PLACEHOLDER);
+    gamma.map(|gamma| value = gamma);
+    value
+}
+
 /// A wrapper struct to auto-build a circuit from a `GateThreadBuilder`.
 #[derive(Clone, Debug)]
 pub struct GateCircuitBuilder<F: ScalarField> {
@@ -494,10 +723,14 @@ impl<F: ScalarField> GateCircuitBuilder<F> {
         gate: &FlexGateConfig<F>,
         lookup_advice: &[Vec<Column<Advice>>],
         q_lookup: &[Option<Selector>],
+        custom_lookups: &[CustomLookupConfig],
         layouter: &mut impl Layouter<F>,
     ) -> HashMap<(usize, usize), (circuit::Cell, usize)> {
         let mut first_pass = SKIP_FIRST_PASS;
         let mut assigned_advices = HashMap::new();
+        // in halo2-pse the prover calls `synthesize` once per phase, and `gamma` is only known in the calls after FirstPhase
+        #[allow(unused_mut)]
+        let mut gamma = gate.gamma.map(|gamma| layouter.get_challenge(gamma));
         layouter
             .assign_region(
                 || "GateCircuitBuilder generated circuit",
@@ -506,44 +739,71 @@ impl<F: ScalarField> GateCircuitBuilder<F> {
                         first_pass = false;
                         return Ok(());
                     }
-                    // only support FirstPhase in this Builder because getting challenge value requires more specialized witness generation during synthesize
+                    // only support FirstPhase and SecondPhase, where SecondPhase witnesses are generated by the phase 1 callbacks
                     // If we are not performing witness generation only, we can skip the first pass and assign threads directly
                     if !self.builder.borrow().witness_gen_only {
                         // clone the builder so we can re-use the circuit for both vk and pk gen
-                        let builder = self.builder.borrow().clone();
-                        for threads in builder.threads.iter().skip(1) {
+                        let mut builder = self.builder.borrow().clone();
+                        for threads in builder.threads.iter().skip(2) {
                             assert!(
                                 threads.is_empty(),
-                                "GateCircuitBuilder only supports FirstPhase for now"
+                                "GateCircuitBuilder only supports FirstPhase and SecondPhase for now"
+                            );
+                        }
+                        if builder.has_phase1_callbacks() {
+                            #[cfg(feature = "halo2-axiom")]
+                            {
+                                region.next_phase();
+                                gamma = gate.gamma.map(|gamma| region.get_challenge(gamma));
+                            }
+                            let q_gamma =
+                                gate.q_gamma.expect("phase 1 callbacks need SecondPhase advice columns");
+                            builder.run_phase1(known_or_placeholder(gamma.unwrap()));
+                            q_gamma.enable(&mut region, 0).expect("enable selector should not fail");
+                        } else {
+                            assert!(
+                                builder.threads[1].is_empty(),
+                                "SecondPhase threads must be created by phase 1 callbacks"
                             );
                         }
                         let assignments = builder.assign_all(
                             gate,
                             lookup_advice,
                             q_lookup,
+                            custom_lookups,
                             &mut region,
                             Default::default(),
                         );
                         *self.break_points.borrow_mut() = assignments.break_points;
                         assigned_advices = assignments.assigned_advices;
                     } else {
-                        // If we are only generating witness, we can skip the first pass and assign threads directly
-                        let builder = self.builder.take();
-                        let break_points = self.break_points.take();
-                        for (phase, (threads, break_points)) in builder
-                            .threads
-                            .into_iter()
-                            .zip(break_points.into_iter())
-                            .enumerate()
-                            .take(1)
-                        {
+                        // If we are only generating witness, we can skip the first pass and assign threads directly.
+                        // Threads are taken once assigned, so in halo2-pse the calls for later phases do not re-assign them.
+                        let mut builder = self.builder.borrow_mut();
+                        let mut break_points = self.break_points.borrow_mut();
+                        let num_phases = if builder.has_phase1_callbacks() { 2 } else { 1 };
+                        for phase in 0..num_phases {
+                            if phase == 1 {
+                                #[cfg(feature = "halo2-axiom")]
+                                {
+                                    region.next_phase();
+                                    gamma = gate.gamma.map(|gamma| region.get_challenge(gamma));
+                                }
+                                let gamma = gamma
+                                    .expect("phase 1 callbacks need SecondPhase advice columns");
+                                let mut known_gamma = None;
+                                gamma.map(|gamma| known_gamma = Some(gamma));
+                                let Some(gamma) = known_gamma else { break };
+                                builder.run_phase1(gamma);
+                            }
                             assign_threads_in(
                                 phase,
-                                threads,
+                                std::mem::take(&mut builder.threads[phase]),
                                 gate,
                                 lookup_advice.get(phase).unwrap_or(&vec![]),
+                                custom_lookups,
                                 &mut region,
-                                break_points,
+                                break_points.get_mut(phase).map(std::mem::take).unwrap_or_default(),
                             );
                         }
                     }
@@ -555,6 +815,12 @@ impl<F: ScalarField> GateCircuitBuilder<F> {
     }
 }
 
+impl<F: ScalarField> AsRef<GateCircuitBuilder<F>> for GateCircuitBuilder<F> {
+    fn as_ref(&self) -> &GateCircuitBuilder<F> {
+        self
+    }
+}
+
 impl<F: ScalarField> Circuit<F> for GateCircuitBuilder<F> {
     type Config = FlexGateConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
@@ -570,6 +836,8 @@ impl<F: ScalarField> Circuit<F> for GateCircuitBuilder<F> {
             strategy,
             num_advice_per_phase,
             num_lookup_advice_per_phase: _,
+            num_custom_lookup_advice_per_phase: _,
+            custom_lookup_widths: _,
             num_fixed,
             k,
         } = serde_json::from_str(&var("FLEX_GATE_CONFIG_PARAMS").unwrap()).unwrap();
@@ -582,7 +850,7 @@ impl<F: ScalarField> Circuit<F> for GateCircuitBuilder<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        self.sub_synthesize(&config, &[], &[], &mut layouter);
+        self.sub_synthesize(&config, &[], &[], &[], &mut layouter);
         Ok(())
     }
 }
@@ -611,6 +879,12 @@ impl<F: ScalarField> RangeCircuitBuilder<F> {
     }
 }
 
+impl<F: ScalarField> AsRef<GateCircuitBuilder<F>> for RangeCircuitBuilder<F> {
+    fn as_ref(&self) -> &GateCircuitBuilder<F> {
+        &self.0
+    }
+}
+
 impl<F: ScalarField> Circuit<F> for RangeCircuitBuilder<F> {
     type Config = RangeConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
@@ -626,6 +900,8 @@ impl<F: ScalarField> Circuit<F> for RangeCircuitBuilder<F> {
             strategy,
             num_advice_per_phase,
             num_lookup_advice_per_phase,
+            num_custom_lookup_advice_per_phase,
+            custom_lookup_widths,
             num_fixed,
             k,
         } = serde_json::from_str(&var("FLEX_GATE_CONFIG_PARAMS").unwrap()).unwrap();
@@ -638,6 +914,8 @@ impl<F: ScalarField> Circuit<F> for RangeCircuitBuilder<F> {
             strategy,
             &num_advice_per_phase,
             &num_lookup_advice_per_phase,
+            &num_custom_lookup_advice_per_phase,
+            &custom_lookup_widths,
             num_fixed,
             lookup_bits,
             k,
@@ -645,6 +923,8 @@ impl<F: ScalarField> Circuit<F> for RangeCircuitBuilder<F> {
     }
 
     /// Performs the actual computation on the circuit (e.g., witness generation), populating the lookup table and filling in all the advice values for a particular proof.
+    ///
+    /// The phase 1 callbacks of the builder are run once the challenge `gamma` is known, see [GateThreadBuilder::register_phase1_callback].
     fn synthesize(
         &self,
         config: Self::Config,
@@ -656,7 +936,16 @@ impl<F: ScalarField> Circuit<F> for RangeCircuitBuilder<F> {
         {
             config.load_lookup_table(&mut layouter).expect("load lookup table should not fail");
         }
-        self.0.sub_synthesize(&config.gate, &config.lookup_advice, &config.q_lookup, &mut layouter);
+        config
+            .load_custom_lookup_tables(&mut layouter, self.0.builder.borrow().lookup_tables())
+            .expect("load custom lookup tables should not fail");
+        self.0.sub_synthesize(
+            &config.gate,
+            &config.lookup_advice,
+            &config.q_lookup,
+            &config.custom_lookups,
+            &mut layouter,
+        );
         Ok(())
     }
 }
@@ -760,12 +1049,16 @@ impl<F: ScalarField> Circuit<F> for RangeWithInstanceCircuitBuilder<F> {
         {
             range.load_lookup_table(&mut layouter).expect("load lookup table should not fail");
         }
+        range
+            .load_custom_lookup_tables(&mut layouter, circuit.builder.borrow().lookup_tables())
+            .expect("load custom lookup tables should not fail");
         // we later `take` the builder, so we need to save this value
         let witness_gen_only = circuit.builder.borrow().witness_gen_only();
         let assigned_advices = circuit.sub_synthesize(
             &range.gate,
             &range.lookup_advice,
             &range.q_lookup,
+            &range.custom_lookups,
             &mut layouter,
         );
 
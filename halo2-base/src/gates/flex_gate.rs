@@ -1,12 +1,12 @@
 use crate::{
     halo2_proofs::{
         plonk::{
-            Advice, Assigned, Column, ConstraintSystem, FirstPhase, Fixed, SecondPhase, Selector,
-            ThirdPhase,
+            Advice, Assigned, Challenge, Column, ConstraintSystem, FirstPhase, Fixed, SecondPhase,
+            Selector, ThirdPhase,
         },
         poly::Rotation,
     },
-    utils::ScalarField,
+    utils::{batch_invert, ScalarField},
     AssignedValue, Context,
     QuantumCell::{self, Constant, Existing, Witness, WitnessFraction},
 };
@@ -96,6 +96,10 @@ pub struct FlexGateConfig<F: ScalarField> {
     pub constants: Vec<Column<Fixed>>,
     /// Number of advice columns for each halo2 phase.
     pub num_advice: [usize; MAX_PHASE],
+    /// Challenge `gamma` usable after [FirstPhase], if there are [SecondPhase] advice columns.
+    pub gamma: Option<Challenge>,
+    /// [Selector] constraining the first cell of the first [SecondPhase] advice column to equal `gamma`.
+    pub q_gamma: Option<Selector>,
     /// [GateStrategy] for the flex gate.
     _strategy: GateStrategy,
     /// Max number of rows in flex gate.
@@ -140,10 +144,19 @@ impl<F: ScalarField> FlexGateConfig<F> {
                         .collect();
                     num_advice_array[phase] = num_columns;
                 }
+                let (gamma, q_gamma) = match basic_gates[1].first() {
+                    Some(gate) => {
+                        let (gamma, q_gamma) = configure_gamma(meta, gate.value);
+                        (Some(gamma), Some(q_gamma))
+                    }
+                    None => (None, None),
+                };
                 Self {
                     basic_gates,
                     constants,
                     num_advice: num_advice_array,
+                    gamma,
+                    q_gamma,
                     _strategy: strategy,
                     /// Warning: this needs to be updated if you create more advice columns after this `FlexGateConfig` is created
                     max_rows: (1 << circuit_degree) - meta.minimum_rows(),
@@ -153,6 +166,24 @@ impl<F: ScalarField> FlexGateConfig<F> {
     }
 }
 
+/// Creates the challenge `gamma` usable after [FirstPhase] and a gate `q * (value - gamma) = 0` on the [SecondPhase] column `value`.
+/// * `meta`: [ConstraintSystem] of the circuit
+/// * `value`: [SecondPhase] advice [Column] to load `gamma` into
+fn configure_gamma<F: ScalarField>(
+    meta: &mut ConstraintSystem<F>,
+    value: Column<Advice>,
+) -> (Challenge, Selector) {
+    let gamma = meta.challenge_usable_after(FirstPhase);
+    let q_gamma = meta.selector();
+    meta.create_gate("load challenge gamma", |meta| {
+        let q = meta.query_selector(q_gamma);
+        let value = meta.query_advice(value, Rotation::cur());
+        let gamma = meta.query_challenge(gamma);
+        vec![q * (value - gamma)]
+    });
+    (gamma, q_gamma)
+}
+
 /// Trait that defines basic arithmetic operations for a gate.
 pub trait GateInstructions<F: ScalarField> {
     /// Returns the [GateStrategy] for the gate.
@@ -304,6 +335,122 @@ pub trait GateInstructions<F: ScalarField> {
         ctx.get(-3)
     }
 
+    /// Constrains and returns the inverses of `a`, computing them with a single field inversion (Montgomery's trick).
+    ///
+    /// Defines a vertical gate of form | 0 | a^-1 | a | 1 | for each element, so the constraints are not satisfiable if some `a[i] = 0`.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `a`: Iterator of [QuantumCell] values to invert
+    fn batch_invert<Q>(
+        &self,
+        ctx: &mut Context<F>,
+        a: impl IntoIterator<Item = Q>,
+    ) -> Vec<AssignedValue<F>>
+    where
+        Q: Into<QuantumCell<F>>,
+    {
+        let a = a.into_iter().map(Into::into).collect::<Vec<QuantumCell<F>>>();
+        let inverses = batch_invert(&a.iter().map(|a| *a.value()).collect::<Vec<_>>());
+        a.into_iter()
+            .zip(inverses)
+            .map(|(a, inv)| {
+                ctx.assign_region([Constant(F::zero()), Witness(inv), a, Constant(F::one())], [0]);
+                ctx.get(-3)
+            })
+            .collect()
+    }
+
+    /// Constrains and returns `a[i] / b[i]` for all `i`, computing the inverses of `b` with a single field inversion (Montgomery's trick).
+    ///
+    /// Defines the same vertical gate as [Self::div_unsafe] for each element.
+    ///
+    /// Assumes `a` and `b` are the same length and `b[i] != 0` for all `i`.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `a`: Iterator of [QuantumCell] values
+    /// * `b`: Iterator of [QuantumCell] values to divide `a` by
+    fn batch_div_unsafe<QA, QB>(
+        &self,
+        ctx: &mut Context<F>,
+        a: impl IntoIterator<Item = QA>,
+        b: impl IntoIterator<Item = QB>,
+    ) -> Vec<AssignedValue<F>>
+    where
+        QA: Into<QuantumCell<F>>,
+        QB: Into<QuantumCell<F>>,
+    {
+        let a = a.into_iter().map(Into::into).collect::<Vec<QuantumCell<F>>>();
+        let b = b.into_iter().map(Into::into).collect::<Vec<QuantumCell<F>>>();
+        assert_eq!(a.len(), b.len());
+        let b_inverses = batch_invert(&b.iter().map(|b| *b.value()).collect::<Vec<_>>());
+        a.into_iter()
+            .zip(b)
+            .zip(b_inverses)
+            .map(|((a, b), b_inv)| {
+                let c = b_inv * a.value();
+                ctx.assign_region([Constant(F::zero()), Witness(c), b, a], [0]);
+                ctx.get(-3)
+            })
+            .collect()
+    }
+
+    /// Constrains and returns `(a[i]^-1, a[i] == 0)` for all `i`, where the inverse of 0 is 0, computing the inverses with a single field inversion (Montgomery's trick).
+    ///
+    /// Defines a vertical gate of form `| out | a | inv | 1 | 0 | a | out | 0 | inv | out | 0 |` for each element, where out = 1 if a = 0, otherwise out = 0, and inv * out = 0.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `a`: Iterator of [AssignedValue]s to invert
+    fn batch_invert_safe(
+        &self,
+        ctx: &mut Context<F>,
+        a: impl IntoIterator<Item = AssignedValue<F>>,
+    ) -> Vec<(AssignedValue<F>, AssignedValue<F>)> {
+        let a = a.into_iter().collect::<Vec<_>>();
+        let inverses = batch_invert(&a.iter().map(|a| *a.value()).collect::<Vec<_>>());
+        a.into_iter()
+            .zip(inverses)
+            .map(|(a, inv)| {
+                let is_zero = F::from(a.value().is_zero_vartime() as u64);
+                let cells = [
+                    Witness(is_zero),
+                    Existing(a),
+                    Witness(inv),
+                    Constant(F::one()),
+                    Constant(F::zero()),
+                    Existing(a),
+                    Witness(is_zero),
+                    Constant(F::zero()),
+                    Witness(inv),
+                    Witness(is_zero),
+                    Constant(F::zero()),
+                ];
+                ctx.assign_region_smart(cells, [0, 4, 7], [(0, 6), (0, 9), (2, 8)], []);
+                (ctx.get(-9), ctx.get(-5))
+            })
+            .collect()
+    }
+
+    /// Constrains and returns `(a[i] / b[i], b[i] == 0)` for all `i`, where division by 0 returns 0, computing the inverses of `b` with a single field inversion (Montgomery's trick).
+    ///
+    /// Assumes `a` and `b` are the same length.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `a`: Iterator of [QuantumCell] values
+    /// * `b`: Iterator of [AssignedValue]s to divide `a` by
+    fn batch_div_safe<Q>(
+        &self,
+        ctx: &mut Context<F>,
+        a: impl IntoIterator<Item = Q>,
+        b: impl IntoIterator<Item = AssignedValue<F>>,
+    ) -> Vec<(AssignedValue<F>, AssignedValue<F>)>
+    where
+        Q: Into<QuantumCell<F>>,
+    {
+        let a = a.into_iter().collect::<Vec<_>>();
+        let b_inverses = self.batch_invert_safe(ctx, b);
+        assert_eq!(a.len(), b_inverses.len());
+        a.into_iter()
+            .zip(b_inverses)
+            .map(|(a, (b_inv, is_zero))| (self.mul(ctx, a, b_inv), is_zero))
+            .collect()
+    }
+
     /// Constrains that `a` is equal to `constant` value.
     /// * `ctx`: [Context] to add the constraints to
     /// * `a`: [QuantumCell] value
@@ -815,7 +962,7 @@ pub trait GateInstructions<F: ScalarField> {
             let sub = self.sub(ctx, Existing(x), Existing(coord.0));
             z = self.mul(ctx, Existing(z), Existing(sub));
         }
-        let mut eval = None;
+        let mut denoms = Vec::with_capacity(coords.len());
         for i in 0..coords.len() {
             // compute (x - x_i) * Prod_{j != i} (x_i - x_j)
             let mut denom = self.sub(ctx, Existing(x), Existing(coords[i].0));
@@ -826,20 +973,17 @@ pub trait GateInstructions<F: ScalarField> {
                 let sub = self.sub(ctx, coords[i].0, coords[j].0);
                 denom = self.mul(ctx, denom, sub);
             }
-            // TODO: batch inversion
-            let is_zero = self.is_zero(ctx, denom);
-            self.assert_is_const(ctx, &is_zero, &F::zero());
-
-            // y_i / denom
-            let quot = self.div_unsafe(ctx, coords[i].1, denom);
-            eval = if let Some(eval) = eval {
-                let eval = self.add(ctx, eval, quot);
-                Some(eval)
-            } else {
-                Some(quot)
-            };
+            denoms.push(denom);
         }
-        let out = self.mul(ctx, eval.unwrap(), z);
+        // the inverses also constrain each denominator to be nonzero
+        let denom_inverses = self.batch_invert(ctx, denoms);
+        // sum_i y_i / denom_i
+        let eval = self.inner_product(
+            ctx,
+            coords.iter().map(|(_, y)| *y),
+            denom_inverses.into_iter().map(Existing),
+        );
+        let out = self.mul(ctx, eval, z);
         (out, z)
     }
 
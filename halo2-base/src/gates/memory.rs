@@ -0,0 +1,226 @@
+use super::{GateInstructions, RangeChip, RangeInstructions};
+use crate::{utils::ScalarField, AssignedValue, Context, QuantumCell::Constant};
+use std::collections::HashMap;
+
+/// A single memory access, in program order.
+#[derive(Clone, Copy, Debug)]
+struct MemoryAccess<F: ScalarField> {
+    addr: u128,
+    timestamp: u64,
+    is_write: bool,
+    /// `addr * 2^(timestamp_bits + 1) + timestamp * 2 + is_write`
+    key: AssignedValue<F>,
+    value: AssignedValue<F>,
+}
+
+/// Chip for random access memory (RAM) at assigned addresses, using offline memory checking.
+///
+/// Every access is recorded in program order as a `(address, timestamp, is_write, value)` tuple, where the
+/// timestamp is the (constant) index of the access. [Self::constrain_sorted] assigns the same accesses sorted by
+/// `(address, timestamp)` and checks that every read in the sorted trace returns the last value written to its
+/// address, or 0 if there is none. [MemoryCheck::verify] finally checks that both traces are permutations of each
+/// other with a grand product, which needs a challenge from a phase after the traces are assigned.
+///
+/// Each access costs O(1) cells: a range check of the address in program order, and a few gates and range checks
+/// in the sorted trace. A read-only memory (ROM) is a memory initialized with [Self::init] and only read afterwards
+/// with [Self::read_rom].
+#[derive(Clone, Debug)]
+pub struct MemoryChip<'range, F: ScalarField> {
+    range: &'range RangeChip<F>,
+    addr_bits: usize,
+    timestamp_bits: usize,
+    /// Current memory contents, for witness generation.
+    memory: HashMap<u128, F>,
+    accesses: Vec<MemoryAccess<F>>,
+    /// Number of values of the last [Self::init].
+    rom_len: usize,
+}
+
+impl<'range, F: ScalarField> MemoryChip<'range, F> {
+    /// Creates an empty memory with addresses in `[0, 2^addr_bits)` allowing up to `2^timestamp_bits - 1` accesses.
+    ///
+    /// Assumes `addr_bits + timestamp_bits + 1 <= F::CAPACITY`.
+    pub fn new(range: &'range RangeChip<F>, addr_bits: usize, timestamp_bits: usize) -> Self {
+        assert!(addr_bits <= 128 && timestamp_bits < 64);
+        assert!(addr_bits + timestamp_bits < F::CAPACITY as usize);
+        Self {
+            range,
+            addr_bits,
+            timestamp_bits,
+            memory: HashMap::new(),
+            accesses: vec![],
+            rom_len: 0,
+        }
+    }
+
+    /// Returns the number of accesses so far.
+    pub fn num_accesses(&self) -> usize {
+        self.accesses.len()
+    }
+
+    /// Constrains and returns the value at `addr`, or 0 if nothing was written to it.
+    ///
+    /// Constrains `addr < 2^addr_bits`.
+    pub fn read(&mut self, ctx: &mut Context<F>, addr: AssignedValue<F>) -> AssignedValue<F> {
+        let value = self.memory.get(&addr.value().get_lower_128()).copied().unwrap_or_else(F::zero);
+        let value = ctx.load_witness(value);
+        self.access(ctx, addr, value, false);
+        value
+    }
+
+    /// Constrains and returns the value at `addr` of a ROM initialized with [Self::init].
+    ///
+    /// Constrains `addr` to be less than the number of values of the last [Self::init], so that out of bounds reads
+    /// cannot return 0.
+    pub fn read_rom(&mut self, ctx: &mut Context<F>, addr: AssignedValue<F>) -> AssignedValue<F> {
+        let size = Constant(F::from(self.rom_len as u64));
+        self.range.check_less_than(ctx, addr, size, self.addr_bits + 1);
+        self.read(ctx, addr)
+    }
+
+    /// Writes `value` at `addr`.
+    ///
+    /// Constrains `addr < 2^addr_bits`.
+    pub fn write(&mut self, ctx: &mut Context<F>, addr: AssignedValue<F>, value: AssignedValue<F>) {
+        self.memory.insert(addr.value().get_lower_128(), *value.value());
+        self.access(ctx, addr, value, true);
+    }
+
+    /// Writes `values[i]` at address `i` for every `i`, e.g. to initialize a ROM read with [Self::read_rom].
+    pub fn init(&mut self, ctx: &mut Context<F>, values: &[AssignedValue<F>]) {
+        assert!(values.len() <= 1 << self.addr_bits, "ROM larger than the memory");
+        self.rom_len = values.len();
+        for (i, value) in values.iter().enumerate() {
+            let addr = ctx.load_constant(F::from(i as u64));
+            self.write(ctx, addr, *value);
+        }
+    }
+
+    fn access(
+        &mut self,
+        ctx: &mut Context<F>,
+        addr: AssignedValue<F>,
+        value: AssignedValue<F>,
+        is_write: bool,
+    ) {
+        let timestamp = self.accesses.len() as u64 + 1;
+        assert!(timestamp < 1 << self.timestamp_bits, "too many memory accesses");
+        self.range.range_check(ctx, addr, self.addr_bits);
+        let gate = self.range.gate();
+        let key = gate.mul_add(
+            ctx,
+            addr,
+            Constant(gate.pow_of_two()[self.timestamp_bits + 1]),
+            Constant(F::from(2 * timestamp + is_write as u64)),
+        );
+        let addr = addr.value().get_lower_128();
+        self.accesses.push(MemoryAccess { addr, timestamp, is_write, key, value });
+    }
+
+    /// Assigns the accesses sorted by `(address, timestamp)` and constrains the memory semantics on them.
+    ///
+    /// Should be called once, after all accesses. The returned [MemoryCheck] must then be verified, see
+    /// [MemoryCheck::verify].
+    pub fn constrain_sorted(&self, ctx: &mut Context<F>) -> MemoryCheck<F> {
+        let gate = self.range.gate();
+        let key_bits = self.addr_bits + self.timestamp_bits + 1;
+        let mut accesses = self.accesses.clone();
+        accesses.sort_by_key(|access| (access.addr, access.timestamp));
+
+        let mut sorted = Vec::with_capacity(accesses.len());
+        let mut prev: Option<[AssignedValue<F>; 3]> = None;
+        for access in accesses {
+            let [addr, timestamp, is_write, value] = [
+                F::from_u128(access.addr),
+                F::from(access.timestamp),
+                F::from(access.is_write as u64),
+                *access.value.value(),
+            ]
+            .map(|v| ctx.load_witness(v));
+            self.range.range_check(ctx, addr, self.addr_bits);
+            self.range.range_check(ctx, timestamp, self.timestamp_bits);
+            gate.assert_bit(ctx, is_write);
+            let key = gate.inner_product(
+                ctx,
+                [addr, timestamp, is_write],
+                [self.timestamp_bits + 1, 1, 0].map(|i| Constant(gate.pow_of_two()[i])),
+            );
+            // the value a read must return: the previous value at the same address, or 0 for a new address
+            let expected = if let Some([prev_key, prev_addr, prev_value]) = prev {
+                // keys are strictly increasing
+                let diff = gate.sub(ctx, key, prev_key);
+                let diff = gate.sub(ctx, diff, Constant(F::one()));
+                self.range.range_check(ctx, diff, key_bits);
+                let same_addr = gate.is_equal(ctx, addr, prev_addr);
+                gate.mul(ctx, same_addr, prev_value)
+            } else {
+                ctx.load_zero()
+            };
+            let value_diff = gate.sub(ctx, value, expected);
+            let read_diff = gate.mul_not(ctx, is_write, value_diff);
+            gate.assert_is_const(ctx, &read_diff, &F::zero());
+
+            sorted.push([key, value]);
+            prev = Some([key, addr, value]);
+        }
+        let program = self.accesses.iter().map(|access| [access.key, access.value]).collect();
+        MemoryCheck { program, sorted }
+    }
+}
+
+/// Accesses of a [MemoryChip] in program order and sorted order, as `(key, value)` pairs where the key packs the
+/// address, timestamp and access type.
+#[derive(Clone, Debug)]
+pub struct MemoryCheck<F: ScalarField> {
+    program: Vec<[AssignedValue<F>; 2]>,
+    sorted: Vec<[AssignedValue<F>; 2]>,
+}
+
+impl<F: ScalarField> MemoryCheck<F> {
+    /// Constrains the sorted accesses to be a permutation of the accesses in program order.
+    ///
+    /// For `n` accesses, checks `prod_i (gamma^(n + 1) - key_i - gamma * value_i)` is the same for both traces.
+    /// As polynomials in `gamma`, the products of two different traces differ, so by Schwartz-Zippel a random
+    /// `gamma` passes with probability at most `n * (n + 1) / |F|` if the traces are not permutations.
+    ///
+    /// `gamma` must be a challenge generated after all accesses were assigned, i.e. `ctx` should be in a later
+    /// phase than the one of the [MemoryChip].
+    pub fn verify(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        gamma: AssignedValue<F>,
+    ) {
+        assert_eq!(self.program.len(), self.sorted.len());
+        let shift = pow_const(ctx, gate, gamma, self.program.len() + 1);
+        let mut grand_product = |trace: &[[AssignedValue<F>; 2]]| {
+            let mut acc = ctx.load_constant(F::one());
+            for [key, value] in trace {
+                let fingerprint = gate.mul_add(ctx, gamma, *value, *key);
+                let factor = gate.sub(ctx, shift, fingerprint);
+                acc = gate.mul(ctx, acc, factor);
+            }
+            acc
+        };
+        let program = grand_product(&self.program);
+        let sorted = grand_product(&self.sorted);
+        ctx.constrain_equal(&program, &sorted);
+    }
+}
+
+/// Constrains and returns `base^exp` by square and multiply.
+pub(crate) fn pow_const<F: ScalarField>(
+    ctx: &mut Context<F>,
+    gate: &impl GateInstructions<F>,
+    base: AssignedValue<F>,
+    exp: usize,
+) -> AssignedValue<F> {
+    let mut acc = ctx.load_constant(F::one());
+    for i in (0..usize::BITS - exp.leading_zeros()).rev() {
+        acc = gate.mul(ctx, acc, acc);
+        if (exp >> i) & 1 == 1 {
+            acc = gate.mul(ctx, acc, base);
+        }
+    }
+    acc
+}
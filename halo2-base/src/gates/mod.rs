@@ -2,6 +2,8 @@
 pub mod builder;
 /// Module implementing our simple custom gate and common functions using it
 pub mod flex_gate;
+/// Module for in-circuit memory checked against a sorted trace
+pub mod memory;
 /// Module using a single lookup table for range checks
 pub mod range;
 
@@ -1,5 +1,8 @@
 use crate::{
-    gates::flex_gate::{FlexGateConfig, GateInstructions, GateStrategy, MAX_PHASE},
+    gates::{
+        builder::GateThreadBuilder,
+        flex_gate::{FlexGateConfig, GateInstructions, GateStrategy, MAX_PHASE},
+    },
     halo2_proofs::{
         circuit::{Layouter, Value},
         plonk::{
@@ -15,9 +18,12 @@ use crate::{
     QuantumCell::{self, Constant, Existing, Witness},
 };
 use num_bigint::BigUint;
-use num_integer::Integer;
+use num_integer::{Integer, Roots};
 use num_traits::One;
-use std::{cmp::Ordering, ops::Shl};
+use std::{
+    cmp::{min, Ordering},
+    ops::Shl,
+};
 
 use super::flex_gate::GateChip;
 
@@ -35,6 +41,73 @@ pub enum RangeStrategy {
     Vertical, // vanilla implementation with vertical basic gate(s)
 }
 
+/// Bitwise operations supported by the bitwise lookup table, see [RangeChip::with_bitwise_lookup_table].
+///
+/// The bitwise lookup table contains, for every operation `op` and `x, y` in `[0, 2^b)` where `b` is the number of
+/// bits of the operands of the table, the packed value `op * 2^(3b) + x * 2^(2b) + y * 2^b + (x op y)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    /// Bitwise and
+    And = 0,
+    /// Bitwise exclusive or
+    Xor = 1,
+    /// Bitwise or
+    Or = 2,
+}
+
+impl BitwiseOp {
+    /// All bitwise operations, in the order of their packed tags.
+    pub const ALL: [BitwiseOp; 3] = [BitwiseOp::And, BitwiseOp::Xor, BitwiseOp::Or];
+
+    /// Applies the operation to `x` and `y`.
+    pub fn apply(&self, x: u64, y: u64) -> u64 {
+        match self {
+            BitwiseOp::And => x & y,
+            BitwiseOp::Xor => x ^ y,
+            BitwiseOp::Or => x | y,
+        }
+    }
+
+    /// Returns the packed table value of `(self, x, y, x op y)` for operands of `bits` bits.
+    pub fn pack(&self, x: u64, y: u64, bits: usize) -> u64 {
+        ((*self as u64) << (3 * bits)) | (x << (2 * bits)) | (y << bits) | self.apply(x, y)
+    }
+}
+
+/// Values of a user-registered fixed lookup table, see [crate::gates::builder::GateThreadBuilder::register_lookup_table].
+#[derive(Clone, Debug, Default)]
+pub struct LookupTable<F: ScalarField> {
+    /// Number of columns of the table.
+    pub width: usize,
+    /// Rows of the table, each of length `width`.
+    pub rows: Vec<Vec<F>>,
+}
+
+/// Id of a user-registered fixed lookup table, as returned by
+/// [crate::gates::builder::GateThreadBuilder::register_tuple_lookup_table].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LookupTableId {
+    /// Index of the table in the registered tables.
+    pub index: usize,
+    /// Number of columns of the table.
+    pub width: usize,
+}
+
+/// Configuration of a user-registered fixed lookup table, see [crate::gates::builder::GateThreadBuilder::register_lookup_table].
+#[derive(Clone, Debug)]
+pub struct CustomLookupConfig {
+    /// Special advice (witness) Columns used only for lookups into `table`, in each phase.
+    ///
+    /// The columns are grouped by `table.len()`: each row of a group is looked up as a tuple in `table`.
+    pub lookup_advice: [Vec<Vec<Column<Advice>>>; MAX_PHASE],
+    /// Selectors of the groups of `lookup_advice`, in each phase. Rows are only looked up where the selector is on.
+    pub q_lookup: [Vec<Selector>; MAX_PHASE],
+    /// Column tagging the rows of the table: 1 on the rows of the table and 0 on the padding rows.
+    pub tag: TableColumn,
+    /// Columns for the table values.
+    pub table: Vec<TableColumn>,
+}
+
 /// Configuration for Range Chip
 #[derive(Clone, Debug)]
 pub struct RangeConfig<F: ScalarField> {
@@ -52,6 +125,8 @@ pub struct RangeConfig<F: ScalarField> {
     pub q_lookup: Vec<Option<Selector>>,
     /// Column for lookup table values.
     pub lookup: TableColumn,
+    /// User-registered fixed lookup tables, indexed by table id.
+    pub custom_lookups: Vec<CustomLookupConfig>,
     /// Defines the number of bits represented in the lookup table [0,2^<sup>lookup_bits</sup>).
     lookup_bits: usize,
     /// Gate Strategy used for specifying advice values.
@@ -68,6 +143,8 @@ impl<F: ScalarField> RangeConfig<F> {
     /// * `range_strategy`: [GateStrategy] of the range chip
     /// * `num_advice`: Number of [Advice] [Column]s without lookup enabled in each phase
     /// * `num_lookup_advice`: Number of `lookup_advice` [Column]s in each phase
+    /// * `num_custom_lookup_advice`: For each user-registered lookup table, the number of groups of lookup advice [Column]s in each phase
+    /// * `custom_lookup_widths`: For each user-registered lookup table, the number of columns of the table
     /// * `num_fixed`: Number of fixed [Column]s in each phase
     /// * `lookup_bits`: Number of bits represented in the LookUp table [0,2^lookup_bits)
     /// * `circuit_degree`: Degree that expresses the size of circuit (i.e., 2^<sup>circuit_degree</sup> is the number of rows in the circuit)
@@ -76,6 +153,8 @@ impl<F: ScalarField> RangeConfig<F> {
         range_strategy: RangeStrategy,
         num_advice: &[usize],
         num_lookup_advice: &[usize],
+        num_custom_lookup_advice: &[Vec<usize>],
+        custom_lookup_widths: &[usize],
         num_fixed: usize,
         lookup_bits: usize,
         // params.k()
@@ -116,13 +195,55 @@ impl<F: ScalarField> RangeConfig<F> {
             }
         }
 
-        let mut config =
-            Self { lookup_advice, q_lookup, lookup, lookup_bits, gate, _strategy: range_strategy };
+        assert_eq!(num_custom_lookup_advice.len(), custom_lookup_widths.len());
+        let custom_lookups = num_custom_lookup_advice
+            .iter()
+            .zip(custom_lookup_widths)
+            .map(|(num_lookup_advice, &width)| {
+                assert!(width > 0, "lookup tables must have at least one column");
+                let mut lookup_advice = [(); MAX_PHASE].map(|_| Vec::new());
+                let mut q_lookup = [(); MAX_PHASE].map(|_| Vec::new());
+                for (phase, &num_groups) in num_lookup_advice.iter().enumerate() {
+                    for _ in 0..num_groups {
+                        q_lookup[phase].push(meta.complex_selector());
+                        let group = (0..width)
+                            .map(|_| {
+                                let a = match phase {
+                                    0 => meta.advice_column(),
+                                    1 => meta.advice_column_in(SecondPhase),
+                                    2 => meta.advice_column_in(ThirdPhase),
+                                    _ => panic!(
+                                        "Currently RangeConfig only supports {MAX_PHASE} phases"
+                                    ),
+                                };
+                                meta.enable_equality(a);
+                                a
+                            })
+                            .collect();
+                        lookup_advice[phase].push(group);
+                    }
+                }
+                let tag = meta.lookup_table_column();
+                let table = (0..width).map(|_| meta.lookup_table_column()).collect();
+                CustomLookupConfig { lookup_advice, q_lookup, tag, table }
+            })
+            .collect();
+
+        let mut config = Self {
+            lookup_advice,
+            q_lookup,
+            lookup,
+            custom_lookups,
+            lookup_bits,
+            gate,
+            _strategy: range_strategy,
+        };
 
         // sanity check: only create lookup table if there are lookup_advice columns
         if !num_lookup_advice.is_empty() {
             config.create_lookup(meta);
         }
+        config.create_custom_lookups(meta);
         config.gate.max_rows = (1 << circuit_degree) - meta.minimum_rows();
         assert!(
             (1 << lookup_bits) <= config.gate.max_rows,
@@ -160,6 +281,31 @@ impl<F: ScalarField> RangeConfig<F> {
         }
     }
 
+    /// Instantiates the lookup arguments of the user-registered lookup tables.
+    ///
+    /// Each row of a group of lookup advice columns is looked up as `(q, q * a_0, ..., q * a_{w-1})` in the columns
+    /// `(tag, table_0, ..., table_{w-1})`, so rows with the selector `q` off only match the padding rows of the table.
+    /// * `meta`: [ConstraintSystem] of the circuit
+    fn create_custom_lookups(&self, meta: &mut ConstraintSystem<F>) {
+        for custom in &self.custom_lookups {
+            let groups = custom
+                .lookup_advice
+                .iter()
+                .zip(&custom.q_lookup)
+                .flat_map(|(groups, q)| groups.iter().zip(q.iter()));
+            for (group, q) in groups {
+                meta.lookup("custom lookup", |meta| {
+                    let q = meta.query_selector(*q);
+                    std::iter::once((q.clone(), custom.tag))
+                        .chain(group.iter().zip(custom.table.iter()).map(|(a, table)| {
+                            (q.clone() * meta.query_advice(*a, Rotation::cur()), *table)
+                        }))
+                        .collect()
+                });
+            }
+        }
+    }
+
     /// Loads the lookup table into the circuit using the provided `layouter`.
     /// * `layouter`: layouter for the circuit
     pub fn load_lookup_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
@@ -179,6 +325,53 @@ impl<F: ScalarField> RangeConfig<F> {
         )?;
         Ok(())
     }
+
+    /// Loads the user-registered lookup tables into the circuit using the provided `layouter`.
+    ///
+    /// The first row of each table is the padding row, with `tag` 0 and all values 0, which is only matched by rows
+    /// of the lookup advice columns with the lookup selector off. The rows of the table follow with `tag` 1.
+    /// * `layouter`: layouter for the circuit
+    /// * `tables`: the values of each table, indexed by table id
+    pub fn load_custom_lookup_tables(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tables: &[LookupTable<F>],
+    ) -> Result<(), Error> {
+        assert_eq!(tables.len(), self.custom_lookups.len(), "wrong number of lookup tables");
+        for (table_id, (custom, values)) in self.custom_lookups.iter().zip(tables).enumerate() {
+            assert_eq!(values.width, custom.table.len(), "wrong width of lookup table {table_id}");
+            assert!(
+                values.rows.len() < self.gate.max_rows,
+                "lookup table {table_id} is too large for the circuit degree plus blinding factors!"
+            );
+            let padding_row = vec![F::zero(); values.width];
+            let rows = std::iter::once((F::zero(), &padding_row))
+                .chain(values.rows.iter().map(|row| (F::one(), row)));
+            layouter.assign_table(
+                || format!("custom lookup {table_id}"),
+                |mut table| {
+                    for (offset, (tag, row)) in rows.clone().enumerate() {
+                        table.assign_cell(
+                            || "custom lookup tag",
+                            custom.tag,
+                            offset,
+                            || Value::known(tag),
+                        )?;
+                        for (column, value) in custom.table.iter().zip(row) {
+                            table.assign_cell(
+                                || "custom lookup table",
+                                *column,
+                                offset,
+                                || Value::known(*value),
+                            )?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// Trait that implements methods to constrain a field element number `x` is within a range of bits.
@@ -195,6 +388,9 @@ pub trait RangeInstructions<F: ScalarField> {
     /// Returns the number of bits the lookup table represents.
     fn lookup_bits(&self) -> usize;
 
+    /// Returns the id of the bitwise lookup table and the number of bits of its operands, if the table is registered.
+    fn bitwise_lookup(&self) -> Option<(LookupTableId, usize)>;
+
     /// Checks and constrains that `a` lies in the range [0, 2<sup>range_bits</sup>).
     ///
     /// Assumes that both `a`<= `range_bits` bits.
@@ -426,6 +622,393 @@ pub trait RangeInstructions<F: ScalarField> {
         self.gate().assert_bit(ctx, bit);
         bit
     }
+
+    /// Constrains and returns `a op b`, where `op` is a [BitwiseOp].
+    ///
+    /// `a` and `b` are decomposed into limbs of the number of bits of the operands of the bitwise lookup table, which
+    /// are combined limb by limb with one lookup into the table each. Panics if the table is not registered, see
+    /// [RangeChip::with_bitwise_lookup_table]. Also constrains `a` and `b` to have at most
+    /// `num_bits` bits.
+    ///
+    /// Assumes `num_bits <= F::CAPACITY`.
+    /// * a: [AssignedValue] first operand
+    /// * b: [AssignedValue] second operand
+    /// * num_bits: number of bits of `a` and `b`
+    fn bitwise_op(
+        &self,
+        ctx: &mut Context<F>,
+        op: BitwiseOp,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let (table, limb_bits) = self
+            .bitwise_lookup()
+            .expect("bitwise operations need the bitwise lookup table to be registered");
+        let gate = self.gate();
+        let a_limbs = decompose_to_limbs(self, ctx, a, num_bits, limb_bits);
+        let b_limbs = decompose_to_limbs(self, ctx, b, num_bits, limb_bits);
+        let out_limbs = a_limbs
+            .into_iter()
+            .zip(b_limbs)
+            .map(|(x, y)| {
+                let [x_val, y_val] = [x, y].map(|v| v.value().get_lower_32() as u64);
+                let z = ctx.load_witness(F::from(op.apply(x_val, y_val)));
+                self.range_check(ctx, z, limb_bits);
+                // all of `x, y, z` are in [0, 2^limb_bits), so the packed value determines them
+                let packed = gate.inner_product(
+                    ctx,
+                    [Constant(F::one()), Existing(x), Existing(y), Existing(z)],
+                    [3, 2, 1, 0].map(|i| {
+                        let pow = gate.pow_of_two()[i * limb_bits];
+                        Constant(if i == 3 { pow * F::from(op as u64) } else { pow })
+                    }),
+                );
+                ctx.lookup(table, [packed]);
+                z
+            })
+            .collect::<Vec<_>>();
+        let limb_base = (0..out_limbs.len()).map(|i| Constant(gate.pow_of_two()[i * limb_bits]));
+        gate.inner_product(ctx, out_limbs, limb_base)
+    }
+
+    /// Constrains and returns `a & b` for `a, b` of at most `num_bits` bits, see [Self::bitwise_op].
+    fn bitwise_and(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        self.bitwise_op(ctx, BitwiseOp::And, a, b, num_bits)
+    }
+
+    /// Constrains and returns `a ^ b` for `a, b` of at most `num_bits` bits, see [Self::bitwise_op].
+    fn bitwise_xor(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        self.bitwise_op(ctx, BitwiseOp::Xor, a, b, num_bits)
+    }
+
+    /// Constrains and returns `a | b` for `a, b` of at most `num_bits` bits, see [Self::bitwise_op].
+    fn bitwise_or(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        self.bitwise_op(ctx, BitwiseOp::Or, a, b, num_bits)
+    }
+
+    /// Constrains and returns `(a << shift) mod 2^num_bits`.
+    ///
+    /// Constrains `shift < num_bits`. Assumes `a` has at most `num_bits` bits and `2 * num_bits <= F::CAPACITY`.
+    /// * a: [AssignedValue] value to shift
+    /// * shift: [AssignedValue] number of bits to shift by
+    /// * num_bits: number of bits of `a`
+    fn var_shift_left(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        check_shift(self, ctx, shift, num_bits);
+        shift_split(self, ctx, a, shift, num_bits).1
+    }
+
+    /// Constrains and returns `a >> shift`.
+    ///
+    /// Constrains `shift < num_bits`. Assumes `a` has at most `num_bits` bits and `2 * num_bits <= F::CAPACITY`.
+    /// * a: [AssignedValue] value to shift
+    /// * shift: [AssignedValue] number of bits to shift by
+    /// * num_bits: number of bits of `a`
+    fn var_shift_right(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        check_shift(self, ctx, shift, num_bits);
+        // a >> shift is the high part of a << (num_bits - shift)
+        let complement = self.gate().sub(ctx, Constant(F::from(num_bits as u64)), shift);
+        shift_split(self, ctx, a, complement, num_bits).0
+    }
+
+    /// Constrains and returns `a` rotated left by `shift` bits, as a `num_bits`-bit integer.
+    ///
+    /// Constrains `shift < num_bits`. Assumes `a` has at most `num_bits` bits and `2 * num_bits <= F::CAPACITY`.
+    /// * a: [AssignedValue] value to rotate
+    /// * shift: [AssignedValue] number of bits to rotate by
+    /// * num_bits: number of bits of `a`
+    fn var_rotate_left(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        check_shift(self, ctx, shift, num_bits);
+        let (hi, lo) = shift_split(self, ctx, a, shift, num_bits);
+        self.gate().add(ctx, hi, lo)
+    }
+
+    /// Constrains and returns `a` rotated right by `shift` bits, as a `num_bits`-bit integer.
+    ///
+    /// Constrains `shift < num_bits`. Assumes `a` has at most `num_bits` bits and `2 * num_bits <= F::CAPACITY`.
+    /// * a: [AssignedValue] value to rotate
+    /// * shift: [AssignedValue] number of bits to rotate by
+    /// * num_bits: number of bits of `a`
+    fn var_rotate_right(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        shift: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        check_shift(self, ctx, shift, num_bits);
+        // rotating right by `shift` is rotating left by `num_bits - shift`, which is in [1, num_bits]
+        let complement = self.gate().sub(ctx, Constant(F::from(num_bits as u64)), shift);
+        let (hi, lo) = shift_split(self, ctx, a, complement, num_bits);
+        self.gate().add(ctx, hi, lo)
+    }
+
+    /// Constrains and returns `floor(sqrt(a))`.
+    ///
+    /// Assumes `a` has at most `num_bits` bits and `num_bits < F::CAPACITY`.
+    /// * a: [AssignedValue] value to take the square root of
+    /// * num_bits: number of bits of `a`
+    fn isqrt(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        assert!(num_bits < F::CAPACITY as usize);
+        let root = ctx.load_witness(biguint_to_fe(&fe_to_biguint(a.value()).sqrt()));
+        check_isqrt(self, ctx, a, root, num_bits);
+        root
+    }
+
+    /// Constrains and returns the bit length of `a`, i.e. the least `l` such that `a < 2^l`, which is 0 for `a = 0`.
+    ///
+    /// Decomposes `a` into `num_bits` bits, so it also constrains `a` to have at most `num_bits` bits.
+    /// * a: [AssignedValue] value to get the bit length of
+    /// * num_bits: number of bits of `a`
+    fn bit_length(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let bits = gate.num_to_bits(ctx, a, num_bits);
+        // seen[i] = 1 if some bit at index >= i is set; the bit length is the number of such indices
+        let mut seen = Vec::with_capacity(num_bits);
+        for bit in bits.into_iter().rev() {
+            let next = match seen.last() {
+                Some(prev) => gate.or(ctx, *prev, bit),
+                None => bit,
+            };
+            seen.push(next);
+        }
+        gate.sum(ctx, seen)
+    }
+
+    /// Constrains and returns `floor(log2(a))`.
+    ///
+    /// Constrains `a != 0` and `a` to have at most `num_bits` bits, see [Self::bit_length].
+    /// * a: [AssignedValue] value to take the logarithm of
+    /// * num_bits: number of bits of `a`
+    fn floor_log2(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let bit_length = self.bit_length(ctx, a, num_bits);
+        let is_zero = gate.is_zero(ctx, bit_length);
+        gate.assert_is_const(ctx, &is_zero, &F::zero());
+        gate.sub(ctx, bit_length, Constant(F::one()))
+    }
+
+    /// Constrains and returns `a^exp` in the field, by square and multiply.
+    ///
+    /// Decomposes `exp` into `max_exp_bits` bits, so it also constrains `exp < 2^max_exp_bits`. The result is the
+    /// integer power only if it is less than the field modulus.
+    /// * a: [AssignedValue] base
+    /// * exp: [AssignedValue] exponent
+    /// * max_exp_bits: number of bits of `exp`
+    fn pow_var(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        exp: AssignedValue<F>,
+        max_exp_bits: usize,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let bits = gate.num_to_bits(ctx, exp, max_exp_bits);
+        let mut acc = ctx.load_constant(F::one());
+        for bit in bits.into_iter().rev() {
+            let sq = gate.mul(ctx, acc, acc);
+            let factor = gate.select(ctx, a, Constant(F::one()), bit);
+            acc = gate.mul(ctx, sq, factor);
+        }
+        acc
+    }
+
+    /// Constrains and returns the minimum of `a` and `b`.
+    ///
+    /// Assumes `a` and `b` have at most `num_bits` bits, see [Self::is_less_than].
+    /// * a: [AssignedValue] first value
+    /// * b: [AssignedValue] second value
+    /// * num_bits: number of bits of `a` and `b`
+    fn min(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let is_lt = self.is_less_than(ctx, a, b, num_bits);
+        self.gate().select(ctx, a, b, is_lt)
+    }
+
+    /// Constrains and returns the maximum of `a` and `b`.
+    ///
+    /// Assumes `a` and `b` have at most `num_bits` bits, see [Self::is_less_than].
+    /// * a: [AssignedValue] first value
+    /// * b: [AssignedValue] second value
+    /// * num_bits: number of bits of `a` and `b`
+    fn max(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let is_lt = self.is_less_than(ctx, a, b, num_bits);
+        self.gate().select(ctx, b, a, is_lt)
+    }
+
+    /// Constrains and returns `a` clamped to `[lo, hi]`, i.e. `min(max(a, lo), hi)`.
+    ///
+    /// Assumes `lo <= hi` and `a`, `lo`, `hi` have at most `num_bits` bits, see [Self::is_less_than].
+    /// * a: [AssignedValue] value to clamp
+    /// * lo: [AssignedValue] lower bound
+    /// * hi: [AssignedValue] upper bound
+    /// * num_bits: number of bits of `a`, `lo` and `hi`
+    fn clamp(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        lo: AssignedValue<F>,
+        hi: AssignedValue<F>,
+        num_bits: usize,
+    ) -> AssignedValue<F> {
+        let a = self.max(ctx, a, lo, num_bits);
+        self.min(ctx, a, hi, num_bits)
+    }
+}
+
+/// Decomposes `a` into `ceil(num_bits / limb_bits)` little-endian limbs of `limb_bits` bits, constraining
+/// `a` to have at most `num_bits` bits.
+fn decompose_to_limbs<F: ScalarField, R: RangeInstructions<F> + ?Sized>(
+    range: &R,
+    ctx: &mut Context<F>,
+    a: AssignedValue<F>,
+    num_bits: usize,
+    limb_bits: usize,
+) -> Vec<AssignedValue<F>> {
+    let gate = range.gate();
+    let num_limbs = (num_bits + limb_bits - 1) / limb_bits;
+    let limbs = ctx.assign_witnesses(
+        decompose_fe_to_u64_limbs(a.value(), num_limbs, limb_bits).into_iter().map(F::from),
+    );
+    for (i, limb) in limbs.iter().enumerate() {
+        range.range_check(ctx, *limb, min(limb_bits, num_bits - i * limb_bits));
+    }
+    let limb_base = (0..num_limbs).map(|i| Constant(gate.pow_of_two()[i * limb_bits]));
+    let composed = gate.inner_product(ctx, limbs.clone(), limb_base);
+    ctx.constrain_equal(&composed, &a);
+    limbs
+}
+
+/// Constrains `root = floor(sqrt(a))`, for `a` of at most `num_bits` bits.
+///
+/// Checks `a = root^2 + rem` with `root < 2^((num_bits + 1) / 2)` and `0 <= rem <= 2 * root`, i.e.
+/// `root^2 <= a < (root + 1)^2`.
+pub(crate) fn check_isqrt<F: ScalarField, R: RangeInstructions<F> + ?Sized>(
+    range: &R,
+    ctx: &mut Context<F>,
+    a: AssignedValue<F>,
+    root: AssignedValue<F>,
+    num_bits: usize,
+) {
+    let gate = range.gate();
+    let root_bits = (num_bits + 1) / 2;
+    range.range_check(ctx, root, root_bits);
+    let root_sq = gate.mul(ctx, root, root);
+    let rem = gate.sub(ctx, a, root_sq);
+    range.range_check(ctx, rem, root_bits + 1);
+    let bound = gate.mul_add(ctx, root, Constant(F::from(2)), Constant(F::one()));
+    range.check_less_than(ctx, rem, bound, root_bits + 1);
+}
+
+/// Constrains `shift < num_bits`.
+fn check_shift<F: ScalarField, R: RangeInstructions<F> + ?Sized>(
+    range: &R,
+    ctx: &mut Context<F>,
+    shift: AssignedValue<F>,
+    num_bits: usize,
+) {
+    assert!(num_bits > 0 && 2 * num_bits <= F::CAPACITY as usize);
+    let shift_bits = bit_length(num_bits as u64 - 1).max(1);
+    range.gate().num_to_bits(ctx, shift, shift_bits);
+    if num_bits < 1 << shift_bits {
+        range.check_less_than(ctx, shift, Constant(F::from(num_bits as u64)), shift_bits);
+    }
+}
+
+/// Returns `(hi, lo)` such that `a * 2^exp = hi * 2^num_bits + lo` with `hi, lo < 2^num_bits`.
+///
+/// Assumes `a` has at most `num_bits` bits and `exp` is in `[0, num_bits]`.
+fn shift_split<F: ScalarField, R: RangeInstructions<F> + ?Sized>(
+    range: &R,
+    ctx: &mut Context<F>,
+    a: AssignedValue<F>,
+    exp: AssignedValue<F>,
+    num_bits: usize,
+) -> (AssignedValue<F>, AssignedValue<F>) {
+    let gate = range.gate();
+    // 2^exp = prod_i (1 + exp_i * (2^(2^i) - 1)) over the bits `exp_i` of `exp`
+    let exp_bits = gate.num_to_bits(ctx, exp, bit_length(num_bits as u64));
+    let mut pow = ctx.load_constant(F::one());
+    for (i, bit) in exp_bits.into_iter().enumerate() {
+        let factor = gate.mul_add(
+            ctx,
+            bit,
+            Constant(gate.pow_of_two()[1 << i] - F::one()),
+            Constant(F::one()),
+        );
+        pow = gate.mul(ctx, pow, factor);
+    }
+    let prod = gate.mul(ctx, a, pow);
+    let prod_val = fe_to_biguint(prod.value());
+    let [hi, lo] = [&prod_val >> num_bits, &prod_val % (BigUint::one() << num_bits)]
+        .map(|v| ctx.load_witness(biguint_to_fe(&v)));
+    range.range_check(ctx, hi, num_bits);
+    range.range_check(ctx, lo, num_bits);
+    let composed = gate.mul_add(ctx, hi, Constant(gate.pow_of_two()[num_bits]), lo);
+    ctx.constrain_equal(&composed, &prod);
+    (hi, lo)
 }
 
 /// A chip that implements RangeInstructions which provides methods to constrain a field element `x` is within a range of bits.
@@ -444,6 +1027,8 @@ pub struct RangeChip<F: ScalarField> {
     /// [Vec] of powers of `2 ** lookup_bits` represented as [QuantumCell::Constant].
     /// These are precomputed and cached as a performance optimization for later limb decompositions. We precompute up to the higher power that fits in `F`, which is `2 ** ((F::CAPACITY / lookup_bits) * lookup_bits)`.
     pub limb_bases: Vec<QuantumCell<F>>,
+    /// Id of the bitwise lookup table and number of bits of its operands, see [Self::with_bitwise_lookup_table].
+    pub bitwise_lookup: Option<(LookupTableId, usize)>,
 }
 
 impl<F: ScalarField> RangeChip<F> {
@@ -464,7 +1049,7 @@ impl<F: ScalarField> RangeChip<F> {
             RangeStrategy::Vertical => GateStrategy::Vertical,
         });
 
-        Self { strategy, gate, lookup_bits, limb_bases }
+        Self { strategy, gate, lookup_bits, limb_bases, bitwise_lookup: None }
     }
 
     /// Creates a new [RangeChip] with the default strategy and provided lookup_bits.
@@ -472,6 +1057,28 @@ impl<F: ScalarField> RangeChip<F> {
     pub fn default(lookup_bits: usize) -> Self {
         Self::new(RangeStrategy::Vertical, lookup_bits)
     }
+
+    /// Registers the bitwise lookup table, see [BitwiseOp], with operands of `bits` bits in `builder`, and returns the
+    /// chip with the bitwise operations of [RangeInstructions] enabled.
+    ///
+    /// The table has `3 * 2^(2 * bits)` rows, so it is opt-in: circuits without bitwise operations do not contain it.
+    /// Tables must be registered in the same order for keygen and proving.
+    /// * builder: [GateThreadBuilder] of the circuit
+    /// * bits: number of bits of the operands of each lookup, e.g. `lookup_bits / 2`
+    pub fn with_bitwise_lookup_table(
+        mut self,
+        builder: &mut GateThreadBuilder<F>,
+        bits: usize,
+    ) -> Self {
+        assert!(bits > 0 && 3 * bits + 2 <= 64, "bitwise lookups need operands of 1 to 20 bits");
+        let values = BitwiseOp::ALL.into_iter().flat_map(|op| {
+            (0..1u64 << (2 * bits))
+                .map(move |xy| F::from(op.pack(xy >> bits, xy & ((1 << bits) - 1), bits)))
+        });
+        let table = builder.register_lookup_table(values);
+        self.bitwise_lookup = Some((table, bits));
+        self
+    }
 }
 
 impl<F: ScalarField> RangeInstructions<F> for RangeChip<F> {
@@ -492,6 +1099,11 @@ impl<F: ScalarField> RangeInstructions<F> for RangeChip<F> {
         self.lookup_bits
     }
 
+    /// Returns the id of the bitwise lookup table and the number of bits of its operands, if the table is registered.
+    fn bitwise_lookup(&self) -> Option<(LookupTableId, usize)> {
+        self.bitwise_lookup
+    }
+
     /// Checks and constrains that `a` lies in the range [0, 2<sup>range_bits</sup>).
     ///
     /// This is done by decomposing `a` into `k` limbs, where `k = ceil(range_bits / lookup_bits)`.
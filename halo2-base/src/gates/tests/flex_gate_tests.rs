@@ -90,6 +90,52 @@ pub fn test_div_unsafe<F: ScalarField>(inputs: &[QuantumCell<F>]) -> F {
     *a.value()
 }
 
+#[test_case(&[1, 2, 3, 7].map(Fr::from) => true ; "batch_invert(): nonzero")]
+#[test_case(&[1, 0, 3].map(Fr::from) => false ; "batch_invert(): zero")]
+pub fn test_batch_invert<F: ScalarField>(inputs: &[F]) -> bool {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = GateChip::default();
+    let a = ctx.assign_witnesses(inputs.iter().copied());
+    let inverses = chip.batch_invert(ctx, a.clone());
+    for (a, inv) in a.iter().zip(inverses) {
+        if *a.value() != F::zero() {
+            assert_eq!(*a.value() * inv.value(), F::one());
+        }
+    }
+    builder.config(6, Some(9));
+    let circuit = GateCircuitBuilder::mock(builder);
+    MockProver::run(6, &circuit, vec![]).unwrap().verify().is_ok()
+}
+
+#[test_case((&[6, 0, 5].map(Fr::from), &[3, 4, 5].map(Fr::from)) => vec![Fr::from(2), Fr::zero(), Fr::one()] ; "batch_div_unsafe()")]
+pub fn test_batch_div_unsafe<F: ScalarField>((a, b): (&[F], &[F])) -> Vec<F> {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = GateChip::default();
+    let a = ctx.assign_witnesses(a.iter().copied());
+    let b = ctx.assign_witnesses(b.iter().copied());
+    let quotients = chip.batch_div_unsafe(ctx, a, b);
+    builder.config(6, Some(9));
+    let circuit = GateCircuitBuilder::mock(builder);
+    MockProver::run(6, &circuit, vec![]).unwrap().assert_satisfied();
+    quotients.iter().map(|q| *q.value()).collect()
+}
+
+#[test_case((&[6, 5, 7, 0].map(Fr::from), &[3, 0, 7, 0].map(Fr::from)) => vec![(Fr::from(2), Fr::zero()), (Fr::zero(), Fr::one()), (Fr::one(), Fr::zero()), (Fr::zero(), Fr::one())] ; "batch_div_safe()")]
+pub fn test_batch_div_safe<F: ScalarField>((a, b): (&[F], &[F])) -> Vec<(F, F)> {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = GateChip::default();
+    let a = ctx.assign_witnesses(a.iter().copied());
+    let b = ctx.assign_witnesses(b.iter().copied());
+    let quotients = chip.batch_div_safe(ctx, a, b);
+    builder.config(6, Some(9));
+    let circuit = GateCircuitBuilder::mock(builder);
+    MockProver::run(6, &circuit, vec![]).unwrap().assert_satisfied();
+    quotients.iter().map(|(q, is_zero)| (*q.value(), *is_zero.value())).collect()
+}
+
 #[test_case(&[1, 1].map(Fr::from); "assert_is_const()")]
 pub fn test_assert_is_const<F: ScalarField>(inputs: &[F]) {
     let mut builder = GateThreadBuilder::mock();
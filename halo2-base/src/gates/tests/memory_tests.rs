@@ -0,0 +1,115 @@
+use std::env::set_var;
+
+use super::*;
+use crate::gates::{
+    builder::{GateThreadBuilder, RangeCircuitBuilder},
+    memory::MemoryChip,
+    GateChip, RangeChip,
+};
+use crate::halo2_proofs::plonk::Assigned;
+use crate::utils::testing::mock_verify;
+use ff::Field;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use test_case::test_case;
+
+// Random reads and writes, returning the offset of the first read cell in the main context.
+fn memory_circuit(
+    builder: &mut GateThreadBuilder<Fr>,
+    lookup_bits: usize,
+    num_accesses: usize,
+) -> Option<usize> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let range = RangeChip::default(lookup_bits);
+    let ctx = builder.main(0);
+    let mut chip = MemoryChip::new(&range, 4, 8);
+    let mut memory = [Fr::zero(); 16];
+    let mut first_read = None;
+    for _ in 0..num_accesses {
+        let addr = rng.gen_range(0..16);
+        let addr_cell = ctx.load_witness(Fr::from(addr as u64));
+        if rng.gen_bool(0.5) {
+            let value = Fr::random(&mut rng);
+            memory[addr] = value;
+            let value = ctx.load_witness(value);
+            chip.write(ctx, addr_cell, value);
+        } else {
+            let value = chip.read(ctx, addr_cell);
+            assert_eq!(*value.value(), memory[addr]);
+            first_read.get_or_insert(value.cell.unwrap().offset);
+        }
+    }
+    assert_eq!(chip.num_accesses(), num_accesses);
+    let check = chip.constrain_sorted(ctx);
+    builder.register_phase1_callback(move |builder, gamma| {
+        check.verify(builder.main(1), &GateChip::default(), gamma);
+    });
+    first_read
+}
+
+#[test_case(1; "single access")]
+#[test_case(50; "random accesses")]
+fn test_memory(num_accesses: usize) {
+    let k = 11;
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    memory_circuit(&mut builder, 8, num_accesses);
+    mock_verify(k, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_rom() {
+    let k = 11;
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(8);
+    let ctx = builder.main(0);
+    let mut chip = MemoryChip::new(&range, 4, 8);
+    let table = ctx.assign_witnesses((0..10).map(|i| Fr::from(i * i)));
+    chip.init(ctx, &table);
+    for i in [3u64, 7, 3, 0, 9] {
+        let idx = ctx.load_witness(Fr::from(i));
+        let value = chip.read_rom(ctx, idx);
+        assert_eq!(*value.value(), Fr::from(i * i));
+    }
+    // out of bounds addresses read 0 without the bounds check of `read_rom`
+    let idx = ctx.load_witness(Fr::from(12));
+    assert_eq!(*chip.read(ctx, idx).value(), Fr::zero());
+    let check = chip.constrain_sorted(ctx);
+    builder.register_phase1_callback(move |builder, gamma| {
+        check.verify(builder.main(1), &GateChip::default(), gamma);
+    });
+    mock_verify(k, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test_case(9, true; "last address")]
+#[test_case(10, false; "out of bounds")]
+#[test_case(15, false; "out of bounds in memory")]
+fn test_rom_bounds(addr: u64, in_bounds: bool) {
+    let k = 11;
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(8);
+    let ctx = builder.main(0);
+    let mut chip = MemoryChip::new(&range, 4, 8);
+    let table = ctx.assign_witnesses((0..10).map(|i| Fr::from(i * i)));
+    chip.init(ctx, &table);
+    let idx = ctx.load_witness(Fr::from(addr));
+    chip.read_rom(ctx, idx);
+    let check = chip.constrain_sorted(ctx);
+    builder.register_phase1_callback(move |builder, gamma| {
+        check.verify(builder.main(1), &GateChip::default(), gamma);
+    });
+    assert_eq!(mock_verify(k, &RangeCircuitBuilder::mock(builder)).is_ok(), in_bounds);
+}
+
+#[test]
+fn test_memory_wrong_read() {
+    let k = 11;
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let offset = memory_circuit(&mut builder, 8, 50).unwrap();
+    let ctx = builder.main(0);
+    let value = ctx.advice[offset].evaluate();
+    ctx.advice[offset] = Assigned::Trivial(value + Fr::one());
+    assert!(mock_verify(k, &RangeCircuitBuilder::mock(builder)).is_err());
+}
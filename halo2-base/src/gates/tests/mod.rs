@@ -4,7 +4,9 @@ mod bitwise_rotate;
 mod flex_gate_tests;
 mod general;
 mod idx_to_indicator;
+mod memory_tests;
 mod neg_prop_tests;
+mod phase1_tests;
 mod pos_prop_tests;
 mod range_gate_tests;
 mod test_ground_truths;
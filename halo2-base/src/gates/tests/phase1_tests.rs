@@ -0,0 +1,51 @@
+use super::*;
+use crate::gates::{
+    builder::{GateThreadBuilder, RangeCircuitBuilder},
+    flex_gate::{GateChip, GateInstructions},
+    range::{RangeChip, RangeInstructions},
+};
+use crate::halo2_proofs::{dev::MockProver, plonk::Assigned};
+use std::env::set_var;
+use test_case::test_case;
+
+/// Computes the random linear combination of `values` with the challenge in phase 1, or only overwrites the loaded challenge if `wrong_gamma`.
+fn rlc_circuit(values: &[u64], wrong_gamma: bool) -> RangeCircuitBuilder<Fr> {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let values = builder.main(0).assign_witnesses(values.iter().map(|v| Fr::from(*v)));
+    builder.register_phase1_callback(move |builder, gamma| {
+        if wrong_gamma {
+            // the loaded challenge is the first cell of phase 1
+            builder.threads[1][0].advice[0] = Assigned::Trivial(Fr::from(7));
+            return;
+        }
+        let gate = GateChip::default();
+        let range = RangeChip::default(lookup_bits);
+        let ctx = builder.main(1);
+        let rlc =
+            values.iter().rev().fold(ctx.load_zero(), |acc, v| gate.mul_add(ctx, acc, gamma, *v));
+        let expected =
+            values.iter().rev().fold(Fr::zero(), |acc, v| acc * gamma.value() + v.value());
+        assert_eq!(*rlc.value(), expected);
+        // lookups of phase 0 values in phase 1
+        for value in &values {
+            range.range_check(ctx, *value, 8);
+        }
+    });
+    builder.config(k, Some(9));
+    RangeCircuitBuilder::mock(builder)
+}
+
+#[test_case(&[1, 2, 3]; "phase 1 rlc of 3 values")]
+#[test_case(&[255; 100]; "phase 1 rlc of 100 values")]
+fn test_phase1_rlc(values: &[u64]) {
+    let circuit = rlc_circuit(values, false);
+    MockProver::run(10, &circuit, vec![]).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_phase1_wrong_gamma() {
+    let circuit = rlc_circuit(&[1, 2, 3], true);
+    assert!(MockProver::run(10, &circuit, vec![]).unwrap().verify().is_err());
+}
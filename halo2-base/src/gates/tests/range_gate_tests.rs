@@ -1,18 +1,20 @@
 use std::env::set_var;
 
 use super::*;
-use crate::halo2_proofs::dev::MockProver;
+use crate::halo2_proofs::{arithmetic::FieldExt, dev::MockProver};
 use crate::utils::{biguint_to_fe, ScalarField};
-use crate::QuantumCell::Witness;
+use crate::QuantumCell::{Constant, Witness};
 use crate::{
     gates::{
         builder::{GateThreadBuilder, RangeCircuitBuilder},
-        range::{RangeChip, RangeInstructions},
+        range::{check_isqrt, BitwiseOp, RangeChip, RangeInstructions},
+        GateChip, GateInstructions,
     },
     utils::BigPrimeField,
     QuantumCell,
 };
 use num_bigint::BigUint;
+use num_integer::Roots;
 use test_case::test_case;
 
 #[test_case(16, 10, Fr::from(100), 8; "range_check() pos")]
@@ -153,3 +155,223 @@ pub fn test_div_mod_var<F: ScalarField + BigPrimeField>(
     let a = chip.div_mod_var(ctx, inputs.0, inputs.1, inputs.2, inputs.3);
     (*a.0.value(), *a.1.value())
 }
+
+#[test_case(10, 8, 0xa5, 0x3c, 8; "bitwise ops 8 bits")]
+#[test_case(11, 8, 0xdead_beef, 0x0f0f_1234, 32; "bitwise ops 32 bits")]
+#[test_case(11, 8, 0x1_ffff, 0x1_5555, 17; "bitwise ops 17 bits")]
+pub fn test_bitwise_ops(k: usize, lookup_bits: usize, a: u64, b: u64, num_bits: usize) {
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let chip =
+        RangeChip::default(lookup_bits).with_bitwise_lookup_table(&mut builder, lookup_bits / 2);
+    let ctx = builder.main(0);
+    let [x, y] = [a, b].map(|v| ctx.load_witness(Fr::from(v)));
+    assert_eq!(*chip.bitwise_and(ctx, x, y, num_bits).value(), Fr::from(a & b));
+    assert_eq!(*chip.bitwise_xor(ctx, x, y, num_bits).value(), Fr::from(a ^ b));
+    assert_eq!(*chip.bitwise_or(ctx, x, y, num_bits).value(), Fr::from(a | b));
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied()
+}
+
+#[test]
+fn test_bitwise_lookup_neg() {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let chip =
+        RangeChip::default(lookup_bits).with_bitwise_lookup_table(&mut builder, lookup_bits / 2);
+    let (table, bits) = chip.bitwise_lookup().unwrap();
+    let ctx = builder.main(0);
+    // (3 & 5) is 1, not 2
+    let packed = ctx.load_witness(Fr::from(BitwiseOp::And.pack(3, 5, bits) + 1));
+    ctx.lookup(table, [packed]);
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    assert!(MockProver::run(k as u32, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_range_check_without_bitwise_table() {
+    // the bitwise lookup table would not fit with an even `lookup_bits = k - 1`, but it is only added on request
+    let (k, lookup_bits) = (11, 10);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let chip = RangeChip::default(lookup_bits);
+    let ctx = builder.main(0);
+    let a = ctx.load_witness(Fr::from(1000));
+    chip.range_check(ctx, a, 10);
+    let params = builder.config(k, Some(9));
+    assert!(params.custom_lookup_widths.is_empty());
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied()
+}
+
+#[test_case(0xdead_beef, 0, 32; "var shifts by 0")]
+#[test_case(0xdead_beef, 4, 32; "var shifts by 4")]
+#[test_case(0xdead_beef, 31, 32; "var shifts by 31")]
+#[test_case(0x1_2345, 9, 17; "var shifts 17 bits")]
+#[test_case(u64::MAX, 63, 64; "var shifts 64 bits")]
+pub fn test_var_shifts(a: u64, shift: u64, num_bits: usize) {
+    let (k, lookup_bits) = (12, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = RangeChip::default(lookup_bits);
+    let [x, s] = [a, shift].map(|v| ctx.load_witness(Fr::from(v)));
+    let (a, shift, mask) = (a as u128, shift as u32, (1u128 << num_bits) - 1);
+    let rotl = ((a << shift) | (a >> (num_bits as u32 - shift))) & mask;
+    let rotr = ((a >> shift) | (a << (num_bits as u32 - shift))) & mask;
+    assert_eq!(
+        *chip.var_shift_left(ctx, x, s, num_bits).value(),
+        Fr::from_u128((a << shift) & mask)
+    );
+    assert_eq!(*chip.var_shift_right(ctx, x, s, num_bits).value(), Fr::from_u128(a >> shift));
+    assert_eq!(*chip.var_rotate_left(ctx, x, s, num_bits).value(), Fr::from_u128(rotl));
+    assert_eq!(*chip.var_rotate_right(ctx, x, s, num_bits).value(), Fr::from_u128(rotr));
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied()
+}
+
+#[test]
+fn test_var_shift_out_of_range() {
+    let (k, lookup_bits) = (12, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = RangeChip::default(lookup_bits);
+    let [x, s] = [0xff, 24].map(|v| ctx.load_witness(Fr::from(v)));
+    chip.var_shift_left(ctx, x, s, 24);
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    assert!(MockProver::run(k as u32, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test_case(0, 8; "integer helpers 0")]
+#[test_case(1, 8; "integer helpers 1")]
+#[test_case(255, 8; "integer helpers 255")]
+#[test_case(1 << 20, 32; "integer helpers power of two")]
+#[test_case(0xdead_beef, 32; "integer helpers 32 bits")]
+#[test_case(u64::MAX, 64; "integer helpers 64 bits")]
+pub fn test_integer_helpers(a: u64, num_bits: usize) {
+    let (k, lookup_bits) = (12, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = RangeChip::default(lookup_bits);
+    let [x, lo, hi, base, exp] = [a, 1000, 1 << 30, 3, 37].map(|v| ctx.load_witness(Fr::from(v)));
+    assert_eq!(*chip.isqrt(ctx, x, num_bits).value(), Fr::from(Roots::sqrt(&a)));
+    let bit_length = 64 - a.leading_zeros() as u64;
+    assert_eq!(*chip.bit_length(ctx, x, num_bits).value(), Fr::from(bit_length));
+    if a != 0 {
+        assert_eq!(*chip.floor_log2(ctx, x, num_bits).value(), Fr::from(bit_length - 1));
+    }
+    assert_eq!(*chip.pow_var(ctx, base, exp, 6).value(), Fr::from_u128(3u128.pow(37)));
+    assert_eq!(*chip.min(ctx, x, lo, 64).value(), Fr::from(a.min(1000)));
+    assert_eq!(*chip.max(ctx, x, lo, 64).value(), Fr::from(a.max(1000)));
+    assert_eq!(*chip.clamp(ctx, x, lo, hi, 64).value(), Fr::from(a.clamp(1000, 1 << 30)));
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied()
+}
+
+#[test_case(24, 5; "isqrt too small")]
+#[test_case(24, 6; "isqrt too large")]
+fn test_isqrt_wrong_witness(a: u64, root: u64) {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = RangeChip::default(lookup_bits);
+    let x = ctx.load_witness(Fr::from(a));
+    // a prover witnessing a wrong root, with every other cell computed from it
+    let root = ctx.load_witness(Fr::from(root));
+    check_isqrt(&chip, ctx, x, root, 8);
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    assert!(MockProver::run(k as u32, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+#[test]
+fn test_floor_log2_zero() {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let chip = RangeChip::default(lookup_bits);
+    let x = ctx.load_zero();
+    chip.floor_log2(ctx, x, 8);
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    assert!(MockProver::run(k as u32, &circuit, vec![]).unwrap().verify().is_err());
+}
+
+// the 4 bit S-box of the PRESENT cipher, packed as `x * 16 + sbox[x]`
+const SBOX: [u64; 16] = [12, 5, 6, 11, 9, 0, 10, 13, 3, 14, 15, 8, 4, 7, 1, 2];
+
+#[test_case(&[(3, 11), (0, 12), (15, 2)], true; "custom lookup pos")]
+#[test_case(&[(3, 11), (0, 13)], false; "custom lookup neg")]
+#[test_case(&[(0, 0)], false; "custom lookup zero")]
+fn test_custom_lookup(lookups: &[(u64, u64)], expected: bool) {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    // an unused table, to check table ids are routed correctly
+    builder.register_lookup_table((0..10).map(Fr::from));
+    let sbox = builder.register_lookup_table((0..16).map(|x| Fr::from(x * 16 + SBOX[x as usize])));
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    for &(x, y) in lookups {
+        let [x, y] = [x, y].map(|v| ctx.load_witness(Fr::from(v)));
+        let packed = gate.mul_add(ctx, x, Constant(Fr::from(16)), y);
+        ctx.lookup(sbox, [packed]);
+    }
+    builder.config(k, Some(9));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    let prover = MockProver::run(k as u32, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify().is_ok(), expected);
+}
+
+#[test_case(9, 600, None; "tuple lookup pos")]
+#[test_case(9, 600, Some(599); "tuple lookup neg")]
+fn test_tuple_lookup(k: usize, num_lookups: u64, wrong: Option<u64>) {
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    // (x, y, x ^ y) for 3 bit x, y
+    let rows = (0..64u64).map(|i| [i >> 3, i & 7, (i >> 3) ^ (i & 7)].map(Fr::from));
+    let xor = builder.register_tuple_lookup_table(rows);
+    let ctx = builder.main(0);
+    for i in 0..num_lookups {
+        let (x, y) = (i % 8, (i / 8) % 8);
+        let z = if wrong == Some(i) { x ^ y ^ 1 } else { x ^ y };
+        let tuple = [x, y, z].map(|v| ctx.load_witness(Fr::from(v)));
+        ctx.lookup_tuple(xor, tuple);
+    }
+    let params = builder.config(k, Some(9));
+    // lookups do not fit in a single group of columns
+    assert_eq!(params.num_custom_lookup_advice_per_phase[0][0], 2);
+    let circuit = RangeCircuitBuilder::mock(builder);
+    let prover = MockProver::run(k as u32, &circuit, vec![]).unwrap();
+    assert_eq!(prover.verify().is_ok(), wrong.is_none());
+}
+
+#[test]
+#[should_panic(expected = "lookup into a table with 3 columns")]
+fn test_lookup_multi_column_table() {
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let table = builder.register_tuple_lookup_table([[Fr::zero(); 3]]);
+    let ctx = builder.main(0);
+    let cells = [0, 0, 0].map(|v| ctx.load_witness(Fr::from(v)));
+    ctx.lookup(table, cells);
+}
+
+#[test]
+#[should_panic(expected = "lookup of a tuple of 2 cells into a table with 3 columns")]
+fn test_lookup_tuple_wrong_width() {
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let table = builder.register_tuple_lookup_table([[Fr::zero(); 3]]);
+    let ctx = builder.main(0);
+    let tuple = [0, 0].map(|v| ctx.load_witness(Fr::from(v)));
+    ctx.lookup_tuple(table, tuple);
+}
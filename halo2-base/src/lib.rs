@@ -39,8 +39,24 @@ pub use halo2_proofs_axiom as halo2_proofs;
 use halo2_proofs::plonk::Assigned;
 use utils::ScalarField;
 
+/// Module for arrays of fixed maximum length with an assigned length.
+pub mod array;
+/// Module for decoding and encoding base64 and hex strings.
+pub mod encoding;
+/// Module for signed fixed-point arithmetic and approximations of elementary functions.
+pub mod fixed_point;
 /// Module that contains the main API for creating and working with circuits.
 pub mod gates;
+/// Module for permutation, multiset equality and sortedness arguments.
+pub mod permutation;
+/// Module for evaluating and checking polynomials in circuit.
+pub mod poly;
+/// Module for matching byte strings against regular expressions.
+pub mod regex;
+/// Module for random linear combinations of arrays with a challenge.
+pub mod rlc;
+/// Module for decoding RLP encoded Ethereum data structures.
+pub mod rlp;
 /// Module for SafeType which enforce value range and realted functions.
 pub mod safe_types;
 /// Utility functions for converting between different types of field elements.
@@ -145,6 +161,12 @@ pub struct Context<F: ScalarField> {
     /// * When there is more than 1 advice column all `advice` cells will be copied to a single lookup enabled column to perform lookups.
     pub cells_to_lookup: Vec<AssignedValue<F>>,
 
+    /// [Vec] tracking, for each user-registered lookup table, all cells to look up in that table.
+    /// * Indexed by the index of the [gates::range::LookupTableId] of the table.
+    /// * For a table with `W` columns, each lookup is a tuple of `W` consecutive cells.
+    /// * These are always copied to the lookup enabled columns of the table.
+    pub cells_to_lookup_custom: Vec<Vec<AssignedValue<F>>>,
+
     /// Cell that represents the zero value as AssignedValue<F>
     pub zero_cell: Option<AssignedValue<F>>,
 
@@ -183,6 +205,7 @@ impl<F: ScalarField> Context<F> {
             context_id,
             advice: Vec::new(),
             cells_to_lookup: Vec::new(),
+            cells_to_lookup_custom: Vec::new(),
             zero_cell: None,
             selector: Vec::new(),
             advice_equality_constraints: Vec::new(),
@@ -265,6 +288,49 @@ impl<F: ScalarField> Context<F> {
         }
     }
 
+    /// Constrains `values` to be in the user-registered single column lookup table `table`.
+    ///
+    /// Panics if `table` has more than one column, see [Self::lookup_tuple].
+    /// * `table`: the id returned by [gates::builder::GateThreadBuilder::register_lookup_table]
+    /// * `values`: the cells to look up
+    pub fn lookup(
+        &mut self,
+        table: gates::range::LookupTableId,
+        values: impl IntoIterator<Item = AssignedValue<F>>,
+    ) {
+        assert_eq!(table.width, 1, "lookup into a table with {} columns", table.width);
+        self.push_lookup(table, values);
+    }
+
+    /// Constrains `tuple` to be a row of the user-registered lookup table `table` with `W` columns.
+    ///
+    /// Panics if `table` does not have `W` columns.
+    /// * `table`: the id returned by [gates::builder::GateThreadBuilder::register_tuple_lookup_table]
+    /// * `tuple`: the cells to look up, one per column of the table
+    pub fn lookup_tuple<const W: usize>(
+        &mut self,
+        table: gates::range::LookupTableId,
+        tuple: [AssignedValue<F>; W],
+    ) {
+        assert_eq!(
+            table.width, W,
+            "lookup of a tuple of {W} cells into a table with {} columns",
+            table.width
+        );
+        self.push_lookup(table, tuple);
+    }
+
+    fn push_lookup(
+        &mut self,
+        table: gates::range::LookupTableId,
+        cells: impl IntoIterator<Item = AssignedValue<F>>,
+    ) {
+        if self.cells_to_lookup_custom.len() <= table.index {
+            self.cells_to_lookup_custom.resize_with(table.index + 1, Vec::new);
+        }
+        self.cells_to_lookup_custom[table.index].extend(cells);
+    }
+
     /// Pushes multiple advice cells to the `advice` column of [Context] and enables them by enabling the corresponding selector specified in `gate_offset`.
     ///
     /// * `inputs`: Iterator that specifies the cells to be assigned
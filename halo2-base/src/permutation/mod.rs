@@ -0,0 +1,222 @@
+use crate::{
+    gates::{memory::pow_const, GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{fe_to_biguint, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+
+#[cfg(test)]
+pub mod tests;
+
+/// Chip for constraining arrays to be permutations or sorted copies of each other.
+///
+/// Permutations and multiset equalities are checked with grand products over a challenge `gamma`, which must be
+/// random and only known after all the compared values are assigned. In a multi-phase circuit it is the challenge of
+/// a later phase, see [crate::gates::builder::GateThreadBuilder::register_phase1_callback]. In a single phase circuit
+/// it can be derived in circuit by hashing all the compared values with [Self::hash_challenge].
+///
+/// A sorted copy of an array is witnessed with [Self::witness_sorted], which constrains it to be sorted, and then
+/// constrained to be a permutation of the array with [Self::assert_permutation].
+#[derive(Clone, Debug)]
+pub struct PermutationChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+}
+
+impl<'a, F: ScalarField> PermutationChip<'a, F> {
+    /// Construct a PermutationChip.
+    pub fn new(range: &'a RangeChip<F>) -> Self {
+        Self { range }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Derives the challenge for comparing `a` and `b` in a single phase circuit by hashing both of them, e.g. with
+    /// a Poseidon sponge that absorbs the elements and squeezes the challenge.
+    ///
+    /// `hash` is called once with the elements of `a` followed by those of `b`, tuple by tuple. This is sound if
+    /// every witness the arrays depend on is among them, or bound to them by constraints: for an array witnessed with
+    /// [Self::witness_sorted], hash the input array and the sorted result.
+    pub fn hash_challenge<const W: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[[AssignedValue<F>; W]],
+        b: &[[AssignedValue<F>; W]],
+        hash: impl FnOnce(&mut Context<F>, &[AssignedValue<F>]) -> AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let elements = a.iter().chain(b).flatten().copied().collect::<Vec<_>>();
+        hash(ctx, &elements)
+    }
+
+    /// Constrains and returns `prod_i (gamma - values[i])`, the product of 1 for an empty array.
+    ///
+    /// Costs O(`values.len()`) cells.
+    pub fn grand_product(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        gamma: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let mut acc = ctx.load_constant(F::one());
+        for value in values {
+            let factor = gate.sub(ctx, gamma, *value);
+            acc = gate.mul(ctx, acc, factor);
+        }
+        acc
+    }
+
+    /// Constrains `b` to be a permutation of `a`.
+    ///
+    /// Checks that `prod_i (gamma - a[i]) == prod_i (gamma - b[i])`. The products are polynomials of degree `n` in
+    /// `gamma`, equal if and only if the arrays are permutations of each other, so by Schwartz-Zippel a random `gamma`
+    /// passes with probability at most `n / |F|` if they are not.
+    ///
+    /// Panics if the arrays have different lengths. Costs O(`a.len()`) cells.
+    pub fn assert_permutation(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+        gamma: AssignedValue<F>,
+    ) {
+        assert_eq!(a.len(), b.len(), "permutations must have the same length");
+        let a = self.grand_product(ctx, a, gamma);
+        let b = self.grand_product(ctx, b, gamma);
+        ctx.constrain_equal(&a, &b);
+    }
+
+    /// Constrains the multisets of the tuples in `a` and `b` to be equal.
+    ///
+    /// For `n` tuples of `W` elements, checks that `prod_i (gamma^m - sum_j a[i][j] * gamma^j)` is the same for both
+    /// arrays, with `m = n * (W - 1) + 1`. The exponent `m` separates the powers of `gamma` in the tuples from the
+    /// powers of `gamma^m` in the products, so that the products of different multisets differ as polynomials in
+    /// `gamma`: a random `gamma` passes with probability at most `n * m / |F|` if the multisets differ.
+    ///
+    /// Panics if the arrays have different lengths. Costs O(`W`) cells per tuple.
+    pub fn assert_multiset_equal<const W: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[[AssignedValue<F>; W]],
+        b: &[[AssignedValue<F>; W]],
+        gamma: AssignedValue<F>,
+    ) {
+        assert!(W > 0);
+        assert_eq!(a.len(), b.len(), "multisets must have the same size");
+        let gate = self.gate();
+        let shift = pow_const(ctx, gate, gamma, a.len() * (W - 1) + 1);
+        let mut powers = vec![Constant(F::one())];
+        let mut power = gamma;
+        for j in 1..W {
+            if j > 1 {
+                power = gate.mul(ctx, power, gamma);
+            }
+            powers.push(power.into());
+        }
+        let mut grand_product = |tuples: &[[AssignedValue<F>; W]]| {
+            let mut acc = ctx.load_constant(F::one());
+            for tuple in tuples {
+                let fingerprint = gate.inner_product(ctx, *tuple, powers.clone());
+                let factor = gate.sub(ctx, shift, fingerprint);
+                acc = gate.mul(ctx, acc, factor);
+            }
+            acc
+        };
+        let a = grand_product(a);
+        let b = grand_product(b);
+        ctx.constrain_equal(&a, &b);
+    }
+
+    /// Constrains `values` to be non-decreasing.
+    ///
+    /// Range checks every value to `num_bits` bits, then checks `values[i] < values[i + 1] + 1` with
+    /// [RangeInstructions::check_less_than]. Assumes `num_bits + 1 < F::CAPACITY`.
+    pub fn assert_sorted(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        num_bits: usize,
+    ) {
+        self.assert_sorted_by(ctx, values, num_bits, false);
+    }
+
+    /// Constrains `values` to be strictly increasing, and so distinct.
+    ///
+    /// Range checks every value to `num_bits` bits, then checks `values[i] < values[i + 1]` with
+    /// [RangeInstructions::check_less_than]. Assumes `num_bits < F::CAPACITY`.
+    pub fn assert_strictly_sorted(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        num_bits: usize,
+    ) {
+        self.assert_sorted_by(ctx, values, num_bits, true);
+    }
+
+    fn assert_sorted_by(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        num_bits: usize,
+        strict: bool,
+    ) {
+        let range = self.range;
+        for value in values {
+            range.range_check(ctx, *value, num_bits);
+        }
+        for pair in values.windows(2) {
+            if strict {
+                range.check_less_than(ctx, pair[0], pair[1], num_bits);
+            } else {
+                let bound = range.gate().add(ctx, pair[1], Constant(F::one()));
+                range.check_less_than(ctx, pair[0], bound, num_bits + 1);
+            }
+        }
+    }
+
+    /// Witnesses and returns `values` sorted in non-decreasing order, constrained to be sorted with
+    /// [Self::assert_sorted].
+    ///
+    /// The result is not constrained to be a permutation of `values`: this must be checked with
+    /// [Self::assert_permutation] and a challenge generated after the result is assigned.
+    pub fn witness_sorted(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        num_bits: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let mut sorted = values.iter().map(|value| *value.value()).collect::<Vec<_>>();
+        sorted.sort_by_cached_key(fe_to_biguint);
+        let sorted = ctx.assign_witnesses(sorted);
+        self.assert_sorted(ctx, &sorted, num_bits);
+        sorted
+    }
+
+    /// Witnesses and returns `tuples` stably sorted by their first element, with the first elements constrained to
+    /// be non-decreasing with [Self::assert_sorted].
+    ///
+    /// The result is not constrained to be a permutation of `tuples`: this must be checked with
+    /// [Self::assert_multiset_equal] and a challenge generated after the result is assigned.
+    pub fn witness_sorted_by_key<const W: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        tuples: &[[AssignedValue<F>; W]],
+        num_bits: usize,
+    ) -> Vec<[AssignedValue<F>; W]> {
+        assert!(W > 0);
+        let mut sorted = tuples.iter().map(|tuple| tuple.map(|x| *x.value())).collect::<Vec<_>>();
+        sorted.sort_by_cached_key(|tuple| fe_to_biguint(&tuple[0]));
+        let sorted =
+            sorted.into_iter().map(|tuple| tuple.map(|x| ctx.load_witness(x))).collect::<Vec<_>>();
+        let keys = sorted.iter().map(|tuple| tuple[0]).collect::<Vec<_>>();
+        self.assert_sorted(ctx, &keys, num_bits);
+        sorted
+    }
+}
@@ -0,0 +1,112 @@
+use super::*;
+use crate::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 10;
+const LOOKUP_BITS: usize = 8;
+
+fn load(ctx: &mut Context<Fr>, values: &[u64]) -> Vec<AssignedValue<Fr>> {
+    ctx.assign_witnesses(values.iter().map(|v| Fr::from(*v)))
+}
+
+fn load_tuples(ctx: &mut Context<Fr>, tuples: &[[u64; 2]]) -> Vec<[AssignedValue<Fr>; 2]> {
+    tuples.iter().map(|tuple| tuple.map(|v| ctx.load_witness(Fr::from(v)))).collect()
+}
+
+#[test_case(&[], &[], true; "empty")]
+#[test_case(&[1, 2, 3], &[3, 1, 2], true; "permutation")]
+#[test_case(&[1, 2, 2], &[2, 1, 2], true; "repeated values")]
+#[test_case(&[1, 2, 2], &[1, 1, 2], false; "different multiplicities")]
+#[test_case(&[1, 2, 3], &[1, 2, 4], false; "different values")]
+fn test_assert_permutation(a: &[u64], b: &[u64], expected: bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let [a, b] = [a, b].map(|values| load(ctx, values));
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        PermutationChip::new(&range).assert_permutation(builder.main(1), &a, &b, gamma);
+    });
+    assert_eq!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok(), expected);
+}
+
+#[test_case(&[[1, 10], [2, 20], [1, 10]], &[[2, 20], [1, 10], [1, 10]], true; "permutation")]
+#[test_case(&[[1, 10], [2, 20]], &[[1, 20], [2, 10]], false; "mixed tuples")]
+#[test_case(&[[1, 10], [2, 20]], &[[10, 1], [20, 2]], false; "swapped elements")]
+#[test_case(&[[1, 10], [1, 10]], &[[1, 10], [2, 20]], false; "different multiplicities")]
+fn test_assert_multiset_equal(a: &[[u64; 2]], b: &[[u64; 2]], expected: bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let [a, b] = [a, b].map(|tuples| load_tuples(ctx, tuples));
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        PermutationChip::new(&range).assert_multiset_equal(builder.main(1), &a, &b, gamma);
+    });
+    assert_eq!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok(), expected);
+}
+
+#[test_case(&[1, 2, 2, 255], false, true; "sorted")]
+#[test_case(&[1, 2, 2, 255], true, false; "strictly sorted repeated")]
+#[test_case(&[0, 1, 7, 255], true, true; "strictly sorted")]
+#[test_case(&[1, 3, 2], false, false; "unsorted")]
+#[test_case(&[1, 2, 256], false, false; "too many bits")]
+fn test_assert_sorted(values: &[u64], strict: bool, expected: bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = PermutationChip::new(&range);
+    let ctx = builder.main(0);
+    let values = load(ctx, values);
+    if strict {
+        chip.assert_strictly_sorted(ctx, &values, 8);
+    } else {
+        chip.assert_sorted(ctx, &values, 8);
+    }
+    assert_eq!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok(), expected);
+}
+
+#[test]
+fn test_sort() {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = PermutationChip::new(&range);
+    let ctx = builder.main(0);
+    let values = load(ctx, &[5, 3, 200, 3, 0]);
+    let sorted = chip.witness_sorted(ctx, &values, 8);
+    assert_eq!(
+        sorted.iter().map(|v| *v.value()).collect::<Vec<_>>(),
+        [0, 3, 3, 5, 200].map(Fr::from)
+    );
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        PermutationChip::new(&range).assert_permutation(builder.main(1), &values, &sorted, gamma);
+    });
+    assert!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok());
+}
+
+#[test]
+fn test_sort_by_key() {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = PermutationChip::new(&range);
+    let ctx = builder.main(0);
+    // (price, quantity) orders
+    let orders = load_tuples(ctx, &[[30, 1], [10, 2], [20, 3], [10, 4]]);
+    let sorted = chip.witness_sorted_by_key(ctx, &orders, 8);
+    let expected = [[10, 2], [10, 4], [20, 3], [30, 1]].map(|tuple| tuple.map(Fr::from));
+    assert_eq!(sorted.iter().map(|tuple| tuple.map(|v| *v.value())).collect::<Vec<_>>(), expected);
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        let chip = PermutationChip::new(&range);
+        chip.assert_multiset_equal(builder.main(1), &orders, &sorted, gamma);
+    });
+    assert!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok());
+}
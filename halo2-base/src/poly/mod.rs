@@ -0,0 +1,220 @@
+use crate::{
+    gates::{GateChip, GateInstructions},
+    utils::ScalarField,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use std::iter;
+
+#[cfg(test)]
+pub mod tests;
+
+/// Returns a primitive `2^log_n`-th root of unity of `F`.
+///
+/// Assumes `log_n <= F::S`.
+pub fn root_of_unity<F: ScalarField>(log_n: usize) -> F {
+    assert!(log_n <= F::S as usize, "field has no root of unity of order 2^{log_n}");
+    let mut omega = F::root_of_unity();
+    for _ in log_n..F::S as usize {
+        omega = omega.square();
+    }
+    omega
+}
+
+/// Chip for evaluating and checking polynomials in circuit.
+///
+/// Polynomials in coefficient form are slices of coefficients from the constant term up. Polynomials in evaluation
+/// form are their evaluations over the domain `{1, w, ..., w^(n - 1)}` where `w` is a primitive `n`-th root of
+/// unity, see [root_of_unity], and `n` is a power of two.
+#[derive(Clone, Debug)]
+pub struct PolyChip<'a, F: ScalarField> {
+    gate: &'a GateChip<F>,
+}
+
+impl<'a, F: ScalarField> PolyChip<'a, F> {
+    /// Construct a PolyChip.
+    pub fn new(gate: &'a GateChip<F>) -> Self {
+        Self { gate }
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.gate
+    }
+
+    /// Constrains and returns `[1, x, ..., x^(n - 1)]`.
+    pub fn powers(
+        &self,
+        ctx: &mut Context<F>,
+        x: AssignedValue<F>,
+        n: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let mut powers = Vec::with_capacity(n);
+        if n > 0 {
+            powers.push(ctx.load_constant(F::one()));
+        }
+        if n > 1 {
+            powers.push(x);
+        }
+        for _ in 2..n {
+            let next = self.gate.mul(ctx, *powers.last().unwrap(), x);
+            powers.push(next);
+        }
+        powers
+    }
+
+    /// Constrains and returns `x^(2^log_n)`, by repeated squaring.
+    pub fn pow_of_two_power(
+        &self,
+        ctx: &mut Context<F>,
+        x: AssignedValue<F>,
+        log_n: usize,
+    ) -> AssignedValue<F> {
+        (0..log_n).fold(x, |acc, _| self.gate.mul(ctx, acc, acc))
+    }
+
+    /// Constrains and returns the evaluation of the polynomial with coefficients `coeffs` at `x`, by Horner's rule.
+    ///
+    /// Costs one `mul_add` per coefficient. Returns 0 if `coeffs` is empty.
+    pub fn evaluate(
+        &self,
+        ctx: &mut Context<F>,
+        coeffs: &[AssignedValue<F>],
+        x: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let Some((last, rest)) = coeffs.split_last() else { return ctx.load_zero() };
+        rest.iter().rev().fold(*last, |acc, coeff| self.gate.mul_add(ctx, acc, x, *coeff))
+    }
+
+    /// Constrains and returns the evaluation of the polynomial with coefficients `coeffs` at `x` as an inner product
+    /// with `powers = [1, x, x^2, ...]`, see [Self::powers].
+    ///
+    /// Cheaper than [Self::evaluate] when evaluating several polynomials at the same point.
+    ///
+    /// Assumes `coeffs.len() <= powers.len()` and `powers[0] = 1`.
+    pub fn evaluate_with_powers(
+        &self,
+        ctx: &mut Context<F>,
+        coeffs: &[AssignedValue<F>],
+        powers: &[AssignedValue<F>],
+    ) -> AssignedValue<F> {
+        assert!(coeffs.len() <= powers.len(), "not enough powers");
+        if coeffs.is_empty() {
+            return ctx.load_zero();
+        }
+        // powers[0] = 1 is a constant, which saves a cell
+        let powers =
+            iter::once(Constant(F::one())).chain(powers[1..].iter().copied().map(Existing));
+        self.gate.inner_product(ctx, coeffs.iter().copied(), powers)
+    }
+
+    /// Constrains `a * b = c` as polynomials in coefficient form, by checking it at the point `r`.
+    ///
+    /// `r` must be a challenge generated after `a`, `b` and `c` were assigned; by Schwartz-Zippel the check then
+    /// passes for `a * b != c` with probability at most `(c.len() - 1) / |F|`.
+    ///
+    /// Assumes `a` and `b` are non-empty and `c.len() = a.len() + b.len() - 1`.
+    pub fn check_mul(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+        c: &[AssignedValue<F>],
+        r: AssignedValue<F>,
+    ) {
+        assert!(!a.is_empty() && !b.is_empty());
+        assert_eq!(c.len(), a.len() + b.len() - 1, "wrong degree of product");
+        let powers = self.powers(ctx, r, c.len());
+        let [a_r, b_r, c_r] = [a, b, c].map(|p| self.evaluate_with_powers(ctx, p, &powers));
+        let ab_r = self.gate.mul(ctx, a_r, b_r);
+        ctx.constrain_equal(&ab_r, &c_r);
+    }
+
+    /// Constrains and returns the evaluation at `z` of the vanishing polynomial `Z_H(X) = X^n - 1` of the domain of size `n = 2^log_n`.
+    pub fn vanishing_eval(
+        &self,
+        ctx: &mut Context<F>,
+        z: AssignedValue<F>,
+        log_n: usize,
+    ) -> AssignedValue<F> {
+        let z_n = self.pow_of_two_power(ctx, z, log_n);
+        self.gate.sub(ctx, z_n, Constant(F::one()))
+    }
+
+    /// Constrains and returns the evaluation at `z` of the vanishing polynomial `prod_i (X - points[i])` of arbitrary `points`.
+    pub fn vanishing_eval_at_points(
+        &self,
+        ctx: &mut Context<F>,
+        points: &[AssignedValue<F>],
+        z: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let mut acc = ctx.load_constant(F::one());
+        for point in points {
+            let diff = self.gate.sub(ctx, z, *point);
+            acc = self.gate.mul(ctx, acc, diff);
+        }
+        acc
+    }
+
+    /// Constrains and returns the evaluations at `z` of the Lagrange basis polynomials `L_i` of the domain of size
+    /// `n = 2^log_n` for `i` in `indices`, i.e. `L_i(z) = w^i (z^n - 1) / (n (z - w^i))`.
+    ///
+    /// Constrains `z` to not be in the domain.
+    pub fn lagrange_basis_evals(
+        &self,
+        ctx: &mut Context<F>,
+        z: AssignedValue<F>,
+        log_n: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Vec<AssignedValue<F>> {
+        let z_h = self.vanishing_eval(ctx, z, log_n);
+        let denoms = self.scaled_denoms(ctx, z, log_n, indices);
+        let denom_inverses = self.gate.batch_invert(ctx, denoms);
+        denom_inverses.into_iter().map(|inv| self.gate.mul(ctx, inv, z_h)).collect()
+    }
+
+    /// Constrains and returns the evaluation at `z` of the polynomial of degree less than `n` whose evaluations over
+    /// the domain of size `n = evals.len()` are `evals`, by the barycentric formula
+    /// `f(z) = (z^n - 1) / n * sum_i evals[i] * w^i / (z - w^i)`.
+    ///
+    /// Costs one field inversion for the witness and a constant number of gates per evaluation.
+    ///
+    /// Assumes `evals.len()` is a power of two. Constrains `z` to not be in the domain.
+    pub fn barycentric_eval(
+        &self,
+        ctx: &mut Context<F>,
+        evals: &[AssignedValue<F>],
+        z: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        assert!(evals.len().is_power_of_two(), "domain size must be a power of two");
+        let log_n = evals.len().trailing_zeros() as usize;
+        let z_h = self.vanishing_eval(ctx, z, log_n);
+        let denoms = self.scaled_denoms(ctx, z, log_n, 0..evals.len());
+        let denom_inverses = self.gate.batch_invert(ctx, denoms);
+        let sum = self.gate.inner_product(
+            ctx,
+            evals.iter().copied(),
+            denom_inverses.into_iter().map(Existing),
+        );
+        self.gate.mul(ctx, sum, z_h)
+    }
+
+    /// Constrains and returns `n (z w^-i - 1) = n (z - w^i) / w^i` for `i` in `indices`, for the domain of size `n = 2^log_n`.
+    fn scaled_denoms(
+        &self,
+        ctx: &mut Context<F>,
+        z: AssignedValue<F>,
+        log_n: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Vec<AssignedValue<F>> {
+        let n = F::from(1u64 << log_n);
+        let omega_inv = root_of_unity::<F>(log_n).invert().unwrap();
+        indices
+            .into_iter()
+            .map(|i| {
+                let omega_inv_i = omega_inv.pow_vartime([i as u64]);
+                self.gate.mul_add(ctx, z, Constant(n * omega_inv_i), Constant(-n))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+use crate::{
+    gates::builder::{GateCircuitBuilder, GateThreadBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use ff::Field;
+use rand::{rngs::StdRng, SeedableRng};
+use test_case::test_case;
+
+const K: usize = 10;
+
+fn eval_native(coeffs: &[Fr], x: Fr) -> Fr {
+    coeffs.iter().rev().fold(Fr::zero(), |acc, c| acc * x + c)
+}
+
+#[test_case(0; "empty")]
+#[test_case(1; "constant")]
+#[test_case(9; "degree 8")]
+fn test_evaluate(len: usize) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let coeffs = (0..len).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    let x = Fr::random(&mut rng);
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let chip = PolyChip::new(&gate);
+    let coeffs_assigned = ctx.assign_witnesses(coeffs.clone());
+    let x_assigned = ctx.load_witness(x);
+    let horner = chip.evaluate(ctx, &coeffs_assigned, x_assigned);
+    let powers = chip.powers(ctx, x_assigned, len);
+    let inner = chip.evaluate_with_powers(ctx, &coeffs_assigned, &powers);
+    assert_eq!(*horner.value(), eval_native(&coeffs, x));
+    assert_eq!(*inner.value(), eval_native(&coeffs, x));
+    assert!(mock_verify(K, &GateCircuitBuilder::mock(builder)).is_ok());
+}
+
+#[test_case(false; "correct product")]
+#[test_case(true; "wrong product")]
+fn test_check_mul(wrong: bool) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let a = (0..4).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    let b = (0..3).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    let mut c = vec![Fr::zero(); 6];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            c[i + j] += a * b;
+        }
+    }
+    if wrong {
+        c[2] += Fr::one();
+    }
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let chip = PolyChip::new(&gate);
+    let [a, b, c] = [a, b, c].map(|p| ctx.assign_witnesses(p));
+    // the point should be a challenge; a random witness is enough for the mock prover
+    let r = ctx.load_witness(Fr::random(&mut rng));
+    chip.check_mul(ctx, &a, &b, &c, r);
+    assert_eq!(mock_verify(K, &GateCircuitBuilder::mock(builder)).is_ok(), !wrong);
+}
+
+#[test_case(0; "domain of size 1")]
+#[test_case(3; "domain of size 8")]
+fn test_barycentric_eval(log_n: usize) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let n = 1 << log_n;
+    let omega = root_of_unity::<Fr>(log_n);
+    assert_eq!(omega.pow_vartime([n as u64]), Fr::one());
+    assert!(log_n == 0 || omega.pow_vartime([n as u64 / 2]) != Fr::one());
+    let coeffs = (0..n).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+    let evals = (0..n).map(|i| eval_native(&coeffs, omega.pow_vartime([i as u64])));
+    let z = Fr::random(&mut rng);
+
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let chip = PolyChip::new(&gate);
+    let evals = ctx.assign_witnesses(evals);
+    let z_assigned = ctx.load_witness(z);
+    let eval = chip.barycentric_eval(ctx, &evals, z_assigned);
+    assert_eq!(*eval.value(), eval_native(&coeffs, z));
+    let z_h = chip.vanishing_eval(ctx, z_assigned, log_n);
+    assert_eq!(*z_h.value(), z.pow_vartime([n as u64]) - Fr::one());
+    // the Lagrange basis sums to 1
+    let basis = chip.lagrange_basis_evals(ctx, z_assigned, log_n, 0..n);
+    assert_eq!(basis.iter().fold(Fr::zero(), |acc, l| acc + l.value()), Fr::one());
+    assert!(mock_verify(K, &GateCircuitBuilder::mock(builder)).is_ok());
+}
+
+#[test]
+fn test_barycentric_eval_in_domain() {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let chip = PolyChip::new(&gate);
+    let evals = ctx.assign_witnesses([1, 2, 3, 4].map(Fr::from));
+    let z = ctx.load_witness(root_of_unity::<Fr>(2));
+    chip.barycentric_eval(ctx, &evals, z);
+    assert!(mock_verify(K, &GateCircuitBuilder::mock(builder)).is_err());
+}
+
+#[test]
+fn test_vanishing_eval_at_points() {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let chip = PolyChip::new(&gate);
+    let points = ctx.assign_witnesses([1, 2, 3].map(Fr::from));
+    let z = ctx.load_witness(Fr::from(5));
+    let z_h = chip.vanishing_eval_at_points(ctx, &points, z);
+    assert_eq!(*z_h.value(), Fr::from(24));
+    let z = ctx.load_witness(Fr::from(2));
+    let z_h = chip.vanishing_eval_at_points(ctx, &points, z);
+    assert_eq!(*z_h.value(), Fr::zero());
+    assert!(mock_verify(K, &GateCircuitBuilder::mock(builder)).is_ok());
+}
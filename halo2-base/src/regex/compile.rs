@@ -0,0 +1,472 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+};
+
+/// Maximum number of states of the DFA of a [Regex].
+pub const MAX_DFA_STATES: usize = 1 << 12;
+/// Maximum number of capture groups of a [Regex].
+pub const MAX_GROUPS: usize = 64;
+/// Maximum count of a bounded repetition `{m,n}`.
+const MAX_REPEAT: usize = 256;
+
+type ByteSet = [bool; 256];
+
+/// Error while compiling a [Regex], at byte `offset` of the pattern, or while registering its lookup tables in a
+/// [super::RegexChip], with `offset` 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegexError {
+    /// Offset in the pattern where the error was found.
+    pub offset: usize,
+    /// Description of the error.
+    pub message: &'static str,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Class(Box<ByteSet>),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Star(Box<Node>),
+    Group(usize, Box<Node>),
+}
+
+fn empty() -> Node {
+    Node::Concat(vec![])
+}
+
+fn byte_set(bytes: impl IntoIterator<Item = u8>) -> ByteSet {
+    let mut set = [false; 256];
+    for byte in bytes {
+        set[byte as usize] = true;
+    }
+    set
+}
+
+fn negate(set: &ByteSet) -> ByteSet {
+    set.map(|b| !b)
+}
+
+/// Returns the byte of `set` if it contains exactly one.
+fn single_byte(set: &ByteSet) -> Option<u8> {
+    let mut bytes = (0..=255u8).filter(|b| set[*b as usize]);
+    let byte = bytes.next()?;
+    bytes.next().is_none().then_some(byte)
+}
+
+/// Recursive descent parser of the pattern, see [Regex::new] for the syntax.
+struct Parser<'a> {
+    pattern: &'a [u8],
+    pos: usize,
+    num_groups: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, offset: usize, message: &'static str) -> RegexError {
+        RegexError { offset, message }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.pattern.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        self.pos += found as usize;
+        found
+    }
+
+    fn next_byte(&mut self, start: usize, message: &'static str) -> Result<u8, RegexError> {
+        let byte = self.peek().ok_or_else(|| self.error(start, message))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn parse_alt(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.eat(b'|') {
+            branches.push(self.parse_concat()?);
+        }
+        Ok(if branches.len() == 1 { branches.pop().unwrap() } else { Node::Alt(branches) })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut items = vec![];
+        while !matches!(self.peek(), None | Some(b'|') | Some(b')')) {
+            items.push(self.parse_repeat()?);
+        }
+        Ok(Node::Concat(items))
+    }
+
+    fn parse_repeat(&mut self) -> Result<Node, RegexError> {
+        let mut node = self.parse_atom()?;
+        loop {
+            let start = self.pos;
+            node = match self.peek() {
+                Some(b'*') => Node::Star(Box::new(node)),
+                Some(b'+') => Node::Concat(vec![node.clone(), Node::Star(Box::new(node))]),
+                Some(b'?') => Node::Alt(vec![node, empty()]),
+                Some(b'{') => {
+                    self.pos += 1;
+                    let min = self.parse_count(start)?;
+                    let max = if self.eat(b',') {
+                        (self.peek() != Some(b'}')).then(|| self.parse_count(start)).transpose()?
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some(b'}') {
+                        return Err(self.error(start, "unclosed repetition"));
+                    }
+                    if max.map_or(false, |max| max < min) {
+                        return Err(self.error(start, "invalid repetition bounds"));
+                    }
+                    let mut items = vec![node.clone(); min];
+                    match max {
+                        Some(max) => items.extend(vec![Node::Alt(vec![node, empty()]); max - min]),
+                        None => items.push(Node::Star(Box::new(node))),
+                    }
+                    Node::Concat(items)
+                }
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+        }
+    }
+
+    fn parse_count(&mut self, start: usize) -> Result<usize, RegexError> {
+        let digits = self.pattern[self.pos..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return Err(self.error(start, "invalid repetition"));
+        }
+        let count = std::str::from_utf8(&self.pattern[self.pos..self.pos + digits])
+            .unwrap()
+            .parse::<usize>()
+            .ok()
+            .filter(|count| *count <= MAX_REPEAT)
+            .ok_or_else(|| self.error(start, "repetition count too large"))?;
+        self.pos += digits;
+        Ok(count)
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let byte = self.next_byte(start, "expected an expression")?;
+        match byte {
+            b'(' => {
+                let group = if self.pattern[self.pos..].starts_with(b"?:") {
+                    self.pos += 2;
+                    None
+                } else {
+                    self.num_groups += 1;
+                    if self.num_groups > MAX_GROUPS {
+                        return Err(self.error(start, "too many capture groups"));
+                    }
+                    Some(self.num_groups - 1)
+                };
+                let node = self.parse_alt()?;
+                if !self.eat(b')') {
+                    return Err(self.error(start, "unclosed group"));
+                }
+                Ok(match group {
+                    Some(group) => Node::Group(group, Box::new(node)),
+                    None => node,
+                })
+            }
+            b'[' => self.parse_class(start),
+            b'.' => Ok(Node::Class(Box::new(negate(&byte_set([b'\n']))))),
+            b'\\' => Ok(Node::Class(Box::new(self.parse_escape(start)?))),
+            b'*' | b'+' | b'?' | b'{' => Err(self.error(start, "nothing to repeat")),
+            b'^' | b'$' => {
+                Err(self.error(start, "anchors are not supported, matches are always anchored"))
+            }
+            _ => Ok(Node::Class(Box::new(byte_set([byte])))),
+        }
+    }
+
+    /// Parses the escape sequence after a `\` at `start`.
+    fn parse_escape(&mut self, start: usize) -> Result<ByteSet, RegexError> {
+        let byte = self.next_byte(start, "incomplete escape")?;
+        let digits = byte_set(b'0'..=b'9');
+        let word = byte_set((b'0'..=b'9').chain(b'a'..=b'z').chain(b'A'..=b'Z').chain([b'_']));
+        let space = byte_set(*b" \t\n\r\x0b\x0c");
+        Ok(match byte {
+            b'd' => digits,
+            b'D' => negate(&digits),
+            b'w' => word,
+            b'W' => negate(&word),
+            b's' => space,
+            b'S' => negate(&space),
+            b'n' => byte_set([b'\n']),
+            b'r' => byte_set([b'\r']),
+            b't' => byte_set([b'\t']),
+            b'x' => {
+                let hex = self
+                    .pattern
+                    .get(self.pos..self.pos + 2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok());
+                let byte = hex
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| self.error(start, "invalid hex escape"))?;
+                self.pos += 2;
+                byte_set([byte])
+            }
+            _ if byte.is_ascii_alphanumeric() || !byte.is_ascii() => {
+                return Err(self.error(start, "unknown escape"))
+            }
+            _ => byte_set([byte]),
+        })
+    }
+
+    /// Parses a bracketed class after the `[` at `start`.
+    fn parse_class(&mut self, start: usize) -> Result<Node, RegexError> {
+        let negated = self.eat(b'^');
+        let mut set = [false; 256];
+        let mut first = true;
+        loop {
+            let item_start = self.pos;
+            let byte = self.next_byte(start, "unclosed class")?;
+            if byte == b']' && !first {
+                break;
+            }
+            first = false;
+            let item = self.parse_class_item(item_start, byte)?;
+            let is_range =
+                self.peek() == Some(b'-') && self.pattern.get(self.pos + 1) != Some(&b']');
+            if !is_range {
+                set.iter_mut().zip(item).for_each(|(b, i)| *b |= i);
+                continue;
+            }
+            self.pos += 1;
+            let end_start = self.pos;
+            let byte = self.next_byte(start, "unclosed class")?;
+            let end = self.parse_class_item(end_start, byte)?;
+            let (lo, hi) = single_byte(&item)
+                .zip(single_byte(&end))
+                .filter(|(lo, hi)| lo <= hi)
+                .ok_or_else(|| self.error(item_start, "invalid class range"))?;
+            (lo..=hi).for_each(|b| set[b as usize] = true);
+        }
+        Ok(Node::Class(Box::new(if negated { negate(&set) } else { set })))
+    }
+
+    /// Parses the class item starting with `byte` at `start`.
+    fn parse_class_item(&mut self, start: usize, byte: u8) -> Result<ByteSet, RegexError> {
+        match byte {
+            b'\\' => self.parse_escape(start),
+            _ if !byte.is_ascii() => Err(self.error(start, "non-ASCII byte in class, use \\xHH")),
+            _ => Ok(byte_set([byte])),
+        }
+    }
+}
+
+/// First and last positions of the language of a node, and whether it contains the empty string.
+struct Positions {
+    first: BTreeSet<usize>,
+    last: BTreeSet<usize>,
+    nullable: bool,
+}
+
+/// Glushkov automaton: an NFA without epsilon transitions, with a state per byte class of the pattern, entered
+/// when a byte of that class is read.
+struct Glushkov {
+    /// Byte class of each state, empty for the start state 0.
+    classes: Vec<ByteSet>,
+    /// Bit mask of the capture groups containing each state.
+    tags: Vec<u64>,
+    /// States which can follow each state.
+    follow: Vec<BTreeSet<usize>>,
+}
+
+impl Glushkov {
+    fn add_follow(&mut self, from: &BTreeSet<usize>, to: &BTreeSet<usize>) {
+        for state in from {
+            self.follow[*state].extend(to);
+        }
+    }
+
+    fn linearize(&mut self, node: &Node, tags: u64) -> Positions {
+        match node {
+            Node::Class(class) => {
+                let state = self.classes.len();
+                self.classes.push(**class);
+                self.tags.push(tags);
+                self.follow.push(BTreeSet::new());
+                let states = BTreeSet::from([state]);
+                Positions { first: states.clone(), last: states, nullable: false }
+            }
+            Node::Concat(items) => {
+                let mut acc =
+                    Positions { first: BTreeSet::new(), last: BTreeSet::new(), nullable: true };
+                for item in items {
+                    let next = self.linearize(item, tags);
+                    self.add_follow(&acc.last, &next.first);
+                    if acc.nullable {
+                        acc.first.extend(&next.first);
+                    }
+                    acc.last = if next.nullable { &acc.last | &next.last } else { next.last };
+                    acc.nullable &= next.nullable;
+                }
+                acc
+            }
+            Node::Alt(branches) => {
+                let mut acc =
+                    Positions { first: BTreeSet::new(), last: BTreeSet::new(), nullable: false };
+                for branch in branches {
+                    let next = self.linearize(branch, tags);
+                    acc.first.extend(next.first);
+                    acc.last.extend(next.last);
+                    acc.nullable |= next.nullable;
+                }
+                acc
+            }
+            Node::Star(node) => {
+                let inner = self.linearize(node, tags);
+                self.add_follow(&inner.last, &inner.first);
+                Positions { nullable: true, ..inner }
+            }
+            Node::Group(group, node) => self.linearize(node, tags | (1 << group)),
+        }
+    }
+}
+
+/// A regular expression over bytes, compiled natively into a DFA deciding matches and a Glushkov NFA locating the
+/// capture groups, see [super::RegexChip].
+#[derive(Clone, Debug)]
+pub struct Regex {
+    num_groups: usize,
+    /// `transitions[s][b]` is the DFA state after reading `b` in state `s`; the start state is 0.
+    pub(super) transitions: Vec<[usize; 256]>,
+    /// Whether each DFA state is accepting.
+    pub(super) accepting: Vec<bool>,
+    /// Byte class of each NFA state, where the start state is 0.
+    pub(super) classes: Vec<ByteSet>,
+    /// Bit mask of the capture groups containing each NFA state.
+    pub(super) tags: Vec<u64>,
+    /// NFA states which can follow each NFA state.
+    pub(super) follow: Vec<Vec<usize>>,
+    /// Whether each NFA state is accepting.
+    pub(super) is_last: Vec<bool>,
+}
+
+impl Regex {
+    /// Compiles `pattern`. Matches are anchored at both ends: the whole input must match.
+    ///
+    /// The syntax is a subset of the usual one, over bytes:
+    /// * literal bytes, and `.` for any byte except `\n`;
+    /// * classes `[a-z_]` and negated classes `[^...]`;
+    /// * escapes `\d`, `\w`, `\s` and their negations `\D`, `\W`, `\S`, `\n`, `\r`, `\t`, `\xHH`, and `\` before
+    ///   any other ASCII punctuation for the literal byte;
+    /// * concatenation, alternation `|`, capture groups `(...)` and non-capturing groups `(?:...)`;
+    /// * repetitions `*`, `+`, `?`, `{m}`, `{m,}` and `{m,n}`.
+    ///
+    /// Capture groups are numbered by their opening parenthesis, from 0.
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser { pattern: pattern.as_bytes(), pos: 0, num_groups: 0 };
+        let node = parser.parse_alt()?;
+        if parser.pos < pattern.len() {
+            return Err(parser.error(parser.pos, "unmatched closing parenthesis"));
+        }
+
+        let mut nfa =
+            Glushkov { classes: vec![[false; 256]], tags: vec![0], follow: vec![BTreeSet::new()] };
+        let positions = nfa.linearize(&node, 0);
+        nfa.follow[0] = positions.first;
+        let mut is_last = vec![false; nfa.classes.len()];
+        is_last[0] = positions.nullable;
+        positions.last.into_iter().for_each(|state| is_last[state] = true);
+        let follow = nfa.follow.into_iter().map(|states| states.into_iter().collect()).collect();
+
+        let mut regex = Self {
+            num_groups: parser.num_groups,
+            transitions: vec![],
+            accepting: vec![],
+            classes: nfa.classes,
+            tags: nfa.tags,
+            follow,
+            is_last,
+        };
+        // subset construction, including the empty set as the dead state
+        let mut sets = vec![vec![0]];
+        let mut ids = HashMap::from([(vec![0], 0)]);
+        while let Some(set) = sets.get(regex.transitions.len()).cloned() {
+            let mut transitions = [0; 256];
+            for (byte, transition) in transitions.iter_mut().enumerate() {
+                let next = regex.step(&set, byte as u8);
+                *transition = *ids.entry(next.clone()).or_insert_with(|| {
+                    sets.push(next);
+                    sets.len() - 1
+                });
+            }
+            if sets.len() > MAX_DFA_STATES {
+                return Err(parser.error(0, "too many DFA states"));
+            }
+            regex.transitions.push(transitions);
+            regex.accepting.push(set.iter().any(|state| regex.is_last[*state]));
+        }
+        Ok(regex)
+    }
+
+    /// Returns the number of capture groups.
+    pub fn num_groups(&self) -> usize {
+        self.num_groups
+    }
+
+    /// Returns the number of states of the DFA, including the dead state.
+    pub fn num_dfa_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    /// Returns the NFA states reached from `states` by reading `byte`.
+    fn step(&self, states: &[usize], byte: u8) -> Vec<usize> {
+        let next = states
+            .iter()
+            .flat_map(|state| &self.follow[*state])
+            .filter(|next| self.classes[**next][byte as usize])
+            .copied()
+            .collect::<BTreeSet<_>>();
+        next.into_iter().collect()
+    }
+
+    /// Returns whether `bytes` matches.
+    pub fn is_match(&self, bytes: &[u8]) -> bool {
+        let state = bytes.iter().fold(0, |state, byte| self.transitions[state][*byte as usize]);
+        self.accepting[state]
+    }
+
+    /// Returns the NFA states after reading each byte on an accepting path, or [None] if `bytes` does not match.
+    ///
+    /// If there are several accepting paths, returns the one with the smallest states from the end.
+    pub(super) fn accepting_path(&self, bytes: &[u8]) -> Option<Vec<usize>> {
+        let mut sets = vec![vec![0]];
+        for byte in bytes {
+            sets.push(self.step(sets.last().unwrap(), *byte));
+        }
+        let mut state = *sets.last().unwrap().iter().find(|state| self.is_last[**state])?;
+        let mut path = Vec::with_capacity(bytes.len());
+        for set in sets[..bytes.len()].iter().rev() {
+            path.push(state);
+            state = *set.iter().find(|prev| self.follow[**prev].contains(&state)).unwrap();
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns, for each capture group, whether each byte is captured by it on an accepting path, or [None] if
+    /// `bytes` does not match. See [Self::accepting_path] for ambiguous patterns.
+    pub fn captures(&self, bytes: &[u8]) -> Option<Vec<Vec<bool>>> {
+        let path = self.accepting_path(bytes)?;
+        let captures = (0..self.num_groups)
+            .map(|group| path.iter().map(|state| (self.tags[*state] >> group) & 1 == 1).collect())
+            .collect();
+        Some(captures)
+    }
+}
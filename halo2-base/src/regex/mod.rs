@@ -0,0 +1,217 @@
+use crate::{
+    array::ArrayChip,
+    gates::{
+        builder::GateThreadBuilder, range::LookupTableId, GateChip, GateInstructions, RangeChip,
+    },
+    safe_types::SafeUint8,
+    utils::ScalarField,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+
+mod compile;
+
+#[cfg(test)]
+pub mod tests;
+
+pub use compile::{Regex, RegexError, MAX_DFA_STATES, MAX_GROUPS};
+
+/// Result of matching bytes against a [Regex], see [RegexChip::match_bytes].
+#[derive(Clone, Debug)]
+pub struct RegexMatch<F: ScalarField> {
+    /// 1 if the bytes match, otherwise 0.
+    pub is_match: AssignedValue<F>,
+    /// For each capture group, 1 for each byte captured by the group, otherwise 0. All zeros if there is no match.
+    pub captures: Vec<Vec<AssignedValue<F>>>,
+}
+
+/// Chip proving that byte strings match a [Regex], and locating its capture groups.
+///
+/// The DFA of the regex is registered as a lookup table of rows `(state, byte, next_state, next_is_accepting)`,
+/// with `256` rows per state. The match flag is read off the DFA traversal, so it is sound in both directions.
+///
+/// Capture groups are located with the Glushkov NFA of the regex, registered as a second lookup table of rows
+/// `(state, byte, next_state, groups, next_is_accepting)`, where `groups` is the bit mask of the capture groups
+/// containing `next_state`, with a row per byte of the class of `next_state`. When the bytes match, the prover
+/// witnesses an accepting path of the NFA, and a byte is captured by a group if the state entered by reading it is in
+/// the group. If the regex is ambiguous, the captures are those of any parse chosen by the prover, see
+/// [Regex::captures] for the parse used for witness generation. This table is not registered for a regex without
+/// capture groups.
+///
+/// Both tables must fit in the lookup columns, which have `2^k` rows, see [Self::new].
+#[derive(Clone, Debug)]
+pub struct RegexChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+    regex: &'a Regex,
+    dfa_table: LookupTableId,
+    /// Id of the NFA table and a row of it, which is looked up in place of disabled lookups.
+    nfa_table: Option<(LookupTableId, [F; 5])>,
+}
+
+impl<'a, F: ScalarField> RegexChip<'a, F> {
+    /// Construct a RegexChip, registering the lookup tables of `regex` in `builder` for a circuit of `2^k` rows.
+    ///
+    /// Returns an error without registering any table if a table does not fit in `2^k` rows together with the padding
+    /// row of the lookup columns; the blinding rows are only checked when the tables are loaded.
+    ///
+    /// Tables must be registered in the same order for keygen and proving.
+    pub fn new(
+        range: &'a RangeChip<F>,
+        regex: &'a Regex,
+        builder: &mut GateThreadBuilder<F>,
+        k: usize,
+    ) -> Result<Self, RegexError> {
+        if regex.num_dfa_states() * 256 >= 1 << k {
+            return Err(RegexError { offset: 0, message: "DFA table too large for 2^k rows" });
+        }
+        let dfa_rows = regex.transitions.iter().enumerate().flat_map(|(state, transitions)| {
+            transitions.iter().enumerate().map(move |(byte, next)| {
+                [state, byte, *next, regex.accepting[*next] as usize].map(|x| F::from(x as u64))
+            })
+        });
+        let nfa_rows = regex
+            .follow
+            .iter()
+            .enumerate()
+            .flat_map(|(state, follow)| {
+                follow.iter().flat_map(move |next| {
+                    let [tags, is_last] = [regex.tags[*next], regex.is_last[*next] as u64];
+                    (0..256).filter(move |byte| regex.classes[*next][*byte]).map(move |byte| {
+                        [state as u64, byte as u64, *next as u64, tags, is_last].map(F::from)
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        if regex.num_groups() > 0 && nfa_rows.len() >= 1 << k {
+            return Err(RegexError { offset: 0, message: "NFA table too large for 2^k rows" });
+        }
+        let dfa_table = builder.register_tuple_lookup_table(dfa_rows);
+        // without transitions, the NFA only matches the empty string, which captures nothing
+        let nfa_table = (regex.num_groups() > 0 && !nfa_rows.is_empty()).then(|| {
+            let dummy = nfa_rows[0];
+            (builder.register_tuple_lookup_table(nfa_rows), dummy)
+        });
+        Ok(Self { range, regex, dfa_table, nfa_table })
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Returns the compiled regex.
+    pub fn regex(&self) -> &Regex {
+        self.regex
+    }
+}
+
+impl<'a, F: ScalarField> RegexChip<'a, F>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// Constrains and returns whether the first `len` bytes of `bytes` match the regex, with the masks of the
+    /// capture groups. Constrains `len <= bytes.len()`; the bytes from `len` on are ignored.
+    ///
+    /// Costs a lookup and O(1) cells per byte for the match, plus a lookup and O(number of capture groups) cells per
+    /// byte for the captures.
+    pub fn match_bytes(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[SafeUint8<F>],
+        len: AssignedValue<F>,
+    ) -> RegexMatch<F> {
+        let gate = self.gate();
+        let regex = self.regex;
+        let mask = ArrayChip::new(self.range).prefix_mask(ctx, len, bytes.len());
+        let bytes = bytes.iter().map(|byte| byte.value()[0]).collect::<Vec<_>>();
+
+        let mut state = ctx.load_zero();
+        let mut is_match = ctx.load_constant(F::from(regex.accepting[0] as u64));
+        for (byte, in_range) in bytes.iter().zip(&mask) {
+            let current = state.value().get_lower_32() as usize;
+            let next = regex.transitions[current][byte.value().get_lower_32() as usize];
+            let [next_state, next_is_match] =
+                [next as u64, regex.accepting[next] as u64].map(|x| ctx.load_witness(F::from(x)));
+            ctx.lookup_tuple(self.dfa_table, [state, *byte, next_state, next_is_match]);
+            // the state is unchanged by the bytes from `len` on
+            state = gate.select(ctx, next_state, state, *in_range);
+            is_match = gate.select(ctx, next_is_match, is_match, *in_range);
+        }
+
+        let captures = match self.nfa_table {
+            Some((table, dummy)) => {
+                self.constrain_captures(ctx, table, dummy, &bytes, &mask, is_match)
+            }
+            None => vec![vec![ctx.load_zero(); bytes.len()]; regex.num_groups()],
+        };
+        RegexMatch { is_match, captures }
+    }
+
+    /// Constrains and returns the capture masks on an accepting path of the NFA, witnessed if `is_match`.
+    ///
+    /// The row `dummy` of the NFA table is looked up in place of the path where it is not constrained.
+    fn constrain_captures(
+        &self,
+        ctx: &mut Context<F>,
+        table: LookupTableId,
+        dummy: [F; 5],
+        bytes: &[AssignedValue<F>],
+        mask: &[AssignedValue<F>],
+        is_match: AssignedValue<F>,
+    ) -> Vec<Vec<AssignedValue<F>>> {
+        let gate = self.gate();
+        let regex = self.regex;
+        let len = mask.iter().filter(|bit| *bit.value() == F::one()).count();
+        let path = if *is_match.value() == F::one() {
+            let bytes = bytes[..len]
+                .iter()
+                .map(|byte| byte.value().get_lower_32() as u8)
+                .collect::<Vec<_>>();
+            regex.accepting_path(&bytes)
+        } else {
+            None
+        };
+
+        let mut captures = vec![Vec::with_capacity(bytes.len()); regex.num_groups()];
+        let mut state = Constant(F::zero());
+        let mut is_last = ctx.load_constant(F::from(regex.is_last[0] as u64));
+        for (i, (byte, in_range)) in bytes.iter().zip(mask).enumerate() {
+            let row = match path.as_ref().and_then(|path| path.get(i)) {
+                Some(next) => [*next as u64, regex.tags[*next], regex.is_last[*next] as u64],
+                None => [0; 3],
+            };
+            let [next_state, tags, next_is_last] = row.map(|x| ctx.load_witness(F::from(x)));
+            // the path is only constrained on the first `len` bytes if there is a match, and the dummy row is
+            // looked up otherwise
+            let enabled = gate.mul(ctx, is_match, *in_range);
+            let tuple = [
+                state,
+                Existing(*byte),
+                Existing(next_state),
+                Existing(tags),
+                Existing(next_is_last),
+            ];
+            let mut dummy = dummy.into_iter();
+            let tuple =
+                tuple.map(|x| gate.select(ctx, x, Constant(dummy.next().unwrap()), enabled));
+            ctx.lookup_tuple(table, tuple);
+
+            let groups = gate.num_to_bits(ctx, tags, regex.num_groups());
+            for (capture, bit) in captures.iter_mut().zip(groups) {
+                capture.push(gate.mul(ctx, enabled, bit));
+            }
+            is_last = gate.select(ctx, next_is_last, is_last, *in_range);
+            state = Existing(next_state);
+        }
+        // a matching path must end in an accepting state
+        let not_last = gate.not(ctx, is_last);
+        let is_invalid = gate.mul(ctx, is_match, not_last);
+        gate.assert_is_const(ctx, &is_invalid, &F::zero());
+        captures
+    }
+}
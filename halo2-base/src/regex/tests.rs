@@ -0,0 +1,212 @@
+use super::*;
+use crate::{
+    gates::builder::RangeCircuitBuilder, halo2_proofs::halo2curves::bn256::Fr,
+    safe_types::SafeTypeChip, utils::testing::mock_verify,
+};
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 13;
+const LOOKUP_BITS: usize = 8;
+const EMAIL: &str = r"from:([a-z]+)@(\w+)\.com";
+
+#[test_case(r"a(b|c)*d", "abcbd", true; "alternation and star")]
+#[test_case(r"a(b|c)*d", "abxd", false; "alternation mismatch")]
+#[test_case(r"[a-z]+@[a-z]+\.com", "alice@example.com", true; "class and plus")]
+#[test_case(r"\d{3}-\d{4}", "555-1234", true; "exact repetition")]
+#[test_case(r"\d{3}-\d{4}", "55-1234", false; "exact repetition mismatch")]
+#[test_case(r"a{2,3}", "aaaa", false; "bounded repetition too many")]
+#[test_case(r"a{2,}", "aaaa", true; "unbounded repetition")]
+#[test_case(r"x?y", "y", true; "optional")]
+#[test_case(r"(?:ab)+", "ababab", true; "non-capturing group")]
+#[test_case(r"(?:ab)+", "", false; "plus empty")]
+#[test_case(r"a*", "", true; "star empty")]
+#[test_case(r"a|", "", true; "empty branch")]
+#[test_case(r"[^a]", "b", true; "negated class")]
+#[test_case(r"[^a]", "a", false; "negated class mismatch")]
+#[test_case(r"a.c", "a\nc", false; "dot newline")]
+#[test_case(r"[]a-]+", "]a-", true; "class literals")]
+#[test_case(r"\x41\.\s", "A. ", true; "escapes")]
+#[test_case(r"abc", "abcd", false; "anchored")]
+fn test_native_is_match(pattern: &str, input: &str, expected: bool) {
+    assert_eq!(Regex::new(pattern).unwrap().is_match(input.as_bytes()), expected);
+}
+
+#[test_case(r"(a", 0, "unclosed group")]
+#[test_case(r"a)", 1, "unmatched closing parenthesis")]
+#[test_case(r"*a", 0, "nothing to repeat")]
+#[test_case(r"[a-", 0, "unclosed class")]
+#[test_case(r"[z-a]", 1, "invalid class range")]
+#[test_case(r"a{3,2}", 1, "invalid repetition bounds")]
+#[test_case(r"a{1000}", 1, "repetition count too large")]
+#[test_case(r"\q", 0, "unknown escape")]
+#[test_case(r"\xg0", 0, "invalid hex escape")]
+#[test_case(r"^a", 0, "anchors are not supported, matches are always anchored")]
+fn test_native_error(pattern: &str, offset: usize, message: &'static str) {
+    assert_eq!(Regex::new(pattern).unwrap_err(), RegexError { offset, message });
+}
+
+#[test]
+fn test_native_captures() {
+    let regex = Regex::new(EMAIL).unwrap();
+    assert_eq!(regex.num_groups(), 2);
+    let input = b"from:bob@mail.com";
+    let captures = regex.captures(input).unwrap();
+    let captured = |group: usize| -> Vec<u8> {
+        input.iter().zip(&captures[group]).filter(|(_, bit)| **bit).map(|(byte, _)| *byte).collect()
+    };
+    assert_eq!(captured(0), b"bob");
+    assert_eq!(captured(1), b"mail");
+    assert!(regex.captures(b"from:bob@mail.org").is_none());
+
+    // each byte is captured by exactly one group in any parse
+    let captures = Regex::new(r"(a*)(a*)").unwrap().captures(b"aaa").unwrap();
+    assert!((0..3).all(|i| captures[0][i] != captures[1][i]));
+}
+
+/// Matches the first `len` bytes of `input`, padded with `#` to `max_len` bytes, and returns the match flag, the
+/// captures and whether the circuit is satisfied.
+fn mock_match(
+    pattern: &str,
+    input: &[u8],
+    len: usize,
+    max_len: usize,
+) -> (bool, Vec<Vec<bool>>, bool) {
+    mock_match_forged(pattern, input, len, max_len, |_| {})
+}
+
+/// Like [mock_match], but the witnesses are generated by a malicious prover from the regex modified by `forge`,
+/// while the lookup tables are those of the honest regex.
+fn mock_match_forged(
+    pattern: &str,
+    input: &[u8],
+    len: usize,
+    max_len: usize,
+    forge: impl FnOnce(&mut Regex),
+) -> (bool, Vec<Vec<bool>>, bool) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let regex = Regex::new(pattern).unwrap();
+    let mut forged = regex.clone();
+    forge(&mut forged);
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = RegexChip::new(&range, &regex, &mut builder, K).unwrap();
+    let chip = RegexChip { regex: &forged, ..chip };
+    let ctx = builder.main(0);
+    let safe = SafeTypeChip::new(&range);
+    let bytes = input
+        .iter()
+        .chain(std::iter::repeat(&b'#'))
+        .take(max_len)
+        .map(|byte| {
+            let byte = ctx.load_witness(Fr::from(*byte as u64));
+            safe.raw_bytes_to(ctx, vec![byte])
+        })
+        .collect::<Vec<SafeUint8<Fr>>>();
+    let len = ctx.load_witness(Fr::from(len as u64));
+    let out = chip.match_bytes(ctx, &bytes, len);
+    let is_match = *out.is_match.value() == Fr::one();
+    let captures = out
+        .captures
+        .iter()
+        .map(|capture| capture.iter().map(|bit| *bit.value() == Fr::one()).collect())
+        .collect();
+    (is_match, captures, mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok())
+}
+
+#[test]
+fn test_match_captures() {
+    let input = b"from:bob@mail.com";
+    let (is_match, captures, verified) = mock_match(EMAIL, input, input.len(), 24);
+    assert!(verified);
+    assert!(is_match);
+    let mut expected = Regex::new(EMAIL).unwrap().captures(input).unwrap();
+    expected.iter_mut().for_each(|capture| capture.resize(24, false));
+    assert_eq!(captures, expected);
+}
+
+#[test]
+fn test_no_match() {
+    let input = b"from:bob@mail.org";
+    let (is_match, captures, verified) = mock_match(EMAIL, input, input.len(), 24);
+    assert!(verified);
+    assert!(!is_match);
+    assert!(captures.iter().flatten().all(|bit| !bit));
+}
+
+#[test]
+fn test_match_prefix() {
+    // only the first `len` bytes are matched
+    let (is_match, _, verified) = mock_match(r"\d+", b"123abc", 3, 8);
+    assert!(verified);
+    assert!(is_match);
+    let (is_match, _, verified) = mock_match(r"\d+", b"123abc", 4, 8);
+    assert!(verified);
+    assert!(!is_match);
+}
+
+#[test]
+fn test_match_empty() {
+    let (is_match, captures, verified) = mock_match(r"(a*)b?", b"", 0, 4);
+    assert!(verified);
+    assert!(is_match);
+    assert_eq!(captures, vec![vec![false; 4]]);
+}
+
+#[test]
+fn test_match_too_long() {
+    let (_, _, verified) = mock_match(r"a*", b"aaaa", 5, 4);
+    assert!(!verified);
+}
+
+#[test]
+fn test_tables_too_large() {
+    let regex = Regex::new(EMAIL).unwrap();
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let range = RangeChip::default(LOOKUP_BITS);
+    let k = 8;
+    assert!(regex.num_dfa_states() * 256 >= 1 << k);
+    assert_eq!(
+        RegexChip::new(&range, &regex, &mut builder, k).unwrap_err(),
+        RegexError { offset: 0, message: "DFA table too large for 2^k rows" }
+    );
+    assert!(builder.lookup_tables().is_empty());
+}
+
+#[test]
+fn test_forged_transition() {
+    // reading `x` as if it were `b` makes the DFA accept `abxd`
+    let (is_match, _, verified) = mock_match_forged(r"a(b|c)*d", b"abxd", 4, 4, |regex| {
+        regex
+            .transitions
+            .iter_mut()
+            .for_each(|transitions| transitions[b'x' as usize] = transitions[b'b' as usize]);
+    });
+    assert!(is_match);
+    assert!(!verified);
+}
+
+#[test]
+fn test_forged_is_match() {
+    let input = b"from:bob@mail.org";
+    let (is_match, _, verified) = mock_match_forged(EMAIL, input, input.len(), 24, |regex| {
+        regex.accepting.iter_mut().for_each(|accepting| *accepting = true);
+    });
+    assert!(is_match);
+    assert!(!verified);
+}
+
+#[test]
+fn test_forged_captures() {
+    // swapping the two capture groups, the DFA is unchanged and the bytes still match
+    let input = b"from:bob@mail.com";
+    let (is_match, captures, verified) =
+        mock_match_forged(EMAIL, input, input.len(), 24, |regex| {
+            regex.tags.iter_mut().for_each(|tags| *tags = (*tags & 1) << 1 | (*tags >> 1) & 1);
+        });
+    assert!(is_match);
+    let mut expected = Regex::new(EMAIL).unwrap().captures(input).unwrap();
+    expected.iter_mut().for_each(|capture| capture.resize(24, false));
+    assert_eq!(captures, vec![expected[1].clone(), expected[0].clone()]);
+    assert!(!verified);
+}
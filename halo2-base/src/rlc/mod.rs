@@ -0,0 +1,269 @@
+use crate::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{bit_length, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::{self, Constant, Existing},
+};
+use std::iter;
+
+#[cfg(test)]
+pub mod tests;
+
+/// Random linear combination of a variable length array, see [RlcChip::compute_rlc].
+#[derive(Clone, Copy, Debug)]
+pub struct RlcTrace<F: ScalarField> {
+    /// The RLC of the first `len` values of the array.
+    pub rlc_val: AssignedValue<F>,
+    /// The length of the array.
+    pub len: AssignedValue<F>,
+    /// The maximum length of the array, fixed at keygen.
+    pub max_len: usize,
+}
+
+/// Chip for random linear combinations (RLC) of arrays with a challenge `gamma`.
+///
+/// The RLC of `[a_0, ..., a_(n - 1)]` is `sum_i a_i * gamma^(n - 1 - i)`, i.e. the evaluation by Horner's rule, which
+/// is how `KeccakTable::input_rlc` accumulates the input bytes of a Keccak256 hash. Then the RLC of a concatenation
+/// `a || b` is `rlc(a) * gamma^len(b) + rlc(b)`.
+///
+/// `gamma` must be a challenge generated after the arrays were assigned, e.g. the one passed to phase 1 callbacks of
+/// [GateThreadBuilder](crate::gates::builder::GateThreadBuilder::register_phase1_callback), and the chip should be
+/// used in a [Context] of that phase. Two different arrays of length at most `n` then have the same RLC with
+/// probability at most `n / |F|`.
+#[derive(Clone, Debug)]
+pub struct RlcChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+    gamma: AssignedValue<F>,
+    /// `gamma^(2^i)` for `i` in `0..gamma_pow_cached.len()`
+    gamma_pow_cached: Vec<AssignedValue<F>>,
+}
+
+impl<'a, F: ScalarField> RlcChip<'a, F> {
+    /// Construct a RlcChip with the challenge `gamma`.
+    pub fn new(range: &'a RangeChip<F>, gamma: AssignedValue<F>) -> Self {
+        Self { range, gamma, gamma_pow_cached: vec![gamma] }
+    }
+
+    /// Returns the challenge `gamma`.
+    pub fn gamma(&self) -> &AssignedValue<F> {
+        &self.gamma
+    }
+
+    /// Returns the cached powers `gamma^(2^i)`, see [Self::load_rlc_cache].
+    pub fn gamma_pow_cached(&self) -> &[AssignedValue<F>] {
+        &self.gamma_pow_cached
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Constrains and caches `gamma^(2^i)` for `i` in `0..cache_bits`, by repeated squaring.
+    ///
+    /// Must be called before [Self::rlc_pow] and the methods of variable length arrays of length less than
+    /// `2^cache_bits`. Costs one `mul` per new power.
+    pub fn load_rlc_cache(&mut self, ctx: &mut Context<F>, cache_bits: usize) {
+        while self.gamma_pow_cached.len() < cache_bits {
+            let last = *self.gamma_pow_cached.last().unwrap();
+            let next = self.gate().mul(ctx, last, last);
+            self.gamma_pow_cached.push(next);
+        }
+    }
+
+    /// Constrains and returns `gamma^pow`, from the bits of `pow` and the cached powers of `gamma`.
+    ///
+    /// Constrains `pow < 2^pow_bits`. Assumes `0 < pow_bits <= cache_bits`, see [Self::load_rlc_cache]. Costs
+    /// `pow_bits` bits, `select`s and `mul`s.
+    pub fn rlc_pow(
+        &self,
+        ctx: &mut Context<F>,
+        pow: AssignedValue<F>,
+        pow_bits: usize,
+    ) -> AssignedValue<F> {
+        assert!(
+            pow_bits <= self.gamma_pow_cached.len(),
+            "powers of gamma are not cached, call `load_rlc_cache` first"
+        );
+        let gate = self.gate();
+        let bits = gate.num_to_bits(ctx, pow, pow_bits);
+        let mut acc = ctx.load_constant(F::one());
+        for (bit, gamma_pow) in bits.into_iter().zip(&self.gamma_pow_cached) {
+            let factor = gate.select(ctx, *gamma_pow, Constant(F::one()), bit);
+            acc = gate.mul(ctx, acc, factor);
+        }
+        acc
+    }
+
+    /// Constrains and returns `gamma^pow` for a constant `pow`, from the cached powers of `gamma`.
+    ///
+    /// Assumes `pow < 2^cache_bits`, see [Self::load_rlc_cache].
+    pub fn rlc_pow_fixed(&self, ctx: &mut Context<F>, pow: usize) -> AssignedValue<F> {
+        let pow_bits = bit_length(pow as u64);
+        assert!(
+            pow_bits <= self.gamma_pow_cached.len(),
+            "powers of gamma are not cached, call `load_rlc_cache` first"
+        );
+        let mut acc = ctx.load_constant(F::one());
+        for (i, gamma_pow) in self.gamma_pow_cached[..pow_bits].iter().enumerate() {
+            if (pow >> i) & 1 == 1 {
+                acc = self.gate().mul(ctx, acc, *gamma_pow);
+            }
+        }
+        acc
+    }
+
+    /// Constrains and returns the RLCs of all prefixes of `values`, i.e. `[rlc(values[..1]), ..., rlc(values)]`.
+    ///
+    /// Costs one `mul_add` per value.
+    pub fn rlc_prefixes<Q>(
+        &self,
+        ctx: &mut Context<F>,
+        values: impl IntoIterator<Item = Q>,
+    ) -> Vec<AssignedValue<F>>
+    where
+        Q: Into<QuantumCell<F>>,
+    {
+        let mut prev = Constant(F::zero());
+        values
+            .into_iter()
+            .map(|value| {
+                let rlc = self.gate().mul_add(ctx, prev, self.gamma, value);
+                prev = Existing(rlc);
+                rlc
+            })
+            .collect()
+    }
+
+    /// Constrains and returns the RLC of the first `len` elements of `values`.
+    ///
+    /// Constrains `len <= values.len()`. The values after the first `len` may be arbitrary. Costs O(`values.len()`)
+    /// cells, for [Self::rlc_prefixes] and the selection of the prefix of length `len`.
+    pub fn compute_rlc<Q>(
+        &self,
+        ctx: &mut Context<F>,
+        values: impl IntoIterator<Item = Q>,
+        len: AssignedValue<F>,
+    ) -> RlcTrace<F>
+    where
+        Q: Into<QuantumCell<F>>,
+    {
+        let prefixes = self.rlc_prefixes(ctx, values);
+        let max_len = prefixes.len();
+        self.range.check_less_than_safe(ctx, len, max_len as u64 + 1);
+        let rlc_val = self.select_prefix(ctx, &prefixes, len);
+        RlcTrace { rlc_val, len, max_len }
+    }
+
+    /// Constrains and returns the RLC of all of `values`.
+    ///
+    /// Costs one `mul_add` per value.
+    pub fn compute_rlc_fixed_len<Q>(
+        &self,
+        ctx: &mut Context<F>,
+        values: impl IntoIterator<Item = Q>,
+    ) -> RlcTrace<F>
+    where
+        Q: Into<QuantumCell<F>>,
+    {
+        let prefixes = self.rlc_prefixes(ctx, values);
+        let max_len = prefixes.len();
+        let rlc_val = match prefixes.last() {
+            Some(rlc) => *rlc,
+            None => ctx.load_zero(),
+        };
+        let len = ctx.load_constant(F::from(max_len as u64));
+        RlcTrace { rlc_val, len, max_len }
+    }
+
+    /// Constrains and returns the RLC of the concatenation of the arrays of `inputs`.
+    ///
+    /// The length of the concatenation is the sum of the lengths of `inputs`, and its maximum length the sum of their
+    /// maximum lengths. Assumes `inputs` is nonempty and every maximum length is less than `2^cache_bits`, see
+    /// [Self::load_rlc_cache]. Costs a [Self::rlc_pow] per input after the first.
+    pub fn rlc_concat(&self, ctx: &mut Context<F>, inputs: &[RlcTrace<F>]) -> RlcTrace<F> {
+        let (first, rest) = inputs.split_first().expect("concatenation of no arrays");
+        let gate = self.gate();
+        rest.iter().fold(*first, |acc, input| {
+            let gamma_pow = self.rlc_pow(ctx, input.len, bit_length(input.max_len as u64).max(1));
+            RlcTrace {
+                rlc_val: gate.mul_add(ctx, acc.rlc_val, gamma_pow, input.rlc_val),
+                len: gate.add(ctx, acc.len, input.len),
+                max_len: acc.max_len + input.max_len,
+            }
+        })
+    }
+
+    /// Constrains `concat` to be the concatenation of the arrays of `inputs`, i.e. to have the RLC and length of
+    /// [Self::rlc_concat] of `inputs`.
+    pub fn constrain_rlc_concat(
+        &self,
+        ctx: &mut Context<F>,
+        inputs: &[RlcTrace<F>],
+        concat: &RlcTrace<F>,
+    ) {
+        let expected = self.rlc_concat(ctx, inputs);
+        self.constrain_equal(ctx, &expected, concat);
+    }
+
+    /// Constrains and returns whether `a` and `b` are RLCs of the same array, i.e. have equal RLCs and lengths.
+    pub fn is_equal(
+        &self,
+        ctx: &mut Context<F>,
+        a: &RlcTrace<F>,
+        b: &RlcTrace<F>,
+    ) -> AssignedValue<F> {
+        let gate = self.gate();
+        let rlc_eq = gate.is_equal(ctx, a.rlc_val, b.rlc_val);
+        let len_eq = gate.is_equal(ctx, a.len, b.len);
+        gate.and(ctx, rlc_eq, len_eq)
+    }
+
+    /// Constrains `a` and `b` to be RLCs of the same array, i.e. to have equal RLCs and lengths.
+    pub fn constrain_equal(&self, ctx: &mut Context<F>, a: &RlcTrace<F>, b: &RlcTrace<F>) {
+        ctx.constrain_equal(&a.rlc_val, &b.rlc_val);
+        ctx.constrain_equal(&a.len, &b.len);
+    }
+
+    /// Constrains the array of `sub` to be `values[start..start + sub.len]`.
+    ///
+    /// Constrains `start + sub.len <= values.len()`, using `rlc(values[..start + sub.len]) = rlc(values[..start]) *
+    /// gamma^sub.len + rlc(sub)`. Assumes `values.len()` and `sub.max_len` are less than `2^cache_bits`, see
+    /// [Self::load_rlc_cache]. Costs O(`values.len()`) cells, for [Self::rlc_prefixes] and the selection of two of them.
+    pub fn constrain_sub_array(
+        &self,
+        ctx: &mut Context<F>,
+        values: &[AssignedValue<F>],
+        start: AssignedValue<F>,
+        sub: &RlcTrace<F>,
+    ) {
+        let gate = self.gate();
+        let prefixes = self.rlc_prefixes(ctx, values.iter().copied());
+        let end = gate.add(ctx, start, sub.len);
+        // bounding `start` as well prevents `start + sub.len` from wrapping around the field modulus
+        self.range.check_less_than_safe(ctx, start, values.len() as u64 + 1);
+        self.range.check_less_than_safe(ctx, end, values.len() as u64 + 1);
+        let prefix = self.select_prefix(ctx, &prefixes, start);
+        let prefix_with_sub = self.select_prefix(ctx, &prefixes, end);
+        let gamma_pow = self.rlc_pow(ctx, sub.len, bit_length(sub.max_len as u64).max(1));
+        let expected = gate.mul_add(ctx, prefix, gamma_pow, sub.rlc_val);
+        ctx.constrain_equal(&expected, &prefix_with_sub);
+    }
+
+    /// Constrains and returns the RLC of the prefix of length `len`, given the RLCs `prefixes` of all nonempty
+    /// prefixes. Assumes `len <= prefixes.len()`.
+    fn select_prefix(
+        &self,
+        ctx: &mut Context<F>,
+        prefixes: &[AssignedValue<F>],
+        len: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let prefixes = iter::once(Constant(F::zero())).chain(prefixes.iter().map(|p| Existing(*p)));
+        self.gate().select_from_idx(ctx, prefixes, len)
+    }
+}
@@ -0,0 +1,98 @@
+use super::*;
+use crate::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use ff::Field;
+use std::env::set_var;
+use test_case::test_case;
+
+fn rlc_native(values: &[Fr], gamma: Fr) -> Fr {
+    values.iter().fold(Fr::zero(), |acc, v| acc * gamma + v)
+}
+
+/// Assigns `1..=10` in phase 0 and runs `f` on them in phase 1.
+fn verify_in_phase1(
+    f: impl Fn(&mut Context<Fr>, &RlcChip<Fr>, &[AssignedValue<Fr>]) + Send + Sync + 'static,
+) -> bool {
+    let (k, lookup_bits) = (10, 8);
+    set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let values = builder.main(0).assign_witnesses((1..=10).map(Fr::from));
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(lookup_bits);
+        let mut chip = RlcChip::new(&range, gamma);
+        let ctx = builder.main(1);
+        chip.load_rlc_cache(ctx, 4);
+        f(ctx, &chip, &values);
+    });
+    mock_verify(k, &RangeCircuitBuilder::mock(builder)).is_ok()
+}
+
+#[test_case(0, true; "compute_rlc() empty")]
+#[test_case(4, true; "compute_rlc() prefix")]
+#[test_case(10, true; "compute_rlc() full")]
+#[test_case(11, false; "compute_rlc() too long")]
+fn test_compute_rlc(len: u64, expected: bool) {
+    let verified = verify_in_phase1(move |ctx, chip, values| {
+        let len_assigned = ctx.load_witness(Fr::from(len));
+        let trace = chip.compute_rlc(ctx, values.iter().copied(), len_assigned);
+        if len as usize <= values.len() {
+            let prefix = &values[..len as usize];
+            let native = prefix.iter().map(|v| *v.value()).collect::<Vec<_>>();
+            assert_eq!(*trace.rlc_val.value(), rlc_native(&native, *chip.gamma().value()));
+            let fixed = chip.compute_rlc_fixed_len(ctx, prefix.iter().copied());
+            chip.constrain_equal(ctx, &trace, &fixed);
+        }
+    });
+    assert_eq!(verified, expected);
+}
+
+#[test_case(&[0, 1, 2, 5, 6], true; "rlc_concat() pos")]
+#[test_case(&[0, 1, 2, 3, 5, 6], false; "rlc_concat() wrong length")]
+#[test_case(&[0, 1, 2, 6, 5], false; "rlc_concat() wrong order")]
+fn test_rlc_concat(concat: &'static [usize], expected: bool) {
+    let verified = verify_in_phase1(move |ctx, chip, values| {
+        let [len_a, len_b] = [3, 2].map(|len| ctx.load_witness(Fr::from(len)));
+        let a = chip.compute_rlc(ctx, values[..5].iter().copied(), len_a);
+        let b = chip.compute_rlc(ctx, values[5..].iter().copied(), len_b);
+        let concat = chip.compute_rlc_fixed_len(ctx, concat.iter().map(|i| values[*i]));
+        let computed = chip.rlc_concat(ctx, &[a, b]);
+        assert_eq!(computed.max_len, 10);
+        chip.constrain_rlc_concat(ctx, &[a, b], &concat);
+    });
+    assert_eq!(verified, expected);
+}
+
+#[test_case(2, &[2, 3, 4], true; "constrain_sub_array() pos")]
+#[test_case(0, &[], true; "constrain_sub_array() empty")]
+#[test_case(7, &[7, 8, 9], true; "constrain_sub_array() suffix")]
+#[test_case(2, &[2, 4, 3], false; "constrain_sub_array() wrong values")]
+#[test_case(3, &[2, 3, 4], false; "constrain_sub_array() wrong start")]
+#[test_case(8, &[8, 9, 0], false; "constrain_sub_array() out of range")]
+fn test_constrain_sub_array(start: u64, sub: &'static [usize], expected: bool) {
+    let verified = verify_in_phase1(move |ctx, chip, values| {
+        let start = ctx.load_witness(Fr::from(start));
+        // pad the sub-array to a maximum length of 4
+        let len = ctx.load_witness(Fr::from(sub.len() as u64));
+        let padded = sub.iter().map(|i| values[*i]).chain(values.iter().copied()).take(4);
+        let sub = chip.compute_rlc(ctx, padded, len);
+        chip.constrain_sub_array(ctx, values, start, &sub);
+    });
+    assert_eq!(verified, expected);
+}
+
+#[test]
+fn test_is_equal() {
+    assert!(verify_in_phase1(|ctx, chip, values| {
+        let a = chip.compute_rlc_fixed_len(ctx, values[..3].iter().copied());
+        let b = chip.compute_rlc_fixed_len(ctx, values[1..4].iter().copied());
+        let c = chip.compute_rlc_fixed_len(ctx, values[..4].iter().copied());
+        assert_eq!(*chip.is_equal(ctx, &a, &a).value(), Fr::one());
+        assert_eq!(*chip.is_equal(ctx, &a, &b).value(), Fr::zero());
+        assert_eq!(*chip.is_equal(ctx, &a, &c).value(), Fr::zero());
+        let pow = chip.rlc_pow_fixed(ctx, 9);
+        assert_eq!(*pow.value(), chip.gamma().value().pow_vartime([9]));
+    }));
+}
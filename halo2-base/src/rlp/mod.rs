@@ -0,0 +1,408 @@
+use crate::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    rlc::{RlcChip, RlcTrace},
+    utils::{bit_length, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+use std::iter;
+
+#[cfg(test)]
+pub mod tests;
+
+/// Returns the maximum number of bytes of the length in the RLP encoding of an item with a payload of at most
+/// `max_len` bytes.
+pub fn max_rlp_len_len(max_len: usize) -> usize {
+    if max_len > 55 {
+        (bit_length(max_len as u64) + 7) / 8
+    } else {
+        0
+    }
+}
+
+/// Returns the maximum length of the RLP encoding of an item with a payload of at most `max_len` bytes.
+pub fn max_rlp_encoding_len(max_len: usize) -> usize {
+    1 + max_rlp_len_len(max_len) + max_len
+}
+
+/// Returns the offset and length of the payload of the RLP item at the start of `bytes`, or [None] if it is not a
+/// valid RLP item.
+fn native_rlp_payload(bytes: &[u8]) -> Option<(usize, usize)> {
+    let prefix = *bytes.first()? as usize;
+    let (offset, len) = match prefix {
+        0x00..=0x7f => return Some((0, 1)),
+        0x80..=0xb7 => (1, prefix - 0x80),
+        0xc0..=0xf7 => (1, prefix - 0xc0),
+        _ => {
+            let len_len = if prefix < 0xc0 { prefix - 0xb7 } else { prefix - 0xf7 };
+            let len = bytes
+                .get(1..1 + len_len)?
+                .iter()
+                .try_fold(0usize, |acc, byte| acc.checked_mul(256)?.checked_add(*byte as usize))?;
+            (1 + len_len, len)
+        }
+    };
+    (offset.checked_add(len)? <= bytes.len()).then_some((offset, len))
+}
+
+/// Returns the encodings of the items of the RLP list at the start of `bytes`, or [None] if it is not a valid RLP
+/// list.
+pub fn native_rlp_list_items(bytes: &[u8]) -> Option<Vec<&[u8]>> {
+    if *bytes.first()? < 0xc0 {
+        return None;
+    }
+    let (offset, len) = native_rlp_payload(bytes)?;
+    let mut payload = &bytes[offset..offset + len];
+    let mut items = vec![];
+    while !payload.is_empty() {
+        let (offset, len) = native_rlp_payload(payload)?;
+        let (item, rest) = payload.split_at(offset + len);
+        items.push(item);
+        payload = rest;
+    }
+    Some(items)
+}
+
+/// Decomposition of the RLP encoding of a string (byte array), see [RlpChip::decompose_rlp_field].
+///
+/// The encoding is `prefix || len_cells[..len_len] || field_cells[..field_len]`, where the prefix is omitted for a
+/// single byte less than `0x80`.
+#[derive(Clone, Debug)]
+pub struct RlpFieldTrace<F: ScalarField> {
+    /// The first byte of the encoding.
+    pub prefix: AssignedValue<F>,
+    /// 1 if the encoding has a prefix byte, 0 if the string is a single byte less than `0x80`.
+    pub prefix_len: AssignedValue<F>,
+    /// The number of bytes of the length of the string, nonzero for strings of more than 55 bytes.
+    pub len_len: AssignedValue<F>,
+    /// The big-endian length of the string in the first `len_len` cells, padded to the maximum number of length bytes.
+    pub len_cells: Vec<AssignedValue<F>>,
+    /// The offset of the string in the encoding, i.e. `prefix_len + len_len`.
+    pub field_offset: AssignedValue<F>,
+    /// The length of the string.
+    pub field_len: AssignedValue<F>,
+    /// The string in the first `field_len` cells, padded to `max_field_len` cells.
+    pub field_cells: Vec<AssignedValue<F>>,
+    /// The length of the encoding.
+    pub rlp_len: AssignedValue<F>,
+    /// The maximum length of the string, fixed at keygen.
+    pub max_field_len: usize,
+}
+
+/// Witness of the decomposition of the RLP encoding of a list, see [RlpChip::decompose_rlp_array_phase0].
+///
+/// The encoding is `prefix || len_cells[..len_len] || field_rlps[0] || ... || field_rlps[num_fields - 1]`, which is
+/// only constrained in [RlpChip::decompose_rlp_array_phase1].
+#[derive(Clone, Debug)]
+pub struct RlpArrayTraceWitness<F: ScalarField> {
+    /// The encoding of the list, padded with arbitrary bytes.
+    pub rlp_array: Vec<AssignedValue<F>>,
+    /// The length of the encoding.
+    pub rlp_len: AssignedValue<F>,
+    /// The first byte of the encoding.
+    pub prefix: AssignedValue<F>,
+    /// The number of bytes of the length of the payload, nonzero for payloads of more than 55 bytes.
+    pub len_len: AssignedValue<F>,
+    /// The big-endian length of the payload in the first `len_len` cells, padded to the maximum number of length bytes.
+    pub len_cells: Vec<AssignedValue<F>>,
+    /// The length of the payload, i.e. the concatenated encodings of the items.
+    pub payload_len: AssignedValue<F>,
+    /// The number of items of the list.
+    pub num_fields: AssignedValue<F>,
+    /// The decomposed encodings of the items, where the items from `num_fields` on are arbitrary.
+    pub fields: Vec<RlpFieldTrace<F>>,
+    /// The encodings of the items, padded with arbitrary bytes.
+    pub field_rlps: Vec<Vec<AssignedValue<F>>>,
+    /// The lengths of the encodings of the items, or 0 for the items from `num_fields` on.
+    pub field_rlp_lens: Vec<AssignedValue<F>>,
+    /// The offsets of the encodings of the items in the encoding of the list.
+    pub field_offsets: Vec<AssignedValue<F>>,
+}
+
+/// Decomposition of the RLP encoding of a list with the RLCs of its items, see [RlpChip::decompose_rlp_array_phase1].
+#[derive(Clone, Debug)]
+pub struct RlpArrayTrace<F: ScalarField> {
+    /// The witness of the decomposition, now constrained.
+    pub witness: RlpArrayTraceWitness<F>,
+    /// The RLC of the encoding of the list.
+    pub rlp_rlc: RlcTrace<F>,
+    /// The RLCs of the strings of the items, with length 0 for the items from `num_fields` on.
+    pub field_rlcs: Vec<RlcTrace<F>>,
+}
+
+/// Chip for decoding RLP, the serialization of Ethereum data structures, into byte arrays.
+///
+/// All inputs are arrays of assigned bytes of fixed maximum length; the encoded data may be shorter and followed by
+/// arbitrary bytes. Besides the ranges of the lengths, the prefixes are constrained to be canonical: lengths of more
+/// than 55 bytes have no leading zero and are only used for strings or payloads of more than 55 bytes, and a single
+/// byte less than `0x80` is not prefixed.
+///
+/// Strings are decoded in a single phase with [Self::decompose_rlp_field]. Lists are decoded in two phases: their
+/// items are witnessed and decoded in [Self::decompose_rlp_array_phase0], and the list is constrained to be their
+/// concatenation with random linear combinations in [Self::decompose_rlp_array_phase1].
+#[derive(Clone, Debug)]
+pub struct RlpChip<'a, F: ScalarField> {
+    range: &'a RangeChip<F>,
+}
+
+impl<'a, F: ScalarField> RlpChip<'a, F> {
+    /// Construct a RlpChip.
+    pub fn new(range: &'a RangeChip<F>) -> Self {
+        Self { range }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Constrains and returns the decomposition of the RLP encoding of a string of at most `max_field_len` bytes at
+    /// the start of `rlp`.
+    ///
+    /// Assumes `rlp` are bytes and has length at least [max_rlp_encoding_len]`(max_field_len)`. Costs O(`max_field_len *
+    /// max_rlp_len_len(max_field_len)`) cells, to shift the string by its variable offset.
+    pub fn decompose_rlp_field(
+        &self,
+        ctx: &mut Context<F>,
+        rlp: &[AssignedValue<F>],
+        max_field_len: usize,
+    ) -> RlpFieldTrace<F> {
+        let max_len_len = max_rlp_len_len(max_field_len);
+        assert!(rlp.len() >= max_rlp_encoding_len(max_field_len));
+        let (gate, range) = (self.gate(), self.range);
+
+        let prefix = rlp[0];
+        // a string has prefix in [0x00, 0xc0)
+        range.check_less_than(ctx, prefix, Constant(F::from(0xc0)), 8);
+        let is_literal = range.is_less_than(ctx, prefix, Constant(F::from(0x80)), 8);
+        let prefix_len = gate.not(ctx, is_literal);
+        let is_short = range.is_less_than(ctx, prefix, Constant(F::from(0xb8)), 8);
+        let is_big = gate.not(ctx, is_short);
+
+        let short_len = gate.sub(ctx, prefix, Constant(F::from(0x80)));
+        let short_len = gate.select(ctx, Constant(F::one()), short_len, is_literal);
+        let big_len_len = gate.sub(ctx, prefix, Constant(F::from(0xb7)));
+        let len_len = gate.mul(ctx, is_big, big_len_len);
+        let (len_cells, field_len) =
+            self.decode_len(ctx, rlp, is_big, len_len, short_len, max_field_len);
+
+        let field_offset = gate.add(ctx, prefix_len, len_len);
+        // the string starts at one of the offsets 0, 1, ..., 1 + max_len_len
+        let num_offsets = max_len_len + 2;
+        let indicator = gate.idx_to_indicator(ctx, field_offset, num_offsets);
+        let field_cells = (0..max_field_len)
+            .map(|i| {
+                let window = (i..i + num_offsets)
+                    .map(|j| rlp.get(j).map_or(Constant(F::zero()), |byte| Existing(*byte)));
+                gate.select_by_indicator(ctx, window, indicator.iter().copied())
+            })
+            .collect::<Vec<_>>();
+        if let Some(first) = field_cells.first() {
+            // a single byte less than 0x80 must not be prefixed by 0x81
+            let is_single = gate.is_equal(ctx, prefix, Constant(F::from(0x81)));
+            let is_small = range.is_less_than(ctx, *first, Constant(F::from(0x80)), 8);
+            let is_not_canonical = gate.and(ctx, is_single, is_small);
+            gate.assert_is_const(ctx, &is_not_canonical, &F::zero());
+        }
+        let rlp_len = gate.add(ctx, field_offset, field_len);
+
+        RlpFieldTrace {
+            prefix,
+            prefix_len,
+            len_len,
+            len_cells,
+            field_offset,
+            field_len,
+            field_cells,
+            rlp_len,
+            max_field_len,
+        }
+    }
+
+    /// Witnesses and decomposes the RLP encoding of a list at the start of `rlp_array` with items that are strings of
+    /// at most `max_field_lens[i]` bytes.
+    ///
+    /// If `is_variable_len`, the list may have fewer than `max_field_lens.len()` items, and otherwise exactly that
+    /// many. Constrains the prefix and length of the list, and the decompositions of the encodings of its items, see
+    /// [Self::decompose_rlp_field]. The encodings of the items are only constrained to be the payload of the list by
+    /// [Self::decompose_rlp_array_phase1], which must be called in a later phase.
+    ///
+    /// Assumes `rlp_array` are bytes and has length at least [max_rlp_encoding_len] of the sum of the maximum lengths
+    /// of the encodings of the items. Costs the decompositions of the items and range checks of their bytes.
+    pub fn decompose_rlp_array_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        rlp_array: Vec<AssignedValue<F>>,
+        max_field_lens: &[usize],
+        is_variable_len: bool,
+    ) -> RlpArrayTraceWitness<F> {
+        let max_field_rlp_lens =
+            max_field_lens.iter().map(|len| max_rlp_encoding_len(*len)).collect::<Vec<_>>();
+        let max_payload_len = max_field_rlp_lens.iter().sum::<usize>();
+        assert!(rlp_array.len() >= max_rlp_encoding_len(max_payload_len));
+        let (gate, range) = (self.gate(), self.range);
+
+        let prefix = rlp_array[0];
+        // a list has prefix in [0xc0, 0xff]
+        let prefix_offset = gate.sub(ctx, prefix, Constant(F::from(0xc0)));
+        range.range_check(ctx, prefix_offset, 6);
+        let is_short = range.is_less_than(ctx, prefix, Constant(F::from(0xf8)), 8);
+        let is_big = gate.not(ctx, is_short);
+        let big_len_len = gate.sub(ctx, prefix, Constant(F::from(0xf7)));
+        let len_len = gate.mul(ctx, is_big, big_len_len);
+        let (len_cells, payload_len) =
+            self.decode_len(ctx, &rlp_array, is_big, len_len, prefix_offset, max_payload_len);
+        let rlp_len = gate.sum(ctx, [Constant(F::one()), Existing(len_len), Existing(payload_len)]);
+
+        let bytes =
+            rlp_array.iter().map(|byte| byte.value().get_lower_32() as u8).collect::<Vec<_>>();
+        let items = native_rlp_list_items(&bytes).unwrap_or_default();
+        let num_fields = if is_variable_len {
+            let num_fields =
+                ctx.load_witness(F::from(items.len().min(max_field_lens.len()) as u64));
+            range.check_less_than_safe(ctx, num_fields, max_field_lens.len() as u64 + 1);
+            num_fields
+        } else {
+            ctx.load_constant(F::from(max_field_lens.len() as u64))
+        };
+
+        let mut field_offset = gate.add(ctx, Constant(F::one()), len_len);
+        let mut fields = Vec::with_capacity(max_field_lens.len());
+        let mut field_rlps = Vec::with_capacity(max_field_lens.len());
+        let mut field_rlp_lens = Vec::with_capacity(max_field_lens.len());
+        let mut field_offsets = Vec::with_capacity(max_field_lens.len());
+        let num_fields_bits = bit_length(max_field_lens.len() as u64).max(1);
+        for (i, (&max_field_len, &max_field_rlp_len)) in
+            max_field_lens.iter().zip(&max_field_rlp_lens).enumerate()
+        {
+            let item = items.get(i).copied().unwrap_or_default();
+            let field_rlp = ctx.assign_witnesses(
+                item.iter()
+                    .copied()
+                    .chain(iter::repeat(0))
+                    .take(max_field_rlp_len)
+                    .map(|byte| F::from(byte as u64)),
+            );
+            for byte in &field_rlp {
+                range.range_check(ctx, *byte, 8);
+            }
+            let field = self.decompose_rlp_field(ctx, &field_rlp, max_field_len);
+            let field_rlp_len = if is_variable_len {
+                let in_list = range.is_less_than(
+                    ctx,
+                    Constant(F::from(i as u64)),
+                    num_fields,
+                    num_fields_bits,
+                );
+                gate.mul(ctx, in_list, field.rlp_len)
+            } else {
+                field.rlp_len
+            };
+            field_offsets.push(field_offset);
+            field_offset = gate.add(ctx, field_offset, field_rlp_len);
+            fields.push(field);
+            field_rlps.push(field_rlp);
+            field_rlp_lens.push(field_rlp_len);
+        }
+
+        RlpArrayTraceWitness {
+            rlp_array,
+            rlp_len,
+            prefix,
+            len_len,
+            len_cells,
+            payload_len,
+            num_fields,
+            fields,
+            field_rlps,
+            field_rlp_lens,
+            field_offsets,
+        }
+    }
+
+    /// Constrains the payload of the list of `witness` to be the concatenation of the encodings of its items, and
+    /// returns the RLCs of the list and of the strings of its items.
+    ///
+    /// `rlc` must use a challenge generated after the phase of [Self::decompose_rlp_array_phase0]. Loads the powers of
+    /// `gamma` needed into the cache of `rlc`. Costs O(`rlp_array.len()`) cells for the RLCs.
+    pub fn decompose_rlp_array_phase1(
+        &self,
+        ctx: &mut Context<F>,
+        rlc: &mut RlcChip<F>,
+        witness: RlpArrayTraceWitness<F>,
+    ) -> RlpArrayTrace<F> {
+        let max_len = witness.rlp_array.len();
+        rlc.load_rlc_cache(ctx, bit_length(max_len as u64));
+        let rlp_rlc = rlc.compute_rlc(ctx, witness.rlp_array.iter().copied(), witness.rlp_len);
+        let prefix = rlc.compute_rlc_fixed_len(ctx, [witness.prefix]);
+        let len = rlc.compute_rlc(ctx, witness.len_cells.iter().copied(), witness.len_len);
+        let mut parts = vec![prefix, len];
+        for (field_rlp, field_rlp_len) in witness.field_rlps.iter().zip(&witness.field_rlp_lens) {
+            parts.push(rlc.compute_rlc(ctx, field_rlp.iter().copied(), *field_rlp_len));
+        }
+        rlc.constrain_rlc_concat(ctx, &parts, &rlp_rlc);
+
+        let gate = self.gate();
+        let field_rlcs = witness
+            .fields
+            .iter()
+            .zip(&witness.field_rlp_lens)
+            .map(|(field, field_rlp_len)| {
+                // the items from `num_fields` on have encodings of length 0
+                let in_list = gate.is_zero(ctx, *field_rlp_len);
+                let in_list = gate.not(ctx, in_list);
+                let field_len = gate.mul(ctx, in_list, field.field_len);
+                rlc.compute_rlc(ctx, field.field_cells.iter().copied(), field_len)
+            })
+            .collect();
+        RlpArrayTrace { witness, rlp_rlc, field_rlcs }
+    }
+
+    /// Constrains and returns the length cells and the length of an RLP item with payload of at most `max_len` bytes,
+    /// given whether its length is in long form, the number of bytes `len_len` of the long form and the short form
+    /// length `short_len`.
+    fn decode_len(
+        &self,
+        ctx: &mut Context<F>,
+        rlp: &[AssignedValue<F>],
+        is_big: AssignedValue<F>,
+        len_len: AssignedValue<F>,
+        short_len: AssignedValue<F>,
+        max_len: usize,
+    ) -> (Vec<AssignedValue<F>>, AssignedValue<F>) {
+        let max_len_len = max_rlp_len_len(max_len);
+        let (gate, range) = (self.gate(), self.range);
+        range.check_less_than_safe(ctx, len_len, max_len_len as u64 + 1);
+        let len_cells = rlp[1..1 + max_len_len].to_vec();
+        // big-endian value of the first `len_len` bytes
+        let mut prev = Constant(F::zero());
+        let len_prefixes = len_cells
+            .iter()
+            .map(|byte| {
+                let value = gate.mul_add(ctx, prev, Constant(F::from(256)), *byte);
+                prev = Existing(value);
+                prev
+            })
+            .collect::<Vec<_>>();
+        let big_len =
+            gate.select_from_idx(ctx, iter::once(Constant(F::zero())).chain(len_prefixes), len_len);
+        let len = gate.select(ctx, big_len, short_len, is_big);
+        range.check_less_than_safe(ctx, len, max_len as u64 + 1);
+
+        // the long form is only used for lengths of more than 55 bytes, without leading zeros
+        let min_len = gate.mul(ctx, is_big, Constant(F::from(56)));
+        let len_minus_min = gate.sub(ctx, len, min_len);
+        range.range_check(ctx, len_minus_min, bit_length(max_len as u64).max(1));
+        if let Some(first) = len_cells.first() {
+            let is_zero = gate.is_zero(ctx, *first);
+            let has_leading_zero = gate.and(ctx, is_big, is_zero);
+            gate.assert_is_const(ctx, &has_leading_zero, &F::zero());
+        }
+        (len_cells, len)
+    }
+}
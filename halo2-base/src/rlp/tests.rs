@@ -0,0 +1,120 @@
+use super::*;
+use crate::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::{
+        mock_verify,
+        rlp::{encode_bytes, encode_list},
+    },
+};
+use std::env::set_var;
+use test_case::test_case;
+
+const K: usize = 11;
+const LOOKUP_BITS: usize = 8;
+
+/// Loads `rlp` padded with `0xff` to `max_len` bytes in phase 0.
+fn load_rlp(
+    builder: &mut GateThreadBuilder<Fr>,
+    rlp: &[u8],
+    max_len: usize,
+) -> Vec<AssignedValue<Fr>> {
+    assert!(rlp.len() <= max_len);
+    let bytes = rlp.iter().copied().chain(iter::repeat(0xff)).take(max_len);
+    builder.main(0).assign_witnesses(bytes.map(|b| Fr::from(b as u64)))
+}
+
+#[test_case(vec![]; "empty string")]
+#[test_case(vec![0x05]; "single byte")]
+#[test_case(vec![0x90]; "single byte with prefix")]
+#[test_case(vec![0xab; 20]; "short string")]
+#[test_case(vec![0xcd; 60]; "long string")]
+fn test_decompose_rlp_field(field: Vec<u8>) {
+    let max_field_len = 60;
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let rlp = load_rlp(&mut builder, &encode_bytes(&field), max_rlp_encoding_len(max_field_len));
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = RlpChip::new(&range);
+    let trace = chip.decompose_rlp_field(builder.main(0), &rlp, max_field_len);
+    assert_eq!(*trace.field_len.value(), Fr::from(field.len() as u64));
+    assert_eq!(*trace.rlp_len.value(), Fr::from(encode_bytes(&field).len() as u64));
+    for (cell, byte) in trace.field_cells.iter().zip(&field) {
+        assert_eq!(*cell.value(), Fr::from(*byte as u64));
+    }
+    assert!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok());
+}
+
+#[test_case(&[0x81, 0x05]; "single byte with prefix")]
+#[test_case(&[0xb8, 0x05, 1, 2, 3, 4, 5]; "long form of short string")]
+#[test_case(&[0xb9, 0x00, 0x3c]; "too many length bytes")]
+#[test_case(&[0xb8, 0x3d]; "string too long")]
+#[test_case(&[0xc0]; "list")]
+fn test_decompose_rlp_field_invalid(rlp: &[u8]) {
+    let max_field_len = 60;
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let rlp = load_rlp(&mut builder, rlp, max_rlp_encoding_len(max_field_len));
+    let range = RangeChip::default(LOOKUP_BITS);
+    RlpChip::new(&range).decompose_rlp_field(builder.main(0), &rlp, max_field_len);
+    assert!(mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_err());
+}
+
+/// Decodes the list `rlp` with items of at most `[32, 1, 32, 60]` bytes and checks the RLCs of the items are those of
+/// `items`, if any.
+fn verify_rlp_array(rlp: Vec<u8>, items: Option<Vec<Vec<u8>>>, is_variable_len: bool) -> bool {
+    let max_field_lens = [32, 1, 32, 60];
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let max_payload_len = max_field_lens.iter().map(|len| max_rlp_encoding_len(*len)).sum();
+    let rlp = load_rlp(&mut builder, &rlp, max_rlp_encoding_len(max_payload_len));
+    let range = RangeChip::default(LOOKUP_BITS);
+    let witness = RlpChip::new(&range).decompose_rlp_array_phase0(
+        builder.main(0),
+        rlp,
+        &max_field_lens,
+        is_variable_len,
+    );
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        let mut rlc = RlcChip::new(&range, gamma);
+        let ctx = builder.main(1);
+        let chip = RlpChip::new(&range);
+        let trace = chip.decompose_rlp_array_phase1(ctx, &mut rlc, witness.clone());
+        let rlc_native = |bytes: &[u8]| {
+            bytes.iter().fold(Fr::zero(), |acc, b| acc * gamma.value() + Fr::from(*b as u64))
+        };
+        if let Some(items) = &items {
+            for (i, field_rlc) in trace.field_rlcs.iter().enumerate() {
+                let item = items.get(i).map_or(&[][..], |item| &item[..]);
+                assert_eq!(*field_rlc.len.value(), Fr::from(item.len() as u64));
+                assert_eq!(*field_rlc.rlc_val.value(), rlc_native(item));
+            }
+        }
+    });
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok()
+}
+
+#[test_case(vec![vec![], vec![0x05], vec![0x12; 32], vec![0x34; 60]], false; "fixed length")]
+#[test_case(vec![vec![0x56; 32], vec![0x7f], vec![], vec![0x80]], false; "fixed length short items")]
+#[test_case(vec![vec![0x56; 32], vec![0x80]], true; "variable length")]
+#[test_case(vec![], true; "variable length empty")]
+fn test_decompose_rlp_array(items: Vec<Vec<u8>>, is_variable_len: bool) {
+    assert!(verify_rlp_array(encode_list(&items), Some(items), is_variable_len));
+}
+
+#[test]
+fn test_decompose_rlp_array_missing_items() {
+    let items = vec![vec![0x56; 32], vec![0x80]];
+    assert!(!verify_rlp_array(encode_list(&items), None, false));
+}
+
+#[test]
+fn test_decompose_rlp_array_invalid_item() {
+    let items = vec![vec![0x56; 32], vec![0x80]];
+    let mut rlp = encode_list(&items);
+    // add a byte to the payload which is not an item
+    rlp[0] += 1;
+    rlp.push(0xff);
+    assert!(!verify_rlp_array(rlp, None, true));
+}
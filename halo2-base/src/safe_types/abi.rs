@@ -0,0 +1,325 @@
+//! Solidity ABI encoding and decoding of static types, e.g. for slicing calldata and event data.
+//!
+//! Every static type is encoded in one or more 32-byte big-endian words:
+//! - `uint<N>` and `address` are left-padded with zeros,
+//! - `int<N>` is the two's complement of the value, left-padded with copies of its sign bit,
+//! - `bool` is a `uint8` equal to 0 or 1,
+//! - `bytes<N>` is right-padded with zeros,
+//! - fixed-size arrays and tuples are the concatenation of the encodings of their members.
+use super::{
+    SafeAddress, SafeBool, SafeBytes32, SafeTypeChip, SafeUint128, SafeUint16, SafeUint256,
+    SafeUint32, SafeUint64, SafeUint8, BITS_PER_BYTE,
+};
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::ScalarField,
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+
+/// Number of bytes of an ABI word.
+pub const WORD_BYTES: usize = 32;
+
+/// Bit widths of the unsigned SafeTypes integers are decoded into.
+const UINT_WIDTHS: [usize; 6] = [8, 16, 32, 64, 128, 256];
+
+/// Description of a static Solidity ABI type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AbiType {
+    /// `uint<N>`, with `N` a multiple of 8 in `8..=256`.
+    Uint(usize),
+    /// `int<N>`, with `N` a multiple of 8 in `8..=256`.
+    Int(usize),
+    /// `address`.
+    Address,
+    /// `bool`.
+    Bool,
+    /// `bytes<N>`, with `N` in `1..=32`.
+    FixedBytes(usize),
+    /// `T[N]`.
+    Array(Box<AbiType>, usize),
+    /// `(T1, T2, ...)`.
+    Tuple(Vec<AbiType>),
+}
+
+impl AbiType {
+    /// Returns the number of bytes of the encoding of this type.
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            AbiType::Array(ty, len) => ty.encoded_len() * len,
+            AbiType::Tuple(types) => types.iter().map(AbiType::encoded_len).sum(),
+            _ => WORD_BYTES,
+        }
+    }
+}
+
+/// A decoded ABI value, with range constraints applied.
+///
+/// `uint<N>` and `int<N>` are decoded into the narrowest `SafeUint` holding `N` bits, e.g. `uint24` into
+/// [AbiValue::Uint32]. For `int<N>` the SafeType holds the two's complement of the value with the width of
+/// the SafeType, e.g. `int24(-1)` is decoded into `Uint32(0xffffffff)`, which [SafeTypeChip::uint_to_int] turns
+/// into a [super::SafeInt32] equal to -1.
+#[derive(Clone, Debug)]
+pub enum AbiValue<F: ScalarField>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// `uint8` or `int8`.
+    Uint8(SafeUint8<F>),
+    /// `uint16` or `int16`.
+    Uint16(SafeUint16<F>),
+    /// `uint<N>` or `int<N>` for `16 < N <= 32`.
+    Uint32(SafeUint32<F>),
+    /// `uint<N>` or `int<N>` for `32 < N <= 64`.
+    Uint64(SafeUint64<F>),
+    /// `uint<N>` or `int<N>` for `64 < N <= 128`.
+    Uint128(SafeUint128<F>),
+    /// `uint<N>` or `int<N>` for `128 < N <= 256`.
+    Uint256(SafeUint256<F>),
+    /// `address`.
+    Address(SafeAddress<F>),
+    /// `bool`.
+    Bool(SafeBool<F>),
+    /// `bytes32`, bytes in the order they appear in the encoding.
+    Bytes32(SafeBytes32<F>),
+    /// `bytes<N>` for `N < 32`, bytes in the order they appear in the encoding.
+    FixedBytes(Vec<SafeUint8<F>>),
+    /// `T[N]`.
+    Array(Vec<AbiValue<F>>),
+    /// `(T1, T2, ...)`.
+    Tuple(Vec<AbiValue<F>>),
+}
+
+/// Returns the width of the SafeType an integer of `bits` bits is decoded into.
+fn uint_width(bits: usize) -> usize {
+    assert!(
+        bits % BITS_PER_BYTE == 0 && (BITS_PER_BYTE..=256).contains(&bits),
+        "invalid integer size {bits}"
+    );
+    UINT_WIDTHS.into_iter().find(|width| *width >= bits).unwrap()
+}
+
+impl<'a, F: ScalarField> SafeTypeChip<'a, F>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// Decodes `data`, the ABI encoding of a value of type `ty`.
+    ///
+    /// Constrains the padding of every word, so each encoded value has a unique decoding.
+    /// Panics if `data.len() != ty.encoded_len()`.
+    pub fn abi_decode(
+        &self,
+        ctx: &mut Context<F>,
+        ty: &AbiType,
+        data: &[SafeUint8<F>],
+    ) -> AbiValue<F> {
+        assert_eq!(data.len(), ty.encoded_len(), "data length does not match ABI type");
+        let bytes = data.iter().map(|byte| byte.value[0]).collect::<Vec<_>>();
+        self.abi_decode_bytes(ctx, ty, &bytes)
+    }
+
+    /// Returns the ABI encoding of `value` as type `ty`.
+    ///
+    /// For `uint<N>` and `int<N>` with `N` narrower than the SafeType of `value`, constrains `value` to fit in
+    /// `N` bits. Panics if `value` does not have the shape of `ty`.
+    pub fn abi_encode(
+        &self,
+        ctx: &mut Context<F>,
+        ty: &AbiType,
+        value: &AbiValue<F>,
+    ) -> Vec<SafeUint8<F>> {
+        let bytes = self.abi_encode_bytes(ctx, ty, value);
+        debug_assert_eq!(bytes.len(), ty.encoded_len());
+        bytes.into_iter().map(|byte| SafeUint8::new(vec![byte])).collect()
+    }
+
+    fn abi_decode_bytes(
+        &self,
+        ctx: &mut Context<F>,
+        ty: &AbiType,
+        bytes: &[AssignedValue<F>],
+    ) -> AbiValue<F> {
+        let gate = self.range_chip.gate();
+        match ty {
+            AbiType::Uint(bits) => {
+                let width = uint_width(*bits);
+                self.assert_zero_bytes(ctx, &bytes[..WORD_BYTES - bits / BITS_PER_BYTE]);
+                self.decode_uint(ctx, &bytes[WORD_BYTES - width / BITS_PER_BYTE..])
+            }
+            AbiType::Int(bits) => {
+                let width = uint_width(*bits);
+                let num_bytes = bits / BITS_PER_BYTE;
+                if num_bytes < WORD_BYTES {
+                    let sign = self.byte_msb(ctx, bytes[WORD_BYTES - num_bytes]);
+                    let extension = gate.mul(ctx, sign, Constant(F::from(u8::MAX as u64)));
+                    for byte in &bytes[..WORD_BYTES - num_bytes] {
+                        ctx.constrain_equal(byte, &extension);
+                    }
+                }
+                // the sign extension up to `width` is part of the two's complement at that width
+                self.decode_uint(ctx, &bytes[WORD_BYTES - width / BITS_PER_BYTE..])
+            }
+            AbiType::Address => {
+                let num_bytes = SafeAddress::<F>::TOTAL_BITS / BITS_PER_BYTE;
+                self.assert_zero_bytes(ctx, &bytes[..WORD_BYTES - num_bytes]);
+                let le = bytes[WORD_BYTES - num_bytes..].iter().rev().copied().collect::<Vec<_>>();
+                AbiValue::Address(self.compose_bytes_le(ctx, &le))
+            }
+            AbiType::Bool => {
+                self.assert_zero_bytes(ctx, &bytes[..WORD_BYTES - 1]);
+                gate.assert_bit(ctx, bytes[WORD_BYTES - 1]);
+                AbiValue::Bool(SafeBool::new(vec![bytes[WORD_BYTES - 1]]))
+            }
+            AbiType::FixedBytes(len) => {
+                assert!((1..=WORD_BYTES).contains(len), "invalid fixed bytes size {len}");
+                self.assert_zero_bytes(ctx, &bytes[*len..]);
+                if *len == WORD_BYTES {
+                    AbiValue::Bytes32(SafeBytes32::new(bytes.to_vec()))
+                } else {
+                    AbiValue::FixedBytes(
+                        bytes[..*len].iter().map(|byte| SafeUint8::new(vec![*byte])).collect(),
+                    )
+                }
+            }
+            AbiType::Array(ty, len) => {
+                let ele_len = ty.encoded_len();
+                AbiValue::Array(
+                    (0..*len)
+                        .map(|i| {
+                            self.abi_decode_bytes(ctx, ty, &bytes[i * ele_len..(i + 1) * ele_len])
+                        })
+                        .collect(),
+                )
+            }
+            AbiType::Tuple(types) => {
+                let mut offset = 0;
+                AbiValue::Tuple(
+                    types
+                        .iter()
+                        .map(|ty| {
+                            let ele_len = ty.encoded_len();
+                            let value =
+                                self.abi_decode_bytes(ctx, ty, &bytes[offset..offset + ele_len]);
+                            offset += ele_len;
+                            value
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    fn abi_encode_bytes(
+        &self,
+        ctx: &mut Context<F>,
+        ty: &AbiType,
+        value: &AbiValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.range_chip.gate();
+        match (ty, value) {
+            (AbiType::Uint(bits) | AbiType::Int(bits), _) => {
+                let width = uint_width(*bits);
+                let be = self.encode_uint(ctx, value, width);
+                // `be` has `width / 8` bytes, the high ones must be equal to the padding
+                let (high, low) = be.split_at((width - bits) / BITS_PER_BYTE);
+                let padding = match ty {
+                    AbiType::Int(_) if *bits < 256 => {
+                        let sign = self.byte_msb(ctx, low[0]);
+                        gate.mul(ctx, sign, Constant(F::from(u8::MAX as u64)))
+                    }
+                    _ => ctx.load_zero(),
+                };
+                for byte in high {
+                    ctx.constrain_equal(byte, &padding);
+                }
+                let mut bytes = vec![padding; WORD_BYTES - low.len()];
+                bytes.extend_from_slice(low);
+                bytes
+            }
+            (AbiType::Address, AbiValue::Address(address)) => {
+                let mut bytes = vec![ctx.load_zero(); WORD_BYTES];
+                let be = self.uint_to_bytes_be(ctx, address);
+                for (byte, value) in bytes[WORD_BYTES - be.len()..].iter_mut().zip(be) {
+                    *byte = value.value[0];
+                }
+                bytes
+            }
+            (AbiType::Bool, AbiValue::Bool(bit)) => {
+                let mut bytes = vec![ctx.load_zero(); WORD_BYTES];
+                bytes[WORD_BYTES - 1] = bit.value[0];
+                bytes
+            }
+            (AbiType::FixedBytes(WORD_BYTES), AbiValue::Bytes32(bytes)) => bytes.value.clone(),
+            (AbiType::FixedBytes(len), AbiValue::FixedBytes(values)) => {
+                assert_eq!(*len, values.len(), "fixed bytes length does not match ABI type");
+                let mut bytes = vec![ctx.load_zero(); WORD_BYTES];
+                for (byte, value) in bytes.iter_mut().zip(values) {
+                    *byte = value.value[0];
+                }
+                bytes
+            }
+            (AbiType::Array(ty, len), AbiValue::Array(values)) => {
+                assert_eq!(*len, values.len(), "array length does not match ABI type");
+                values.iter().flat_map(|value| self.abi_encode_bytes(ctx, ty, value)).collect()
+            }
+            (AbiType::Tuple(types), AbiValue::Tuple(values)) => {
+                assert_eq!(types.len(), values.len(), "tuple length does not match ABI type");
+                types
+                    .iter()
+                    .zip(values)
+                    .flat_map(|(ty, value)| self.abi_encode_bytes(ctx, ty, value))
+                    .collect()
+            }
+            _ => panic!("value does not match ABI type {ty:?}"),
+        }
+    }
+
+    /// Composes big-endian bytes into the unsigned SafeType of the same width.
+    fn decode_uint(&self, ctx: &mut Context<F>, be: &[AssignedValue<F>]) -> AbiValue<F> {
+        let le = be.iter().rev().copied().collect::<Vec<_>>();
+        match le.len() * BITS_PER_BYTE {
+            8 => AbiValue::Uint8(SafeUint8::new(le)),
+            16 => AbiValue::Uint16(self.compose_bytes_le(ctx, &le)),
+            32 => AbiValue::Uint32(self.compose_bytes_le(ctx, &le)),
+            64 => AbiValue::Uint64(self.compose_bytes_le(ctx, &le)),
+            128 => AbiValue::Uint128(self.compose_bytes_le(ctx, &le)),
+            256 => AbiValue::Uint256(self.compose_bytes_le(ctx, &le)),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Decomposes an unsigned integer value of width `width` into big-endian bytes.
+    fn encode_uint(
+        &self,
+        ctx: &mut Context<F>,
+        value: &AbiValue<F>,
+        width: usize,
+    ) -> Vec<AssignedValue<F>> {
+        let be = match (width, value) {
+            (8, AbiValue::Uint8(a)) => return a.value.clone(),
+            (16, AbiValue::Uint16(a)) => self.uint_to_bytes_be(ctx, a),
+            (32, AbiValue::Uint32(a)) => self.uint_to_bytes_be(ctx, a),
+            (64, AbiValue::Uint64(a)) => self.uint_to_bytes_be(ctx, a),
+            (128, AbiValue::Uint128(a)) => self.uint_to_bytes_be(ctx, a),
+            (256, AbiValue::Uint256(a)) => self.uint_to_bytes_be(ctx, a),
+            _ => panic!("value is not a {width}-bit integer"),
+        };
+        be.into_iter().map(|byte| byte.value[0]).collect()
+    }
+
+    /// Returns the most significant bit of `byte`.
+    fn byte_msb(&self, ctx: &mut Context<F>, byte: AssignedValue<F>) -> AssignedValue<F> {
+        self.range_chip.gate().num_to_bits(ctx, byte, BITS_PER_BYTE)[BITS_PER_BYTE - 1]
+    }
+
+    /// Constrains range checked bytes to be zero.
+    fn assert_zero_bytes(&self, ctx: &mut Context<F>, bytes: &[AssignedValue<F>]) {
+        if bytes.is_empty() {
+            return;
+        }
+        // each byte is in [0, 256), so the sum cannot wrap around the modulus
+        let gate = self.range_chip.gate();
+        let sum = gate.sum(ctx, bytes.iter().copied());
+        gate.assert_is_const(ctx, &sum, &F::zero());
+    }
+}
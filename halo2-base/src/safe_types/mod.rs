@@ -1,3 +1,4 @@
+use crate::utils::{biguint_to_fe, bit_length, fe_to_biguint};
 pub use crate::{
     gates::{
         flex_gate::GateInstructions,
@@ -7,8 +8,13 @@ pub use crate::{
     AssignedValue, Context,
     QuantumCell::{self, Constant, Existing, Witness},
 };
+use num_bigint::BigUint;
 use std::cmp::{max, min};
 
+/// Solidity ABI encoding and decoding of static types.
+pub mod abi;
+mod signed;
+
 #[cfg(test)]
 pub mod tests;
 
@@ -16,6 +22,9 @@ type RawAssignedValues<F> = Vec<AssignedValue<F>>;
 
 const BITS_PER_BYTE: usize = 8;
 
+/// Number of bits of the limbs [SafeTypeChip::checked_mul] multiplies types wider than the native field with.
+pub const MUL_LIMB_BITS: usize = 64;
+
 /// SafeType's goal is to avoid out-of-range undefined behavior.
 /// When building circuits, it's common to use mulitple AssignedValue<F> to represent
 /// a logical varaible. For example, we might want to represent a hash with 32 AssignedValue<F>
@@ -45,8 +54,8 @@ impl<F: ScalarField, const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>
     pub const VALUE_LENGTH: usize =
         (TOTAL_BITS + BYTES_PER_ELE * BITS_PER_BYTE - 1) / (BYTES_PER_ELE * BITS_PER_BYTE);
 
-    // new is private so Safetype can only be constructed by this crate.
-    fn new(raw_values: RawAssignedValues<F>) -> Self {
+    // new is crate-private so Safetype can only be constructed by this crate.
+    pub(crate) fn new(raw_values: RawAssignedValues<F>) -> Self {
         assert!(raw_values.len() == Self::VALUE_LENGTH, "Invalid raw values length");
         Self { value: raw_values }
     }
@@ -55,6 +64,34 @@ impl<F: ScalarField, const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>
     pub fn value(&self) -> &RawAssignedValues<F> {
         &self.value
     }
+
+    /// Returns the number of bits of each element: [Self::BITS_PER_ELE], except possibly for the last element.
+    fn element_bits() -> Vec<usize> {
+        (0..Self::VALUE_LENGTH)
+            .map(|i| min(Self::BITS_PER_ELE, TOTAL_BITS - i * Self::BITS_PER_ELE))
+            .collect()
+    }
+}
+
+/// Signed counterpart of [SafeType]: holds the two's complement of a `TOTAL_BITS`-bit signed integer in a
+/// [SafeType] of the same layout, matching Solidity `int<TOTAL_BITS>`.
+#[derive(Clone, Debug)]
+pub struct SignedSafeType<F: ScalarField, const BYTES_PER_ELE: usize, const TOTAL_BITS: usize> {
+    inner: SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+}
+
+impl<F: ScalarField, const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>
+    SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>
+{
+    /// Return the two's complement of the value in little-endian.
+    pub fn value(&self) -> &RawAssignedValues<F> {
+        self.inner.value()
+    }
+
+    /// Returns the two's complement of the value as an unsigned [SafeType].
+    pub fn as_unsigned(&self) -> &SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        &self.inner
+    }
 }
 
 /// Represent TOTAL_BITS with the least number of AssignedValue<F>.
@@ -77,9 +114,28 @@ pub type SafeUint64<F> = CompactSafeType<F, 64>;
 pub type SafeUint128<F> = CompactSafeType<F, 128>;
 /// SafeType for uint256.
 pub type SafeUint256<F> = CompactSafeType<F, 256>;
+/// SafeType for address.
+pub type SafeAddress<F> = CompactSafeType<F, 160>;
 /// SafeType for bytes32.
 pub type SafeBytes32<F> = SafeType<F, 1, 256>;
 
+#[allow(type_alias_bounds)]
+type CompactSignedSafeType<F: ScalarField, const TOTAL_BITS: usize> =
+    SignedSafeType<F, { ((F::NUM_BITS - 1) / 8) as usize }, TOTAL_BITS>;
+
+/// SignedSafeType for int8.
+pub type SafeInt8<F> = CompactSignedSafeType<F, 8>;
+/// SignedSafeType for int16.
+pub type SafeInt16<F> = CompactSignedSafeType<F, 16>;
+/// SignedSafeType for int32.
+pub type SafeInt32<F> = CompactSignedSafeType<F, 32>;
+/// SignedSafeType for int64.
+pub type SafeInt64<F> = CompactSignedSafeType<F, 64>;
+/// SignedSafeType for int128.
+pub type SafeInt128<F> = CompactSignedSafeType<F, 128>;
+/// SignedSafeType for int256.
+pub type SafeInt256<F> = CompactSignedSafeType<F, 256>;
+
 /// Chip for SafeType
 pub struct SafeTypeChip<'a, F: ScalarField> {
     range_chip: &'a RangeChip<F>,
@@ -141,6 +197,359 @@ impl<'a, F: ScalarField> SafeTypeChip<'a, F> {
         }
     }
 
-    // TODO: Add comprasion. e.g. is_less_than(SafeUint8, SafeUint8) -> SafeBool
-    // TODO: Add type castings. e.g. uint256 -> bytes32/uint32 -> uint64
+    /// Returns 1 if `a < b`, otherwise 0, assuming `a` and `b` have at most `bits` bits.
+    ///
+    /// Unlike [RangeInstructions::is_less_than], this works up to `bits = F::CAPACITY - 1`: with `lt` witnessed
+    /// as a bit, `b - a - 1 + (1 - lt) * 2^bits` is in `[0, 2^bits)` iff `lt == (a < b)`.
+    fn is_less_than_bits(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        b: AssignedValue<F>,
+        bits: usize,
+    ) -> AssignedValue<F> {
+        let gate = self.range_chip.gate();
+        let is_lt = fe_to_biguint(a.value()) < fe_to_biguint(b.value());
+        let is_lt = ctx.load_witness(F::from(is_lt as u64));
+        gate.assert_bit(ctx, is_lt);
+        let pow = gate.pow_of_two()[bits];
+        let diff = gate.sub(ctx, b, a);
+        let diff = gate.add(ctx, diff, Constant(pow - F::one()));
+        let diff = gate.mul_add(ctx, is_lt, Constant(-pow), diff);
+        self.range_chip.range_check(ctx, diff, bits);
+        is_lt
+    }
+
+    /// Splits `a < 2^(bits + 1)` into `(a mod 2^bits, a >> bits)`.
+    fn split_carry(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        bits: usize,
+    ) -> (AssignedValue<F>, AssignedValue<F>) {
+        let gate = self.range_chip.gate();
+        let carry = fe_to_biguint(a.value()).bit(bits as u64);
+        let carry = ctx.load_witness(F::from(carry as u64));
+        gate.assert_bit(ctx, carry);
+        let lo = gate.mul_add(ctx, carry, Constant(-gate.pow_of_two()[bits]), a);
+        self.range_chip.range_check(ctx, lo, bits);
+        (lo, carry)
+    }
+
+    /// Splits `a < 2^(bits + hi_bits)` into `(a mod 2^bits, a >> bits)`, range checking both parts.
+    fn split_bits(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        bits: usize,
+        hi_bits: usize,
+    ) -> (AssignedValue<F>, AssignedValue<F>) {
+        let gate = self.range_chip.gate();
+        let a_val = fe_to_biguint(a.value());
+        let [lo, hi] = [&a_val % (BigUint::from(1u64) << bits), &a_val >> bits]
+            .map(|x| ctx.load_witness(biguint_to_fe(&x)));
+        self.range_chip.range_check(ctx, lo, bits);
+        self.range_chip.range_check(ctx, hi, hi_bits);
+        let composed = gate.mul_add(ctx, hi, Constant(gate.pow_of_two()[bits]), lo);
+        ctx.constrain_equal(&composed, &a);
+        (lo, hi)
+    }
+
+    /// Decomposes `a < 2^(8 * num_bytes)` into `num_bytes` little-endian bytes.
+    fn decompose_to_bytes(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        num_bytes: usize,
+    ) -> RawAssignedValues<F> {
+        let gate = self.range_chip.gate();
+        let mut byte_values = fe_to_biguint(a.value()).to_bytes_le();
+        byte_values.resize(num_bytes, 0);
+        let bytes = ctx.assign_witnesses(byte_values.into_iter().map(|byte| F::from(byte as u64)));
+        for byte in bytes.iter() {
+            self.range_chip.range_check(ctx, *byte, BITS_PER_BYTE);
+        }
+        let byte_base = (0..num_bytes).map(|i| Constant(gate.pow_of_two()[i * BITS_PER_BYTE]));
+        let composed = gate.inner_product(ctx, bytes.clone(), byte_base);
+        ctx.constrain_equal(&composed, &a);
+        bytes
+    }
+
+    /// Composes little-endian bytes, assumed to be already range checked, into a SafeType.
+    fn compose_bytes_le<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[AssignedValue<F>],
+    ) -> SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        let gate = self.range_chip.gate();
+        let value = bytes
+            .chunks(BYTES_PER_ELE)
+            .map(|chunk| {
+                if chunk.len() == 1 {
+                    return chunk[0];
+                }
+                let byte_base = (0..chunk.len())
+                    .map(|i| Constant(gate.pow_of_two()[i * BITS_PER_BYTE]))
+                    .collect::<Vec<_>>();
+                gate.inner_product(ctx, chunk.to_vec(), byte_base)
+            })
+            .collect();
+        SafeType::new(value)
+    }
+}
+
+impl<'a, F: ScalarField> SafeTypeChip<'a, F>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// Returns 1 if `a < b` as unsigned integers, otherwise 0.
+    ///
+    /// Elements are compared from the most significant one, so this works for types spanning several elements,
+    /// e.g. [SafeUint256].
+    pub fn is_less_than<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeBool<F> {
+        let gate = self.range_chip.gate();
+        let element_bits = SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits();
+        let mut is_lt = ctx.load_zero();
+        // going up from the least significant element: a < b iff a_i < b_i, or a_i == b_i and a[..i] < b[..i]
+        for ((a, b), bits) in a.value.iter().zip(b.value.iter()).zip(element_bits) {
+            let lt = self.is_less_than_bits(ctx, *a, *b, bits);
+            let eq = gate.is_equal(ctx, *a, *b);
+            is_lt = gate.or_and(ctx, lt, eq, is_lt);
+        }
+        SafeBool::new(vec![is_lt])
+    }
+
+    /// Returns 1 if `a == b`, otherwise 0.
+    pub fn is_equal<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeBool<F> {
+        let gate = self.range_chip.gate();
+        let mut is_eq = ctx.load_constant(F::one());
+        for (a, b) in a.value.iter().zip(b.value.iter()) {
+            let eq = gate.is_equal(ctx, *a, *b);
+            is_eq = gate.and(ctx, is_eq, eq);
+        }
+        SafeBool::new(vec![is_eq])
+    }
+
+    /// Returns `(a + b) mod 2^TOTAL_BITS` and whether the addition overflowed.
+    pub fn checked_add<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> (SafeType<F, BYTES_PER_ELE, TOTAL_BITS>, SafeBool<F>) {
+        let gate = self.range_chip.gate();
+        let element_bits = SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits();
+        let mut carry = ctx.load_zero();
+        let mut value = Vec::with_capacity(element_bits.len());
+        for ((a, b), bits) in a.value.iter().zip(b.value.iter()).zip(element_bits) {
+            let sum = gate.sum(ctx, [*a, *b, carry]);
+            let (lo, hi) = self.split_carry(ctx, sum, bits);
+            value.push(lo);
+            carry = hi;
+        }
+        (SafeType::new(value), SafeBool::new(vec![carry]))
+    }
+
+    /// Returns `(a - b) mod 2^TOTAL_BITS` and whether the subtraction underflowed, i.e. whether `a < b`.
+    pub fn checked_sub<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> (SafeType<F, BYTES_PER_ELE, TOTAL_BITS>, SafeBool<F>) {
+        let gate = self.range_chip.gate();
+        let element_bits = SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits();
+        let mut borrow = ctx.load_zero();
+        let mut value = Vec::with_capacity(element_bits.len());
+        for ((a, b), bits) in a.value.iter().zip(b.value.iter()).zip(element_bits) {
+            // a - b - borrow + 2^bits is in [0, 2^(bits + 1)), with bit `bits` set iff there is no borrow
+            let diff = gate.sub(ctx, *a, *b);
+            let diff = gate.sub(ctx, diff, borrow);
+            let diff = gate.add(ctx, diff, Constant(gate.pow_of_two()[bits]));
+            let (lo, no_borrow) = self.split_carry(ctx, diff, bits);
+            value.push(lo);
+            borrow = gate.not(ctx, no_borrow);
+        }
+        (SafeType::new(value), SafeBool::new(vec![borrow]))
+    }
+
+    /// Returns `(a * b) mod 2^TOTAL_BITS` and whether the multiplication overflowed.
+    ///
+    /// Types with a single element such that `2 * TOTAL_BITS <= F::CAPACITY`, e.g. up to [SafeUint64] for BN254,
+    /// are multiplied natively. Wider types, e.g. [SafeUint256], are decomposed into bytes and multiplied as limbs
+    /// of [MUL_LIMB_BITS] bits, which costs about `3 * TOTAL_BITS / 8` byte range checks and `(TOTAL_BITS / 64)^2`
+    /// limb products.
+    pub fn checked_mul<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> (SafeType<F, BYTES_PER_ELE, TOTAL_BITS>, SafeBool<F>) {
+        let gate = self.range_chip.gate();
+        if SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::VALUE_LENGTH > 1
+            || 2 * TOTAL_BITS > F::CAPACITY as usize
+        {
+            return self.checked_mul_limbs(ctx, a, b);
+        }
+        let prod = gate.mul(ctx, a.value[0], b.value[0]);
+        let (lo, hi) = self.split_bits(ctx, prod, TOTAL_BITS, TOTAL_BITS);
+        let no_overflow = gate.is_zero(ctx, hi);
+        let overflow = gate.not(ctx, no_overflow);
+        (SafeType::new(vec![lo]), SafeBool::new(vec![overflow]))
+    }
+
+    /// [Self::checked_mul] on limbs of [MUL_LIMB_BITS] bits, for types too wide to be multiplied natively.
+    fn checked_mul_limbs<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> (SafeType<F, BYTES_PER_ELE, TOTAL_BITS>, SafeBool<F>) {
+        let gate = self.range_chip.gate();
+        let limb_bytes = MUL_LIMB_BITS / BITS_PER_BYTE;
+        let [a, b] = [a, b].map(|x| {
+            let bytes = self.uint_to_bytes_le(ctx, x);
+            bytes
+                .chunks(limb_bytes)
+                .map(|chunk| {
+                    let byte_base = (0..chunk.len())
+                        .map(|i| Constant(gate.pow_of_two()[i * BITS_PER_BYTE]))
+                        .collect::<Vec<_>>();
+                    gate.inner_product(ctx, chunk.iter().map(|byte| byte.value[0]), byte_base)
+                })
+                .collect::<Vec<_>>()
+        });
+        let num_limbs = a.len();
+        // coefficients of the product are less than `num_limbs * 2^(2 * MUL_LIMB_BITS)`, so carries stay below
+        // `2^(MUL_LIMB_BITS + carry_bits)`
+        let carry_bits = bit_length(num_limbs as u64) + 1;
+        assert!(
+            2 * MUL_LIMB_BITS + carry_bits <= F::CAPACITY as usize,
+            "limb products do not fit in F"
+        );
+        let mut carry = ctx.load_zero();
+        let mut prod = Vec::with_capacity(2 * num_limbs);
+        for i in 0..2 * num_limbs - 1 {
+            let start = (i + 1).saturating_sub(num_limbs);
+            let end = (i + 1).min(num_limbs);
+            let b_limbs = (start..end).map(|j| Existing(b[i - j]));
+            let coeff = gate.inner_product(ctx, a[start..end].iter().copied(), b_limbs);
+            let coeff = gate.add(ctx, coeff, carry);
+            let (lo, hi) = self.split_bits(ctx, coeff, MUL_LIMB_BITS, MUL_LIMB_BITS + carry_bits);
+            prod.push(lo);
+            carry = hi;
+        }
+        prod.push(carry);
+        // the low `TOTAL_BITS` bits of the product are the result, the product overflowed if any other bit is set
+        let (num_low_limbs, rem_bits) = (TOTAL_BITS / MUL_LIMB_BITS, TOTAL_BITS % MUL_LIMB_BITS);
+        let mut high = prod.split_off(num_low_limbs);
+        if rem_bits > 0 {
+            let (lo, hi) = self.split_bits(ctx, high[0], rem_bits, MUL_LIMB_BITS - rem_bits);
+            prod.push(lo);
+            high[0] = hi;
+        }
+        let high = gate.sum(ctx, high);
+        let no_overflow = gate.is_zero(ctx, high);
+        let overflow = gate.not(ctx, no_overflow);
+        let limb_bits = (0..prod.len()).map(|i| min(MUL_LIMB_BITS, TOTAL_BITS - i * MUL_LIMB_BITS));
+        let bytes = prod
+            .into_iter()
+            .zip(limb_bits)
+            .flat_map(|(limb, bits)| {
+                self.decompose_to_bytes(ctx, limb, (bits + BITS_PER_BYTE - 1) / BITS_PER_BYTE)
+            })
+            .collect::<Vec<_>>();
+        (self.compose_bytes_le(ctx, &bytes), SafeBool::new(vec![overflow]))
+    }
+
+    /// Returns `(a + b) mod 2^TOTAL_BITS`.
+    pub fn wrapping_add<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        self.checked_add(ctx, a, b).0
+    }
+
+    /// Returns `(a - b) mod 2^TOTAL_BITS`.
+    pub fn wrapping_sub<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        self.checked_sub(ctx, a, b).0
+    }
+
+    /// Returns `(a * b) mod 2^TOTAL_BITS`.
+    pub fn wrapping_mul<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        self.checked_mul(ctx, a, b).0
+    }
+
+    /// Casts an unsigned integer to a wider one with the same element size, e.g. [SafeUint32] to [SafeUint64].
+    pub fn upcast<const BYTES_PER_ELE: usize, const FROM_BITS: usize, const TO_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, FROM_BITS>,
+    ) -> SafeType<F, BYTES_PER_ELE, TO_BITS> {
+        assert!(FROM_BITS <= TO_BITS);
+        // elements of `a` are aligned with the elements of the output, only the missing high elements are zero
+        let mut value = a.value.clone();
+        value.resize(SafeType::<F, BYTES_PER_ELE, TO_BITS>::VALUE_LENGTH, ctx.load_zero());
+        SafeType::new(value)
+    }
+
+    /// Decomposes an unsigned integer into `ceil(TOTAL_BITS / 8)` little-endian bytes.
+    pub fn uint_to_bytes_le<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> Vec<SafeUint8<F>> {
+        let element_bits = SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits();
+        let mut bytes = Vec::with_capacity((TOTAL_BITS + BITS_PER_BYTE - 1) / BITS_PER_BYTE);
+        for (a, bits) in a.value.iter().zip(element_bits) {
+            let num_bytes = (bits + BITS_PER_BYTE - 1) / BITS_PER_BYTE;
+            bytes.extend(self.decompose_to_bytes(ctx, *a, num_bytes));
+        }
+        bytes.into_iter().map(|byte| SafeUint8::new(vec![byte])).collect()
+    }
+
+    /// Decomposes an unsigned integer into `ceil(TOTAL_BITS / 8)` big-endian bytes.
+    pub fn uint_to_bytes_be<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> Vec<SafeUint8<F>> {
+        let mut bytes = self.uint_to_bytes_le(ctx, a);
+        bytes.reverse();
+        bytes
+    }
+
+    /// Casts a [SafeUint256] to a [SafeBytes32]. Byte `i` of the output is byte `i` of the little-endian
+    /// representation of `a`, consistent with [Self::raw_bytes_to] on the same raw bytes.
+    pub fn uint256_to_bytes32(&self, ctx: &mut Context<F>, a: &SafeUint256<F>) -> SafeBytes32<F> {
+        let bytes = self.uint_to_bytes_le(ctx, a);
+        SafeBytes32::new(bytes.into_iter().map(|byte| byte.value[0]).collect())
+    }
+
+    /// Casts a [SafeBytes32] to a [SafeUint256], inverse of [Self::uint256_to_bytes32].
+    pub fn bytes32_to_uint256(&self, ctx: &mut Context<F>, a: &SafeBytes32<F>) -> SafeUint256<F> {
+        self.compose_bytes_le(ctx, &a.value)
+    }
 }
@@ -0,0 +1,199 @@
+use super::{SafeBool, SafeType, SafeTypeChip, SignedSafeType};
+use crate::{
+    gates::{GateInstructions, RangeInstructions},
+    utils::{fe_to_biguint, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+
+impl<'a, F: ScalarField> SafeTypeChip<'a, F> {
+    /// Reinterprets an unsigned integer as the two's complement of a signed integer. No constraints are added.
+    pub fn uint_to_int<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        a: SafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        SignedSafeType { inner: a }
+    }
+
+    /// Reinterprets a signed integer as the unsigned integer of its two's complement. No constraints are added.
+    pub fn int_to_uint<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        a: SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        a.inner
+    }
+
+    /// Splits `a < 2^bits` into `(a >> (bits - 1), a mod 2^(bits - 1))`.
+    fn split_msb(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        bits: usize,
+    ) -> (AssignedValue<F>, AssignedValue<F>) {
+        let gate = self.range_chip.gate();
+        let msb = fe_to_biguint(a.value()).bit(bits as u64 - 1);
+        let msb = ctx.load_witness(F::from(msb as u64));
+        gate.assert_bit(ctx, msb);
+        let low = gate.mul_add(ctx, msb, Constant(-gate.pow_of_two()[bits - 1]), a);
+        self.range_chip.range_check(ctx, low, bits - 1);
+        (msb, low)
+    }
+}
+
+impl<'a, F: ScalarField> SafeTypeChip<'a, F>
+where
+    [(); ((F::NUM_BITS - 1) / 8) as usize]:,
+{
+    /// Returns the sign bit of `a`, i.e. 1 if `a < 0`, otherwise 0.
+    pub fn sign_bit<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeBool<F> {
+        let bits = *SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits().last().unwrap();
+        let (sign, _) = self.split_msb(ctx, *a.value().last().unwrap(), bits);
+        SafeBool::new(vec![sign])
+    }
+
+    /// Returns 1 if `a < b` as signed integers, otherwise 0.
+    pub fn is_less_than_signed<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        b: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SafeBool<F> {
+        let gate = self.range_chip.gate();
+        // with equal signs, two's complements compare like the values; otherwise the negative one is smaller
+        let is_lt = self.is_less_than(ctx, &a.inner, &b.inner).value[0];
+        let [sign_a, sign_b] = [a, b].map(|x| self.sign_bit(ctx, x).value[0]);
+        let signs_differ = gate.xor(ctx, sign_a, sign_b);
+        SafeBool::new(vec![gate.select(ctx, sign_a, is_lt, signs_differ)])
+    }
+
+    /// Returns `-a` and whether the negation overflowed, i.e. whether `a` is the minimum value `-2^(TOTAL_BITS - 1)`.
+    pub fn checked_neg<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> (SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>, SafeBool<F>) {
+        let gate = self.range_chip.gate();
+        let zero = SafeType::new(vec![
+            ctx.load_zero();
+            SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::VALUE_LENGTH
+        ]);
+        let (neg, _) = self.checked_sub(ctx, &zero, &a.inner);
+        let neg = SignedSafeType { inner: neg };
+        // only the minimum value is negative with a negative negation
+        let [sign_a, sign_neg] = [a, &neg].map(|x| self.sign_bit(ctx, x).value[0]);
+        let overflow = gate.and(ctx, sign_a, sign_neg);
+        (neg, SafeBool::new(vec![overflow]))
+    }
+
+    /// Returns `-a`, wrapping the minimum value to itself.
+    pub fn wrapping_neg<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        self.checked_neg(ctx, a).0
+    }
+
+    /// Returns `a >> shift` rounding towards negative infinity, like Solidity `sar`.
+    ///
+    /// Decomposes `a` into `TOTAL_BITS` bits. `shift` must be less than `TOTAL_BITS`.
+    pub fn arithmetic_shr<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+        shift: usize,
+    ) -> SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        assert!(shift < TOTAL_BITS);
+        let gate = self.range_chip.gate();
+        let element_bits = SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::element_bits();
+        let bits = a
+            .value()
+            .iter()
+            .zip(element_bits.iter())
+            .flat_map(|(a, bits)| gate.num_to_bits(ctx, *a, *bits))
+            .collect::<Vec<_>>();
+        let sign = bits[TOTAL_BITS - 1];
+        let mut shifted = bits[shift..].iter().copied().chain(std::iter::repeat(sign).take(shift));
+        let value = element_bits
+            .iter()
+            .map(|bits| {
+                let bits = shifted.by_ref().take(*bits).collect::<Vec<_>>();
+                let bit_base = (0..bits.len()).map(|i| Constant(gate.pow_of_two()[i]));
+                gate.inner_product(ctx, bits, bit_base)
+            })
+            .collect();
+        SignedSafeType { inner: SafeType::new(value) }
+    }
+
+    /// Sign-extends a signed integer to a wider one with the same element size, e.g. [super::SafeInt32] to
+    /// [super::SafeInt64].
+    pub fn sign_extend<const BYTES_PER_ELE: usize, const FROM_BITS: usize, const TO_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, FROM_BITS>,
+    ) -> SignedSafeType<F, BYTES_PER_ELE, TO_BITS> {
+        assert!(FROM_BITS <= TO_BITS);
+        let gate = self.range_chip.gate();
+        let from_bits = SafeType::<F, BYTES_PER_ELE, FROM_BITS>::element_bits();
+        let to_bits = SafeType::<F, BYTES_PER_ELE, TO_BITS>::element_bits();
+        let sign = self.sign_bit(ctx, a).value[0];
+        let top = from_bits.len() - 1;
+        let value = to_bits
+            .iter()
+            .enumerate()
+            .map(|(i, bits)| {
+                let pow = gate.pow_of_two()[*bits];
+                if i < top {
+                    a.value()[i]
+                } else if i == top {
+                    // set the bits between the sign bit and the top of the element
+                    let extension = pow - gate.pow_of_two()[from_bits[top]];
+                    gate.mul_add(ctx, sign, Constant(extension), a.value()[top])
+                } else {
+                    gate.mul(ctx, sign, Constant(pow - F::one()))
+                }
+            })
+            .collect();
+        SignedSafeType { inner: SafeType::new(value) }
+    }
+
+    /// Returns the field element `a`, i.e. `p - |a|` for negative `a`.
+    ///
+    /// Only supports types with a single element such that `TOTAL_BITS < F::CAPACITY`.
+    pub fn int_to_fe<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: &SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS>,
+    ) -> AssignedValue<F> {
+        assert_eq!(SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::VALUE_LENGTH, 1);
+        assert!(TOTAL_BITS < F::CAPACITY as usize);
+        let gate = self.range_chip.gate();
+        let sign = self.sign_bit(ctx, a).value[0];
+        gate.mul_add(ctx, sign, Constant(-gate.pow_of_two()[TOTAL_BITS]), a.value()[0])
+    }
+
+    /// Constrains the field element `a` to represent an integer in `[-2^(TOTAL_BITS - 1), 2^(TOTAL_BITS - 1))`
+    /// and returns it as a signed integer. Inverse of [Self::int_to_fe].
+    ///
+    /// Only supports types with a single element such that `TOTAL_BITS < F::CAPACITY`.
+    pub fn fe_to_int<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+    ) -> SignedSafeType<F, BYTES_PER_ELE, TOTAL_BITS> {
+        assert_eq!(SafeType::<F, BYTES_PER_ELE, TOTAL_BITS>::VALUE_LENGTH, 1);
+        assert!(TOTAL_BITS < F::CAPACITY as usize);
+        let gate = self.range_chip.gate();
+        let half = gate.pow_of_two()[TOTAL_BITS - 1];
+        // a + 2^(TOTAL_BITS - 1) is in [0, 2^TOTAL_BITS), and is the two's complement of `a` with its top bit flipped
+        let shifted = gate.add(ctx, a, Constant(half));
+        let (msb, low) = self.split_msb(ctx, shifted, TOTAL_BITS);
+        let sign = gate.not(ctx, msb);
+        let value = gate.mul_add(ctx, sign, Constant(half), low);
+        SignedSafeType { inner: SafeType::new(vec![value]) }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     halo2_proofs::{halo2curves::bn256::Fr, poly::kzg::commitment::ParamsKZG},
-    utils::testing::{check_proof, gen_proof},
+    utils::testing::{check_proof, gen_proof, mock_verify},
 };
 
 use super::*;
@@ -254,3 +254,347 @@ fn test_raw_bytes_to_bytes32() {
         false,
     );
 }
+
+const UINT_BYTES: usize = SafeUint256::<Fr>::BYTES_PER_ELE;
+
+fn load_uint<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+    ctx: &mut Context<Fr>,
+    chip: &SafeTypeChip<Fr>,
+    value: &BigUint,
+) -> SafeType<Fr, BYTES_PER_ELE, TOTAL_BITS> {
+    let mut bytes = value.to_bytes_le();
+    bytes.resize(max(TOTAL_BITS, BITS_PER_BYTE) / BITS_PER_BYTE, 0);
+    let bytes = ctx.assign_witnesses(bytes.into_iter().map(|b| Fr::from(b as u64)));
+    chip.raw_bytes_to(ctx, bytes)
+}
+
+fn uint_value<const BYTES_PER_ELE: usize, const TOTAL_BITS: usize>(
+    a: &SafeType<Fr, BYTES_PER_ELE, TOTAL_BITS>,
+) -> BigUint {
+    let bits = SafeType::<Fr, BYTES_PER_ELE, TOTAL_BITS>::BITS_PER_ELE;
+    a.value()
+        .iter()
+        .rev()
+        .fold(BigUint::from(0u64), |acc, x| (acc << bits) + fe_to_biguint(x.value()))
+}
+
+fn u256(hex: &str) -> BigUint {
+    BigUint::parse_bytes(hex.as_bytes(), 16).unwrap()
+}
+
+#[test]
+fn test_safe_uint_comparisons() {
+    env::set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range_chip = RangeChip::<Fr>::default(8);
+    let chip = SafeTypeChip::new(&range_chip);
+
+    let values = [0u64, 1, 0xffff_ffff, u64::MAX - 1, u64::MAX];
+    for a in values {
+        for b in values {
+            let [x, y] = [a, b].map(|v| load_uint::<UINT_BYTES, 64>(ctx, &chip, &BigUint::from(v)));
+            assert_eq!(
+                *chip.is_less_than(ctx, &x, &y).value()[0].value(),
+                Fr::from((a < b) as u64)
+            );
+            assert_eq!(*chip.is_equal(ctx, &x, &y).value()[0].value(), Fr::from((a == b) as u64));
+        }
+    }
+    // uint256 spans two elements: only the high byte differs, or only the low element differs
+    let values = [
+        u256("0"),
+        u256("ff"),
+        u256("100000000000000000000000000000000000000000000000000000000000000"),
+        u256("1000000000000000000000000000000000000000000000000000000000000ff"),
+        u256("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"),
+    ];
+    for a in values.iter() {
+        for b in values.iter() {
+            let [x, y] = [a, b].map(|v| load_uint::<UINT_BYTES, 256>(ctx, &chip, v));
+            assert_eq!(
+                *chip.is_less_than(ctx, &x, &y).value()[0].value(),
+                Fr::from((a < b) as u64)
+            );
+            assert_eq!(*chip.is_equal(ctx, &x, &y).value()[0].value(), Fr::from((a == b) as u64));
+        }
+    }
+    mock_verify(12, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_safe_uint_arithmetic() {
+    env::set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range_chip = RangeChip::<Fr>::default(8);
+    let chip = SafeTypeChip::new(&range_chip);
+
+    let values = [0u64, 1, 3, 0xffff_ffff, 0x1_0000_0000, u64::MAX];
+    for a in values {
+        for b in values {
+            let [x, y] = [a, b].map(|v| load_uint::<UINT_BYTES, 64>(ctx, &chip, &BigUint::from(v)));
+            let (sum, overflow) = chip.checked_add(ctx, &x, &y);
+            assert_eq!(uint_value(&sum), BigUint::from(a.wrapping_add(b)));
+            assert_eq!(*overflow.value()[0].value(), Fr::from(a.checked_add(b).is_none() as u64));
+            let (diff, underflow) = chip.checked_sub(ctx, &x, &y);
+            assert_eq!(uint_value(&diff), BigUint::from(a.wrapping_sub(b)));
+            assert_eq!(*underflow.value()[0].value(), Fr::from((a < b) as u64));
+            let (prod, overflow) = chip.checked_mul(ctx, &x, &y);
+            assert_eq!(uint_value(&prod), BigUint::from(a.wrapping_mul(b)));
+            assert_eq!(*overflow.value()[0].value(), Fr::from(a.checked_mul(b).is_none() as u64));
+        }
+    }
+
+    // carries and borrows propagate across the elements of a uint256
+    let modulus = BigUint::from(1u64) << 256;
+    let values = [
+        u256("0"),
+        u256("1"),
+        u256("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"),
+        u256("ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"),
+    ];
+    for a in values.iter() {
+        for b in values.iter() {
+            let [x, y] = [a, b].map(|v| load_uint::<UINT_BYTES, 256>(ctx, &chip, v));
+            let sum = chip.wrapping_add(ctx, &x, &y);
+            assert_eq!(uint_value(&sum), (a + b) % &modulus);
+            let (diff, underflow) = chip.checked_sub(ctx, &x, &y);
+            assert_eq!(uint_value(&diff), (a + &modulus - b) % &modulus);
+            assert_eq!(*underflow.value()[0].value(), Fr::from((a < b) as u64));
+            let (prod, overflow) = chip.checked_mul(ctx, &x, &y);
+            assert_eq!(uint_value(&prod), (a * b) % &modulus);
+            assert_eq!(*overflow.value()[0].value(), Fr::from((a * b >= modulus) as u64));
+        }
+    }
+
+    // products of types wider than the native field are split at `TOTAL_BITS` within a limb
+    let modulus = BigUint::from(1u64) << 160;
+    let values = [u256("0"), u256("3"), u256("ffffffffffffffffffff"), &modulus - 1u64];
+    for a in values.iter() {
+        for b in values.iter() {
+            let [x, y] = [a, b].map(|v| load_uint::<UINT_BYTES, 160>(ctx, &chip, v));
+            let (prod, overflow) = chip.checked_mul(ctx, &x, &y);
+            assert_eq!(uint_value(&prod), (a * b) % &modulus);
+            assert_eq!(*overflow.value()[0].value(), Fr::from((a * b >= modulus) as u64));
+        }
+    }
+    mock_verify(12, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_safe_type_casts() {
+    env::set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range_chip = RangeChip::<Fr>::default(8);
+    let chip = SafeTypeChip::new(&range_chip);
+
+    let value = u256("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20");
+    let uint256 = load_uint::<UINT_BYTES, 256>(ctx, &chip, &value);
+    let bytes32 = chip.uint256_to_bytes32(ctx, &uint256);
+    let expected = value.to_bytes_le().into_iter().map(|b| Fr::from(b as u64)).collect_vec();
+    assert_eq!(bytes32.value().iter().map(|b| *b.value()).collect_vec(), expected);
+    let roundtrip = chip.bytes32_to_uint256(ctx, &bytes32);
+    assert_eq!(uint_value(&roundtrip), value);
+
+    let be = chip.uint_to_bytes_be(ctx, &uint256);
+    let expected = value.to_bytes_be().into_iter().map(|b| Fr::from(b as u64)).collect_vec();
+    assert_eq!(be.iter().map(|b| *b.value()[0].value()).collect_vec(), expected);
+
+    let uint32 = load_uint::<UINT_BYTES, 32>(ctx, &chip, &BigUint::from(0xdeadbeefu64));
+    let uint64: SafeUint64<Fr> = chip.upcast(ctx, &uint32);
+    assert_eq!(uint_value(&uint64), BigUint::from(0xdeadbeefu64));
+    let le = chip.uint_to_bytes_le(ctx, &uint64);
+    let expected = [0xef, 0xbe, 0xad, 0xde, 0, 0, 0, 0].map(|b| Fr::from(b as u64));
+    assert_eq!(le.iter().map(|b| *b.value()[0].value()).collect_vec(), expected);
+    mock_verify(12, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+fn load_abi_data(
+    ctx: &mut Context<Fr>,
+    chip: &SafeTypeChip<Fr>,
+    data: &[u8],
+) -> Vec<SafeUint8<Fr>> {
+    data.iter().map(|b| load_uint::<UINT_BYTES, 8>(ctx, chip, &BigUint::from(*b))).collect()
+}
+
+fn abi_word(value: &[u8], pad: u8, left: bool) -> Vec<u8> {
+    let padding = vec![pad; abi::WORD_BYTES - value.len()];
+    if left {
+        [padding, value.to_vec()].concat()
+    } else {
+        [value.to_vec(), padding].concat()
+    }
+}
+
+#[test]
+fn test_abi_decode_encode() {
+    use abi::{AbiType, AbiValue};
+    env::set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range_chip = RangeChip::<Fr>::default(8);
+    let chip = SafeTypeChip::new(&range_chip);
+
+    // (uint256, int24, address, bool, bytes32, uint8[2], bytes4)
+    let ty = AbiType::Tuple(vec![
+        AbiType::Uint(256),
+        AbiType::Int(24),
+        AbiType::Address,
+        AbiType::Bool,
+        AbiType::FixedBytes(32),
+        AbiType::Array(Box::new(AbiType::Uint(8)), 2),
+        AbiType::FixedBytes(4),
+    ]);
+    let uint = u256("0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20");
+    let address = (0..20u8).map(|i| i * 0x11).collect_vec();
+    let bytes32 = (0..32u8).map(|i| i * 3).collect_vec();
+    let data = [
+        uint.to_bytes_be(),
+        abi_word(&[0xff, 0xff, 0xfe], 0xff, true),
+        abi_word(&address, 0, true),
+        abi_word(&[1], 0, true),
+        bytes32.clone(),
+        abi_word(&[7], 0, true),
+        abi_word(&[0xff], 0, true),
+        abi_word(&[0xde, 0xad, 0xbe, 0xef], 0, false),
+    ]
+    .concat();
+    assert_eq!(data.len(), ty.encoded_len());
+    let assigned = load_abi_data(ctx, &chip, &data);
+
+    let value = chip.abi_decode(ctx, &ty, &assigned);
+    let AbiValue::Tuple(members) = &value else { panic!("expected tuple") };
+    assert_eq!(members.len(), 7);
+    match &members[..] {
+        [AbiValue::Uint256(a), AbiValue::Uint32(b), AbiValue::Address(c), AbiValue::Bool(d), AbiValue::Bytes32(e), AbiValue::Array(f), AbiValue::FixedBytes(g)] =>
+        {
+            assert_eq!(uint_value(a), uint);
+            // int24(-2) as the two's complement at 32 bits
+            assert_eq!(uint_value(b), BigUint::from(0xffff_fffeu64));
+            assert_eq!(uint_value(c), BigUint::from_bytes_be(&address));
+            assert_eq!(*d.value()[0].value(), Fr::one());
+            assert_eq!(
+                e.value().iter().map(|b| *b.value()).collect_vec(),
+                bytes32.iter().map(|b| Fr::from(*b as u64)).collect_vec()
+            );
+            let f = f
+                .iter()
+                .map(|v| match v {
+                    AbiValue::Uint8(v) => uint_value(v),
+                    _ => panic!("expected uint8"),
+                })
+                .collect_vec();
+            assert_eq!(f, [7u64, 255].map(BigUint::from));
+            assert_eq!(
+                g.iter().map(|b| *b.value()[0].value()).collect_vec(),
+                [0xde, 0xad, 0xbe, 0xef].map(|b| Fr::from(b as u64))
+            );
+        }
+        _ => panic!("unexpected decoded types"),
+    }
+
+    let encoded = chip.abi_encode(ctx, &ty, &value);
+    assert_eq!(
+        encoded.iter().map(|b| *b.value()[0].value()).collect_vec(),
+        data.iter().map(|b| Fr::from(*b as u64)).collect_vec()
+    );
+    mock_verify(12, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_abi_decode_invalid_padding() {
+    use abi::AbiType;
+    env::set_var("LOOKUP_BITS", "8");
+    let cases = [
+        // uint8 with a non-zero padding byte
+        (AbiType::Uint(8), abi_word(&[1, 7], 0, true)),
+        // negative int16 padded with zeros
+        (AbiType::Int(16), abi_word(&[0xff, 0xfe], 0, true)),
+        // positive int16 padded with 0xff
+        (AbiType::Int(16), abi_word(&[0x7f, 0xfe], 0xff, true)),
+        // bool which is not 0 or 1
+        (AbiType::Bool, abi_word(&[2], 0, true)),
+        // bytes2 with a non-zero padding byte
+        (AbiType::FixedBytes(2), abi_word(&[1, 2, 3], 0, false)),
+    ];
+    for (ty, data) in cases {
+        let mut builder = GateThreadBuilder::mock();
+        let ctx = builder.main(0);
+        let range_chip = RangeChip::<Fr>::default(8);
+        let chip = SafeTypeChip::new(&range_chip);
+        let assigned = load_abi_data(ctx, &chip, &data);
+        chip.abi_decode(ctx, &ty, &assigned);
+        assert!(mock_verify(12, &RangeCircuitBuilder::mock(builder)).is_err(), "{ty:?}");
+    }
+}
+
+fn load_int64(ctx: &mut Context<Fr>, chip: &SafeTypeChip<Fr>, value: i64) -> SafeInt64<Fr> {
+    chip.uint_to_int(load_uint::<UINT_BYTES, 64>(ctx, chip, &BigUint::from(value as u64)))
+}
+
+fn int64_value(a: &SafeInt64<Fr>) -> i64 {
+    let value = uint_value(a.as_unsigned());
+    value.to_u64_digits().first().copied().unwrap_or(0) as i64
+}
+
+#[test]
+fn test_safe_int_ops() {
+    env::set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range_chip = RangeChip::<Fr>::default(8);
+    let chip = SafeTypeChip::new(&range_chip);
+
+    let values = [0i64, 1, -1, 0x1234_5678, -0x1234_5678, i64::MIN, i64::MAX];
+    for a in values {
+        let x = load_int64(ctx, &chip, a);
+        assert_eq!(*chip.sign_bit(ctx, &x).value()[0].value(), Fr::from((a < 0) as u64));
+        let (neg, overflow) = chip.checked_neg(ctx, &x);
+        assert_eq!(int64_value(&neg), a.wrapping_neg());
+        assert_eq!(*overflow.value()[0].value(), Fr::from((a == i64::MIN) as u64));
+        for shift in [0, 1, 7, 63] {
+            assert_eq!(int64_value(&chip.arithmetic_shr(ctx, &x, shift)), a >> shift);
+        }
+        let fe = chip.int_to_fe(ctx, &x);
+        let expected = if a < 0 { -Fr::from(a.unsigned_abs()) } else { Fr::from(a as u64) };
+        assert_eq!(*fe.value(), expected);
+        let roundtrip: SafeInt64<Fr> = chip.fe_to_int(ctx, fe);
+        assert_eq!(int64_value(&roundtrip), a);
+        for b in values {
+            let y = load_int64(ctx, &chip, b);
+            assert_eq!(
+                *chip.is_less_than_signed(ctx, &x, &y).value()[0].value(),
+                Fr::from((a < b) as u64)
+            );
+        }
+    }
+
+    // int32 -> int64 and int64 -> int256 sign extension
+    let int32: SafeInt32<Fr> =
+        chip.uint_to_int(load_uint::<UINT_BYTES, 32>(ctx, &chip, &BigUint::from(-5i32 as u32)));
+    let int64: SafeInt64<Fr> = chip.sign_extend(ctx, &int32);
+    assert_eq!(int64_value(&int64), -5);
+    let int256: SafeInt256<Fr> = chip.sign_extend(ctx, &int64);
+    let expected = (BigUint::from(1u64) << 256) - 5u64;
+    assert_eq!(uint_value(int256.as_unsigned()), expected);
+    // sar on a value spanning two elements
+    let shifted = chip.arithmetic_shr(ctx, &int256, 250);
+    assert_eq!(uint_value(shifted.as_unsigned()), (BigUint::from(1u64) << 256) - 1u64);
+    mock_verify(12, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_fe_to_int_out_of_range() {
+    env::set_var("LOOKUP_BITS", "8");
+    for value in [Fr::from(128), -Fr::from(129)] {
+        let mut builder = GateThreadBuilder::mock();
+        let ctx = builder.main(0);
+        let range_chip = RangeChip::<Fr>::default(8);
+        let chip = SafeTypeChip::new(&range_chip);
+        let a = ctx.load_witness(value);
+        let _: SafeInt8<Fr> = chip.fe_to_int(ctx, a);
+        assert!(mock_verify(12, &RangeCircuitBuilder::mock(builder)).is_err());
+    }
+}
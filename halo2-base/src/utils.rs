@@ -290,6 +290,30 @@ pub fn compose(input: Vec<BigUint>, bit_len: usize) -> BigUint {
     input.iter().rev().fold(BigUint::zero(), |acc, val| (acc << bit_len) + val)
 }
 
+/// Returns the inverses of `values`, mapping 0 to 0, with a single field inversion (Montgomery's trick).
+/// * `values`: the field elements to invert
+pub fn batch_invert<F: ScalarField>(values: &[F]) -> Vec<F> {
+    // prefix[i] is the product of the nonzero values in values[..i]
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut acc = F::one();
+    for value in values {
+        prefix.push(acc);
+        if !value.is_zero_vartime() {
+            acc *= value;
+        }
+    }
+    // acc_inv is the inverse of the product of the nonzero values in values[..=i]
+    let mut acc_inv = acc.invert().unwrap();
+    let mut inverses = vec![F::zero(); values.len()];
+    for ((inverse, value), prefix) in inverses.iter_mut().zip(values).zip(prefix).rev() {
+        if !value.is_zero_vartime() {
+            *inverse = acc_inv * prefix;
+            acc_inv *= value;
+        }
+    }
+    inverses
+}
+
 #[cfg(feature = "halo2-axiom")]
 pub use halo2_proofs_axiom::halo2curves::CurveAffineExt;
 
@@ -404,7 +428,9 @@ pub mod fs {
 /// Utilities for testing
 #[cfg(any(test, feature = "test-utils"))]
 pub mod testing {
+    use crate::gates::builder::GateCircuitBuilder;
     use crate::halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
         halo2curves::bn256::{Bn256, Fr, G1Affine},
         plonk::{create_proof, verify_proof, Circuit, ProvingKey, VerifyingKey},
         poly::commitment::ParamsProver,
@@ -418,6 +444,18 @@ pub mod testing {
     };
     use rand::rngs::OsRng;
 
+    /// Configures `circuit` for `2^k` rows, 9 of which are left to blinding factors, and returns the result of the
+    /// [MockProver] on it, e.g. `mock_verify(k, &RangeCircuitBuilder::mock(builder))`.
+    ///
+    /// The circuit is configured once it is created, so that phase 1 callbacks registered while creating it count.
+    pub fn mock_verify<C: Circuit<Fr> + AsRef<GateCircuitBuilder<Fr>>>(
+        k: usize,
+        circuit: &C,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        circuit.as_ref().builder.borrow().config(k, Some(9));
+        MockProver::run(k as u32, circuit, vec![]).unwrap().verify()
+    }
+
     /// helper function to generate a proof with real prover
     pub fn gen_proof(
         params: &ParamsKZG<Bn256>,
@@ -461,6 +499,36 @@ pub mod testing {
             assert!(res.is_err());
         }
     }
+
+    /// Reference RLP encodings to build test vectors
+    pub mod rlp {
+        use std::iter;
+
+        /// Returns the RLP prefix of a string (`offset = 0x80`) or of a list (`offset = 0xc0`) of `len` payload bytes.
+        pub fn encode_len(len: usize, offset: u8) -> Vec<u8> {
+            if len <= 55 {
+                vec![offset + len as u8]
+            } else {
+                let len_bytes =
+                    len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect::<Vec<_>>();
+                iter::once(offset + 55 + len_bytes.len() as u8).chain(len_bytes).collect()
+            }
+        }
+
+        /// Returns the RLP encoding of the string `bytes`.
+        pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+            if bytes.len() == 1 && bytes[0] < 0x80 {
+                return bytes.to_vec();
+            }
+            [encode_len(bytes.len(), 0x80), bytes.to_vec()].concat()
+        }
+
+        /// Returns the RLP encoding of the list of the strings `items`.
+        pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+            let payload = items.iter().map(|item| encode_bytes(item)).collect::<Vec<_>>().concat();
+            [encode_len(payload.len(), 0xc0), payload].concat()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -478,6 +546,19 @@ mod tests {
         assert_eq!(fe_to_bigint(&bigint_to_fe::<Fr>(&-BigInt::one())), -BigInt::one());
     }
 
+    #[test]
+    fn test_batch_invert() {
+        let values = [3, 0, 1, 12345, 0].map(Fr::from);
+        let inverses = batch_invert(&values);
+        for (value, inverse) in values.iter().zip(inverses) {
+            if *value == Fr::zero() {
+                assert_eq!(inverse, Fr::zero());
+            } else {
+                assert_eq!(inverse * value, Fr::one());
+            }
+        }
+    }
+
     #[test]
     fn test_decompose_biguint() {
         let mut rng = OsRng;
@@ -0,0 +1,527 @@
+//! Non-modular arithmetic on big unsigned integers of a variable number of limbs.
+//!
+//! [BigUintChip] operates on [ProperUint]s, whose limbs are range checked to `limb_bits` bits. Unlike [FpChip], the
+//! integers are not reduced modulo a fixed prime: results grow by as many limbs as needed to hold them exactly, and
+//! moduli are circuit values. This is what RSA signature and VDF verification need.
+//!
+//! Products are constrained by witnessing the result and checking that the difference between the polynomial product
+//! of the limbs and the result carries to zero, see [check_carry_to_zero::truncate].
+//!
+//! [FpChip]: crate::fields::fp::FpChip
+use super::{big_is_equal, big_less_than, check_carry_to_zero, sub, OverflowInteger, ProperUint};
+use halo2_base::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{biguint_to_fe, bit_length, fe_to_biguint, BigPrimeField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+#[cfg(test)]
+mod tests;
+
+/// Operands with at most this many limbs are multiplied with the schoolbook method by [BigUintChip::mul_karatsuba].
+///
+/// A Karatsuba step saves a quarter of the `3 * k^2` cells of a schoolbook product of `k` limbs, but costs about
+/// `24 * k` cells of additions, so it only pays off for more than 32 limbs.
+pub const KARATSUBA_THRESHOLD: usize = 32;
+
+/// Chip for arithmetic on big unsigned integers represented as [ProperUint]s with limbs of `limb_bits` bits.
+///
+/// Integers with `k` limbs are less than `2^(limb_bits * k)`. Costs are given for operands of `k` limbs.
+#[derive(Clone, Debug)]
+pub struct BigUintChip<'range, F: BigPrimeField> {
+    /// Chip for the range checks of limbs and carries.
+    pub range: &'range RangeChip<F>,
+    /// Number of bits of each limb.
+    pub limb_bits: usize,
+    /// `2^limb_bits` in the native field.
+    pub limb_base: F,
+    /// `2^limb_bits` as a big integer.
+    pub limb_base_big: BigInt,
+}
+
+impl<'range, F: BigPrimeField> BigUintChip<'range, F> {
+    /// Creates a new chip for limbs of `limb_bits` bits.
+    ///
+    /// Products of limbs must fit in the native field with room for carries, so `limb_bits` should be well below
+    /// `F::CAPACITY / 2`: operations assert this for the number of limbs of their operands.
+    pub fn new(range: &'range RangeChip<F>, limb_bits: usize) -> Self {
+        assert!(limb_bits > 0);
+        assert!(2 * limb_bits < F::CAPACITY as usize);
+        let limb_base = biguint_to_fe(&(BigUint::one() << limb_bits));
+        Self { range, limb_bits, limb_base, limb_base_big: BigInt::one() << limb_bits }
+    }
+
+    /// Returns the gate of [Self::range].
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Loads `value` as a witness of `num_limbs` range checked limbs.
+    ///
+    /// # Panics
+    /// If `value` does not fit in `num_limbs` limbs.
+    pub fn load_witness(
+        &self,
+        ctx: &mut Context<F>,
+        value: &BigUint,
+        num_limbs: usize,
+    ) -> ProperUint<F> {
+        assert!(value.bits() as usize <= self.limb_bits * num_limbs, "value does not fit in limbs");
+        self.assign_limbs(ctx, value, num_limbs)
+    }
+
+    /// Loads `value` as a constant of `num_limbs` limbs.
+    ///
+    /// # Panics
+    /// If `value` does not fit in `num_limbs` limbs.
+    pub fn load_constant(
+        &self,
+        ctx: &mut Context<F>,
+        value: &BigUint,
+        num_limbs: usize,
+    ) -> ProperUint<F> {
+        assert!(value.bits() as usize <= self.limb_bits * num_limbs, "value does not fit in limbs");
+        let limbs =
+            self.decompose(value, num_limbs).into_iter().map(|limb| ctx.load_constant(limb));
+        ProperUint(limbs.collect())
+    }
+
+    /// Returns the value of `a`, computed outside the circuit.
+    pub fn value(&self, a: &ProperUint<F>) -> BigUint {
+        self.evaluate(a.limbs())
+    }
+
+    /// Returns `a + b`, with one more limb than the longer operand for the final carry.
+    ///
+    /// Costs a range check of `limb_bits` bits and O(1) cells per limb.
+    pub fn add(&self, ctx: &mut Context<F>, a: &ProperUint<F>, b: &ProperUint<F>) -> ProperUint<F> {
+        let gate = self.gate();
+        let k = a.limbs().len().max(b.limbs().len());
+        let (a, b) = (self.pad(ctx, a, k), self.pad(ctx, b, k));
+        let mut limbs = Vec::with_capacity(k + 1);
+        let mut carry = None;
+        for (a_limb, b_limb) in a.limbs().iter().zip(b.limbs()) {
+            let sum = gate.add(ctx, *a_limb, *b_limb);
+            let sum = match carry {
+                Some(carry) => gate.add(ctx, sum, carry),
+                None => sum,
+            };
+            // the sum is less than `2^(limb_bits + 1)`, so the carry is a bit
+            let (next_carry, limb) =
+                self.range.div_mod(ctx, sum, BigUint::one() << self.limb_bits, self.limb_bits + 1);
+            limbs.push(limb);
+            carry = Some(next_carry);
+        }
+        limbs.push(carry.unwrap_or_else(|| ctx.load_zero()));
+        ProperUint(limbs)
+    }
+
+    /// Returns `(a - b mod 2^(limb_bits * k), borrow)` where `borrow` is 1 if `a < b`, otherwise 0, and `k` is the
+    /// number of limbs of the longer operand.
+    ///
+    /// Costs O(1) cells and a comparison of `limb_bits + 1` bits per limb.
+    pub fn sub(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+    ) -> (ProperUint<F>, AssignedValue<F>) {
+        let k = a.limbs().len().max(b.limbs().len());
+        let (a, b) = (self.pad(ctx, a, k), self.pad(ctx, b, k));
+        // the limbs of the difference are proper since those of `a` and `b` are
+        let (diff, borrow) = sub::assign(self.range, ctx, a, b, self.limb_bits, self.limb_base);
+        (ProperUint(diff.limbs), borrow)
+    }
+
+    /// Returns 1 if `a < b`, otherwise 0.
+    ///
+    /// Costs O(1) cells and a comparison of `limb_bits + 1` bits per limb.
+    pub fn is_less_than(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+    ) -> AssignedValue<F> {
+        let k = a.limbs().len().max(b.limbs().len());
+        let (a, b) = (self.pad(ctx, a, k), self.pad(ctx, b, k));
+        big_less_than::assign(self.range, ctx, a, b, self.limb_bits, self.limb_base)
+    }
+
+    /// Returns 1 if `a == b`, otherwise 0. The operands may have different numbers of limbs.
+    ///
+    /// Costs O(1) cells per limb.
+    pub fn is_equal(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+    ) -> AssignedValue<F> {
+        let k = a.limbs().len().max(b.limbs().len());
+        let (a, b) = (self.pad(ctx, a, k), self.pad(ctx, b, k));
+        big_is_equal::assign(self.gate(), ctx, a, b)
+    }
+
+    /// Returns `a * b` with schoolbook multiplication, with as many limbs as `a` and `b` together.
+    ///
+    /// Costs about `3 * a.limbs().len() * b.limbs().len()` cells, plus range checks and a carry check per limb of
+    /// the product.
+    pub fn mul(&self, ctx: &mut Context<F>, a: &ProperUint<F>, b: &ProperUint<F>) -> ProperUint<F> {
+        let product = self.schoolbook(ctx, a.limbs(), b.limbs());
+        self.carry(ctx, product, a.limbs().len(), b.limbs().len())
+    }
+
+    /// Returns `a * b` with Karatsuba multiplication, with as many limbs as `a` and `b` together. The shorter operand
+    /// is padded with zero limbs.
+    ///
+    /// Same as [Self::mul] for at most [KARATSUBA_THRESHOLD] limbs, and cheaper above.
+    pub fn mul_karatsuba(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+    ) -> ProperUint<F> {
+        let product = self.karatsuba_padded(ctx, a.limbs(), b.limbs());
+        self.carry(ctx, product, a.limbs().len(), b.limbs().len())
+    }
+
+    /// Returns `(a / b, a % b)`, with as many limbs as `a` and `b` respectively. Constrains `b != 0`.
+    ///
+    /// Costs a product of `a.limbs().len()` by `b.limbs().len()` limbs, and a comparison of the remainder with `b`.
+    pub fn div_rem(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+    ) -> (ProperUint<F>, ProperUint<F>) {
+        let gate = self.gate();
+        let (k_a, k_b) = (a.limbs().len(), b.limbs().len());
+        let b_value = self.value(b);
+        // the circuit is not satisfied if `b == 0`, witness zeros then
+        let (q, r) = if b_value.is_zero() {
+            (BigUint::zero(), BigUint::zero())
+        } else {
+            self.value(a).div_rem(&b_value)
+        };
+        let q = self.assign_limbs(ctx, &q, k_a);
+        let r = self.assign_limbs(ctx, &r, k_b);
+        let r_lt_b = self.is_less_than(ctx, &r, b);
+        gate.assert_is_const(ctx, &r_lt_b, &F::one());
+
+        // `q * b + r < (q + 1) * b <= 2^(limb_bits * (k_a + k_b))` and `a < 2^(limb_bits * k_a)`
+        let mut lhs = self.karatsuba_padded(ctx, q.limbs(), b.limbs());
+        for (limb, r_limb) in lhs.iter_mut().zip(r.limbs()) {
+            *limb = gate.add(ctx, *limb, *r_limb);
+        }
+        let max_limb_bits = self.product_limb_bits(k_a.min(k_b) + 1);
+        self.assert_equal_limbs(ctx, &lhs, a.limbs(), max_limb_bits, k_a + k_b);
+        (q, r)
+    }
+
+    /// Returns `a * b mod m`, with as many limbs as `m`. Constrains `m != 0`.
+    ///
+    /// Assumes `a, b < m`, which holds for outputs of this function: otherwise the quotient may not fit in the limbs
+    /// of `m`, and the circuit is not satisfied.
+    ///
+    /// Costs two products of about `m.limbs().len()` limbs each, and a comparison of the remainder with `m`.
+    pub fn mul_mod(
+        &self,
+        ctx: &mut Context<F>,
+        a: &ProperUint<F>,
+        b: &ProperUint<F>,
+        m: &ProperUint<F>,
+    ) -> ProperUint<F> {
+        let gate = self.gate();
+        let (k_a, k_b, k_m) = (a.limbs().len(), b.limbs().len(), m.limbs().len());
+        let m_value = self.value(m);
+        let (q, r) = if m_value.is_zero() {
+            (BigUint::zero(), BigUint::zero())
+        } else {
+            (self.value(a) * self.value(b)).div_rem(&m_value)
+        };
+        // the quotient is truncated if it does not fit, and the carry check fails
+        let q = self.assign_limbs(ctx, &q, k_m);
+        let r = self.assign_limbs(ctx, &r, k_m);
+        let r_lt_m = self.is_less_than(ctx, &r, m);
+        gate.assert_is_const(ctx, &r_lt_m, &F::one());
+
+        // `a * b < 2^(limb_bits * (k_a + k_b))` and `q * m + r < (q + 1) * m <= 2^(limb_bits * 2 * k_m)`
+        let lhs = self.karatsuba_padded(ctx, a.limbs(), b.limbs());
+        let mut rhs = self.karatsuba_padded(ctx, q.limbs(), m.limbs());
+        for (limb, r_limb) in rhs.iter_mut().zip(r.limbs()) {
+            *limb = gate.add(ctx, *limb, *r_limb);
+        }
+        let max_limb_bits = self.product_limb_bits(k_a.min(k_b).max(k_m + 1));
+        self.assert_equal_limbs(ctx, &lhs, &rhs, max_limb_bits, (k_a + k_b).max(2 * k_m));
+        r
+    }
+
+    /// Returns `base^exp mod m`, with as many limbs as `m`, for an exponent in the circuit of at most `exp_bits` bits.
+    /// Constrains `m != 0` and `exp < 2^exp_bits`.
+    ///
+    /// Costs a reduction of `base` and two [Self::mul_mod] per bit of `exp_bits`, whatever the value of `exp`: pass
+    /// the smallest bound known for the exponent rather than `limb_bits` times its number of limbs.
+    ///
+    /// # Panics
+    /// If `exp_bits` is more than `limb_bits` times the number of limbs of `exp`.
+    pub fn mod_exp(
+        &self,
+        ctx: &mut Context<F>,
+        base: &ProperUint<F>,
+        exp: &ProperUint<F>,
+        exp_bits: usize,
+        m: &ProperUint<F>,
+    ) -> ProperUint<F> {
+        assert!(
+            exp_bits <= self.limb_bits * exp.limbs().len(),
+            "exp_bits exceeds the limbs of exp"
+        );
+        let gate = self.gate();
+        let k_m = m.limbs().len();
+        let (_, base) = self.div_rem(ctx, base, m);
+        // `1 * 1 = q * m + r` has a quotient of at most 1, so `1` need not be reduced
+        let mut acc = self.load_constant(ctx, &BigUint::one(), k_m);
+        let mut bits = Vec::with_capacity(exp_bits);
+        for (i, limb) in exp.limbs().iter().enumerate() {
+            let num_bits = exp_bits.saturating_sub(i * self.limb_bits).min(self.limb_bits);
+            if num_bits == 0 {
+                gate.assert_is_const(ctx, limb, &F::zero());
+            } else {
+                // constrains `limb < 2^num_bits`
+                bits.extend(gate.num_to_bits(ctx, *limb, num_bits));
+            }
+        }
+        for bit in bits.into_iter().rev() {
+            acc = self.mul_mod(ctx, &acc, &acc, m);
+            let product = self.mul_mod(ctx, &acc, &base, m);
+            let limbs = product
+                .limbs()
+                .iter()
+                .zip(acc.limbs())
+                .map(|(x, y)| gate.select(ctx, *x, *y, bit))
+                .collect();
+            acc = ProperUint(limbs);
+        }
+        acc
+    }
+
+    /// Returns `base^exp mod m`, with as many limbs as `m`, for a constant exponent such as an RSA public exponent.
+    /// Constrains `m != 0`.
+    ///
+    /// Costs a reduction of `base`, a [Self::mul_mod] per bit of `exp` after the first, and another per bit set.
+    pub fn mod_exp_const(
+        &self,
+        ctx: &mut Context<F>,
+        base: &ProperUint<F>,
+        exp: &BigUint,
+        m: &ProperUint<F>,
+    ) -> ProperUint<F> {
+        if exp.is_zero() {
+            let one = self.load_constant(ctx, &BigUint::one(), 1);
+            return self.div_rem(ctx, &one, m).1;
+        }
+        let (_, base) = self.div_rem(ctx, base, m);
+        let mut acc = base.clone();
+        for i in (0..exp.bits() - 1).rev() {
+            acc = self.mul_mod(ctx, &acc, &acc, m);
+            if exp.bit(i) {
+                acc = self.mul_mod(ctx, &acc, &base, m);
+            }
+        }
+        acc
+    }
+
+    /// Returns the coefficients of the polynomial product of the limbs of `a` and `b`.
+    fn schoolbook(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        (0..a.len() + b.len() - 1)
+            .map(|i| {
+                let start = (i + 1).saturating_sub(b.len());
+                let end = (i + 1).min(a.len());
+                let b_limbs = (start..end).map(|j| b[i - j]);
+                gate.inner_product(ctx, a[start..end].iter().copied(), b_limbs.map(Into::into))
+            })
+            .collect()
+    }
+
+    /// Returns the coefficients of the polynomial product of the limbs of `a` and `b`, of the same length, splitting
+    /// them in halves recursively above [KARATSUBA_THRESHOLD] limbs.
+    fn karatsuba(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let k = a.len();
+        debug_assert_eq!(k, b.len());
+        if k <= KARATSUBA_THRESHOLD {
+            return self.schoolbook(ctx, a, b);
+        }
+        let gate = self.gate();
+        let half = k / 2;
+        let (a_lo, a_hi) = a.split_at(half);
+        let (b_lo, b_hi) = b.split_at(half);
+        let z0 = self.karatsuba(ctx, a_lo, b_lo);
+        let z2 = self.karatsuba(ctx, a_hi, b_hi);
+        // the high halves are at least as long as the low halves
+        let a_sum = self.add_no_carry(ctx, a_hi, a_lo);
+        let b_sum = self.add_no_carry(ctx, b_hi, b_lo);
+        let z1 = self.karatsuba(ctx, &a_sum, &b_sum);
+
+        // `a * b = z0 + (z1 - z0 - z2) * x^half + z2 * x^(2 * half)`
+        (0..2 * k - 1)
+            .map(|i| {
+                let mut terms = vec![];
+                terms.extend(z0.get(i).map(|z| (*z, F::one())));
+                if let Some(j) = i.checked_sub(half) {
+                    terms.extend(z1.get(j).map(|z| (*z, F::one())));
+                    terms.extend(z0.get(j).map(|z| (*z, -F::one())));
+                    terms.extend(z2.get(j).map(|z| (*z, -F::one())));
+                }
+                if let Some(j) = i.checked_sub(2 * half) {
+                    terms.extend(z2.get(j).map(|z| (*z, F::one())));
+                }
+                let (cells, coeffs): (Vec<_>, Vec<_>) = terms.into_iter().unzip();
+                gate.inner_product(ctx, cells, coeffs.into_iter().map(Constant))
+            })
+            .collect()
+    }
+
+    /// Returns the coefficients of the polynomial product of the limbs of `a` and `b`, padding the shorter one.
+    fn karatsuba_padded(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        if a.len() == b.len() {
+            return self.karatsuba(ctx, a, b);
+        }
+        let (k, len) = (a.len().max(b.len()), a.len() + b.len() - 1);
+        let zero = ctx.load_zero();
+        let [a, b] = [a, b]
+            .map(|x| x.iter().copied().chain(std::iter::repeat(zero)).take(k).collect::<Vec<_>>());
+        let mut product = self.karatsuba(ctx, &a, &b);
+        // the coefficients from the padding are zero
+        product.truncate(len);
+        product
+    }
+
+    /// Returns `a + b` limb by limb, assuming `a` has at least as many limbs as `b`.
+    fn add_no_carry(
+        &self,
+        ctx: &mut Context<F>,
+        a: &[AssignedValue<F>],
+        b: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        a.iter()
+            .enumerate()
+            .map(|(i, x)| match b.get(i) {
+                Some(y) => gate.add(ctx, *x, *y),
+                None => *x,
+            })
+            .collect()
+    }
+
+    /// Witnesses the proper limbs of the integer with the coefficients `product` of a product of `k_a` by `k_b` limbs,
+    /// and constrains them.
+    fn carry(
+        &self,
+        ctx: &mut Context<F>,
+        product: Vec<AssignedValue<F>>,
+        k_a: usize,
+        k_b: usize,
+    ) -> ProperUint<F> {
+        let value = self.evaluate(&product);
+        let out = self.assign_limbs(ctx, &value, k_a + k_b);
+        let max_limb_bits = self.product_limb_bits(k_a.min(k_b));
+        self.assert_equal_limbs(ctx, &product, out.limbs(), max_limb_bits, k_a + k_b);
+        out
+    }
+
+    /// Constrains the integers with limbs `lhs` and `rhs` to be equal, assuming both are nonnegative, with limbs of at
+    /// most `max_limb_bits` bits, and less than `2^(limb_bits * num_limbs)`.
+    fn assert_equal_limbs(
+        &self,
+        ctx: &mut Context<F>,
+        lhs: &[AssignedValue<F>],
+        rhs: &[AssignedValue<F>],
+        max_limb_bits: usize,
+        num_limbs: usize,
+    ) {
+        assert!(
+            max_limb_bits + self.range.lookup_bits() < F::CAPACITY as usize,
+            "limbs are too large for the native field"
+        );
+        let gate = self.gate();
+        let diff = (0..num_limbs)
+            .map(|i| match (lhs.get(i), rhs.get(i)) {
+                (Some(x), Some(y)) => gate.sub(ctx, *x, *y),
+                (Some(x), None) => *x,
+                (None, Some(y)) => gate.neg(ctx, *y),
+                (None, None) => ctx.load_zero(),
+            })
+            .collect();
+        // the difference is less than `2^(limb_bits * num_limbs)` in absolute value, and a multiple of it
+        check_carry_to_zero::truncate(
+            self.range,
+            ctx,
+            OverflowInteger::new(diff, max_limb_bits),
+            self.limb_bits,
+            self.limb_base,
+            &self.limb_base_big,
+        );
+    }
+
+    /// Returns the number of bits of the coefficients of a product of operands with at most `k` limbs in common,
+    /// leaving a bit for an added or subtracted proper integer.
+    fn product_limb_bits(&self, k: usize) -> usize {
+        2 * self.limb_bits + bit_length(k as u64) + 1
+    }
+
+    /// Returns `a` with zero limbs appended up to `k` limbs.
+    fn pad(&self, ctx: &mut Context<F>, a: &ProperUint<F>, k: usize) -> ProperUint<F> {
+        let mut limbs = a.limbs().to_vec();
+        if limbs.len() < k {
+            let zero = ctx.load_zero();
+            limbs.resize(k, zero);
+        }
+        ProperUint(limbs)
+    }
+
+    /// Witnesses `value` truncated to `num_limbs` range checked limbs.
+    fn assign_limbs(
+        &self,
+        ctx: &mut Context<F>,
+        value: &BigUint,
+        num_limbs: usize,
+    ) -> ProperUint<F> {
+        let limbs = ctx.assign_witnesses(self.decompose(value, num_limbs));
+        for limb in &limbs {
+            self.range.range_check(ctx, *limb, self.limb_bits);
+        }
+        ProperUint(limbs)
+    }
+
+    /// Returns the `num_limbs` lowest limbs of `value`.
+    fn decompose(&self, value: &BigUint, num_limbs: usize) -> Vec<F> {
+        let mask = (BigUint::one() << self.limb_bits) - 1u32;
+        (0..num_limbs).map(|i| biguint_to_fe(&((value >> (self.limb_bits * i)) & &mask))).collect()
+    }
+
+    /// Returns `sum_i limbs[i] * 2^(limb_bits * i)` for nonnegative limbs.
+    fn evaluate(&self, limbs: &[AssignedValue<F>]) -> BigUint {
+        limbs.iter().rev().fold(BigUint::zero(), |acc, limb| {
+            (acc << self.limb_bits) + fe_to_biguint(limb.value())
+        })
+    }
+}
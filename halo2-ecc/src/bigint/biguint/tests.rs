@@ -0,0 +1,164 @@
+use super::*;
+use crate::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+use halo2_base::gates::builder::{GateThreadBuilder, RangeCircuitBuilder};
+use num_bigint::RandBigInt;
+use rand_core::OsRng;
+use test_case::test_case;
+
+const LIMB_BITS: usize = 64;
+
+/// Runs `f` in a circuit of `2^k` rows and returns the values of its outputs and whether the circuit is satisfied.
+fn run(
+    k: usize,
+    f: impl FnOnce(&mut Context<Fr>, &BigUintChip<Fr>) -> Vec<ProperUint<Fr>>,
+) -> (Vec<BigUint>, bool) {
+    let lookup_bits = k - 1;
+    std::env::set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let range = RangeChip::<Fr>::default(lookup_bits);
+    let chip = BigUintChip::new(&range, LIMB_BITS);
+    let out = f(builder.main(0), &chip);
+    let values = out.iter().map(|x| chip.value(x)).collect();
+    builder.config(k, Some(20));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    (values, MockProver::run(k as u32, &circuit, vec![]).unwrap().verify().is_ok())
+}
+
+#[test]
+fn test_add_sub() {
+    let a = OsRng.gen_biguint(256) | (BigUint::one() << 255u32);
+    let b = OsRng.gen_biguint(200);
+    let (out, verified) = run(10, |ctx, chip| {
+        let [x, y] = [&a, &b].map(|v| chip.load_witness(ctx, v, 4));
+        let sum = chip.add(ctx, &x, &y);
+        let (diff, borrow) = chip.sub(ctx, &x, &y);
+        let (neg_diff, neg_borrow) = chip.sub(ctx, &y, &x);
+        vec![sum, diff, ProperUint(vec![borrow]), neg_diff, ProperUint(vec![neg_borrow])]
+    });
+    assert!(verified);
+    let modulus = BigUint::one() << 256;
+    assert_eq!(out[0], &a + &b);
+    assert_eq!(out[1], &a - &b);
+    assert_eq!(out[2], BigUint::zero());
+    assert_eq!(out[3], &modulus + &b - &a);
+    assert_eq!(out[4], BigUint::one());
+}
+
+#[test]
+fn test_add_carry() {
+    let max = (BigUint::one() << 256) - 1u32;
+    let (out, verified) = run(10, |ctx, chip| {
+        let x = chip.load_witness(ctx, &max, 4);
+        let y = chip.load_witness(ctx, &BigUint::one(), 1);
+        let sum = chip.add(ctx, &x, &y);
+        assert_eq!(sum.limbs().len(), 5);
+        vec![sum]
+    });
+    assert!(verified);
+    assert_eq!(out[0], BigUint::one() << 256);
+}
+
+#[test_case(5, 7 => (1, 0); "less")]
+#[test_case(7, 7 => (0, 1); "equal")]
+#[test_case(1 << 70, 7 => (0, 0); "greater with more limbs")]
+#[test_case(7, 1 << 70 => (1, 0); "less with fewer limbs")]
+fn test_compare(a: u128, b: u128) -> (u64, u64) {
+    let (out, verified) = run(10, |ctx, chip| {
+        let x = chip.load_witness(ctx, &BigUint::from(a), if a >> 64 == 0 { 1 } else { 2 });
+        let y = chip.load_witness(ctx, &BigUint::from(b), 2);
+        let lt = chip.is_less_than(ctx, &x, &y);
+        let eq = chip.is_equal(ctx, &x, &y);
+        vec![ProperUint(vec![lt]), ProperUint(vec![eq])]
+    });
+    assert!(verified);
+    let [lt, eq] = [&out[0], &out[1]].map(|x| x.iter_u64_digits().next().unwrap_or(0));
+    (lt, eq)
+}
+
+#[test]
+fn test_mul() {
+    // above `KARATSUBA_THRESHOLD` limbs, with operands of different lengths
+    let a = OsRng.gen_biguint(40 * LIMB_BITS as u64);
+    let b = OsRng.gen_biguint(37 * LIMB_BITS as u64);
+    let (out, verified) = run(16, |ctx, chip| {
+        let x = chip.load_witness(ctx, &a, 40);
+        let y = chip.load_witness(ctx, &b, 37);
+        vec![chip.mul(ctx, &x, &y), chip.mul_karatsuba(ctx, &x, &y)]
+    });
+    assert!(verified);
+    assert_eq!(out[0], &a * &b);
+    assert_eq!(out[1], &a * &b);
+}
+
+#[test]
+fn test_div_rem() {
+    let a = OsRng.gen_biguint(512);
+    let b = OsRng.gen_biguint(200);
+    let (out, verified) = run(12, |ctx, chip| {
+        let x = chip.load_witness(ctx, &a, 8);
+        let y = chip.load_witness(ctx, &b, 4);
+        let (q, r) = chip.div_rem(ctx, &x, &y);
+        vec![q, r]
+    });
+    assert!(verified);
+    assert_eq!(out[0], &a / &b);
+    assert_eq!(out[1], &a % &b);
+}
+
+#[test]
+fn test_div_by_zero() {
+    let (_, verified) = run(12, |ctx, chip| {
+        let x = chip.load_witness(ctx, &BigUint::from(5u32), 1);
+        let y = chip.load_witness(ctx, &BigUint::zero(), 1);
+        let (q, r) = chip.div_rem(ctx, &x, &y);
+        vec![q, r]
+    });
+    assert!(!verified);
+}
+
+#[test_case(20, 1, 20; "one limb")]
+#[test_case(70, 2, 70; "two limbs")]
+#[test_case(20, 2, 24; "zero high limb")]
+fn test_mod_exp(bits: u64, num_limbs: usize, exp_bits: usize) {
+    let base = OsRng.gen_biguint(200);
+    let exp = OsRng.gen_biguint(bits);
+    let m = OsRng.gen_biguint(128) | BigUint::one();
+    let (out, verified) = run(16, |ctx, chip| {
+        let base = chip.load_witness(ctx, &base, 4);
+        let exp = chip.load_witness(ctx, &exp, num_limbs);
+        let m = chip.load_witness(ctx, &m, 2);
+        vec![chip.mod_exp(ctx, &base, &exp, exp_bits, &m)]
+    });
+    assert!(verified);
+    assert_eq!(out[0], base.modpow(&exp, &m));
+}
+
+#[test_case(1, 8; "low limb")]
+#[test_case(2, 64; "high limb")]
+fn test_mod_exp_too_long(num_limbs: usize, exp_bits: usize) {
+    let exp = BigUint::one() << (exp_bits as u32);
+    let (_, verified) = run(16, |ctx, chip| {
+        let base = chip.load_witness(ctx, &BigUint::from(3u32), 1);
+        let exp = chip.load_witness(ctx, &exp, num_limbs);
+        let m = chip.load_witness(ctx, &BigUint::from(1000003u32), 1);
+        vec![chip.mod_exp(ctx, &base, &exp, exp_bits, &m)]
+    });
+    assert!(!verified);
+}
+
+#[test_case(65537; "rsa public exponent")]
+#[test_case(1; "one")]
+#[test_case(0; "zero")]
+fn test_mod_exp_const(exp: u64) {
+    // textbook RSA verification with a 1024-bit modulus
+    let m = OsRng.gen_biguint(1024) | (BigUint::one() << 1023u32);
+    let signature = OsRng.gen_biguint_below(&m);
+    let exp = BigUint::from(exp);
+    let (out, verified) = run(17, |ctx, chip| {
+        let signature = chip.load_witness(ctx, &signature, 16);
+        let m = chip.load_witness(ctx, &m, 16);
+        vec![chip.mod_exp_const(ctx, &signature, &exp, &m)]
+    });
+    assert!(verified);
+    assert_eq!(out[0], signature.modpow(&exp, &m));
+}
@@ -11,6 +11,7 @@ pub mod add_no_carry;
 pub mod big_is_equal;
 pub mod big_is_zero;
 pub mod big_less_than;
+pub mod biguint;
 pub mod carry_mod;
 pub mod check_carry_mod_to_zero;
 pub mod check_carry_to_zero;
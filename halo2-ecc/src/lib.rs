@@ -7,6 +7,7 @@
 pub mod bigint;
 pub mod ecc;
 pub mod fields;
+pub mod transcript;
 
 pub mod bn254;
 pub mod secp256k1;
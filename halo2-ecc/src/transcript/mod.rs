@@ -0,0 +1,296 @@
+//! Fiat-Shamir transcripts over the Poseidon sponge of [poseidon::PoseidonChip].
+//!
+//! [TranscriptChip] absorbs native field elements, the limbs of [ProperCrtUint]s and the coordinates of [EcPoint]s,
+//! and squeezes challenges as field elements, bits or curve points. [NativeTranscript] performs the same operations
+//! outside the circuit: for the same sequence of calls, with non-native values loaded with
+//! [FieldChip::load_private], both produce exactly the same challenges.
+use crate::bigint::{big_less_than, CRTInteger, ProperCrtUint};
+use crate::ecc::EcPoint;
+use crate::fields::{fp::FpChip, FieldChip, PrimeField, Selectable};
+use group::ff::Field;
+use halo2_base::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{biguint_to_fe, decompose_biguint, fe_to_biguint, modulus, CurveAffineExt},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use num_bigint::BigUint;
+use num_traits::One;
+use poseidon::{Poseidon, PoseidonChip};
+
+#[cfg(test)]
+mod tests;
+
+/// Number of bits in the low part of the canonical decomposition of a challenge. Challenges can be squeezed as at
+/// most this many bits.
+pub const MAX_CHALLENGE_BITS: usize = 128;
+
+/// Number of candidate x-coordinates tried when squeezing a curve point. All of them are not on the curve, and no point
+/// can be squeezed, with probability about `2^-HASH_TO_CURVE_ATTEMPTS`.
+pub const HASH_TO_CURVE_ATTEMPTS: usize = 64;
+
+/// In-circuit Fiat-Shamir transcript.
+pub struct TranscriptChip<'range, F: PrimeField, const T: usize, const RATE: usize> {
+    /// Range chip used to decompose challenges and constrain squeezed points.
+    pub range: &'range RangeChip<F>,
+    poseidon: PoseidonChip<F, T, RATE>,
+}
+
+impl<'range, F: PrimeField, const T: usize, const RATE: usize> TranscriptChip<'range, F, T, RATE> {
+    /// Creates a new transcript using Poseidon with `r_f` full rounds and `r_p` partial rounds.
+    pub fn new(ctx: &mut Context<F>, range: &'range RangeChip<F>, r_f: usize, r_p: usize) -> Self {
+        let poseidon = PoseidonChip::new(ctx, r_f, r_p).expect("failed to create Poseidon chip");
+        Self { range, poseidon }
+    }
+
+    /// Returns the gate chip of `range`.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Absorbs native field elements.
+    pub fn absorb(&mut self, values: &[AssignedValue<F>]) {
+        self.poseidon.update(values);
+    }
+
+    /// Absorbs the limbs of `a`. The native value is determined by the limbs, so it is not absorbed.
+    pub fn absorb_crt_uint(&mut self, a: &ProperCrtUint<F>) {
+        self.poseidon.update(a.limbs());
+    }
+
+    /// Absorbs the limbs of the `x` and then `y` coordinates of `point`.
+    pub fn absorb_point(&mut self, point: &EcPoint<F, ProperCrtUint<F>>) {
+        self.absorb_crt_uint(&point.x);
+        self.absorb_crt_uint(&point.y);
+    }
+
+    /// Absorbs everything absorbed since the last squeeze and returns a challenge as a native field element.
+    pub fn squeeze(&mut self, ctx: &mut Context<F>) -> AssignedValue<F> {
+        let gate = self.range.gate();
+        self.poseidon.squeeze(ctx, gate).expect("Poseidon squeeze failed")
+    }
+
+    /// Squeezes a challenge and returns its `num_bits` least significant bits, in little-endian order.
+    ///
+    /// The challenge is decomposed as `hi * 2^128 + lo`, with `(hi, lo)` constrained to be lexicographically less
+    /// than the corresponding decomposition of the modulus so that the bits are uniquely determined.
+    /// * `num_bits`: at most [MAX_CHALLENGE_BITS]
+    pub fn squeeze_bits(&mut self, ctx: &mut Context<F>, num_bits: usize) -> Vec<AssignedValue<F>> {
+        assert!(num_bits <= MAX_CHALLENGE_BITS);
+        let challenge = self.squeeze(ctx);
+        let range = self.range;
+        let gate = range.gate();
+        let hi_bits = F::NUM_BITS as usize - MAX_CHALLENGE_BITS;
+
+        let [hi, lo] = split_challenge(&fe_to_biguint(challenge.value()));
+        let [hi, lo] = [hi, lo].map(|x| ctx.load_witness(biguint_to_fe(&x)));
+        range.range_check(ctx, hi, hi_bits);
+        range.range_check(ctx, lo, MAX_CHALLENGE_BITS);
+        let composed = gate.mul_add(
+            ctx,
+            hi,
+            Constant(biguint_to_fe(&(BigUint::from(1u64) << MAX_CHALLENGE_BITS))),
+            lo,
+        );
+        ctx.constrain_equal(&composed, &challenge);
+
+        let [p_hi, p_lo] = split_challenge(&modulus::<F>()).map(|x| Constant(biguint_to_fe(&x)));
+        let hi_lt = range.is_less_than(ctx, hi, p_hi, hi_bits);
+        let hi_eq = gate.is_equal(ctx, hi, p_hi);
+        let lo_lt = range.is_less_than(ctx, lo, p_lo, MAX_CHALLENGE_BITS);
+        let is_canonical = gate.or_and(ctx, hi_lt, hi_eq, lo_lt);
+        gate.assert_is_const(ctx, &is_canonical, &F::one());
+
+        let mut bits = gate.num_to_bits(ctx, lo, MAX_CHALLENGE_BITS);
+        bits.truncate(num_bits);
+        bits
+    }
+
+    /// Squeezes a challenge `c` and hashes it to a point of the curve `C` by try-and-increment.
+    ///
+    /// The point is `(x, y)` for the first `x = c + i`, with `i < HASH_TO_CURVE_ATTEMPTS`, such that `x^3 + a x + b` is
+    /// a square, where `c` is read as an integer in the base field, and `y` is its even square root. That
+    /// `x^3 + a x + b` is not a square for the previous `i` is proven with a square root of its product with a fixed
+    /// non-residue. The discrete logarithm of the point is unknown, and the point is never the point at infinity.
+    ///
+    /// Panics if the modulus of `F` is larger than the modulus of the base field of `C`, and the circuit is not
+    /// satisfied if no attempt gives a point, see [HASH_TO_CURVE_ATTEMPTS]. Assumes `C` has no points of order 2.
+    /// * `chip`: field chip for the base field of `C`
+    pub fn squeeze_point<C>(
+        &mut self,
+        ctx: &mut Context<F>,
+        chip: &FpChip<F, C::Base>,
+    ) -> EcPoint<F, ProperCrtUint<F>>
+    where
+        C: CurveAffineExt,
+        C::Base: PrimeField,
+    {
+        let challenge = self.squeeze(ctx);
+        let range = self.range;
+        let gate = range.gate();
+        let x0 = challenge_to_base::<F, C::Base>(challenge.value());
+
+        // the limbs of `x0` are the integer value of the challenge, which is less than the modulus of `F`
+        let assigned_x0 = chip.load_private(ctx, x0);
+        ctx.constrain_equal(assigned_x0.native(), &challenge);
+        let native_modulus = chip.load_constant_uint(ctx, modulus::<F>());
+        let limb_base = biguint_to_fe(&(BigUint::one() << chip.limb_bits));
+        let is_canonical = big_less_than::assign(
+            range,
+            ctx,
+            assigned_x0.clone(),
+            native_modulus,
+            chip.limb_bits,
+            limb_base,
+        );
+        gate.assert_is_const(ctx, &is_canonical, &F::one());
+
+        let non_residue = non_residue::<C::Base>();
+        let a = (C::a() != C::Base::zero()).then(|| chip.load_constant(ctx, C::a()));
+        let mut found = ctx.load_zero();
+        let mut point: Option<(CRTInteger<F>, ProperCrtUint<F>)> = None;
+        for (i, (is_square, root)) in hash_to_curve_attempts::<C>(x0).into_iter().enumerate() {
+            let x = chip.add_constant_no_carry(ctx, assigned_x0.clone(), C::Base::from(i as u64));
+            let x_sq = chip.mul(ctx, x.clone(), x.clone());
+            let mut rhs = chip.mul_no_carry(ctx, x_sq, x.clone());
+            if let Some(a) = &a {
+                let ax = chip.mul_no_carry(ctx, x.clone(), a);
+                rhs = chip.add_no_carry(ctx, rhs, ax);
+            }
+            let rhs = chip.add_constant_no_carry(ctx, rhs, C::b());
+
+            // `root^2` is `rhs` if it is a square, and `non_residue * rhs` otherwise, which is then a square
+            let is_square = ctx.load_witness(F::from(is_square));
+            gate.assert_bit(ctx, is_square);
+            let root = chip.load_private(ctx, root);
+            let rhs_non_residue = chip.scalar_mul_no_carry(ctx, rhs.clone(), non_residue as i64);
+            let target = chip.select(ctx, rhs, rhs_non_residue, is_square);
+            let root_sq = chip.mul_no_carry(ctx, root.clone(), root.clone());
+            let diff = chip.sub_no_carry(ctx, root_sq, target);
+            chip.check_carry_mod_to_zero(ctx, diff);
+
+            let is_first = gate.mul_not(ctx, found, is_square);
+            found = gate.add(ctx, found, is_first);
+            point = Some(match point {
+                None => (x, root),
+                Some((px, py)) => {
+                    (chip.select(ctx, x, px, is_first), chip.select(ctx, root, py, is_first))
+                }
+            });
+        }
+        gate.assert_is_const(ctx, &found, &F::one());
+
+        let (x, y) = point.unwrap();
+        let x = chip.carry_mod(ctx, x);
+        // `y` is determined by its square once it is reduced and even
+        chip.enforce_less_than(ctx, y.clone());
+        let low = y.limbs()[0];
+        let half = ctx.load_witness(biguint_to_fe(&(fe_to_biguint(low.value()) >> 1usize)));
+        range.range_check(ctx, half, chip.limb_bits - 1);
+        let twice = gate.mul(ctx, half, Constant(F::from(2)));
+        ctx.constrain_equal(&twice, &low);
+        EcPoint::new(x, y)
+    }
+}
+
+/// Native counterpart of [TranscriptChip].
+pub struct NativeTranscript<F: PrimeField, const T: usize, const RATE: usize> {
+    poseidon: Poseidon<F, T, RATE>,
+}
+
+impl<F: PrimeField, const T: usize, const RATE: usize> NativeTranscript<F, T, RATE> {
+    /// Creates a new transcript using Poseidon with `r_f` full rounds and `r_p` partial rounds.
+    pub fn new(r_f: usize, r_p: usize) -> Self {
+        Self { poseidon: Poseidon::new(r_f, r_p) }
+    }
+
+    /// Absorbs native field elements.
+    pub fn absorb(&mut self, values: &[F]) {
+        self.poseidon.update(values);
+    }
+
+    /// Absorbs the limbs of `a`, decomposed into `num_limbs` limbs of `limb_bits` bits as by [FieldChip::load_private].
+    pub fn absorb_crt_uint(&mut self, a: &BigUint, limb_bits: usize, num_limbs: usize) {
+        self.poseidon.update(&decompose_biguint::<F>(a, num_limbs, limb_bits));
+    }
+
+    /// Absorbs the limbs of the `x` and then `y` coordinates of `point`.
+    pub fn absorb_point<C>(&mut self, point: C, limb_bits: usize, num_limbs: usize)
+    where
+        C: CurveAffineExt,
+        C::Base: PrimeField,
+    {
+        let (x, y) = point.into_coordinates();
+        self.absorb_crt_uint(&fe_to_biguint(&x), limb_bits, num_limbs);
+        self.absorb_crt_uint(&fe_to_biguint(&y), limb_bits, num_limbs);
+    }
+
+    /// Absorbs everything absorbed since the last squeeze and returns a challenge.
+    pub fn squeeze(&mut self) -> F {
+        self.poseidon.squeeze()
+    }
+
+    /// Squeezes a challenge and returns its `num_bits` least significant bits, in little-endian order.
+    pub fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        assert!(num_bits <= MAX_CHALLENGE_BITS);
+        let challenge = fe_to_biguint(&self.squeeze());
+        (0..num_bits).map(|i| challenge.bit(i as u64)).collect()
+    }
+
+    /// Squeezes a challenge and hashes it to a point of the curve `C` exactly as [TranscriptChip::squeeze_point].
+    ///
+    /// Panics if the modulus of `F` is larger than the modulus of the base field of `C`, or if no attempt gives a
+    /// point.
+    pub fn squeeze_point<C>(&mut self) -> C
+    where
+        C: CurveAffineExt,
+        C::Base: PrimeField,
+    {
+        let x0 = challenge_to_base::<F, C::Base>(&self.squeeze());
+        let (i, y) = hash_to_curve_attempts::<C>(x0)
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, (is_square, root))| is_square.then_some((i, root)))
+            .expect("no attempt to hash to the curve gives a point");
+        C::from_xy(x0 + C::Base::from(i as u64), y).unwrap()
+    }
+}
+
+/// Returns the challenge `c` as an element of the base field `Fp`, with the same integer value.
+fn challenge_to_base<F: PrimeField, Fp: PrimeField>(c: &F) -> Fp {
+    assert!(modulus::<F>() <= modulus::<Fp>(), "challenges must fit in the base field");
+    biguint_to_fe(&fe_to_biguint(c))
+}
+
+/// Returns the smallest quadratic non-residue of `Fp`.
+fn non_residue<Fp: PrimeField>() -> u64 {
+    (2..).find(|n| bool::from(Fp::from(*n).sqrt().is_none())).unwrap()
+}
+
+/// Returns, for each attempt `i` of hashing `x0` to the curve `C`, whether `x^3 + a x + b` is a square for
+/// `x = x0 + i`, and its even square root if it is, otherwise a square root of its product with [non_residue].
+fn hash_to_curve_attempts<C>(x0: C::Base) -> Vec<(bool, C::Base)>
+where
+    C: CurveAffineExt,
+    C::Base: PrimeField,
+{
+    let non_residue = C::Base::from(non_residue::<C::Base>());
+    (0..HASH_TO_CURVE_ATTEMPTS as u64)
+        .map(|i| {
+            let x = x0 + C::Base::from(i);
+            let rhs = x * x * x + C::a() * x + C::b();
+            let (is_square, root) = match Option::<C::Base>::from(rhs.sqrt()) {
+                Some(root) => (true, root),
+                None => (false, (non_residue * rhs).sqrt().unwrap()),
+            };
+            let root = if fe_to_biguint(&root).bit(0) { -root } else { root };
+            (is_square, root)
+        })
+        .collect()
+}
+
+/// Returns `[a >> 128, a mod 2^128]`.
+fn split_challenge(a: &BigUint) -> [BigUint; 2] {
+    let lo_mask = (BigUint::from(1u64) << MAX_CHALLENGE_BITS) - 1u64;
+    [a >> MAX_CHALLENGE_BITS, a & lo_mask]
+}
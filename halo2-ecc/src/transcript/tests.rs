@@ -0,0 +1,96 @@
+use super::*;
+use crate::ecc::EccChip;
+use crate::fields::fp::FpChip;
+use crate::halo2_proofs::{
+    arithmetic::CurveAffine,
+    dev::MockProver,
+    halo2curves::{
+        bn256::{Fq, Fr, G1Affine},
+        secp256k1::{self, Secp256k1Affine},
+    },
+};
+use group::ff::Field;
+use halo2_base::gates::builder::{GateThreadBuilder, RangeCircuitBuilder};
+use num_bigint::RandBigInt;
+use rand_core::OsRng;
+
+const R_F: usize = 8;
+const R_P: usize = 57;
+
+#[test]
+fn test_transcript_matches_native() {
+    let k = 18;
+    let lookup_bits = k - 1;
+    let (limb_bits, num_limbs) = (88, 3);
+    std::env::set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::<Fr>::default(lookup_bits);
+    let fp_chip = FpChip::<Fr, Fq>::new(&range, limb_bits, num_limbs);
+    let ecc_chip = EccChip::new(&fp_chip);
+
+    let values = [(); 3].map(|_| Fr::random(OsRng));
+    let uint = OsRng.gen_biguint(200);
+    let point = G1Affine::random(OsRng);
+
+    let mut native = NativeTranscript::<Fr, 3, 2>::new(R_F, R_P);
+    native.absorb(&values);
+    native.absorb_crt_uint(&uint, limb_bits, num_limbs);
+    let native_challenge = native.squeeze();
+    native.absorb_point(point, limb_bits, num_limbs);
+    let native_bits = native.squeeze_bits(100);
+    let native_point = native.squeeze_point::<G1Affine>();
+    let native_next = native.squeeze();
+
+    let mut transcript = TranscriptChip::<Fr, 3, 2>::new(ctx, &range, R_F, R_P);
+    let assigned = ctx.assign_witnesses(values);
+    transcript.absorb(&assigned);
+    transcript.absorb_crt_uint(&fp_chip.load_private(ctx, biguint_to_fe::<Fq>(&uint)));
+    assert_eq!(*transcript.squeeze(ctx).value(), native_challenge);
+    transcript.absorb_point(&ecc_chip.load_private_unchecked(ctx, (point.x, point.y)));
+    let bits = transcript.squeeze_bits(ctx, 100);
+    assert_eq!(
+        bits.iter().map(|bit| *bit.value()).collect::<Vec<_>>(),
+        native_bits.into_iter().map(|bit| Fr::from(bit as u64)).collect::<Vec<_>>()
+    );
+    let squeezed_point = transcript.squeeze_point::<G1Affine>(ctx, &fp_chip);
+    assert_eq!(squeezed_point.x.value(), fe_to_biguint(&native_point.x));
+    assert_eq!(squeezed_point.y.value(), fe_to_biguint(&native_point.y));
+    assert_eq!(*transcript.squeeze(ctx).value(), native_next);
+
+    builder.config(k, Some(20));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied();
+}
+
+#[test]
+fn test_squeeze_point_matches_native() {
+    // the base field of secp256k1 is larger than `Fr`, unlike its scalar field
+    let k = 18;
+    let lookup_bits = k - 1;
+    std::env::set_var("LOOKUP_BITS", lookup_bits.to_string());
+    let mut builder = GateThreadBuilder::<Fr>::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::<Fr>::default(lookup_bits);
+    let fp_chip = FpChip::<Fr, secp256k1::Fp>::new(&range, 88, 3);
+
+    let values = [(); 2].map(|_| Fr::random(OsRng));
+    let mut native = NativeTranscript::<Fr, 3, 2>::new(R_F, R_P);
+    native.absorb(&values);
+    let native_points = [(); 3].map(|_| native.squeeze_point::<Secp256k1Affine>());
+
+    let mut transcript = TranscriptChip::<Fr, 3, 2>::new(ctx, &range, R_F, R_P);
+    let assigned = ctx.assign_witnesses(values);
+    transcript.absorb(&assigned);
+    for native_point in native_points {
+        assert!(bool::from(native_point.is_on_curve()));
+        assert!(!fe_to_biguint(&native_point.y).bit(0));
+        let point = transcript.squeeze_point::<Secp256k1Affine>(ctx, &fp_chip);
+        assert_eq!(point.x.value(), fe_to_biguint(&native_point.x));
+        assert_eq!(point.y.value(), fe_to_biguint(&native_point.y));
+    }
+
+    builder.config(k, Some(20));
+    let circuit = RangeCircuitBuilder::mock(builder);
+    MockProver::run(k as u32, &circuit, vec![]).unwrap().assert_satisfied();
+}
@@ -0,0 +1,469 @@
+//! BLAKE2b and BLAKE2s (RFC 7693) built on top of [halo2_base] gates.
+//!
+//! Every word is kept both as a little-endian vector of assigned bits and as its composed native value:
+//! the bits are used for XOR and rotations, which are free re-indexings of the bits, while the native value is
+//! used for additions modulo 2<sup>w</sup>, whose results are decomposed back into bits with
+//! [GateInstructions::num_to_bits] and truncated.
+//!
+//! [GateInstructions::const_right_rotate_unsafe] is not used for the rotations: it does not range check the two
+//! halves it splits its input into, so its output is not determined by its input. Since every rotation in BLAKE2
+//! directly follows a XOR, the bits are already available and rotating them costs nothing.
+//!
+//! Message bytes only enter the compression function through additions, so they are range checked with a lookup
+//! and composed into words without being decomposed into bits.
+//!
+//! Digest length, salt and personalization are fixed at keygen, which is enough to recompute the challenges of
+//! the `Blake2bWrite` transcript of `halo2_proofs`, see [Blake2Chip::halo2_transcript]. Keyed hashing is not
+//! supported.
+#![allow(clippy::too_many_arguments)]
+
+use halo2_base::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{bit_length, BigPrimeField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+
+/// Initialization vectors and message permutations
+pub mod constants;
+
+#[cfg(test)]
+mod tests;
+
+pub use constants::{BLAKE2B_IV, BLAKE2S_IV, SIGMA};
+
+/// Number of words in the hash state.
+pub const NUM_STATE_WORDS: usize = 8;
+/// Number of words in a message block.
+pub const NUM_BLOCK_WORDS: usize = 16;
+
+/// The two members of the BLAKE2 family.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blake2Variant {
+    /// BLAKE2b, optimized for 64-bit platforms, with digests of up to 64 bytes.
+    #[default]
+    Blake2b,
+    /// BLAKE2s, optimized for 32-bit platforms, with digests of up to 32 bytes.
+    Blake2s,
+}
+
+impl Blake2Variant {
+    /// Returns the number of bits in a word.
+    pub fn word_bits(&self) -> usize {
+        match self {
+            Self::Blake2b => 64,
+            Self::Blake2s => 32,
+        }
+    }
+
+    /// Returns the number of bytes in a word.
+    pub fn word_bytes(&self) -> usize {
+        self.word_bits() / 8
+    }
+
+    /// Returns the number of bytes in a message block.
+    pub fn block_bytes(&self) -> usize {
+        NUM_BLOCK_WORDS * self.word_bytes()
+    }
+
+    /// Returns the maximum number of bytes in a digest.
+    pub fn max_digest_bytes(&self) -> usize {
+        NUM_STATE_WORDS * self.word_bytes()
+    }
+
+    /// Returns the number of rounds of the compression function.
+    pub fn num_rounds(&self) -> usize {
+        match self {
+            Self::Blake2b => 12,
+            Self::Blake2s => 10,
+        }
+    }
+
+    /// Returns the rotation constants `(R1, R2, R3, R4)` of the mixing function.
+    pub fn rotations(&self) -> [usize; 4] {
+        match self {
+            Self::Blake2b => [32, 24, 16, 63],
+            Self::Blake2s => [16, 12, 8, 7],
+        }
+    }
+
+    /// Returns the initialization vector.
+    pub fn iv(&self) -> [u64; NUM_STATE_WORDS] {
+        match self {
+            Self::Blake2b => BLAKE2B_IV,
+            Self::Blake2s => BLAKE2S_IV,
+        }
+    }
+}
+
+/// A word represented by its little-endian bits, each constrained to be boolean, together with its native value.
+#[derive(Clone, Debug)]
+pub struct AssignedWord<F: BigPrimeField> {
+    bits: Vec<AssignedValue<F>>,
+    native: AssignedValue<F>,
+}
+
+impl<F: BigPrimeField> AssignedWord<F> {
+    /// Returns the little-endian bits of the word.
+    pub fn bits(&self) -> &[AssignedValue<F>] {
+        &self.bits
+    }
+
+    /// Returns the value of the word as a field element.
+    pub fn native(&self) -> AssignedValue<F> {
+        self.native
+    }
+}
+
+/// Chip for computing BLAKE2b or BLAKE2s digests of byte strings.
+#[derive(Clone, Debug)]
+pub struct Blake2Chip<'a, F: BigPrimeField> {
+    /// Which member of the BLAKE2 family to compute.
+    pub variant: Blake2Variant,
+    /// Number of bytes in the digest, between 1 and [Blake2Variant::max_digest_bytes].
+    pub digest_bytes: usize,
+    /// Salt, at most `2 * word_bytes` bytes, zero padded.
+    pub salt: Vec<u8>,
+    /// Personalization, at most `2 * word_bytes` bytes, zero padded.
+    pub personal: Vec<u8>,
+    /// Underlying range chip, used for byte range checks and the variable length padding.
+    pub range: &'a RangeChip<F>,
+}
+
+impl<'a, F: BigPrimeField> Blake2Chip<'a, F> {
+    /// Creates a new [Blake2Chip] for `variant` with `digest_bytes` bytes of output and no salt or personalization.
+    pub fn new(range: &'a RangeChip<F>, variant: Blake2Variant, digest_bytes: usize) -> Self {
+        assert!(digest_bytes > 0 && digest_bytes <= variant.max_digest_bytes());
+        Self { variant, digest_bytes, salt: vec![], personal: vec![], range }
+    }
+
+    /// Creates the BLAKE2b chip used by the `Blake2bWrite` and `Blake2bRead` transcripts of `halo2_proofs`:
+    /// 64 byte digests personalized with `"Halo2-Transcript"`.
+    pub fn halo2_transcript(range: &'a RangeChip<F>) -> Self {
+        Self::new(range, Blake2Variant::Blake2b, 64).with_personal(b"Halo2-Transcript")
+    }
+
+    /// Sets the salt of the parameter block.
+    pub fn with_salt(mut self, salt: &[u8]) -> Self {
+        assert!(salt.len() <= 2 * self.variant.word_bytes());
+        self.salt = salt.to_vec();
+        self
+    }
+
+    /// Sets the personalization of the parameter block.
+    pub fn with_personal(mut self, personal: &[u8]) -> Self {
+        assert!(personal.len() <= 2 * self.variant.word_bytes());
+        self.personal = personal.to_vec();
+        self
+    }
+
+    /// Returns the underlying [GateInstructions].
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Returns the initial hash state: the initialization vector XOR the parameter block.
+    pub fn initial_state(&self) -> [u64; NUM_STATE_WORDS] {
+        let word_bytes = self.variant.word_bytes();
+        let to_words = |bytes: &[u8]| {
+            let mut bytes = bytes.to_vec();
+            bytes.resize(2 * word_bytes, 0);
+            [0, 1].map(|i| {
+                let word = &bytes[i * word_bytes..(i + 1) * word_bytes];
+                word.iter().rev().fold(0u64, |acc, byte| (acc << 8) + *byte as u64)
+            })
+        };
+        let mut state = self.variant.iv();
+        // digest length, key length 0, fanout 1, depth 1
+        state[0] ^= 0x01010000 ^ self.digest_bytes as u64;
+        let [salt0, salt1] = to_words(&self.salt);
+        let [personal0, personal1] = to_words(&self.personal);
+        state[4] ^= salt0;
+        state[5] ^= salt1;
+        state[6] ^= personal0;
+        state[7] ^= personal1;
+        state
+    }
+
+    /// Constrains and returns the digest bytes of the message `bytes`, whose length is known at keygen.
+    ///
+    /// Each element of `bytes` is constrained to be a byte.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `bytes`: the message, as assigned bytes
+    pub fn digest_fixed_len(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let block_bytes = self.variant.block_bytes();
+        let num_blocks = num_blocks(bytes.len(), block_bytes);
+        let mut padded = bytes.to_vec();
+        padded.resize(num_blocks * block_bytes, ctx.load_zero());
+
+        let mut state = self.load_initial_state(ctx);
+        for (i, block) in padded.chunks(block_bytes).enumerate() {
+            let is_last = i + 1 == num_blocks;
+            let counter = if is_last { bytes.len() } else { (i + 1) * block_bytes };
+            let counter = self.load_constant_word(ctx, counter as u64);
+            let is_last = ctx.load_constant(F::from(is_last as u64));
+            state = self.compress(ctx, &state, block, &counter, is_last);
+        }
+        self.state_to_digest(ctx, &state)
+    }
+
+    /// Constrains and returns the digest bytes of the first `len` bytes of `bytes`.
+    ///
+    /// The maximum message length is `bytes.len()` and is fixed at keygen; the circuit always compresses
+    /// `max(1, ceil(bytes.len() / block_bytes))` blocks and selects the state after the block containing the last
+    /// byte of the message. The bytes of `bytes` at indices `>= len` are ignored. The bytes at indices `< len` are
+    /// constrained to be bytes.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `bytes`: the message padded with arbitrary values to the maximum length
+    /// * `len`: the length of the message in bytes, constrained to be `<= bytes.len()`
+    pub fn digest_var_len(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[AssignedValue<F>],
+        len: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        let max_len = bytes.len();
+        let block_bytes = self.variant.block_bytes();
+        let num_blocks = num_blocks(max_len, block_bytes);
+        assert!(bit_length(max_len as u64) < self.variant.word_bits());
+
+        // len <= max_len, so exactly one entry of `is_msg_end` is 1
+        self.range.check_less_than_safe(ctx, len, max_len as u64 + 1);
+        let is_msg_end = gate.idx_to_indicator(ctx, len, max_len + 1);
+
+        // the final block is the one containing byte `len - 1`, or the first block if the message is empty
+        let is_empty = gate.is_zero(ctx, len);
+        let last_byte = gate.sub(ctx, len, Constant(F::one()));
+        let last_byte = gate.add(ctx, last_byte, is_empty);
+        let num_bits = bit_length(max_len as u64).max(1);
+        let (last_block, _) = self.range.div_mod(ctx, last_byte, block_bytes as u64, num_bits);
+        let is_last_block = gate.idx_to_indicator(ctx, last_block, num_blocks);
+
+        let mut in_msg = ctx.load_constant(F::one());
+        let mut padded = Vec::with_capacity(num_blocks * block_bytes);
+        for (byte, is_msg_end) in bytes.iter().zip(is_msg_end) {
+            in_msg = gate.sub(ctx, in_msg, is_msg_end);
+            padded.push(gate.mul(ctx, *byte, in_msg));
+        }
+        padded.resize(num_blocks * block_bytes, ctx.load_zero());
+
+        let mut state = self.load_initial_state(ctx);
+        let mut digests = Vec::with_capacity(num_blocks);
+        for (i, block) in padded.chunks(block_bytes).enumerate() {
+            // the counter is the number of message bytes up to and including this block
+            let counter = gate.select(
+                ctx,
+                len,
+                Constant(F::from(((i + 1) * block_bytes) as u64)),
+                is_last_block[i],
+            );
+            let counter = self.mod_word(ctx, counter, 0);
+            state = self.compress(ctx, &state, block, &counter, is_last_block[i]);
+            digests.push(self.state_to_digest(ctx, &state));
+        }
+        (0..self.digest_bytes)
+            .map(|i| {
+                gate.select_by_indicator(
+                    ctx,
+                    digests.iter().map(|digest| digest[i]),
+                    is_last_block.iter().copied(),
+                )
+            })
+            .collect()
+    }
+
+    /// Constrains and returns the state after applying the compression function to `state` and `block`.
+    ///
+    /// Each element of `block` is constrained to be a byte.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `state`: the current hash state
+    /// * `block`: `block_bytes` assigned bytes
+    /// * `counter`: the number of message bytes hashed so far, including this block; the high word of the
+    ///   counter is always zero since the message length fits in a word
+    /// * `is_last`: whether this is the final block, constrained to be boolean
+    pub fn compress(
+        &self,
+        ctx: &mut Context<F>,
+        state: &[AssignedWord<F>],
+        block: &[AssignedValue<F>],
+        counter: &AssignedWord<F>,
+        is_last: AssignedValue<F>,
+    ) -> Vec<AssignedWord<F>> {
+        let variant = self.variant;
+        assert_eq!(state.len(), NUM_STATE_WORDS);
+        assert_eq!(block.len(), variant.block_bytes());
+        let gate = self.gate();
+        gate.assert_bit(ctx, is_last);
+
+        // message words are only added, so they do not need to be decomposed into bits
+        let m = block
+            .chunks(variant.word_bytes())
+            .map(|word| {
+                for byte in word {
+                    self.range.range_check(ctx, *byte, 8);
+                }
+                gate.inner_product(
+                    ctx,
+                    word.iter().copied(),
+                    (0..word.len()).map(|i| Constant(gate.pow_of_two()[8 * i])),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut v = state.to_vec();
+        v.extend(self.load_initial_words(ctx, variant.iv()));
+        v[12] = self.xor_rotr(ctx, &v[12], counter, 0);
+        let v14 = v[14].bits.iter().map(|bit| gate.xor(ctx, *bit, is_last)).collect();
+        v[14] = self.word_from_bits(ctx, v14);
+
+        for round in 0..variant.num_rounds() {
+            let s = &SIGMA[round % SIGMA.len()];
+            for (i, [a, b, c, d]) in [
+                [0, 4, 8, 12],
+                [1, 5, 9, 13],
+                [2, 6, 10, 14],
+                [3, 7, 11, 15],
+                [0, 5, 10, 15],
+                [1, 6, 11, 12],
+                [2, 7, 8, 13],
+                [3, 4, 9, 14],
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                self.mix(ctx, &mut v, [a, b, c, d], m[s[2 * i]], m[s[2 * i + 1]]);
+            }
+        }
+
+        (0..NUM_STATE_WORDS)
+            .map(|i| {
+                let bits = (0..variant.word_bits())
+                    .map(|j| {
+                        let x = gate.xor(ctx, state[i].bits[j], v[i].bits[j]);
+                        gate.xor(ctx, x, v[i + NUM_STATE_WORDS].bits[j])
+                    })
+                    .collect();
+                self.word_from_bits(ctx, bits)
+            })
+            .collect()
+    }
+
+    /// Constrains the mixing function `G` on the words of `v` at `[a, b, c, d]` with message words `x` and `y`.
+    fn mix(
+        &self,
+        ctx: &mut Context<F>,
+        v: &mut [AssignedWord<F>],
+        [a, b, c, d]: [usize; 4],
+        x: AssignedValue<F>,
+        y: AssignedValue<F>,
+    ) {
+        let gate = self.gate();
+        let [r1, r2, r3, r4] = self.variant.rotations();
+
+        let sum = gate.sum(ctx, [v[a].native, v[b].native, x]);
+        v[a] = self.mod_word(ctx, sum, 2);
+        v[d] = self.xor_rotr(ctx, &v[d], &v[a], r1);
+        let sum = gate.add(ctx, v[c].native, v[d].native);
+        v[c] = self.mod_word(ctx, sum, 1);
+        v[b] = self.xor_rotr(ctx, &v[b], &v[c], r2);
+        let sum = gate.sum(ctx, [v[a].native, v[b].native, y]);
+        v[a] = self.mod_word(ctx, sum, 2);
+        v[d] = self.xor_rotr(ctx, &v[d], &v[a], r3);
+        let sum = gate.add(ctx, v[c].native, v[d].native);
+        v[c] = self.mod_word(ctx, sum, 1);
+        v[b] = self.xor_rotr(ctx, &v[b], &v[c], r4);
+    }
+
+    /// Loads [Self::initial_state] as constants.
+    pub fn load_initial_state(&self, ctx: &mut Context<F>) -> Vec<AssignedWord<F>> {
+        self.load_initial_words(ctx, self.initial_state())
+    }
+
+    /// Constrains and returns the little-endian digest bytes of `state`, truncated to [Self::digest_bytes].
+    pub fn state_to_digest(
+        &self,
+        ctx: &mut Context<F>,
+        state: &[AssignedWord<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let mut digest = state
+            .iter()
+            .flat_map(|word| word.bits.chunks(8).map(|byte| self.compose_bits(ctx, byte)))
+            .collect::<Vec<_>>();
+        digest.truncate(self.digest_bytes);
+        digest
+    }
+
+    fn load_initial_words(
+        &self,
+        ctx: &mut Context<F>,
+        words: [u64; NUM_STATE_WORDS],
+    ) -> Vec<AssignedWord<F>> {
+        words.into_iter().map(|word| self.load_constant_word(ctx, word)).collect()
+    }
+
+    fn load_constant_word(&self, ctx: &mut Context<F>, word: u64) -> AssignedWord<F> {
+        let bits =
+            (0..self.variant.word_bits()).map(|i| ctx.load_constant(F::from((word >> i) & 1)));
+        AssignedWord { bits: bits.collect(), native: ctx.load_constant(F::from(word)) }
+    }
+
+    fn word_from_bits(&self, ctx: &mut Context<F>, bits: Vec<AssignedValue<F>>) -> AssignedWord<F> {
+        let native = self.compose_bits(ctx, &bits);
+        AssignedWord { bits, native }
+    }
+
+    /// Constrains and returns `sum_i bits[i] * 2^i`.
+    fn compose_bits(&self, ctx: &mut Context<F>, bits: &[AssignedValue<F>]) -> AssignedValue<F> {
+        let gate = self.gate();
+        gate.inner_product(
+            ctx,
+            bits.iter().copied(),
+            gate.pow_of_two()[..bits.len()].iter().map(|c| Constant(*c)),
+        )
+    }
+
+    /// Constrains and returns `a mod 2^w`, assuming `a` has at most `w + carry_bits` bits.
+    fn mod_word(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        carry_bits: usize,
+    ) -> AssignedWord<F> {
+        let word_bits = self.variant.word_bits();
+        let mut bits = self.gate().num_to_bits(ctx, a, word_bits + carry_bits);
+        if carry_bits == 0 {
+            return AssignedWord { bits, native: a };
+        }
+        bits.truncate(word_bits);
+        self.word_from_bits(ctx, bits)
+    }
+
+    /// Constrains and returns `(a ^ b) >>> n`.
+    fn xor_rotr(
+        &self,
+        ctx: &mut Context<F>,
+        a: &AssignedWord<F>,
+        b: &AssignedWord<F>,
+        n: usize,
+    ) -> AssignedWord<F> {
+        let gate = self.gate();
+        let word_bits = self.variant.word_bits();
+        let bits = (0..word_bits)
+            .map(|i| {
+                let j = (i + n) % word_bits;
+                gate.xor(ctx, a.bits[j], b.bits[j])
+            })
+            .collect();
+        self.word_from_bits(ctx, bits)
+    }
+}
+
+/// Returns the number of blocks the compression function is applied to for a message of `len` bytes.
+pub fn num_blocks(len: usize, block_bytes: usize) -> usize {
+    ((len + block_bytes - 1) / block_bytes).max(1)
+}
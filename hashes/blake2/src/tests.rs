@@ -0,0 +1,85 @@
+use std::env::set_var;
+
+use super::*;
+use halo2_base::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use test_case::test_case;
+
+const K: usize = 17;
+const LOOKUP_BITS: usize = 8;
+
+fn check_digest(digest: &[AssignedValue<Fr>], expected: &str) {
+    let expected = hex::decode(expected).unwrap().into_iter().map(|b| Fr::from(b as u64));
+    let digest = digest.iter().map(|byte| *byte.value());
+    assert_eq!(digest.collect::<Vec<_>>(), expected.collect::<Vec<_>>());
+}
+
+// expected digests from RFC 7693 appendices A and B, and python's hashlib
+#[test_case(Blake2Variant::Blake2b, b"abc".to_vec(), "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"; "blake2b abc")]
+#[test_case(Blake2Variant::Blake2s, b"abc".to_vec(), "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"; "blake2s abc")]
+#[test_case(Blake2Variant::Blake2b, vec![], "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"; "blake2b empty")]
+#[test_case(Blake2Variant::Blake2s, vec![], "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9"; "blake2s empty")]
+#[test_case(Blake2Variant::Blake2b, (0..128).collect(), "2319e3789c47e2daa5fe807f61bec2a1a6537fa03f19ff32e87eecbfd64b7e0e8ccff439ac333b040f19b0c4ddd11a61e24ac1fe0f10a039806c5dcc0da3d115"; "blake2b one full block")]
+#[test_case(Blake2Variant::Blake2s, (0..64).collect(), "56f34e8b96557e90c1f24b52d0c89d51086acf1b00f634cf1dde9233b8eaaa3e"; "blake2s one full block")]
+#[test_case(Blake2Variant::Blake2b, (0..200).collect(), "fb3c1f0f56a56f8e316fdf5d853c8c872c39635d083634c3904fc3ac07d1b578e85ff0e480e92d44ade33b62e893ee32343e79ddf6ef292e89b582d312502314"; "blake2b two blocks")]
+#[test_case(Blake2Variant::Blake2s, (0..200).collect(), "6d244e1a06ce4ef578dd0f63aff0936706735119ca9c8d22d86c801414ab9741"; "blake2s four blocks")]
+fn test_blake2_fixed_len(variant: Blake2Variant, msg: Vec<u8>, expected: &str) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = Blake2Chip::new(&range, variant, variant.max_digest_bytes());
+
+    let bytes = ctx.assign_witnesses(msg.into_iter().map(|b| Fr::from(b as u64)));
+    let digest = chip.digest_fixed_len(ctx, &bytes);
+    check_digest(&digest, expected);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_blake2_params() {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let bytes = ctx.assign_witnesses(b"abc".map(|b| Fr::from(b as u64)));
+
+    let transcript = Blake2Chip::halo2_transcript(&range);
+    let digest = transcript.digest_fixed_len(ctx, &bytes);
+    check_digest(&digest, "1a461cab63d8b3dbc9daab1574729f55d54e844b17b69ab1fce6dae8caecb9d24bb550cd9270aa53c8e9277d68008b3080da0b66fc6853231e15abec0b0159e5");
+
+    let truncated = Blake2Chip::new(&range, Blake2Variant::Blake2b, 32);
+    let digest = truncated.digest_fixed_len(ctx, &bytes);
+    check_digest(&digest, "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319");
+
+    let salted = Blake2Chip::new(&range, Blake2Variant::Blake2s, 20)
+        .with_salt(b"saltsalt")
+        .with_personal(b"personal");
+    let digest = salted.digest_fixed_len(ctx, &bytes);
+    check_digest(&digest, "7e8fd4993bea6b17d7c1c62477a6b755aef09d2d");
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test_case(Blake2Variant::Blake2b, 3, 130, "40a374727302d9a4769c17b5f409ff32f58aa24ff122d7603e4fda1509e919d4107a52c57570a6d94e50967aea573b11f86f473f537565c66f7039830a85d186"; "blake2b short")]
+#[test_case(Blake2Variant::Blake2b, 0, 3, "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"; "blake2b empty")]
+#[test_case(Blake2Variant::Blake2b, 128, 200, "2319e3789c47e2daa5fe807f61bec2a1a6537fa03f19ff32e87eecbfd64b7e0e8ccff439ac333b040f19b0c4ddd11a61e24ac1fe0f10a039806c5dcc0da3d115"; "blake2b one full block")]
+#[test_case(Blake2Variant::Blake2s, 64, 150, "56f34e8b96557e90c1f24b52d0c89d51086acf1b00f634cf1dde9233b8eaaa3e"; "blake2s one full block")]
+#[test_case(Blake2Variant::Blake2s, 200, 200, "6d244e1a06ce4ef578dd0f63aff0936706735119ca9c8d22d86c801414ab9741"; "blake2s max len")]
+fn test_blake2_var_len(variant: Blake2Variant, len: usize, max_len: usize, expected: &str) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = Blake2Chip::new(&range, variant, variant.max_digest_bytes());
+
+    // the message is `0, 1, ..., len - 1` padded with non-zero garbage, which must be ignored
+    let msg = (0..max_len).map(|i| if i < len { i as u8 } else { 0xff });
+    let bytes = ctx.assign_witnesses(msg.map(|b| Fr::from(b as u64)));
+    let len = ctx.load_witness(Fr::from(len as u64));
+    let digest = chip.digest_var_len(ctx, &bytes, len);
+    check_digest(&digest, expected);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
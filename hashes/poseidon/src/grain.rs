@@ -0,0 +1,58 @@
+use halo2_base::utils::{biguint_to_fe, modulus, ScalarField};
+use num_bigint::BigUint;
+use std::collections::VecDeque;
+
+/// The Grain LFSR of the Poseidon reference implementation, used to sample round constants.
+///
+/// See `generate_parameters_grain.sage` in <https://extgit.iaik.tugraz.at/krypto/hadeshash>.
+pub(crate) struct Grain {
+    state: VecDeque<bool>,
+}
+
+impl Grain {
+    /// Initializes the LFSR for a prime field of `num_bits` bits and the S-box `x^alpha`, with state width `t`,
+    /// `r_f` full rounds and `r_p` partial rounds.
+    pub(crate) fn new(num_bits: usize, t: usize, r_f: usize, r_p: usize) -> Self {
+        let mut state = VecDeque::with_capacity(80);
+        // field type 1 = prime field, S-box type 0 = x^alpha
+        for (value, len) in [(1, 2), (0, 4), (num_bits, 12), (t, 12), (r_f, 10), (r_p, 10)] {
+            state.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        state.extend([true; 30]);
+        let mut grain = Self { state };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let bit = [0, 13, 23, 38, 51, 62].iter().fold(false, |acc, i| acc ^ self.state[*i]);
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    /// Returns the next output bit: bits are produced in pairs and the second bit is kept only if the first is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.step();
+            let bit = self.step();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Samples a field element by rejection sampling on big-endian bit strings of the bit length of the modulus.
+    pub(crate) fn next_field_element<F: ScalarField>(&mut self) -> F {
+        let modulus = modulus::<F>();
+        loop {
+            let value = (0..modulus.bits())
+                .fold(BigUint::from(0u64), |acc, _| (acc << 1usize) + self.next_bit() as u64);
+            if value < modulus {
+                return biguint_to_fe(&value);
+            }
+        }
+    }
+}
@@ -9,6 +9,22 @@ use halo2_base::{
     QuantumCell::{Constant, Existing},
 };
 
+mod grain;
+/// The Poseidon2 permutation
+pub mod poseidon2;
+/// The Rescue-Prime permutation
+pub mod rescue;
+/// Sponge construction over an arbitrary permutation
+pub mod sponge;
+
+#[cfg(test)]
+mod tests;
+
+pub use ::poseidon::Poseidon;
+pub use poseidon2::Poseidon2Permutation;
+pub use rescue::RescuePrimePermutation;
+pub use sponge::{PermutationInstructions, SpongeChip, SpongeInstructions};
+
 struct PoseidonState<F: ScalarField, const T: usize, const RATE: usize> {
     s: [AssignedValue<F>; T],
 }
@@ -196,3 +212,23 @@ impl<F: ScalarField, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE>
         self.state.apply_mds(ctx, gate, mds);
     }
 }
+
+impl<F: ScalarField, const T: usize, const RATE: usize> SpongeInstructions<F>
+    for PoseidonChip<F, T, RATE>
+{
+    fn clear(&mut self) {
+        PoseidonChip::clear(self)
+    }
+
+    fn update(&mut self, elements: &[AssignedValue<F>]) {
+        PoseidonChip::update(self, elements)
+    }
+
+    fn squeeze(
+        &mut self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        PoseidonChip::squeeze(self, ctx, gate)
+    }
+}
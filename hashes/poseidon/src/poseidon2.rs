@@ -0,0 +1,207 @@
+use halo2_base::{
+    gates::GateInstructions,
+    utils::{modulus, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+
+use crate::{grain::Grain, sponge::PermutationInstructions};
+
+/// The 4x4 MDS matrix `M4` of the Poseidon2 paper, used to build the external linear layer when `4 | T`.
+const M4: [[u64; 4]; 4] = [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+/// The Poseidon2 permutation (<https://eprint.iacr.org/2023/323>) with S-box `x^5`.
+///
+/// Compared to Poseidon, the partial rounds use the internal matrix `M_I = 1 + diag(internal_diag)`, which costs
+/// one multiplication per element instead of a dense matrix product. The external matrix `M_E` is `circ(2, 1, 1)`
+/// for `T = 3`, `circ(2, 1)` for `T = 2` and built from `M4` when `T` is a multiple of 4.
+///
+/// Round constants are sampled with the Grain LFSR of the reference implementation, `T` per full round and one per
+/// partial round, so for BN254 with `T = 3`, `r_f = 8`, `r_p = 56` this is the instance of
+/// <https://github.com/HorizenLabs/poseidon2>.
+#[derive(Clone, Debug)]
+pub struct Poseidon2Permutation<F: ScalarField, const T: usize> {
+    r_f: usize,
+    external_constants: Vec<[F; T]>,
+    internal_constants: Vec<F>,
+    internal_diag: [F; T],
+}
+
+impl<F: ScalarField, const T: usize> Poseidon2Permutation<F, T> {
+    /// Creates the permutation with `r_f` full and `r_p` partial rounds, using the internal matrix of the reference
+    /// instances. Only `T = 2` and `T = 3` have a reference internal matrix; use [Self::with_internal_diag] otherwise.
+    pub fn new(r_f: usize, r_p: usize) -> Self {
+        let internal_diag = match T {
+            2 => vec![F::one(), F::from(2)],
+            3 => vec![F::one(), F::one(), F::from(2)],
+            _ => panic!("no reference internal matrix for T = {T}"),
+        };
+        Self::with_internal_diag(r_f, r_p, internal_diag.try_into().unwrap())
+    }
+
+    /// Creates the permutation with `r_f` full and `r_p` partial rounds and internal matrix
+    /// `M_I = 1 + diag(internal_diag)`. The caller is responsible for choosing `internal_diag` so that `M_I` is
+    /// invertible and has no invariant subspaces, see section 5.3 of the paper.
+    pub fn with_internal_diag(r_f: usize, r_p: usize, internal_diag: [F; T]) -> Self {
+        assert!(T == 2 || T == 3 || T % 4 == 0, "unsupported state width {T}");
+        assert!(r_f % 2 == 0);
+        let mut grain = Grain::new(modulus::<F>().bits() as usize, T, r_f, r_p);
+        // constants are sampled in round order: first half of the full rounds, partial rounds, second half
+        let mut external_constants = Vec::with_capacity(r_f);
+        let mut internal_constants = Vec::with_capacity(r_p);
+        for round in 0..r_f + r_p {
+            if round < r_f / 2 || round >= r_f / 2 + r_p {
+                external_constants.push([(); T].map(|_| grain.next_field_element()));
+            } else {
+                internal_constants.push(grain.next_field_element());
+            }
+        }
+        Self { r_f, external_constants, internal_constants, internal_diag }
+    }
+
+    fn external_layer(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        state: [AssignedValue<F>; T],
+    ) -> [AssignedValue<F>; T] {
+        let state = if T % 4 == 0 {
+            state
+                .chunks(4)
+                .flat_map(|chunk| {
+                    M4.map(|row| {
+                        let row = row.map(|c| Constant(F::from(c)));
+                        gate.inner_product(ctx, chunk.iter().copied(), row)
+                    })
+                })
+                .collect::<Vec<_>>()
+        } else {
+            state.to_vec()
+        };
+        if T == 4 {
+            return state.try_into().unwrap();
+        }
+        // add the sum of all elements in the same position mod 4 (or of all elements for T = 2, 3)
+        let stride = if T % 4 == 0 { 4 } else { 1 };
+        let sums = (0..stride)
+            .map(|i| gate.sum(ctx, state.iter().skip(i).step_by(stride).copied()))
+            .collect::<Vec<_>>();
+        let state = state.iter().enumerate().map(|(i, x)| gate.add(ctx, *x, sums[i % stride]));
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+
+    fn internal_layer(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        state: [AssignedValue<F>; T],
+    ) -> [AssignedValue<F>; T] {
+        let sum = gate.sum(ctx, state);
+        let state = state
+            .iter()
+            .zip(self.internal_diag.iter())
+            .map(|(x, d)| gate.mul_add(ctx, *x, Constant(*d), sum));
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+
+    fn external_layer_native(&self, state: [F; T]) -> [F; T] {
+        let state = if T % 4 == 0 {
+            state
+                .chunks(4)
+                .flat_map(|chunk| {
+                    M4.map(|row| {
+                        row.iter()
+                            .zip(chunk.iter())
+                            .fold(F::zero(), |acc, (c, x)| acc + F::from(*c) * x)
+                    })
+                })
+                .collect::<Vec<_>>()
+        } else {
+            state.to_vec()
+        };
+        if T == 4 {
+            return state.try_into().unwrap();
+        }
+        let stride = if T % 4 == 0 { 4 } else { 1 };
+        let sums = (0..stride)
+            .map(|i| state.iter().skip(i).step_by(stride).fold(F::zero(), |acc, x| acc + x))
+            .collect::<Vec<_>>();
+        let state = state.iter().enumerate().map(|(i, x)| *x + sums[i % stride]);
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+
+    fn internal_layer_native(&self, state: [F; T]) -> [F; T] {
+        let sum = state.iter().fold(F::zero(), |acc, x| acc + x);
+        let state = state.iter().zip(self.internal_diag.iter()).map(|(x, d)| *x * d + sum);
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+}
+
+/// Constrains and returns `(x + constant)^5`.
+pub(crate) fn pow5_with_constant<F: ScalarField>(
+    ctx: &mut Context<F>,
+    gate: &impl GateInstructions<F>,
+    x: AssignedValue<F>,
+    constant: F,
+) -> AssignedValue<F> {
+    let x = gate.add(ctx, x, Constant(constant));
+    let x2 = gate.mul(ctx, x, x);
+    let x4 = gate.mul(ctx, x2, x2);
+    gate.mul(ctx, x, x4)
+}
+
+fn pow5<F: ScalarField>(x: F) -> F {
+    let x2 = x.square();
+    x2.square() * x
+}
+
+impl<F: ScalarField, const T: usize> PermutationInstructions<F, T> for Poseidon2Permutation<F, T> {
+    fn permute(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        state: [AssignedValue<F>; T],
+    ) -> [AssignedValue<F>; T] {
+        let (first, last) = self.external_constants.split_at(self.r_f / 2);
+        let mut state = self.external_layer(ctx, gate, state);
+        for constants in first {
+            for (x, c) in state.iter_mut().zip(constants.iter()) {
+                *x = pow5_with_constant(ctx, gate, *x, *c);
+            }
+            state = self.external_layer(ctx, gate, state);
+        }
+        for c in self.internal_constants.iter() {
+            state[0] = pow5_with_constant(ctx, gate, state[0], *c);
+            state = self.internal_layer(ctx, gate, state);
+        }
+        for constants in last {
+            for (x, c) in state.iter_mut().zip(constants.iter()) {
+                *x = pow5_with_constant(ctx, gate, *x, *c);
+            }
+            state = self.external_layer(ctx, gate, state);
+        }
+        state
+    }
+
+    fn permute_native(&self, state: [F; T]) -> [F; T] {
+        let (first, last) = self.external_constants.split_at(self.r_f / 2);
+        let mut state = self.external_layer_native(state);
+        for constants in first {
+            for (x, c) in state.iter_mut().zip(constants.iter()) {
+                *x = pow5(*x + c);
+            }
+            state = self.external_layer_native(state);
+        }
+        for c in self.internal_constants.iter() {
+            state[0] = pow5(state[0] + c);
+            state = self.internal_layer_native(state);
+        }
+        for constants in last {
+            for (x, c) in state.iter_mut().zip(constants.iter()) {
+                *x = pow5(*x + c);
+            }
+            state = self.external_layer_native(state);
+        }
+        state
+    }
+}
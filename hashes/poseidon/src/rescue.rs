@@ -0,0 +1,200 @@
+use halo2_base::{
+    gates::GateInstructions,
+    utils::{biguint_to_fe, modulus, ScalarField},
+    AssignedValue, Context,
+    QuantumCell::Constant,
+};
+use num_bigint::BigUint;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+use crate::sponge::PermutationInstructions;
+
+/// The Rescue-Prime permutation (<https://eprint.iacr.org/2020/1143>) with S-box `x^5` and its inverse `x^(1/5)`.
+///
+/// Each round applies `x^5` to every element, the MDS matrix and round constants, then `x^(1/5)` to every element,
+/// the MDS matrix and round constants again. In-circuit the inverse S-box is witnessed and checked by raising it to
+/// the fifth power, so both halves of a round cost three multiplications per element.
+///
+/// The parameters are derived as in the reference implementation (`rescue_prime.sage`) from the field, the width
+/// `T`, the capacity and the security level: the number of rounds from the Groebner basis attack bound plus 50%,
+/// the MDS matrix from a Vandermonde matrix in powers of the smallest primitive element, and the round constants
+/// from SHAKE256 seeded with `Rescue-XLIX(p,T,capacity,security_level)`.
+#[derive(Clone, Debug)]
+pub struct RescuePrimePermutation<F: ScalarField, const T: usize> {
+    mds: [[F; T]; T],
+    round_constants: Vec<[F; T]>,
+    /// Little-endian limbs of `5^{-1} mod (p - 1)`.
+    alpha_inv: Vec<u64>,
+}
+
+impl<F: ScalarField, const T: usize> RescuePrimePermutation<F, T> {
+    /// Creates the Rescue-Prime instance of `F` with width `T`, used in a sponge with `capacity` elements, for
+    /// `security_level` bits of security.
+    ///
+    /// `primitive_element` must be the smallest generator of the multiplicative group of `F`, which the reference
+    /// implementation uses for the MDS matrix, e.g. 5 for the BN254 scalar field. Panics if `x^5` is not the S-box
+    /// of the reference implementation for `F`, i.e. if `x^3` is a permutation of `F` or `x^5` is not.
+    pub fn new(capacity: usize, security_level: usize, primitive_element: u64) -> Self {
+        assert!(0 < capacity && capacity < T);
+        let p = modulus::<F>();
+        let p_minus_one = &p - 1u64;
+        let alpha = BigUint::from(5u64);
+        // the reference takes the smallest `alpha >= 3` coprime to `p - 1`
+        assert!(&p_minus_one % 3u64 == BigUint::from(0u64), "x^3 is a permutation of F");
+        assert!(&p_minus_one % &alpha != BigUint::from(0u64), "x^5 is not a permutation of F");
+        // 5 * alpha_inv = 1 + k * (p - 1) for some k in 1..5
+        let alpha_inv = (1..5u64)
+            .map(|k| &p_minus_one * k + 1u64)
+            .find(|x| x % &alpha == BigUint::from(0u64))
+            .unwrap()
+            / &alpha;
+
+        let num_rounds = Self::reference_num_rounds(capacity, security_level);
+        let mds = Self::mds(F::from(primitive_element));
+
+        let bytes_per_int = (p.bits() as usize + 7) / 8 + 1;
+        let seed = format!("Rescue-XLIX({},{},{},{})", p, T, capacity, security_level);
+        let mut shake = Shake256::default();
+        shake.update(seed.as_bytes());
+        let mut reader = shake.finalize_xof();
+        let mut next_constant = || {
+            let mut bytes = vec![0u8; bytes_per_int];
+            reader.read(&mut bytes);
+            biguint_to_fe(&(BigUint::from_bytes_le(&bytes) % &p))
+        };
+        let round_constants =
+            (0..2 * num_rounds).map(|_| [(); T].map(|_| next_constant())).collect();
+        Self { mds, round_constants, alpha_inv: alpha_inv.to_u64_digits() }
+    }
+
+    /// Returns the number of rounds of the permutation.
+    pub fn num_rounds(&self) -> usize {
+        self.round_constants.len() / 2
+    }
+
+    /// Returns the number of rounds of the reference implementation for S-box `x^5`: 50% more than the number of
+    /// rounds `l` that resists the Groebner basis attack, with `l >= 5`.
+    fn reference_num_rounds(capacity: usize, security_level: usize) -> usize {
+        let rate = T - capacity;
+        let target = BigUint::from(1u64) << security_level;
+        let binomial =
+            |n: usize, k: usize| (0..k).fold(BigUint::from(1u64), |acc, i| acc * (n - i) / (i + 1));
+        let l = (1..25)
+            .find(|&l| {
+                // degree of regularity `(alpha - 1) * T * (l - 1) / 2 + 2` and number of variables
+                let d_con = 2 * T * (l - 1) + 2;
+                let v = T * (l - 1) + rate;
+                binomial(v + d_con, v).pow(2) > target
+            })
+            .unwrap_or(24);
+        (3 * l.max(5) + 1) / 2
+    }
+
+    /// Returns the transpose of the right half of the reduced row echelon form of the `T x 2T` Vandermonde matrix
+    /// `g^(i * j)`.
+    fn mds(g: F) -> [[F; T]; T] {
+        let mut v = (0..T)
+            .map(|i| (0..2 * T).map(|j| g.pow_vartime([(i * j) as u64])).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for col in 0..T {
+            let pivot = (col..T).find(|&row| v[row][col] != F::zero()).unwrap();
+            v.swap(col, pivot);
+            let inv = v[col][col].invert().unwrap();
+            v[col].iter_mut().for_each(|x| *x *= inv);
+            for row in 0..T {
+                if row != col {
+                    let factor = v[row][col];
+                    let pivot_row = v[col].clone();
+                    v[row].iter_mut().zip(pivot_row).for_each(|(x, y)| *x -= factor * y);
+                }
+            }
+        }
+        let mut mds = [[F::zero(); T]; T];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, m) in row.iter_mut().enumerate() {
+                *m = v[j][T + i];
+            }
+        }
+        mds
+    }
+
+    fn linear_layer(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        state: [AssignedValue<F>; T],
+        constants: &[F; T],
+    ) -> [AssignedValue<F>; T] {
+        let state = self.mds.iter().zip(constants.iter()).map(|(row, c)| {
+            let x = gate.inner_product(ctx, state, row.iter().map(|m| Constant(*m)));
+            gate.add(ctx, x, Constant(*c))
+        });
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+
+    fn linear_layer_native(&self, state: [F; T], constants: &[F; T]) -> [F; T] {
+        let state = self
+            .mds
+            .iter()
+            .zip(constants.iter())
+            .map(|(row, c)| row.iter().zip(state.iter()).fold(*c, |acc, (m, x)| acc + *m * x));
+        state.collect::<Vec<_>>().try_into().unwrap()
+    }
+
+    /// Constrains and returns `x^(1/5)`.
+    fn inv_pow5(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        x: AssignedValue<F>,
+    ) -> AssignedValue<F> {
+        let y = ctx.load_witness(x.value().pow_vartime(&self.alpha_inv));
+        let y2 = gate.mul(ctx, y, y);
+        let y4 = gate.mul(ctx, y2, y2);
+        let y5 = gate.mul(ctx, y, y4);
+        ctx.constrain_equal(&y5, &x);
+        y
+    }
+}
+
+impl<F: ScalarField, const T: usize> PermutationInstructions<F, T>
+    for RescuePrimePermutation<F, T>
+{
+    fn permute(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        mut state: [AssignedValue<F>; T],
+    ) -> [AssignedValue<F>; T] {
+        for constants in self.round_constants.chunks(2) {
+            for x in state.iter_mut() {
+                let x2 = gate.mul(ctx, *x, *x);
+                let x4 = gate.mul(ctx, x2, x2);
+                *x = gate.mul(ctx, *x, x4);
+            }
+            state = self.linear_layer(ctx, gate, state, &constants[0]);
+            for x in state.iter_mut() {
+                *x = self.inv_pow5(ctx, gate, *x);
+            }
+            state = self.linear_layer(ctx, gate, state, &constants[1]);
+        }
+        state
+    }
+
+    fn permute_native(&self, mut state: [F; T]) -> [F; T] {
+        for constants in self.round_constants.chunks(2) {
+            for x in state.iter_mut() {
+                *x = x.pow_vartime([5]);
+            }
+            state = self.linear_layer_native(state, &constants[0]);
+            for x in state.iter_mut() {
+                *x = x.pow_vartime(&self.alpha_inv);
+            }
+            state = self.linear_layer_native(state, &constants[1]);
+        }
+        state
+    }
+}
@@ -0,0 +1,138 @@
+use ::poseidon::State;
+use halo2_base::halo2_proofs::plonk::Error;
+use halo2_base::{
+    gates::GateInstructions,
+    utils::ScalarField,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+
+/// Instructions for a cryptographic permutation of a state of `T` field elements.
+pub trait PermutationInstructions<F: ScalarField, const T: usize> {
+    /// Constrains and returns the permutation of `state`.
+    fn permute(
+        &self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        state: [AssignedValue<F>; T],
+    ) -> [AssignedValue<F>; T];
+
+    /// Returns the permutation of `state`, computed natively.
+    fn permute_native(&self, state: [F; T]) -> [F; T];
+}
+
+/// The absorb/squeeze interface shared by [crate::PoseidonChip] and [SpongeChip].
+pub trait SpongeInstructions<F: ScalarField> {
+    /// Resets the sponge to its initial state.
+    fn clear(&mut self);
+
+    /// Queues `elements` to be absorbed on the next [Self::squeeze].
+    fn update(&mut self, elements: &[AssignedValue<F>]);
+
+    /// Absorbs the queued elements, permuting the state for every `RATE` of them, and returns the first rate
+    /// element of the state.
+    fn squeeze(
+        &mut self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<AssignedValue<F>, Error>;
+}
+
+/// A sponge over any [PermutationInstructions], with the same initial state and padding as [crate::PoseidonChip]:
+/// the capacity element starts at `2^64`, inputs are added to the rate elements and the last chunk is padded with
+/// a single `1` followed by zeros, adding one extra permutation if the last chunk is full.
+pub struct SpongeChip<F: ScalarField, P, const T: usize, const RATE: usize> {
+    permutation: P,
+    init_state: [AssignedValue<F>; T],
+    state: [AssignedValue<F>; T],
+    absorbing: Vec<AssignedValue<F>>,
+}
+
+impl<F: ScalarField, P: PermutationInstructions<F, T>, const T: usize, const RATE: usize>
+    SpongeChip<F, P, T, RATE>
+{
+    /// Creates a sponge over `permutation`, loading the initial state as constants in `ctx`.
+    ///
+    /// Panics unless `T == RATE + 1`, as the sponge has a capacity of one element.
+    pub fn new(ctx: &mut Context<F>, permutation: P) -> Self {
+        assert_eq!(RATE + 1, T, "the sponge has a capacity of one element");
+        let init_state = State::<F, T>::default()
+            .words()
+            .into_iter()
+            .map(|x| ctx.load_constant(x))
+            .collect::<Vec<AssignedValue<F>>>();
+        let init_state: [AssignedValue<F>; T] = init_state.try_into().unwrap();
+        Self { permutation, init_state, state: init_state, absorbing: Vec::new() }
+    }
+
+    /// Returns the underlying permutation.
+    pub fn permutation(&self) -> &P {
+        &self.permutation
+    }
+
+    /// Returns the native result of absorbing `elements` into a fresh sponge and squeezing once.
+    pub fn hash_native(permutation: &P, elements: &[F]) -> F {
+        let mut state = State::<F, T>::default().words();
+        let mut chunks = elements.chunks(RATE).map(|chunk| chunk.to_vec()).collect::<Vec<_>>();
+        if chunks.last().map_or(true, |chunk| chunk.len() == RATE) {
+            chunks.push(vec![]);
+        }
+        for chunk in chunks {
+            for (x, input) in state.iter_mut().skip(1).zip(chunk.iter()) {
+                *x += input;
+            }
+            if chunk.len() < RATE {
+                state[chunk.len() + 1] += F::one();
+            }
+            state = permutation.permute_native(state);
+        }
+        state[1]
+    }
+
+    fn absorb_chunk(
+        &mut self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+        chunk: &[AssignedValue<F>],
+    ) {
+        assert!(chunk.len() <= RATE);
+        for (x, input) in self.state.iter_mut().skip(1).zip(chunk.iter()) {
+            *x = gate.add(ctx, Existing(*x), Existing(*input));
+        }
+        if chunk.len() < RATE {
+            let pad = &mut self.state[chunk.len() + 1];
+            *pad = gate.add(ctx, Existing(*pad), Constant(F::one()));
+        }
+        self.state = self.permutation.permute(ctx, gate, self.state);
+    }
+}
+
+impl<F: ScalarField, P: PermutationInstructions<F, T>, const T: usize, const RATE: usize>
+    SpongeInstructions<F> for SpongeChip<F, P, T, RATE>
+{
+    fn clear(&mut self) {
+        self.state = self.init_state;
+        self.absorbing.clear();
+    }
+
+    fn update(&mut self, elements: &[AssignedValue<F>]) {
+        self.absorbing.extend_from_slice(elements);
+    }
+
+    fn squeeze(
+        &mut self,
+        ctx: &mut Context<F>,
+        gate: &impl GateInstructions<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let input_elements = std::mem::take(&mut self.absorbing);
+        let mut padding_offset = 0;
+        for chunk in input_elements.chunks(RATE) {
+            padding_offset = RATE - chunk.len();
+            self.absorb_chunk(ctx, gate, chunk);
+        }
+        if padding_offset == 0 {
+            self.absorb_chunk(ctx, gate, &[]);
+        }
+        Ok(self.state[1])
+    }
+}
@@ -0,0 +1,175 @@
+use super::*;
+use ::poseidon::Poseidon;
+use halo2_base::{
+    gates::{
+        builder::{GateCircuitBuilder, GateThreadBuilder, RangeCircuitBuilder},
+        GateChip, RangeChip,
+    },
+    halo2_proofs::{arithmetic::Field, halo2curves::bn256::Fr},
+    permutation::PermutationChip,
+    utils::{biguint_to_fe, testing::mock_verify},
+};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use std::env::set_var;
+
+const K: usize = 14;
+
+fn fe(hex: &str) -> Fr {
+    biguint_to_fe(&BigUint::parse_bytes(hex.as_bytes(), 16).unwrap())
+}
+
+fn check_permutation<const T: usize>(permutation: &impl PermutationInstructions<Fr, T>) {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let gate = GateChip::default();
+    let inputs = [(); T].map(|_| Fr::random(OsRng));
+    let state = ctx.assign_witnesses(inputs).try_into().unwrap();
+    let state = permutation.permute(ctx, &gate, state);
+    assert_eq!(state.map(|x| *x.value()), permutation.permute_native(inputs));
+    mock_verify(K, &GateCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test]
+fn test_poseidon2_reference_vector() {
+    // https://github.com/HorizenLabs/poseidon2, bn256 instance with t = 3
+    let permutation = Poseidon2Permutation::<Fr, 3>::new(8, 56);
+    let output = permutation.permute_native([0, 1, 2].map(Fr::from));
+    let expected = [
+        "0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033",
+        "303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570",
+        "1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8",
+    ];
+    assert_eq!(output, expected.map(fe));
+}
+
+#[test]
+fn test_poseidon2_permutation() {
+    check_permutation(&Poseidon2Permutation::<Fr, 3>::new(8, 56));
+    check_permutation(&Poseidon2Permutation::<Fr, 2>::new(8, 56));
+    let diag = [1, 2, 3, 4, 5, 6, 7, 8].map(Fr::from);
+    check_permutation(&Poseidon2Permutation::<Fr, 8>::with_internal_diag(8, 57, diag));
+}
+
+#[test]
+fn test_rescue_prime_reference_vector() {
+    // computed with a Python port of rescue_prime.sage, the Rescue-Prime reference implementation, for the BN254 field
+    // with m = 3, capacity 1 and 128 bits of security, which has 14 rounds
+    let permutation = RescuePrimePermutation::<Fr, 3>::new(1, 128, 5);
+    assert_eq!(permutation.num_rounds(), 14);
+    let output = permutation.permute_native([0, 1, 2].map(Fr::from));
+    let expected = [
+        "0dc30ccd5d64e5bea071e99087ef86d433eb156aa0500a823298f9bb05328bd2",
+        "189893368d5815608c56e44cc67f7e821e093bb6254a0553f9ff69f4d99debc8",
+        "1acafc768221448ebc51fa2cd1e3c9b2044a0c04f3509d833b0a82c7e3462610",
+    ];
+    assert_eq!(output, expected.map(fe));
+}
+
+#[test]
+fn test_rescue_prime_permutation() {
+    // the inverse S-box is checked in-circuit by raising it to the fifth power
+    check_permutation(&RescuePrimePermutation::<Fr, 3>::new(1, 128, 5));
+    check_permutation(&RescuePrimePermutation::<Fr, 5>::new(1, 128, 5));
+}
+
+/// Hashes `inputs` with any sponge, to check that both chips share the same interface.
+fn sponge_hash(
+    ctx: &mut Context<Fr>,
+    sponge: &mut impl SpongeInstructions<Fr>,
+    inputs: &[AssignedValue<Fr>],
+) -> Fr {
+    let gate = GateChip::default();
+    sponge.clear();
+    sponge.update(inputs);
+    *sponge.squeeze(ctx, &gate).unwrap().value()
+}
+
+#[test]
+fn test_sponges() {
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    for len in [0, 1, 2, 5] {
+        let inputs = (0..len).map(|_| Fr::random(OsRng)).collect::<Vec<_>>();
+        let assigned = ctx.assign_witnesses(inputs.clone());
+
+        let mut poseidon = PoseidonChip::<Fr, 3, 2>::new(ctx, 8, 57).unwrap();
+        let mut native = Poseidon::<Fr, 3, 2>::new(8, 57);
+        native.update(&inputs);
+        assert_eq!(sponge_hash(ctx, &mut poseidon, &assigned), native.squeeze());
+
+        let permutation = Poseidon2Permutation::<Fr, 3>::new(8, 56);
+        let native = SpongeChip::<_, _, 3, 2>::hash_native(&permutation, &inputs);
+        let mut poseidon2 = SpongeChip::<_, _, 3, 2>::new(ctx, permutation);
+        assert_eq!(sponge_hash(ctx, &mut poseidon2, &assigned), native);
+
+        let permutation = RescuePrimePermutation::<Fr, 3>::new(1, 128, 5);
+        let native = SpongeChip::<_, _, 3, 2>::hash_native(&permutation, &inputs);
+        let mut rescue = SpongeChip::<_, _, 3, 2>::new(ctx, permutation);
+        assert_eq!(sponge_hash(ctx, &mut rescue, &assigned), native);
+    }
+    mock_verify(K, &GateCircuitBuilder::mock(builder)).unwrap();
+}
+
+fn poseidon_challenge<const W: usize>(
+    ctx: &mut Context<Fr>,
+    chip: &PermutationChip<Fr>,
+    a: &[[AssignedValue<Fr>; W]],
+    b: &[[AssignedValue<Fr>; W]],
+) -> AssignedValue<Fr> {
+    let mut poseidon = PoseidonChip::<Fr, 3, 2>::new(ctx, 8, 57).unwrap();
+    chip.hash_challenge(ctx, a, b, |ctx, elements| {
+        poseidon.update(elements);
+        poseidon.squeeze(ctx, chip.gate()).unwrap()
+    })
+}
+
+/// Sorts in a single phase, with the challenge of the permutation argument squeezed after absorbing both arrays.
+fn sort_with_poseidon_challenge(values: &[u64], tamper: bool) -> bool {
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(8);
+    let chip = PermutationChip::new(&range);
+    let ctx = builder.main(0);
+    let values = ctx.assign_witnesses(values.iter().map(|v| Fr::from(*v)));
+    let mut sorted = chip.witness_sorted(ctx, &values, 8);
+    if tamper {
+        // a sorted array which is not a permutation
+        sorted = ctx.assign_witnesses((0..values.len() as u64).map(Fr::from));
+    }
+    let [a, b] = [&values, &sorted].map(|values| values.iter().map(|x| [*x]).collect::<Vec<_>>());
+    let gamma = poseidon_challenge(ctx, &chip, &a, &b);
+    chip.assert_permutation(ctx, &values, &sorted, gamma);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok()
+}
+
+#[test]
+fn test_permutation_with_poseidon_challenge() {
+    assert!(sort_with_poseidon_challenge(&[9, 4, 250, 4, 0, 17], false));
+    assert!(!sort_with_poseidon_challenge(&[9, 4, 250, 4, 0, 17], true));
+}
+
+/// Checks multiset equality in a single phase, with the challenge squeezed after absorbing both multisets.
+fn multiset_with_poseidon_challenge(a: &[[u64; 2]], b: &[[u64; 2]]) -> bool {
+    set_var("LOOKUP_BITS", "8");
+    let mut builder = GateThreadBuilder::mock();
+    let range = RangeChip::default(8);
+    let chip = PermutationChip::new(&range);
+    let ctx = builder.main(0);
+    let [a, b] = [a, b].map(|tuples| {
+        tuples.iter().map(|tuple| tuple.map(|v| ctx.load_witness(Fr::from(v)))).collect::<Vec<_>>()
+    });
+    let gamma = poseidon_challenge(ctx, &chip, &a, &b);
+    chip.assert_multiset_equal(ctx, &a, &b, gamma);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok()
+}
+
+#[test]
+fn test_multiset_equal_with_poseidon_challenge() {
+    assert!(multiset_with_poseidon_challenge(
+        &[[1, 10], [2, 20], [1, 10]],
+        &[[2, 20], [1, 10], [1, 10]]
+    ));
+    assert!(!multiset_with_poseidon_challenge(&[[1, 10], [2, 20]], &[[1, 20], [2, 10]]));
+    assert!(!multiset_with_poseidon_challenge(&[[1, 10], [1, 10]], &[[1, 10], [2, 20]]));
+}
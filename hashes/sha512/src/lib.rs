@@ -0,0 +1,394 @@
+//! SHA-512, SHA-384, SHA-512/256 and SHA-512/224 (FIPS 180-4) built on top of [halo2_base] gates.
+//!
+//! Every 64-bit word is kept as a little-endian vector of assigned bits, so rotations and shifts
+//! are free re-indexings and the boolean functions are evaluated bit by bit with [GateInstructions].
+//! Additions modulo 2<sup>64</sup> are done on the composed native values, which are then decomposed
+//! back into bits with [GateInstructions::num_to_bits] and truncated.
+//!
+//! The byte-level API mirrors the keccak chip: the input is a vector of bytes, and for variable length
+//! inputs the padding is computed in-circuit from an assigned length, with the maximum number of
+//! blocks fixed at keygen by the length of the input vector.
+#![allow(clippy::too_many_arguments)]
+
+use halo2_base::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    utils::{bit_length, BigPrimeField},
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing},
+};
+
+/// Round constants and initial hash values
+pub mod constants;
+
+#[cfg(test)]
+mod tests;
+
+pub use constants::{
+    ROUND_CONSTANTS, SHA384_INITIAL_STATE, SHA512_224_INITIAL_STATE, SHA512_256_INITIAL_STATE,
+    SHA512_INITIAL_STATE,
+};
+
+/// Number of bits in a word.
+pub const WORD_BITS: usize = 64;
+/// Number of bytes in a message block.
+pub const BLOCK_BYTES: usize = 128;
+/// Number of bytes at the end of the final block that hold the message length in bits.
+pub const LENGTH_BYTES: usize = 16;
+/// Number of rounds of the compression function.
+pub const NUM_ROUNDS: usize = 80;
+/// Number of words in the hash state.
+pub const NUM_STATE_WORDS: usize = 8;
+
+/// The members of the SHA-512 family supported by [Sha512Chip].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sha512Variant {
+    /// SHA-512 with a 64 byte digest.
+    #[default]
+    Sha512,
+    /// SHA-384 with a 48 byte digest: different initial state, output truncated to the first 6 words.
+    Sha384,
+    /// SHA-512/256 with a 32 byte digest: different initial state, output truncated to the first 4 words.
+    Sha512_256,
+    /// SHA-512/224 with a 28 byte digest: different initial state, output truncated to the first 28 bytes.
+    Sha512_224,
+}
+
+impl Sha512Variant {
+    /// Returns the initial hash value of this variant.
+    pub fn initial_state(&self) -> [u64; NUM_STATE_WORDS] {
+        match self {
+            Self::Sha512 => SHA512_INITIAL_STATE,
+            Self::Sha384 => SHA384_INITIAL_STATE,
+            Self::Sha512_256 => SHA512_256_INITIAL_STATE,
+            Self::Sha512_224 => SHA512_224_INITIAL_STATE,
+        }
+    }
+
+    /// Returns the number of bytes in the digest.
+    pub fn digest_bytes(&self) -> usize {
+        match self {
+            Self::Sha512 => 64,
+            Self::Sha384 => 48,
+            Self::Sha512_256 => 32,
+            Self::Sha512_224 => 28,
+        }
+    }
+}
+
+/// A 64-bit word represented by its little-endian bits, each constrained to be boolean.
+#[derive(Clone, Debug)]
+pub struct AssignedWord<F: BigPrimeField>(Vec<AssignedValue<F>>);
+
+impl<F: BigPrimeField> AssignedWord<F> {
+    /// Returns the little-endian bits of the word.
+    pub fn bits(&self) -> &[AssignedValue<F>] {
+        &self.0
+    }
+
+    /// Returns the word right rotated by `n` bits.
+    fn rotr(&self, n: usize) -> Self {
+        Self((0..WORD_BITS).map(|i| self.0[(i + n) % WORD_BITS]).collect())
+    }
+
+    /// Returns the word right shifted by `n` bits, filling with `zero`.
+    fn shr(&self, n: usize, zero: AssignedValue<F>) -> Self {
+        Self((0..WORD_BITS).map(|i| if i + n < WORD_BITS { self.0[i + n] } else { zero }).collect())
+    }
+}
+
+/// Chip for computing SHA-512 family digests of byte strings.
+#[derive(Clone, Debug)]
+pub struct Sha512Chip<'a, F: BigPrimeField> {
+    /// Which member of the SHA-512 family to compute.
+    pub variant: Sha512Variant,
+    /// Underlying range chip, used for the variable length padding.
+    pub range: &'a RangeChip<F>,
+}
+
+impl<'a, F: BigPrimeField> Sha512Chip<'a, F> {
+    /// Creates a new [Sha512Chip] for `variant`.
+    pub fn new(range: &'a RangeChip<F>, variant: Sha512Variant) -> Self {
+        Self { variant, range }
+    }
+
+    /// Returns the underlying [GateInstructions].
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Constrains and returns the big-endian digest bytes of the message `bytes`, whose length is known at keygen.
+    ///
+    /// Each element of `bytes` is constrained to be a byte.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `bytes`: the message, as assigned bytes
+    pub fn digest_fixed_len(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[AssignedValue<F>],
+    ) -> Vec<AssignedValue<F>> {
+        let padded = pad_native(bytes.len())
+            .into_iter()
+            .enumerate()
+            .map(|(i, byte)| match byte {
+                None => bytes[i],
+                Some(byte) => ctx.load_constant(F::from(byte as u64)),
+            })
+            .collect::<Vec<_>>();
+
+        let mut state = self.load_initial_state(ctx);
+        for block in padded.chunks(BLOCK_BYTES) {
+            state = self.compress(ctx, &state, block);
+        }
+        self.state_to_digest(ctx, &state)
+    }
+
+    /// Constrains and returns the big-endian digest bytes of the first `len` bytes of `bytes`.
+    ///
+    /// The maximum message length is `bytes.len()` and is fixed at keygen; the circuit always compresses
+    /// `(bytes.len() + 16) / 128 + 1` blocks and selects the state after the final block of the padded message.
+    /// The bytes of `bytes` at indices `>= len` are ignored. The bytes at indices `< len` are constrained to be bytes.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `bytes`: the message padded with arbitrary values to the maximum length
+    /// * `len`: the length of the message in bytes, constrained to be `<= bytes.len()`
+    pub fn digest_var_len(
+        &self,
+        ctx: &mut Context<F>,
+        bytes: &[AssignedValue<F>],
+        len: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let gate = self.gate();
+        let max_len = bytes.len();
+        let num_blocks = (max_len + LENGTH_BYTES) / BLOCK_BYTES + 1;
+        let padded_len = num_blocks * BLOCK_BYTES;
+        let num_bits = bit_length(padded_len as u64);
+
+        // len <= max_len < padded_len, so exactly one entry of `is_pad_start` is 1
+        self.range.check_less_than_safe(ctx, len, max_len as u64 + 1);
+        let is_pad_start = gate.idx_to_indicator(ctx, len, padded_len);
+
+        // the final block is the one containing byte `len + 16`
+        let len_plus = gate.add(ctx, len, Constant(F::from(LENGTH_BYTES as u64)));
+        let (last_block, _) = self.range.div_mod(ctx, len_plus, BLOCK_BYTES as u64, num_bits);
+        let is_last_block = gate.idx_to_indicator(ctx, last_block, num_blocks);
+
+        // little-endian bytes of the message length in bits: `8 * len < 2^(num_bits + 3)`
+        let len_in_bits = {
+            let zero = ctx.load_zero();
+            let mut bits = vec![zero; 3];
+            bits.extend(gate.num_to_bits(ctx, len, num_bits));
+            bits.chunks(8).map(|byte| self.compose_bits(ctx, byte)).collect::<Vec<_>>()
+        };
+        assert!(len_in_bits.len() <= LENGTH_BYTES);
+
+        let mut in_msg = ctx.load_constant(F::one());
+        let mut padded = Vec::with_capacity(padded_len);
+        for (i, is_pad_start) in is_pad_start.into_iter().enumerate() {
+            in_msg = gate.sub(ctx, in_msg, is_pad_start);
+            let mut byte = match bytes.get(i) {
+                Some(byte) => gate.mul(ctx, *byte, in_msg),
+                None => ctx.load_zero(),
+            };
+            byte = gate.mul_add(ctx, is_pad_start, Constant(F::from(0x80)), byte);
+            // the length is written in big-endian in the last 16 bytes of the final block
+            let idx_in_length = (BLOCK_BYTES - 1) - i % BLOCK_BYTES;
+            if let Some(len_byte) = len_in_bits.get(idx_in_length) {
+                byte = gate.mul_add(ctx, is_last_block[i / BLOCK_BYTES], *len_byte, byte);
+            }
+            padded.push(byte);
+        }
+
+        let mut state = self.load_initial_state(ctx);
+        let mut digests = Vec::with_capacity(num_blocks);
+        for block in padded.chunks(BLOCK_BYTES) {
+            state = self.compress(ctx, &state, block);
+            digests.push(self.state_to_digest(ctx, &state));
+        }
+        (0..self.variant.digest_bytes())
+            .map(|i| {
+                gate.select_by_indicator(
+                    ctx,
+                    digests.iter().map(|digest| digest[i]),
+                    is_last_block.iter().copied(),
+                )
+            })
+            .collect()
+    }
+
+    /// Constrains and returns the state after applying the compression function to `state` and `block`.
+    ///
+    /// Each element of `block` is constrained to be a byte.
+    /// * `ctx`: [Context] to add the constraints to
+    /// * `state`: the current hash state
+    /// * `block`: [BLOCK_BYTES] assigned bytes
+    pub fn compress(
+        &self,
+        ctx: &mut Context<F>,
+        state: &[AssignedWord<F>],
+        block: &[AssignedValue<F>],
+    ) -> Vec<AssignedWord<F>> {
+        assert_eq!(state.len(), NUM_STATE_WORDS);
+        assert_eq!(block.len(), BLOCK_BYTES);
+        let gate = self.gate();
+        let zero = ctx.load_zero();
+
+        // message schedule
+        let mut w = block
+            .chunks(WORD_BITS / 8)
+            .map(|word| {
+                let bits = word.iter().rev().flat_map(|byte| gate.num_to_bits(ctx, *byte, 8));
+                AssignedWord(bits.collect())
+            })
+            .collect::<Vec<_>>();
+        let mut w_native =
+            w.iter().map(|word| self.compose_bits(ctx, word.bits())).collect::<Vec<_>>();
+        for t in 16..NUM_ROUNDS {
+            let s0 =
+                self.xor3(ctx, &w[t - 15].rotr(1), &w[t - 15].rotr(8), &w[t - 15].shr(7, zero));
+            let s1 = self.xor3(ctx, &w[t - 2].rotr(19), &w[t - 2].rotr(61), &w[t - 2].shr(6, zero));
+            let s0 = self.compose_bits(ctx, s0.bits());
+            let s1 = self.compose_bits(ctx, s1.bits());
+            let sum = gate.sum(ctx, [s1, w_native[t - 7], s0, w_native[t - 16]]);
+            let word = self.mod_word(ctx, sum, 2);
+            w_native.push(self.compose_bits(ctx, word.bits()));
+            w.push(word);
+        }
+
+        let mut vars = state.to_vec();
+        for t in 0..NUM_ROUNDS {
+            let [a, b, c, d, e, f, g, h] = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| &vars[i]);
+            let sigma1 = self.xor3(ctx, &e.rotr(14), &e.rotr(18), &e.rotr(41));
+            let ch = AssignedWord(
+                (0..WORD_BITS).map(|i| gate.select(ctx, f.0[i], g.0[i], e.0[i])).collect(),
+            );
+            let sigma0 = self.xor3(ctx, &a.rotr(28), &a.rotr(34), &a.rotr(39));
+            // maj(a, b, c) = b if b == c, otherwise a
+            let maj = AssignedWord(
+                (0..WORD_BITS)
+                    .map(|i| {
+                        let b_xor_c = gate.xor(ctx, b.0[i], c.0[i]);
+                        gate.select(ctx, a.0[i], b.0[i], b_xor_c)
+                    })
+                    .collect(),
+            );
+
+            let [h, d, sigma1, ch, sigma0, maj] =
+                [h, d, &sigma1, &ch, &sigma0, &maj].map(|word| self.compose_bits(ctx, word.bits()));
+            let t1 = gate.sum(
+                ctx,
+                [
+                    Existing(h),
+                    Existing(sigma1),
+                    Existing(ch),
+                    Constant(F::from(ROUND_CONSTANTS[t])),
+                    Existing(w_native[t]),
+                ],
+            );
+            // t1 < 5 * 2^64, so both sums below are less than 2^67
+            let new_e = gate.add(ctx, t1, d);
+            let new_a = gate.sum(ctx, [t1, sigma0, maj]);
+            let new_e = self.mod_word(ctx, new_e, 3);
+            let new_a = self.mod_word(ctx, new_a, 3);
+
+            vars = vec![
+                new_a,
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                new_e,
+                e.clone(),
+                f.clone(),
+                g.clone(),
+            ];
+        }
+
+        state
+            .iter()
+            .zip(vars.iter())
+            .map(|(x, y)| {
+                let x = self.compose_bits(ctx, x.bits());
+                let y = self.compose_bits(ctx, y.bits());
+                let sum = gate.add(ctx, x, y);
+                self.mod_word(ctx, sum, 1)
+            })
+            .collect()
+    }
+
+    /// Loads the initial hash value of [Self::variant] as constants.
+    pub fn load_initial_state(&self, ctx: &mut Context<F>) -> Vec<AssignedWord<F>> {
+        self.variant
+            .initial_state()
+            .iter()
+            .map(|word| {
+                let bits = (0..WORD_BITS).map(|i| F::from((word >> i) & 1));
+                AssignedWord(bits.map(|bit| ctx.load_constant(bit)).collect())
+            })
+            .collect()
+    }
+
+    /// Constrains and returns the big-endian digest bytes of `state`, truncated according to [Self::variant].
+    pub fn state_to_digest(
+        &self,
+        ctx: &mut Context<F>,
+        state: &[AssignedWord<F>],
+    ) -> Vec<AssignedValue<F>> {
+        // SHA-512/224 ends in the middle of a word, so truncate the big-endian bytes rather than the words
+        state
+            .iter()
+            .flat_map(|word| word.0.chunks(8).rev())
+            .take(self.variant.digest_bytes())
+            .map(|byte| self.compose_bits(ctx, byte))
+            .collect()
+    }
+
+    /// Constrains and returns `sum_i bits[i] * 2^i`.
+    fn compose_bits(&self, ctx: &mut Context<F>, bits: &[AssignedValue<F>]) -> AssignedValue<F> {
+        let gate = self.gate();
+        gate.inner_product(
+            ctx,
+            bits.iter().copied(),
+            gate.pow_of_two()[..bits.len()].iter().map(|c| Constant(*c)),
+        )
+    }
+
+    /// Constrains and returns `a mod 2^64` as bits, assuming `a` has at most `64 + carry_bits` bits.
+    fn mod_word(
+        &self,
+        ctx: &mut Context<F>,
+        a: AssignedValue<F>,
+        carry_bits: usize,
+    ) -> AssignedWord<F> {
+        let mut bits = self.gate().num_to_bits(ctx, a, WORD_BITS + carry_bits);
+        bits.truncate(WORD_BITS);
+        AssignedWord(bits)
+    }
+
+    /// Constrains and returns the bitwise XOR of three words.
+    fn xor3(
+        &self,
+        ctx: &mut Context<F>,
+        a: &AssignedWord<F>,
+        b: &AssignedWord<F>,
+        c: &AssignedWord<F>,
+    ) -> AssignedWord<F> {
+        let gate = self.gate();
+        AssignedWord(
+            (0..WORD_BITS)
+                .map(|i| {
+                    let x = gate.xor(ctx, a.0[i], b.0[i]);
+                    gate.xor(ctx, x, c.0[i])
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Returns the padded message for a message of `len` bytes, where `None` stands for the message byte at that index
+/// and `Some(byte)` is a padding byte.
+pub fn pad_native(len: usize) -> Vec<Option<u8>> {
+    let num_blocks = (len + LENGTH_BYTES) / BLOCK_BYTES + 1;
+    let mut padded = (0..len).map(|_| None).collect::<Vec<_>>();
+    padded.push(Some(0x80));
+    padded.resize(num_blocks * BLOCK_BYTES - LENGTH_BYTES, Some(0));
+    padded.extend(((len as u128) * 8).to_be_bytes().map(Some));
+    padded
+}
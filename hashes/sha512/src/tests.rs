@@ -0,0 +1,135 @@
+use std::env::set_var;
+
+use super::*;
+use halo2_base::{
+    gates::builder::{GateThreadBuilder, RangeCircuitBuilder},
+    halo2_proofs::halo2curves::bn256::Fr,
+    utils::testing::mock_verify,
+};
+use test_case::test_case;
+
+const K: usize = 17;
+const LOOKUP_BITS: usize = 8;
+
+// FIPS 180-4 example vectors: one-block, empty and two-block messages
+const ABC: &str = "abc";
+const EMPTY: &str = "";
+const TWO_BLOCK: &str = "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu";
+const SHA512_ABC: &str = "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+
+fn check_digest(digest: &[AssignedValue<Fr>], expected: &str) {
+    let expected = hex::decode(expected).unwrap().into_iter().map(|b| Fr::from(b as u64));
+    let digest = digest.iter().map(|byte| *byte.value());
+    assert_eq!(digest.collect::<Vec<_>>(), expected.collect::<Vec<_>>());
+}
+
+#[test_case(Sha512Variant::Sha512, ABC, "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"; "sha512 abc")]
+#[test_case(Sha512Variant::Sha512, EMPTY, "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"; "sha512 empty")]
+#[test_case(Sha512Variant::Sha512, TWO_BLOCK, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"; "sha512 two blocks")]
+#[test_case(Sha512Variant::Sha384, ABC, "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"; "sha384 abc")]
+#[test_case(Sha512Variant::Sha384, TWO_BLOCK, "09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039"; "sha384 two blocks")]
+#[test_case(Sha512Variant::Sha512_256, ABC, "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23"; "sha512_256 abc")]
+#[test_case(Sha512Variant::Sha512_256, TWO_BLOCK, "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a"; "sha512_256 two blocks")]
+#[test_case(Sha512Variant::Sha512_224, ABC, "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"; "sha512_224 abc")]
+#[test_case(Sha512Variant::Sha512_224, TWO_BLOCK, "23fec5bb94d60b23308192640b0c453335d664734fe40e7268674af9"; "sha512_224 two blocks")]
+fn test_sha512_fixed_len(variant: Sha512Variant, msg: &str, expected: &str) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = Sha512Chip::new(&range, variant);
+
+    let bytes = ctx.assign_witnesses(msg.bytes().map(|b| Fr::from(b as u64)));
+    let digest = chip.digest_fixed_len(ctx, &bytes);
+    check_digest(&digest, expected);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+#[test_case(Sha512Variant::Sha512, ABC, 130, "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f"; "sha512 abc")]
+#[test_case(Sha512Variant::Sha512, EMPTY, 3, "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"; "sha512 empty")]
+#[test_case(Sha512Variant::Sha512, TWO_BLOCK, 120, "8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909"; "sha512 two blocks")]
+#[test_case(Sha512Variant::Sha384, ABC, 120, "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"; "sha384 abc")]
+#[test_case(Sha512Variant::Sha512_256, TWO_BLOCK, 200, "3928e184fb8690f840da3988121d31be65cb9d3ef83ee6146feac861e19b563a"; "sha512_256 two blocks")]
+#[test_case(Sha512Variant::Sha512_224, ABC, 120, "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"; "sha512_224 abc")]
+fn test_sha512_var_len(variant: Sha512Variant, msg: &str, max_len: usize, expected: &str) {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = Sha512Chip::new(&range, variant);
+
+    // pad the message with non-zero garbage, which must be ignored
+    let mut msg = msg.as_bytes().to_vec();
+    let len = msg.len();
+    msg.resize(max_len, 0xff);
+    let bytes = ctx.assign_witnesses(msg.into_iter().map(|b| Fr::from(b as u64)));
+    let len = ctx.load_witness(Fr::from(len as u64));
+    let digest = chip.digest_var_len(ctx, &bytes, len);
+    check_digest(&digest, expected);
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).unwrap();
+}
+
+/// Hashes the first `len` bytes of `msg` padded to `max_len`, constrains the digest to equal `expected` with the
+/// byte at `tamper` flipped, and returns whether the circuit is satisfied.
+fn verify_var_len(
+    msg: &str,
+    len: usize,
+    max_len: usize,
+    expected: &str,
+    tamper: Option<usize>,
+) -> bool {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let ctx = builder.main(0);
+    let range = RangeChip::default(LOOKUP_BITS);
+    let chip = Sha512Chip::new(&range, Sha512Variant::Sha512);
+
+    let mut msg = msg.as_bytes().to_vec();
+    msg.resize(max_len, 0xff);
+    let bytes = ctx.assign_witnesses(msg.into_iter().map(|b| Fr::from(b as u64)));
+    let len = ctx.load_witness(Fr::from(len as u64));
+    let digest = chip.digest_var_len(ctx, &bytes, len);
+    let mut expected = hex::decode(expected).unwrap();
+    if let Some(i) = tamper {
+        expected[i] ^= 1;
+    }
+    for (byte, expected) in digest.iter().zip(expected) {
+        let expected = ctx.load_witness(Fr::from(expected as u64));
+        ctx.constrain_equal(byte, &expected);
+    }
+    mock_verify(K, &RangeCircuitBuilder::mock(builder)).is_ok()
+}
+
+#[test]
+fn test_sha512_var_len_constrained() {
+    assert!(verify_var_len(ABC, 3, 3, SHA512_ABC, None));
+}
+
+#[test_case(0; "first byte")]
+#[test_case(63; "last byte")]
+fn test_sha512_var_len_tampered_digest(tamper: usize) {
+    assert!(!verify_var_len(ABC, 3, 120, SHA512_ABC, Some(tamper)));
+}
+
+#[test]
+fn test_sha512_var_len_too_long() {
+    // bytes past the end of the input are zero, so this is the digest of "abc\0": the witness is consistent with
+    // `len = 4` except for `len <= max_len`
+    let expected = "7ce05eda233e545a2d5c626862a5ddaafb09b9d8ec3bec08aa458b7c9e7d939d84a57d5a20d8a9002983aabae2457b19c50ba326bf5b081f75b41342f42c3383";
+    assert!(!verify_var_len(ABC, 4, 3, expected, None));
+}
+
+#[test]
+fn test_pad_native() {
+    for len in [0, 3, 111, 112, 127, 128, 239, 240] {
+        let padded = pad_native(len);
+        assert_eq!(padded.len() % BLOCK_BYTES, 0);
+        assert_eq!(
+            padded.len(),
+            (len + 1 + LENGTH_BYTES + BLOCK_BYTES - 1) / BLOCK_BYTES * BLOCK_BYTES
+        );
+        assert_eq!(padded[len], Some(0x80));
+        let bit_len = padded[padded.len() - LENGTH_BYTES..].iter().map(|b| b.unwrap());
+        assert_eq!(bit_len.fold(0u128, |acc, b| (acc << 8) + b as u128), 8 * len as u128);
+    }
+}
@@ -0,0 +1,412 @@
+//! Keccak256 hashes of bytes assigned in a [GateThreadBuilder], constrained by the keccak circuit.
+//!
+//! The [KeccakChip] records the hashes queried while building the circuit, and the [KeccakCircuitBuilder] assigns
+//! them in the keccak circuit next to the [RangeCircuitBuilder] and links both with copy constraints on the random
+//! linear combinations (RLCs) of the inputs and outputs of the hashes.
+
+use crate::{
+    halo2_proofs::{
+        circuit::{Cell, Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error, FirstPhase},
+    },
+    keccak_packed_multi::{
+        get_keccak_capacity, get_num_keccak_f, get_num_rows_per_round, multi_keccak_phase0,
+        multi_keccak_phase1, KeccakCircuitConfig,
+    },
+    util::{eth_types::Field, NUM_ROUNDS},
+};
+use ethers_core::utils::keccak256;
+use halo2_base::{
+    gates::{
+        builder::{
+            FlexGateConfigParams, GateCircuitBuilder, GateThreadBuilder,
+            MultiPhaseThreadBreakPoints, RangeCircuitBuilder,
+        },
+        range::RangeConfig,
+        RangeChip,
+    },
+    rlc::RlcChip,
+    AssignedValue, Context, SKIP_FIRST_PASS,
+};
+use std::{
+    env::var,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A Keccak256 hash queried with [KeccakChip::keccak_fixed_len] or [KeccakChip::keccak_var_len].
+#[derive(Clone, Debug)]
+pub struct KeccakQuery<F: Field> {
+    /// The input bytes, where the bytes after the first `len` are arbitrary.
+    pub input_assigned: Vec<AssignedValue<F>>,
+    /// The length of the input, or [None] if it is all of `input_assigned`.
+    pub len: Option<AssignedValue<F>>,
+    /// The 32 bytes of the hash.
+    pub output_assigned: Vec<AssignedValue<F>>,
+    /// The values of the first `len` input bytes.
+    pub input_bytes: Vec<u8>,
+}
+
+impl<F: Field> KeccakQuery<F> {
+    /// The maximum length of the input.
+    pub fn max_len(&self) -> usize {
+        self.input_assigned.len()
+    }
+
+    /// The number of keccak_f permutations the query takes in the keccak circuit, see [KeccakChip::keccak_inputs].
+    pub fn num_keccak_f(&self) -> usize {
+        get_num_keccak_f(self.max_len())
+    }
+}
+
+/// Chip for Keccak256 hashes of assigned bytes.
+///
+/// The hashes are only witnessed in the [GateThreadBuilder] and are constrained by the keccak circuit of a
+/// [KeccakCircuitBuilder]: in phase 1 the RLCs of the input and output of each query are computed with the challenge
+/// of the keccak circuit, see [Self::assign_phase1], and constrained to equal the `input_rlc` and `output_rlc` of the
+/// keccak table at the last keccak_f permutation of the hash.
+///
+/// RLCs do not distinguish arrays which only differ by leading zero bytes, so the length of each query is also
+/// constrained to equal the `input_len` of the keccak table: its `len`, or its maximum length for fixed length queries.
+#[derive(Clone, Debug, Default)]
+pub struct KeccakChip<F: Field> {
+    queries: Vec<KeccakQuery<F>>,
+}
+
+impl<F: Field> KeccakChip<F> {
+    /// Construct a KeccakChip without queries.
+    pub fn new() -> Self {
+        Self { queries: vec![] }
+    }
+
+    /// Returns the hashes queried so far.
+    pub fn queries(&self) -> &[KeccakQuery<F>] {
+        &self.queries
+    }
+
+    /// Witnesses and returns the 32 bytes of the Keccak256 hash of `input`.
+    ///
+    /// The output bytes are constrained to be bytes by the keccak circuit.
+    pub fn keccak_fixed_len(
+        &mut self,
+        ctx: &mut Context<F>,
+        input: Vec<AssignedValue<F>>,
+    ) -> Vec<AssignedValue<F>> {
+        let input_bytes = input.iter().map(|byte| byte.value().get_lower_32() as u8).collect();
+        self.query(ctx, input, None, input_bytes)
+    }
+
+    /// Witnesses and returns the 32 bytes of the Keccak256 hash of the first `len` bytes of `input`.
+    ///
+    /// Constrains `len <= input.len()` in phase 1. The query takes the keccak_f permutations of an input of
+    /// `input.len()` bytes in the keccak circuit, whatever `len` is.
+    pub fn keccak_var_len(
+        &mut self,
+        ctx: &mut Context<F>,
+        input: Vec<AssignedValue<F>>,
+        len: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let len_val = len.value().get_lower_32() as usize;
+        let input_bytes =
+            input.iter().take(len_val).map(|byte| byte.value().get_lower_32() as u8).collect();
+        self.query(ctx, input, Some(len), input_bytes)
+    }
+
+    fn query(
+        &mut self,
+        ctx: &mut Context<F>,
+        input_assigned: Vec<AssignedValue<F>>,
+        len: Option<AssignedValue<F>>,
+        input_bytes: Vec<u8>,
+    ) -> Vec<AssignedValue<F>> {
+        let output = keccak256(&input_bytes);
+        let output_assigned = ctx.assign_witnesses(output.map(|byte| F::from(byte as u64)));
+        self.queries.push(KeccakQuery {
+            input_assigned,
+            len,
+            output_assigned: output_assigned.clone(),
+            input_bytes,
+        });
+        output_assigned
+    }
+
+    /// Constrains and returns the RLCs of the input and of the output of each query, with the challenge of `rlc`.
+    ///
+    /// Constrains the lengths of variable length queries to be at most their maximum lengths.
+    pub fn assign_phase1(
+        &self,
+        ctx: &mut Context<F>,
+        rlc: &RlcChip<F>,
+    ) -> Vec<(AssignedValue<F>, AssignedValue<F>)> {
+        self.queries
+            .iter()
+            .map(|query| {
+                let input = query.input_assigned.iter().copied();
+                let input_rlc = match query.len {
+                    Some(len) => rlc.compute_rlc(ctx, input, len),
+                    None => rlc.compute_rlc_fixed_len(ctx, input),
+                };
+                let output_rlc =
+                    rlc.compute_rlc_fixed_len(ctx, query.output_assigned.iter().copied());
+                (input_rlc.rlc_val, output_rlc.rlc_val)
+            })
+            .collect()
+    }
+
+    /// Returns the inputs to hash in the keccak circuit.
+    ///
+    /// The circuit layout must not depend on the lengths of variable length inputs, so each query takes the keccak_f
+    /// permutations of its maximum length: its input is preceded by enough hashes of the empty input that the hash of
+    /// the query always ends at the last of these permutations, see [Self::query_output_indices].
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        self.queries
+            .iter()
+            .flat_map(|query| {
+                let num_padding = query.num_keccak_f() - get_num_keccak_f(query.input_bytes.len());
+                vec![vec![]; num_padding].into_iter().chain([query.input_bytes.clone()])
+            })
+            .collect()
+    }
+
+    /// Returns for each query the index of the keccak_f permutation in the keccak circuit at which its hash ends.
+    pub fn query_output_indices(&self) -> Vec<usize> {
+        let mut num_keccak_f = 0;
+        self.queries
+            .iter()
+            .map(|query| {
+                num_keccak_f += query.num_keccak_f();
+                num_keccak_f - 1
+            })
+            .collect()
+    }
+}
+
+/// Configuration of a [KeccakCircuitBuilder].
+#[derive(Clone, Debug)]
+pub struct KeccakCircuitBuilderConfig<F: Field> {
+    /// The configuration of the [RangeCircuitBuilder]
+    pub range: RangeConfig<F>,
+    /// The configuration of the keccak circuit, which uses the challenge of the phase 1 callbacks
+    pub keccak: KeccakCircuitConfig<F>,
+}
+
+/// Cells of phase 1 assigned by the callback of a [KeccakCircuitBuilder].
+#[derive(Clone, Debug)]
+struct KeccakAssignments<F: Field> {
+    one: AssignedValue<F>,
+    rlcs: Vec<(AssignedValue<F>, AssignedValue<F>)>,
+    lens: Vec<AssignedValue<F>>,
+}
+
+/// A [RangeCircuitBuilder] together with the keccak circuit constraining the hashes of a [KeccakChip].
+///
+/// Registers a phase 1 callback on the [GateThreadBuilder] which computes the RLCs of the queries, so it must be
+/// created once all queries are made. Uses the environment variable `LOOKUP_BITS` like [RangeCircuitBuilder].
+#[derive(Clone, Debug)]
+pub struct KeccakCircuitBuilder<F: Field> {
+    /// The underlying circuit builder
+    pub circuit: RangeCircuitBuilder<F>,
+    /// The keccak queries of the circuit
+    pub keccak: KeccakChip<F>,
+    /// The cells assigned by the phase 1 callback, updated whenever it runs
+    assignments: Arc<Mutex<Option<KeccakAssignments<F>>>>,
+}
+
+impl<F: Field> KeccakCircuitBuilder<F> {
+    /// See [`RangeCircuitBuilder::keygen`]
+    pub fn keygen(builder: GateThreadBuilder<F>, keccak: KeccakChip<F>) -> Self {
+        Self::new(builder, keccak, RangeCircuitBuilder::keygen)
+    }
+
+    /// See [`RangeCircuitBuilder::mock`]
+    pub fn mock(builder: GateThreadBuilder<F>, keccak: KeccakChip<F>) -> Self {
+        Self::new(builder, keccak, RangeCircuitBuilder::mock)
+    }
+
+    /// See [`RangeCircuitBuilder::prover`]
+    pub fn prover(
+        builder: GateThreadBuilder<F>,
+        keccak: KeccakChip<F>,
+        break_points: MultiPhaseThreadBreakPoints,
+    ) -> Self {
+        Self::new(builder, keccak, |builder| RangeCircuitBuilder::prover(builder, break_points))
+    }
+
+    fn new(
+        mut builder: GateThreadBuilder<F>,
+        keccak: KeccakChip<F>,
+        circuit: impl FnOnce(GateThreadBuilder<F>) -> RangeCircuitBuilder<F>,
+    ) -> Self {
+        let lookup_bits = var("LOOKUP_BITS").unwrap_or_else(|_| "0".to_string()).parse().unwrap();
+        let assignments = Arc::new(Mutex::new(None));
+        if !keccak.queries.is_empty() {
+            let (keccak, assignments) = (keccak.clone(), assignments.clone());
+            builder.register_phase1_callback(move |builder, gamma| {
+                let range = RangeChip::default(lookup_bits);
+                let rlc = RlcChip::new(&range, gamma);
+                let ctx = builder.main(1);
+                let one = ctx.load_constant(F::one());
+                let rlcs = keccak.assign_phase1(ctx, &rlc);
+                let lens = keccak
+                    .queries
+                    .iter()
+                    .map(|query| {
+                        query
+                            .len
+                            .unwrap_or_else(|| ctx.load_constant(F::from(query.max_len() as u64)))
+                    })
+                    .collect();
+                *assignments.lock().unwrap() = Some(KeccakAssignments { one, rlcs, lens });
+            });
+        }
+        Self { circuit: circuit(builder), keccak, assignments }
+    }
+
+    /// Calls [`GateThreadBuilder::config`]
+    pub fn config(&self, k: u32, minimum_rows: Option<usize>) -> FlexGateConfigParams {
+        self.circuit.0.builder.borrow().config(k as usize, minimum_rows)
+    }
+
+    /// Gets the break points of the circuit.
+    pub fn break_points(&self) -> MultiPhaseThreadBreakPoints {
+        self.circuit.0.break_points.borrow().clone()
+    }
+}
+
+impl<F: Field> AsRef<GateCircuitBuilder<F>> for KeccakCircuitBuilder<F> {
+    fn as_ref(&self) -> &GateCircuitBuilder<F> {
+        &self.circuit.0
+    }
+}
+
+impl<F: Field> Circuit<F> for KeccakCircuitBuilder<F> {
+    type Config = KeccakCircuitBuilderConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        unimplemented!()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let range = RangeCircuitBuilder::configure(meta);
+        // the RLCs of the phase 1 callbacks and of the keccak table must use the same challenge
+        let challenge = range.gate.gamma.unwrap_or_else(|| meta.challenge_usable_after(FirstPhase));
+        let keccak = KeccakCircuitConfig::new(meta, challenge);
+        meta.enable_equality(keccak.keccak_table.is_enabled);
+        KeccakCircuitBuilderConfig { range, keccak }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // copied from RangeCircuitBuilder::synthesize but with the keccak circuit assigned around it
+        let range = config.range;
+        let circuit = &self.circuit.0;
+        // only load lookup table if we are actually doing lookups
+        if range.lookup_advice.iter().map(|a| a.len()).sum::<usize>() != 0
+            || !range.q_lookup.iter().all(|q| q.is_none())
+        {
+            range.load_lookup_table(&mut layouter).expect("load lookup table should not fail");
+        }
+        range
+            .load_custom_lookup_tables(&mut layouter, circuit.builder.borrow().lookup_tables())
+            .expect("load custom lookup tables should not fail");
+        config.keccak.load_aux_tables(&mut layouter)?;
+
+        let inputs = self.keccak.keccak_inputs();
+        let capacity = get_keccak_capacity(range.gate.max_rows);
+        let (rows, squeeze_digests) = multi_keccak_phase0::<F>(&inputs, Some(capacity));
+
+        // the keccak circuit in FirstPhase, in its own region so that it is assigned before the phase 1 callbacks run
+        let mut first_pass = SKIP_FIRST_PASS;
+        let mut is_final_cells: Vec<Cell> = vec![];
+        let mut input_len_cells: Vec<Cell> = vec![];
+        layouter.assign_region(
+            || "keccak circuit phase 0",
+            |mut region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                let cells = config.keccak.assign(&mut region, &rows);
+                (is_final_cells, input_len_cells) = cells
+                    .iter()
+                    .map(|(is_enabled, input_len)| (is_enabled.cell(), input_len.cell()))
+                    .unzip();
+                Ok(())
+            },
+        )?;
+
+        // we later `take` the builder, so we need to save this value
+        let witness_gen_only = circuit.builder.borrow().witness_gen_only();
+        let assigned_advices = circuit.sub_synthesize(
+            &range.gate,
+            &range.lookup_advice,
+            &range.q_lookup,
+            &range.custom_lookups,
+            &mut layouter,
+        );
+
+        // the RLCs of the keccak table in SecondPhase, which the gate region has moved to in halo2-axiom
+        let mut first_pass = SKIP_FIRST_PASS;
+        #[allow(unused_mut)]
+        let mut challenge = layouter.get_challenge(config.keccak.challenge());
+        layouter.assign_region(
+            || "keccak circuit phase 1",
+            |mut region| {
+                if first_pass {
+                    first_pass = false;
+                    return Ok(());
+                }
+                #[cfg(feature = "halo2-axiom")]
+                {
+                    challenge = region.get_challenge(config.keccak.challenge());
+                }
+                let (input_rlcs, output_rlcs) = multi_keccak_phase1(
+                    &mut region,
+                    &config.keccak.keccak_table,
+                    inputs.iter().map(|input| input.as_slice()),
+                    challenge,
+                    squeeze_digests.clone(),
+                );
+                if witness_gen_only {
+                    return Ok(());
+                }
+                let assignments = self.assignments.lock().unwrap();
+                let Some(assignments) = assignments.as_ref() else { return Ok(()) };
+                let gate_cell = |value: &AssignedValue<F>| {
+                    let cell = value.cell.unwrap();
+                    assigned_advices[&(cell.context_id, cell.offset)].0
+                };
+                let one = gate_cell(&assignments.one);
+                let num_rows_per_round = get_num_rows_per_round();
+                for (((input_rlc, output_rlc), len), idx) in assignments
+                    .rlcs
+                    .iter()
+                    .zip(assignments.lens.iter())
+                    .zip(self.keccak.query_output_indices())
+                {
+                    // the hash of the query ends at the last round of the keccak_f permutation `idx`
+                    let row = (1 + idx * (NUM_ROUNDS + 1) + NUM_ROUNDS) * num_rows_per_round;
+                    for (left, right) in [
+                        (is_final_cells[row], one),
+                        (input_len_cells[row], gate_cell(len)),
+                        (input_rlcs[idx].cell(), gate_cell(input_rlc)),
+                        (output_rlcs[idx].cell(), gate_cell(output_rlc)),
+                    ] {
+                        #[cfg(feature = "halo2-axiom")]
+                        region.constrain_equal(&left, &right);
+                        #[cfg(not(feature = "halo2-axiom"))]
+                        region.constrain_equal(left, right).unwrap();
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+use crate::halo2_proofs::{halo2curves::bn256::Fr, plonk::Assigned};
+use halo2_base::utils::testing::mock_verify;
+use std::{env::set_var, iter};
+
+const K: usize = 14;
+const LOOKUP_BITS: usize = 8;
+
+/// Hashes each input, padded with zeros to its maximum length if it has one, and runs `prank` on the builder and the
+/// assigned hashes before verifying the circuit.
+fn verify_keccak(
+    inputs: &[(&[u8], Option<usize>)],
+    prank: impl FnOnce(&mut GateThreadBuilder<Fr>, &[Vec<AssignedValue<Fr>>]),
+) -> bool {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let mut keccak = KeccakChip::new();
+    let ctx = builder.main(0);
+    let mut outputs = vec![];
+    for (input, max_len) in inputs {
+        let max_len = max_len.unwrap_or(input.len());
+        let padded = input.iter().copied().chain(iter::repeat(0)).take(max_len);
+        let assigned = ctx.assign_witnesses(padded.map(|byte| Fr::from(byte as u64)));
+        let output = if max_len == input.len() {
+            keccak.keccak_fixed_len(ctx, assigned)
+        } else {
+            let len = ctx.load_witness(Fr::from(input.len() as u64));
+            keccak.keccak_var_len(ctx, assigned, len)
+        };
+        if input.len() <= max_len {
+            let expected = keccak256(input).map(|byte| Fr::from(byte as u64));
+            assert_eq!(output.iter().map(|byte| *byte.value()).collect::<Vec<_>>(), expected);
+        }
+        outputs.push(output);
+    }
+    prank(&mut builder, &outputs);
+    mock_verify(K, &KeccakCircuitBuilder::mock(builder, keccak)).is_ok()
+}
+
+#[test]
+fn test_keccak_chip() {
+    let long_input = (0u8..150).collect::<Vec<_>>();
+    let inputs: [(&[u8], _); 5] = [
+        (&[], None),
+        (b"abc", None),
+        (&[0xab; 136], None),
+        (&long_input, Some(300)),
+        (&[], Some(10)),
+    ];
+    assert!(verify_keccak(&inputs, |_, _| {}));
+}
+
+#[test]
+fn test_keccak_chip_wrong_output() {
+    let inputs: [(&[u8], _); 2] = [(b"abc", None), (&[0xcd; 20], Some(40))];
+    assert!(!verify_keccak(&inputs, |builder, outputs| {
+        let byte = outputs[1][0];
+        let offset = byte.cell.unwrap().offset;
+        builder.main(0).advice[offset] = Assigned::Trivial(byte.value() + Fr::one());
+    }));
+}
+
+#[test]
+fn test_keccak_chip_var_len_too_long() {
+    let inputs: [(&[u8], _); 1] = [(&[0xef; 11], Some(10))];
+    assert!(!verify_keccak(&inputs, |_, _| {}));
+}
+
+#[test]
+fn test_keccak_chip_leading_zero() {
+    // [0x00, 0x61] and [0x61] have the same RLC, so only the length tells them apart
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let mut keccak = KeccakChip::new();
+    let ctx = builder.main(0);
+    let input = ctx.assign_witnesses([0x00, 0x61].map(Fr::from));
+    keccak.query(ctx, input, None, vec![0x61]);
+    assert!(mock_verify(K, &KeccakCircuitBuilder::mock(builder, keccak)).is_err());
+}
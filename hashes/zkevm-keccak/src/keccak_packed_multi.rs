@@ -1,11 +1,12 @@
 use super::util::{
     constraint_builder::BaseConstraintBuilder,
     eth_types::Field,
-    expression::{and, not, select, Expr},
+    expression::{and, not, select, sum, Expr},
     field_xor, get_absorb_positions, get_num_bits_per_lookup, into_bits, load_lookup_table,
     load_normalize_table, load_pack_table, pack, pack_u64, pack_with_base, rotate, scatter,
-    target_part_sizes, to_bytes, unpack, CHI_BASE_LOOKUP_TABLE, NUM_BYTES_PER_WORD, NUM_ROUNDS,
-    NUM_WORDS_TO_ABSORB, NUM_WORDS_TO_SQUEEZE, RATE, RATE_IN_BITS, RHO_MATRIX, ROUND_CST,
+    target_part_sizes, to_bytes, unpack, KeccakVariant, CHI_BASE_LOOKUP_TABLE,
+    MAX_NUM_WORDS_TO_ABSORB, NUM_BITS_PER_BYTE, NUM_BYTES_PER_WORD, NUM_BYTES_TO_SQUEEZE,
+    NUM_ROUNDS, NUM_WORDS_TO_ABSORB, NUM_WORDS_TO_SQUEEZE, RATE, RHO_MATRIX, ROUND_CST,
 };
 use crate::halo2_proofs::{
     arithmetic::FieldExt,
@@ -19,7 +20,7 @@ use crate::halo2_proofs::{
 use halo2_base::halo2_proofs::{circuit::AssignedCell, plonk::Assigned};
 use itertools::Itertools;
 use log::{debug, info};
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::env::var;
 use std::marker::PhantomData;
 
@@ -64,6 +65,15 @@ pub fn get_keccak_capacity(num_rows: usize) -> usize {
     (num_rows / get_num_rows_per_round() - 1 - NUM_WORDS_TO_ABSORB) / (NUM_ROUNDS + 1)
 }
 
+/// The number of keccak_f's that can be done in a circuit created with
+/// `KeccakCircuitConfig::new_with_domain_separation`
+///
+/// `num_rows` should be number of usable rows without blinding factors
+pub fn get_keccak_capacity_with_domain_separation(num_rows: usize) -> usize {
+    // - MAX_NUM_WORDS_TO_ABSORB because that many words are absorbed after any row where `q_absorb == 1`
+    (num_rows / get_num_rows_per_round() - 1 - MAX_NUM_WORDS_TO_ABSORB) / (NUM_ROUNDS + 1)
+}
+
 pub fn get_num_keccak_f(byte_length: usize) -> usize {
     // ceil( (byte_length + 1) / RATE )
     byte_length / RATE + 1
@@ -93,11 +103,17 @@ pub struct KeccakRow<F: FieldExt> {
     q_round_last: bool,
     q_padding: bool,
     q_padding_last: bool,
+    // `[q_padding, q_padding_last]` for the rates of 576 and 1344 bits, only used with domain separation
+    q_padding_rates: [[bool; 2]; 2],
     round_cst: F,
     is_final: bool,
+    // `None` for rows in the layout without domain separation
+    variant: Option<KeccakVariant>,
+    // Whether the keccak_f only squeezes output, only used with domain separation
+    is_squeeze_only: bool,
     cell_values: Vec<F>,
-    // We have no need for length as RLC equality checks length implicitly
-    // length: usize,
+    // The number of input bytes absorbed so far, as RLC equality does not check leading zeros
+    length: usize,
     // SecondPhase values will be assigned separately
     // data_rlc: Value<F>,
     // hash_rlc: Value<F>,
@@ -114,8 +130,12 @@ impl<F: FieldExt> KeccakRow<F> {
                 q_round_last: false,
                 q_padding: false,
                 q_padding_last: false,
+                q_padding_rates: [[false; 2]; 2],
                 round_cst: F::zero(),
                 is_final: false,
+                variant: None,
+                is_squeeze_only: false,
+                length: 0,
                 cell_values: Vec::new(),
             })
             .collect()
@@ -359,10 +379,10 @@ impl<F: FieldExt> CellManager<F> {
 pub struct KeccakTable {
     /// True when the row is enabled
     pub is_enabled: Column<Advice>,
-    /// Byte array input as `RLC(reversed(input))`
+    /// Byte array input as `RLC(reversed(input))`, starting from `KeccakVariant::input_rlc_init`
     pub input_rlc: Column<Advice>, // RLC of input bytes
-    // Byte array input length
-    // pub input_len: Column<Advice>,
+    /// Byte array input length
+    pub input_len: Column<Advice>,
     /// RLC of the hash result
     pub output_rlc: Column<Advice>, // RLC of hash of input bytes
 }
@@ -371,15 +391,12 @@ impl KeccakTable {
     /// Construct a new KeccakTable
     pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
         let input_rlc = meta.advice_column_in(SecondPhase);
+        let input_len = meta.advice_column();
         let output_rlc = meta.advice_column_in(SecondPhase);
         meta.enable_equality(input_rlc);
+        meta.enable_equality(input_len);
         meta.enable_equality(output_rlc);
-        Self {
-            is_enabled: meta.advice_column(),
-            input_rlc,
-            // input_len: meta.advice_column(),
-            output_rlc,
-        }
+        Self { is_enabled: meta.advice_column(), input_rlc, input_len, output_rlc }
     }
 }
 
@@ -814,6 +831,60 @@ mod transform_to {
     }
 }
 
+/// The columns of a config created with `new_with_domain_separation`, see [KeccakVariant].
+#[derive(Clone, Copy, Debug)]
+struct VariantColumns {
+    // `[is_sha3, is_shake, is_rate_576, is_rate_1344]`, the flags selecting the domain separator and the rate of
+    // the hash, see `KeccakVariant::flags`
+    flags: [Column<Advice>; 4],
+    // The number of output bytes of the hash
+    output_len: Column<Advice>,
+    // Whether the keccak_f only squeezes output, because the input was absorbed by a previous keccak_f
+    is_squeeze_only: Column<Advice>,
+    // `[q_padding, q_padding_last]` for the rates of 576 and 1344 bits
+    q_padding_rates: [[Column<Fixed>; 2]; 2],
+}
+
+/// The cells of a round that are only present in a config created with `new_with_domain_separation`.
+#[derive(Clone, Debug)]
+pub(crate) struct VariantCells<F> {
+    // The expected value of each byte if it is padding, apart from the final 0x80:
+    // the domain byte if it is the first padding byte, otherwise 0
+    padding_bytes: Vec<Cell<F>>,
+    // 1 if the word of this round is within the rate of the hash
+    q_rate: Cell<F>,
+    // 1 if the word of this round is the last one within the rate of the hash
+    q_rate_last: Cell<F>,
+    // `q_rate` if the keccak_f absorbs input, otherwise 0
+    q_absorb_rate: Cell<F>,
+    // The last `is_padding` of the previous round if `q_absorb_rate`, otherwise 0
+    is_padding_prev: Cell<F>,
+    // Whether the last byte within the rate is padding, set on the last round
+    has_padding: Cell<F>,
+    // Whether each byte of the word squeezed in this round is part of the output
+    is_squeezes: Vec<Cell<F>>,
+    // Whether the last byte within the rate is part of the output, set on the last round
+    is_fully_squeezed: Cell<F>,
+    // The number of output bytes squeezed so far
+    squeeze_len: Cell<F>,
+}
+
+impl<F: FieldExt> VariantCells<F> {
+    fn new(mut query_cell: impl FnMut() -> Cell<F>) -> Self {
+        Self {
+            padding_bytes: (0..NUM_BYTES_PER_WORD).map(|_| query_cell()).collect(),
+            q_rate: query_cell(),
+            q_rate_last: query_cell(),
+            q_absorb_rate: query_cell(),
+            is_padding_prev: query_cell(),
+            has_padding: query_cell(),
+            is_squeezes: (0..NUM_BYTES_PER_WORD).map(|_| query_cell()).collect(),
+            is_fully_squeezed: query_cell(),
+            squeeze_len: query_cell(),
+        }
+    }
+}
+
 /// KeccakConfig
 #[derive(Clone, Debug)]
 pub struct KeccakCircuitConfig<F> {
@@ -826,6 +897,8 @@ pub struct KeccakCircuitConfig<F> {
     q_round_last: Column<Fixed>,
     q_padding: Column<Fixed>,
     q_padding_last: Column<Fixed>,
+    // Only present in a config created with `new_with_domain_separation`
+    variant_columns: Option<VariantColumns>,
 
     pub keccak_table: KeccakTable,
 
@@ -843,8 +916,29 @@ impl<F: Field> KeccakCircuitConfig<F> {
     pub fn challenge(&self) -> Challenge {
         self.challenge
     }
-    /// Return a new KeccakCircuitConfig
+    /// Return a new KeccakCircuitConfig for keccak-256 only
     pub fn new(meta: &mut ConstraintSystem<F>, challenge: Challenge) -> Self {
+        Self::configure(meta, challenge, false)
+    }
+
+    /// Return a new KeccakCircuitConfig that can also hash with the other [KeccakVariant]s.
+    ///
+    /// This adds advice and fixed columns and cells per round to the layout of [Self::new], absorbs up to
+    /// `MAX_NUM_WORDS_TO_ABSORB` words per keccak_f and squeezes them on the first rounds of the keccak_f,
+    /// so its rows must be generated with `multi_keccak_phase0_with_variants` and its capacity is given by
+    /// [get_keccak_capacity_with_domain_separation].
+    pub fn new_with_domain_separation(
+        meta: &mut ConstraintSystem<F>,
+        challenge: Challenge,
+    ) -> Self {
+        Self::configure(meta, challenge, true)
+    }
+
+    fn configure(
+        meta: &mut ConstraintSystem<F>,
+        challenge: Challenge,
+        domain_separation: bool,
+    ) -> Self {
         let q_enable = meta.fixed_column();
         // let q_enable_row = meta.fixed_column();
         let q_first = meta.fixed_column();
@@ -854,10 +948,21 @@ impl<F: Field> KeccakCircuitConfig<F> {
         let q_padding = meta.fixed_column();
         let q_padding_last = meta.fixed_column();
         let round_cst = meta.fixed_column();
+        let variant_columns = domain_separation.then(|| VariantColumns {
+            flags: array_init::array_init(|_| meta.advice_column()),
+            output_len: meta.advice_column(),
+            is_squeeze_only: meta.advice_column(),
+            q_padding_rates: array_init::array_init(|_| [meta.fixed_column(), meta.fixed_column()]),
+        });
+        let (num_words_to_absorb, num_words_to_squeeze) = if domain_separation {
+            (MAX_NUM_WORDS_TO_ABSORB, MAX_NUM_WORDS_TO_ABSORB)
+        } else {
+            (NUM_WORDS_TO_ABSORB, NUM_WORDS_TO_SQUEEZE)
+        };
         let keccak_table = KeccakTable::construct(meta);
 
         let is_final = keccak_table.is_enabled;
-        // let length = keccak_table.input_len;
+        let length = keccak_table.input_len;
         let data_rlc = keccak_table.input_rlc;
         let hash_rlc = keccak_table.output_rlc;
 
@@ -876,6 +981,28 @@ impl<F: Field> KeccakCircuitConfig<F> {
             // A new hash is started when the previous hash is done or on the first row
             meta.query_fixed(q_first, rot) + meta.query_advice(is_final, rot)
         };
+        // The first padding byte of the hash: 0x01 for keccak, 0x06 for SHA3, 0x1f for SHAKE
+        let domain_byte = |meta: &mut VirtualCells<F>, variant_columns: VariantColumns| {
+            let [is_sha3, is_shake, _, _] = variant_columns.flags;
+            1.expr()
+                + meta.query_advice(is_sha3, Rotation::cur()) * 5.expr()
+                + meta.query_advice(is_shake, Rotation::cur()) * 30.expr()
+        };
+        // The value the data rlc of the hash starts from, see `KeccakVariant::input_rlc_init`
+        let data_rlc_init = |meta: &mut VirtualCells<F>, variant_columns: VariantColumns| {
+            let [is_sha3, is_shake, is_rate_576, is_rate_1344] =
+                variant_columns.flags.map(|column| meta.query_advice(column, Rotation::cur()));
+            // The "keccak variant" gate makes this 0 unless the hash is SHAKE
+            let shake_output_len = meta.query_advice(variant_columns.output_len, Rotation::cur())
+                - (1.expr() - is_shake.clone()) * (NUM_BYTES_TO_SQUEEZE as u64).expr()
+                - is_rate_576.clone() * (NUM_BYTES_TO_SQUEEZE as u64).expr();
+            (is_sha3 * 5.expr()
+                + is_shake * 30.expr()
+                + is_rate_576 * 32.expr()
+                + is_rate_1344 * 64.expr()
+                + shake_output_len * 128.expr())
+                * 256.expr()
+        };
 
         // Round constant
         let mut round_cst_expr = 0.expr();
@@ -897,10 +1024,10 @@ impl<F: Field> KeccakCircuitConfig<F> {
         let absorb_from = cell_manager.query_cell(meta);
         let absorb_data = cell_manager.query_cell(meta);
         let absorb_result = cell_manager.query_cell(meta);
-        let mut absorb_from_next = vec![0u64.expr(); NUM_WORDS_TO_ABSORB];
-        let mut absorb_data_next = vec![0u64.expr(); NUM_WORDS_TO_ABSORB];
-        let mut absorb_result_next = vec![0u64.expr(); NUM_WORDS_TO_ABSORB];
-        for i in 0..NUM_WORDS_TO_ABSORB {
+        let mut absorb_from_next = vec![0u64.expr(); num_words_to_absorb];
+        let mut absorb_data_next = vec![0u64.expr(); num_words_to_absorb];
+        let mut absorb_result_next = vec![0u64.expr(); num_words_to_absorb];
+        for i in 0..num_words_to_absorb {
             let rot = ((i + 1) * num_rows_per_round) as i32;
             absorb_from_next[i] = absorb_from.at_offset(meta, rot).expr();
             absorb_data_next[i] = absorb_data.at_offset(meta, rot).expr();
@@ -964,6 +1091,8 @@ impl<F: Field> KeccakCircuitConfig<F> {
         // Padding data
         cell_manager.start_region();
         let is_paddings = input_bytes.iter().map(|_| cell_manager.query_cell(meta)).collect_vec();
+        let variant_cells =
+            variant_columns.map(|_| VariantCells::new(|| cell_manager.query_cell(meta)));
         info!("- Post padding:");
         info!("Lookups: {}", lookup_counter);
         info!("Columns: {}", cell_manager.get_width());
@@ -1197,9 +1326,12 @@ impl<F: Field> KeccakCircuitConfig<F> {
 
         // Squeeze data
         let squeeze_from = cell_manager.query_cell(meta);
-        let mut squeeze_from_prev = vec![0u64.expr(); NUM_WORDS_TO_SQUEEZE];
+        let mut squeeze_from_prev = vec![0u64.expr(); num_words_to_squeeze];
         for (idx, squeeze_from_prev) in squeeze_from_prev.iter_mut().enumerate() {
-            let rot = (-(idx as i32) - 1) * num_rows_per_round as i32;
+            // With domain separation word `idx` is squeezed on round `idx`, like it is absorbed
+            let round =
+                if domain_separation { idx as i32 - NUM_ROUNDS as i32 } else { -(idx as i32) - 1 };
+            let rot = round * num_rows_per_round as i32;
             *squeeze_from_prev = squeeze_from.at_offset(meta, rot).expr();
         }
         // Squeeze
@@ -1234,7 +1366,7 @@ impl<F: Field> KeccakCircuitConfig<F> {
         meta.create_gate("absorb", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
             let continue_hash = not::expr(start_new_hash(meta, Rotation::cur()));
-            let absorb_positions = get_absorb_positions();
+            let absorb_positions = get_absorb_positions(num_words_to_absorb);
             let mut a_slice = 0;
             for j in 0..5 {
                 for i in 0..5 {
@@ -1270,23 +1402,29 @@ impl<F: Field> KeccakCircuitConfig<F> {
 
         // Collect the bytes that are spread out over previous rows
         let mut hash_bytes = Vec::new();
-        for i in 0..NUM_WORDS_TO_SQUEEZE {
-            for byte in squeeze_bytes.iter() {
-                let rot = (-(i as i32) - 1) * num_rows_per_round as i32;
-                hash_bytes.push(byte.cell.at_offset(meta, rot).expr());
+        if !domain_separation {
+            for i in 0..NUM_WORDS_TO_SQUEEZE {
+                for byte in squeeze_bytes.iter() {
+                    let rot = (-(i as i32) - 1) * num_rows_per_round as i32;
+                    hash_bytes.push(byte.cell.at_offset(meta, rot).expr());
+                }
             }
         }
 
         // Squeeze
         meta.create_gate("squeeze", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
-            let start_new_hash = start_new_hash(meta, Rotation::cur());
-            // The words to squeeze
-            let hash_words: Vec<_> =
-                pre_s.into_iter().take(4).map(|a| a[0].clone()).take(4).collect();
+            // With domain separation any keccak_f can squeeze output, which the "squeeze rlc" gate collects
+            let is_squeezed =
+                if domain_separation { 1.expr() } else { start_new_hash(meta, Rotation::cur()) };
+            // The words to squeeze, in the same order as they are absorbed
+            let hash_words: Vec<_> = get_absorb_positions(num_words_to_squeeze)
+                .into_iter()
+                .map(|(i, j)| pre_s[i][j].clone())
+                .collect();
             // Verify if we converted the correct words to bytes on previous rows
             for (idx, word) in hash_words.iter().enumerate() {
-                cb.condition(start_new_hash.clone(), |cb| {
+                cb.condition(is_squeezed.clone(), |cb| {
                     cb.require_equal(
                         "squeeze verify packed",
                         word.clone(),
@@ -1295,10 +1433,18 @@ impl<F: Field> KeccakCircuitConfig<F> {
                 });
             }
 
-            let challenge_expr = meta.query_challenge(challenge);
-            let rlc =
-                hash_bytes.into_iter().reduce(|rlc, x| rlc * challenge_expr.clone() + x).unwrap();
-            cb.require_equal("hash rlc check", rlc, meta.query_advice(hash_rlc, Rotation::cur()));
+            if !domain_separation {
+                let challenge_expr = meta.query_challenge(challenge);
+                let rlc = hash_bytes
+                    .into_iter()
+                    .reduce(|rlc, x| rlc * challenge_expr.clone() + x)
+                    .unwrap();
+                cb.require_equal(
+                    "hash rlc check",
+                    rlc,
+                    meta.query_advice(hash_rlc, Rotation::cur()),
+                );
+            }
             cb.gate(meta.query_fixed(q_round_last, Rotation::cur()))
         });
 
@@ -1324,43 +1470,222 @@ impl<F: Field> KeccakCircuitConfig<F> {
             meta,
             -(((NUM_ROUNDS + 1 - NUM_WORDS_TO_ABSORB) * num_rows_per_round) as i32),
         );
+        // With domain separation, the selectors and the last byte of the last round within each of the rates
+        let last_in_rates = variant_cells.as_ref().map(|variant_cells| {
+            [
+                KeccakVariant::Sha3_512.num_words_to_absorb(),
+                NUM_WORDS_TO_ABSORB,
+                MAX_NUM_WORDS_TO_ABSORB,
+            ]
+            .map(|num_words| {
+                let rot = (num_words as i32 - 1 - NUM_ROUNDS as i32) * num_rows_per_round as i32;
+                (
+                    variant_cells.q_rate_last.at_offset(meta, rot),
+                    is_paddings.last().unwrap().at_offset(meta, rot),
+                    variant_cells.is_squeezes.last().unwrap().at_offset(meta, rot),
+                )
+            })
+        });
         meta.create_gate("is final", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
-            // All absorb rows except the first row
-            cb.condition(
-                meta.query_fixed(q_absorb, Rotation::cur())
-                    - meta.query_fixed(q_first, Rotation::cur()),
-                |cb| {
-                    cb.require_equal(
-                        "is_final needs to be the same as the last is_padding in the block",
-                        meta.query_advice(is_final, Rotation::cur()),
-                        last_is_padding_in_block.expr(),
-                    );
-                },
-            );
+            let q_absorb = meta.query_fixed(q_absorb, Rotation::cur());
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let is_final = meta.query_advice(is_final, Rotation::cur());
+            match (variant_columns, &variant_cells, &last_in_rates) {
+                (Some(variant_columns), Some(variant_cells), Some(last_in_rates)) => {
+                    let next = Rotation(num_rows_per_round as i32);
+                    let is_squeeze_only =
+                        meta.query_advice(variant_columns.is_squeeze_only, Rotation::cur());
+                    let is_squeeze_only_next =
+                        meta.query_advice(variant_columns.is_squeeze_only, next);
+                    let has_padding = variant_cells.has_padding.expr();
+                    let is_fully_squeezed = variant_cells.is_fully_squeezed.expr();
+                    // All absorb rows except the first row
+                    cb.condition(q_absorb - q_first.clone(), |cb| {
+                        cb.require_equal(
+                            "has_padding needs to be the same as the last is_padding within the rate",
+                            has_padding.clone(),
+                            sum::expr(last_in_rates.iter().map(|(q_rate_last, is_padding, _)| {
+                                q_rate_last.expr() * is_padding.expr()
+                            })),
+                        );
+                        cb.require_equal(
+                            "is_fully_squeezed needs to be the same as the last is_squeeze within the rate",
+                            is_fully_squeezed.clone(),
+                            sum::expr(last_in_rates.iter().map(|(q_rate_last, _, is_squeeze)| {
+                                q_rate_last.expr() * is_squeeze.expr()
+                            })),
+                        );
+                        // The hash can only end once all of its input is absorbed and all of its output squeezed
+                        cb.require_zero(
+                            "is_final only after the last padding",
+                            is_final.clone()
+                                * (1.expr() - has_padding.clone() - is_squeeze_only.clone()),
+                        );
+                        cb.require_equal(
+                            "is_final only once the output is squeezed",
+                            is_final.clone() * variant_cells.squeeze_len.expr(),
+                            is_final.clone()
+                                * meta.query_advice(variant_columns.output_len, Rotation::cur()),
+                        );
+                        // The keccak_f's after the last padding only squeeze, until the hash ends
+                        cb.require_equal(
+                            "is_squeeze_only of the next keccak_f",
+                            is_squeeze_only_next.clone(),
+                            has_padding + is_squeeze_only - is_final.clone(),
+                        );
+                        cb.require_zero(
+                            "squeeze the whole rate before squeezing another keccak_f",
+                            is_squeeze_only_next.clone() * not::expr(is_fully_squeezed),
+                        );
+                    });
+                    cb.condition(q_first, |cb| {
+                        cb.require_zero(
+                            "the first keccak_f needs to absorb input",
+                            is_squeeze_only_next,
+                        );
+                    });
+                }
+                _ => {
+                    // All absorb rows except the first row
+                    cb.condition(q_absorb - q_first, |cb| {
+                        cb.require_equal(
+                            "is_final needs to be the same as the last is_padding in the block",
+                            is_final.clone(),
+                            last_is_padding_in_block.expr(),
+                        );
+                    });
+                }
+            }
             // For all the rows of a round, only the first row can have `is_final == 1`.
             cb.condition(
                 (1..num_rows_per_round as i32)
                     .map(|i| meta.query_fixed(q_enable, Rotation(-i)))
                     .fold(0.expr(), |acc, elem| acc + elem),
                 |cb| {
-                    cb.require_zero(
-                        "is_final only when q_enable",
-                        meta.query_advice(is_final, Rotation::cur()),
-                    );
+                    cb.require_zero("is_final only when q_enable", is_final);
                 },
             );
             cb.gate(1.expr())
         });
 
+        // Keccak variant
+        if let (Some(variant_columns), Some(variant_cells)) = (variant_columns, &variant_cells) {
+            meta.create_gate("keccak variant", |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                let [is_sha3, is_shake, is_rate_576, is_rate_1344] =
+                    variant_columns.flags.map(|column| meta.query_advice(column, Rotation::cur()));
+                let output_len = meta.query_advice(variant_columns.output_len, Rotation::cur());
+                let is_squeeze_only =
+                    meta.query_advice(variant_columns.is_squeeze_only, Rotation::cur());
+                cb.require_boolean("boolean is_sha3", is_sha3.clone());
+                cb.require_boolean("boolean is_shake", is_shake.clone());
+                cb.require_boolean("boolean is_rate_576", is_rate_576.clone());
+                cb.require_boolean("boolean is_rate_1344", is_rate_1344.clone());
+                cb.require_boolean("boolean is_squeeze_only", is_squeeze_only.clone());
+                cb.require_zero(
+                    "is_sha3 and is_shake exclusive",
+                    is_sha3.clone() * is_shake.clone(),
+                );
+                cb.require_zero("rate 576 only for SHA3", is_rate_576.clone() * not::expr(is_sha3));
+                cb.require_zero(
+                    "rate 1344 only for SHAKE",
+                    is_rate_1344.clone() * not::expr(is_shake.clone()),
+                );
+                // SHA3-512 has an output of 512 bits, keccak-256 and SHA3-256 one of 256 bits
+                cb.require_zero(
+                    "fixed output length",
+                    not::expr(is_shake)
+                        * (output_len
+                            - (NUM_BYTES_TO_SQUEEZE as u64).expr()
+                            - is_rate_576.clone() * (NUM_BYTES_TO_SQUEEZE as u64).expr()),
+                );
+
+                // Select the padding selectors of the rate of the hash, the default ones are those of rate 1088
+                let [[q_padding_576, q_padding_last_576], [q_padding_1344, q_padding_last_1344]] =
+                    variant_columns.q_padding_rates.map(|columns| {
+                        columns.map(|column| meta.query_fixed(column, Rotation::cur()))
+                    });
+                let select_rate = |q_1088: Expression<F>, q_576, q_1344| {
+                    q_1088.clone()
+                        + is_rate_576.clone() * (q_576 - q_1088.clone())
+                        + is_rate_1344.clone() * (q_1344 - q_1088)
+                };
+                cb.require_equal(
+                    "q_rate",
+                    variant_cells.q_rate.expr(),
+                    select_rate(
+                        meta.query_fixed(q_padding, Rotation::cur()),
+                        q_padding_576,
+                        q_padding_1344,
+                    ),
+                );
+                cb.require_equal(
+                    "q_rate_last",
+                    variant_cells.q_rate_last.expr(),
+                    select_rate(
+                        meta.query_fixed(q_padding_last, Rotation::cur()),
+                        q_padding_last_576,
+                        q_padding_last_1344,
+                    ),
+                );
+                cb.require_equal(
+                    "q_absorb_rate",
+                    variant_cells.q_absorb_rate.expr(),
+                    variant_cells.q_rate.expr() * not::expr(is_squeeze_only),
+                );
+                cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+            });
+            meta.create_gate("keccak variant equality", |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                let prev = Rotation(-(num_rows_per_round as i32));
+                // The variant can only change on the first round of a new hash
+                cb.condition(
+                    and::expr([
+                        meta.query_fixed(q_enable, Rotation::cur())
+                            - meta.query_fixed(q_first, Rotation::cur()),
+                        not::expr(start_new_hash(meta, prev)),
+                    ]),
+                    |cb| {
+                        for column in
+                            variant_columns.flags.into_iter().chain([variant_columns.output_len])
+                        {
+                            cb.require_equal(
+                                "keccak variant equality check",
+                                meta.query_advice(column, Rotation::cur()),
+                                meta.query_advice(column, prev),
+                            );
+                        }
+                    },
+                );
+                // is_squeeze_only can only change on the first round of a keccak_f
+                cb.condition(
+                    meta.query_fixed(q_enable, Rotation::cur())
+                        - meta.query_fixed(q_first, Rotation::cur())
+                        - meta.query_fixed(q_absorb, prev),
+                    |cb| {
+                        cb.require_equal(
+                            "is_squeeze_only equality check",
+                            meta.query_advice(variant_columns.is_squeeze_only, Rotation::cur()),
+                            meta.query_advice(variant_columns.is_squeeze_only, prev),
+                        );
+                    },
+                );
+                cb.gate(1.expr())
+            });
+        }
+
         // Padding
         // May be cleaner to do this padding logic in the byte conversion lookup but
         // currently easier to do it like this.
         let prev_is_padding =
             is_paddings.last().unwrap().at_offset(meta, -(num_rows_per_round as i32));
+        // With domain separation the padding is checked on the rounds of the largest rate, within the rate of the hash
+        let q_padding_max =
+            variant_columns.map(|variant_columns| variant_columns.q_padding_rates[1][0]);
         meta.create_gate("padding", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
-            let q_padding = meta.query_fixed(q_padding, Rotation::cur());
+            let q_padding = meta.query_fixed(q_padding_max.unwrap_or(q_padding), Rotation::cur());
             let q_padding_last = meta.query_fixed(q_padding_last, Rotation::cur());
 
             // All padding selectors need to be boolean
@@ -1377,20 +1702,76 @@ impl<F: Field> KeccakCircuitConfig<F> {
                     is_paddings.last().unwrap().expr(),
                 );
             });
+            if let Some(variant_cells) = &variant_cells {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let not_absorbed = not::expr(variant_cells.q_absorb_rate.expr());
+                // Only bytes within the rate of a keccak_f that absorbs input can be padding,
+                // and the other absorbed bytes need to be zero
+                for (is_padding, byte) in is_paddings.iter().zip(input_bytes.iter()) {
+                    cb.condition(q_enable.clone(), |cb| {
+                        cb.require_zero(
+                            "is_padding only within the rate",
+                            not_absorbed.clone() * is_padding.expr(),
+                        );
+                    });
+                    cb.condition(q_padding.clone(), |cb| {
+                        cb.require_zero(
+                            "input byte zero outside the rate",
+                            not_absorbed.clone() * byte.expr.clone(),
+                        );
+                    });
+                }
+                // The padding continues from the previous round only within the rate, so it starts again on the
+                // first round of a keccak_f
+                cb.condition(q_enable - meta.query_fixed(q_first, Rotation::cur()), |cb| {
+                    cb.require_equal(
+                        "is_padding_prev",
+                        variant_cells.is_padding_prev.expr(),
+                        variant_cells.q_absorb_rate.expr() * prev_is_padding.expr(),
+                    );
+                });
+            }
             // Now for each padding selector
             for idx in 0..is_paddings.len() {
                 // Previous padding selector can be on the previous row
-                let is_padding_prev =
-                    if idx == 0 { prev_is_padding.expr() } else { is_paddings[idx - 1].expr() };
+                let is_padding_prev = match (idx, &variant_cells) {
+                    (0, Some(variant_cells)) => variant_cells.is_padding_prev.expr(),
+                    (0, None) => prev_is_padding.expr(),
+                    _ => is_paddings[idx - 1].expr(),
+                };
                 let is_first_padding = is_paddings[idx].expr() - is_padding_prev.clone();
 
                 // Check padding transition 0 -> 1 done only once
                 cb.condition(q_padding.expr(), |cb| {
                     cb.require_boolean("padding step boolean", is_first_padding.clone());
                 });
+                // The first padding byte holds the domain separator, which is 1 without domain separation
+                let padding_byte = match (&variant_cells, variant_columns) {
+                    (Some(variant_cells), Some(variant_columns)) => {
+                        cb.condition(q_padding.expr(), |cb| {
+                            cb.require_equal(
+                                "padding byte value",
+                                variant_cells.padding_bytes[idx].expr(),
+                                is_first_padding.clone() * domain_byte(meta, variant_columns),
+                            );
+                        });
+                        variant_cells.padding_bytes[idx].expr()
+                    }
+                    _ => is_first_padding.clone(),
+                };
 
                 // Padding start/intermediate/end byte checks
-                if idx == is_paddings.len() - 1 {
+                if let (true, Some(variant_cells)) = (idx == is_paddings.len() - 1, &variant_cells)
+                {
+                    // The last byte within the rate gets 128 added, `q_rate_last` keeps the degree at 3
+                    cb.condition(and::expr([q_padding.expr(), is_paddings[idx].expr()]), |cb| {
+                        cb.require_equal(
+                            "padding start/intermediate/end byte",
+                            input_bytes[idx].expr.clone(),
+                            padding_byte.clone() + variant_cells.q_rate_last.expr() * 128.expr(),
+                        );
+                    });
+                } else if idx == is_paddings.len() - 1 {
                     // These can be combined in the future, but currently this would increase the
                     // degree by one Padding start/intermediate byte, all
                     // padding rows except the last one
@@ -1400,11 +1781,11 @@ impl<F: Field> KeccakCircuitConfig<F> {
                             is_paddings[idx].expr(),
                         ]),
                         |cb| {
-                            // Input bytes need to be zero, or one if this is the first padding byte
+                            // Input bytes need to be zero, or the domain byte if this is the first padding byte
                             cb.require_equal(
                                 "padding start/intermediate byte last byte",
                                 input_bytes[idx].expr.clone(),
-                                is_first_padding.expr(),
+                                padding_byte.clone(),
                             );
                         },
                     );
@@ -1413,22 +1794,22 @@ impl<F: Field> KeccakCircuitConfig<F> {
                         and::expr([q_padding_last.expr(), is_paddings[idx].expr()]),
                         |cb| {
                             // The input byte needs to be 128, unless it's also the first padding
-                            // byte then it's 129
+                            // byte then it's 128 + the domain byte
                             cb.require_equal(
                                 "padding start/end byte",
                                 input_bytes[idx].expr.clone(),
-                                is_first_padding.expr() + 128.expr(),
+                                padding_byte.clone() + 128.expr(),
                             );
                         },
                     );
                 } else {
                     // Padding start/intermediate byte
                     cb.condition(and::expr([q_padding.expr(), is_paddings[idx].expr()]), |cb| {
-                        // Input bytes need to be zero, or one if this is the first padding byte
+                        // Input bytes need to be zero, or the domain byte if this is the first padding byte
                         cb.require_equal(
                             "padding start/intermediate byte",
                             input_bytes[idx].expr.clone(),
-                            is_first_padding.expr(),
+                            padding_byte,
                         );
                     });
                 }
@@ -1443,24 +1824,48 @@ impl<F: Field> KeccakCircuitConfig<F> {
         meta.create_gate("data rlc", |meta| {
             let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
 
-            let q_padding = meta.query_fixed(q_padding, Rotation::cur());
+            let q_padding = meta.query_fixed(q_padding_max.unwrap_or(q_padding), Rotation::cur());
             let start_new_hash_prev = start_new_hash(meta, Rotation(-(num_rows_per_round as i32)));
+            // Whether each byte is left out of the data rlc, with domain separation also when it is not absorbed
+            let is_skippeds = is_paddings
+                .iter()
+                .map(|is_padding| match &variant_cells {
+                    Some(variant_cells) => {
+                        is_padding.expr() + not::expr(variant_cells.q_absorb_rate.expr())
+                    }
+                    None => is_padding.expr(),
+                })
+                .collect_vec();
             let data_rlc_prev = meta.query_advice(data_rlc, Rotation(-(num_rows_per_round as i32)));
+            let length_prev = meta.query_advice(length, Rotation(-(num_rows_per_round as i32)));
+            let length = meta.query_advice(length, Rotation::cur());
 
             // Update the length/data_rlc on rows where we absorb data
             cb.condition(q_padding.expr(), |cb| {
+                cb.require_equal(
+                    "update length",
+                    length.clone(),
+                    length_prev.clone() * not::expr(start_new_hash_prev.expr())
+                        + sum::expr(
+                            is_skippeds.iter().map(|is_skipped| not::expr(is_skipped.clone())),
+                        ),
+                );
                 let challenge_expr = meta.query_challenge(challenge);
                 // Use intermediate cells to keep the degree low
                 let mut new_data_rlc =
                     data_rlc_prev.clone() * not::expr(start_new_hash_prev.expr());
+                if let Some(variant_columns) = variant_columns {
+                    new_data_rlc = new_data_rlc
+                        + start_new_hash_prev.expr() * data_rlc_init(meta, variant_columns);
+                }
                 let mut data_rlcs = (0..NUM_BYTES_PER_WORD)
                     .map(|i| meta.query_advice(data_rlc, Rotation(i as i32 + 1)));
                 let intermed_rlc = data_rlcs.next().unwrap();
                 cb.require_equal("initial data rlc", intermed_rlc.clone(), new_data_rlc);
                 new_data_rlc = intermed_rlc;
-                for (byte, is_padding) in input_bytes.iter().zip(is_paddings.iter()) {
+                for (byte, is_skipped) in input_bytes.iter().zip(is_skippeds) {
                     new_data_rlc = select::expr(
-                        is_padding.expr(),
+                        is_skipped,
                         new_data_rlc.clone(),
                         new_data_rlc * challenge_expr.clone() + byte.expr.clone(),
                     );
@@ -1487,6 +1892,7 @@ impl<F: Field> KeccakCircuitConfig<F> {
                     not::expr(q_padding),
                 ]),
                 |cb| {
+                    cb.require_equal("length equality check", length, length_prev);
                     cb.require_equal(
                         "data_rlc equality check",
                         meta.query_advice(data_rlc, Rotation::cur()),
@@ -1497,6 +1903,119 @@ impl<F: Field> KeccakCircuitConfig<F> {
             cb.gate(1.expr())
         });
 
+        // Output rlc, with domain separation the output is collected over the keccak_f's of the hash while they
+        // squeeze their words
+        if let (Some(variant_columns), Some(variant_cells)) = (variant_columns, &variant_cells) {
+            let prev = -(num_rows_per_round as i32);
+            let is_squeeze_prev = variant_cells.is_squeezes.last().unwrap().at_offset(meta, prev);
+            let squeeze_len_prev = variant_cells.squeeze_len.at_offset(meta, prev);
+            meta.create_gate("squeeze rlc", |meta| {
+                let mut cb = BaseConstraintBuilder::new(MAX_DEGREE);
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                let q_first = meta.query_fixed(q_first, Rotation::cur());
+                let q_padding_max =
+                    meta.query_fixed(variant_columns.q_padding_rates[1][0], Rotation::cur());
+                // The first round of a keccak_f
+                let q_start = meta.query_fixed(q_absorb, Rotation(prev));
+                let is_squeeze_only =
+                    meta.query_advice(variant_columns.is_squeeze_only, Rotation::cur());
+                let is_squeezes =
+                    variant_cells.is_squeezes.iter().map(|cell| cell.expr()).collect_vec();
+
+                // All squeeze selectors need to be boolean and zero outside the rate, and the output is a prefix of the
+                // bytes squeezed by the keccak_f
+                cb.condition(q_enable.clone(), |cb| {
+                    for (idx, is_squeeze) in is_squeezes.iter().enumerate() {
+                        cb.require_boolean("is_squeeze boolean", is_squeeze.clone());
+                        cb.require_zero(
+                            "is_squeeze only within the rate",
+                            not::expr(variant_cells.q_rate.expr()) * is_squeeze.clone(),
+                        );
+                        if idx > 0 {
+                            cb.require_zero(
+                                "is_squeeze step",
+                                is_squeeze.clone() * not::expr(is_squeezes[idx - 1].clone()),
+                            );
+                        }
+                    }
+                });
+                let squeeze_len = variant_cells.squeeze_len.expr();
+                let num_squeezed = sum::expr(is_squeezes.iter().cloned());
+                cb.condition(q_enable.clone() - q_first.clone() - q_start.clone(), |cb| {
+                    cb.require_zero(
+                        "is_squeeze step",
+                        is_squeezes[0].clone() * not::expr(is_squeeze_prev.expr()),
+                    );
+                    cb.require_equal(
+                        "update squeeze_len",
+                        squeeze_len.clone(),
+                        squeeze_len_prev.expr() + num_squeezed.clone(),
+                    );
+                });
+                // The output of a keccak_f that absorbs input starts from scratch
+                cb.condition(q_start.clone(), |cb| {
+                    cb.require_equal(
+                        "initial squeeze_len",
+                        squeeze_len,
+                        is_squeeze_only.clone() * squeeze_len_prev.expr() + num_squeezed,
+                    );
+                });
+
+                // Use intermediate cells to keep the degree low
+                let hash_rlc_prev = meta.query_advice(hash_rlc, Rotation(prev));
+                let intermed_rlc = meta.query_advice(hash_rlc, Rotation(1));
+                cb.condition(q_start.clone(), |cb| {
+                    cb.require_equal(
+                        "initial hash rlc",
+                        intermed_rlc.clone(),
+                        is_squeeze_only * hash_rlc_prev.clone(),
+                    );
+                });
+                cb.condition(q_padding_max.clone() - q_start, |cb| {
+                    cb.require_equal(
+                        "initial hash rlc",
+                        intermed_rlc.clone(),
+                        hash_rlc_prev.clone(),
+                    );
+                });
+                cb.condition(q_padding_max.clone(), |cb| {
+                    let challenge_expr = meta.query_challenge(challenge);
+                    let mut hash_rlcs = (1..NUM_BYTES_PER_WORD)
+                        .map(|i| meta.query_advice(hash_rlc, Rotation(i as i32 + 1)));
+                    let mut new_hash_rlc = intermed_rlc;
+                    for (byte, is_squeeze) in squeeze_bytes.iter().zip(is_squeezes) {
+                        new_hash_rlc = select::expr(
+                            is_squeeze,
+                            new_hash_rlc.clone() * challenge_expr.clone() + byte.expr.clone(),
+                            new_hash_rlc,
+                        );
+                        if let Some(intermed_rlc) = hash_rlcs.next() {
+                            cb.require_equal(
+                                "intermediate hash rlc",
+                                intermed_rlc.clone(),
+                                new_hash_rlc,
+                            );
+                            new_hash_rlc = intermed_rlc;
+                        }
+                    }
+                    cb.require_equal(
+                        "update hash rlc",
+                        meta.query_advice(hash_rlc, Rotation::cur()),
+                        new_hash_rlc,
+                    );
+                });
+                // Keep the hash rlc the same on rows where we don't squeeze
+                cb.condition(q_enable - q_first - q_padding_max, |cb| {
+                    cb.require_equal(
+                        "hash_rlc equality check",
+                        meta.query_advice(hash_rlc, Rotation::cur()),
+                        hash_rlc_prev,
+                    );
+                });
+                cb.gate(1.expr())
+            });
+        }
+
         info!("Degree: {}", meta.degree());
         info!("Minimum rows: {}", meta.minimum_rows());
         info!("Total Lookups: {}", total_lookup_counter);
@@ -1526,6 +2045,7 @@ impl<F: Field> KeccakCircuitConfig<F> {
             q_round_last,
             q_padding,
             q_padding_last,
+            variant_columns,
             keccak_table,
             cell_manager,
             round_cst,
@@ -1540,13 +2060,28 @@ impl<F: Field> KeccakCircuitConfig<F> {
 }
 
 impl<F: Field> KeccakCircuitConfig<F> {
-    pub fn assign(&self, region: &mut Region<'_, F>, witness: &[KeccakRow<F>]) {
-        for (offset, keccak_row) in witness.iter().enumerate() {
-            self.set_row(region, offset, keccak_row);
-        }
+    /// Assigns the rows of `witness` and returns the assigned `is_enabled` and `input_len` cells of the keccak table,
+    /// one pair per row.
+    pub fn assign<'v>(
+        &self,
+        region: &mut Region<'_, F>,
+        witness: &[KeccakRow<F>],
+    ) -> Vec<(KeccakAssignedValue<'v, F>, KeccakAssignedValue<'v, F>)> {
+        witness
+            .iter()
+            .enumerate()
+            .map(|(offset, keccak_row)| self.set_row(region, offset, keccak_row))
+            .collect()
     }
 
-    pub fn set_row(&self, region: &mut Region<'_, F>, offset: usize, row: &KeccakRow<F>) {
+    /// Assigns the row `row` at `offset` and returns the assigned `is_enabled` and `input_len` cells of the keccak
+    /// table.
+    pub fn set_row<'v>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &KeccakRow<F>,
+    ) -> (KeccakAssignedValue<'v, F>, KeccakAssignedValue<'v, F>) {
         // Fixed selectors
         for (_, column, value) in &[
             ("q_enable", self.q_enable, F::from(row.q_enable)),
@@ -1560,12 +2095,53 @@ impl<F: Field> KeccakCircuitConfig<F> {
             assign_fixed_custom(region, *column, offset, *value);
         }
 
-        assign_advice_custom(
+        let is_enabled = assign_advice_custom(
             region,
             self.keccak_table.is_enabled,
             offset,
             Value::known(F::from(row.is_final)),
         );
+        let input_len = assign_advice_custom(
+            region,
+            self.keccak_table.input_len,
+            offset,
+            Value::known(F::from(row.length as u64)),
+        );
+        if let Some(variant_columns) = self.variant_columns {
+            // Dummy rows have no cells, so they fit either layout
+            assert!(
+                row.variant.is_some() || row.cell_values.is_empty(),
+                "keccak rows for a config with domain separation need a KeccakVariant"
+            );
+            let variant = row.variant.unwrap_or_default();
+            for (column, value) in variant_columns.flags.into_iter().zip(variant.flags()) {
+                assign_advice_custom(region, column, offset, Value::known(F::from(value)));
+            }
+            // The dummy rows are assigned as a keccak-256 hash
+            assign_advice_custom(
+                region,
+                variant_columns.output_len,
+                offset,
+                Value::known(F::from(variant.output_len() as u64)),
+            );
+            assign_advice_custom(
+                region,
+                variant_columns.is_squeeze_only,
+                offset,
+                Value::known(F::from(row.is_squeeze_only)),
+            );
+            for (columns, values) in variant_columns.q_padding_rates.iter().zip(row.q_padding_rates)
+            {
+                for (column, value) in columns.iter().zip(values) {
+                    assign_fixed_custom(region, *column, offset, F::from(value));
+                }
+            }
+        } else {
+            assert!(
+                row.variant.is_none(),
+                "keccak rows with a KeccakVariant need a config created with `new_with_domain_separation`"
+            );
+        }
 
         // Cell values
         row.cell_values.iter().zip(self.cell_manager.columns()).for_each(|(bit, column)| {
@@ -1574,6 +2150,8 @@ impl<F: Field> KeccakCircuitConfig<F> {
 
         // Round constant
         assign_fixed_custom(region, self.round_cst, offset, row.round_cst);
+
+        (is_enabled, input_len)
     }
 
     pub fn load_aux_tables(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
@@ -1634,6 +2212,72 @@ pub fn keccak_phase1<'v, F: Field>(
     }
 }
 
+/// Computes and assigns the input and output RLC values of a hash of type `variant`, whose keccak_f's squeezed
+/// `squeezed_bytes` as returned by `keccak_phase0_with_variant`.
+/// The input RLC starts from `variant.input_rlc_init()` instead of zero, and the output RLC is collected over the
+/// keccak_f's of the hash.
+#[allow(clippy::too_many_arguments)]
+pub fn keccak_phase1_with_variant<'v, F: Field>(
+    region: &mut Region<F>,
+    keccak_table: &KeccakTable,
+    bytes: &[u8],
+    variant: KeccakVariant,
+    squeezed_bytes: &[Vec<u8>],
+    challenge: Value<F>,
+    input_rlcs: &mut Vec<KeccakAssignedValue<'v, F>>,
+    output_rlcs: &mut Vec<KeccakAssignedValue<'v, F>>,
+    offset: &mut usize,
+) {
+    debug_assert_eq!(squeezed_bytes.len(), variant.num_keccak_f(bytes.len()));
+    let num_rows_per_round = get_num_rows_per_round();
+    let num_words_to_absorb = variant.num_words_to_absorb();
+
+    let mut data_rlc = Value::known(F::from(variant.input_rlc_init()));
+    let mut hash_rlc = Value::known(F::zero());
+
+    for (chunk_idx, squeezed) in squeezed_bytes.iter().enumerate() {
+        // Only the keccak_f's that absorb input start the output from scratch
+        if chunk_idx <= bytes.len() / variant.rate() {
+            hash_rlc = Value::known(F::zero());
+        }
+        for round in 0..NUM_ROUNDS + 1 {
+            if round < MAX_NUM_WORDS_TO_ABSORB {
+                for idx in 0..NUM_BYTES_PER_WORD {
+                    assign_advice_custom(
+                        region,
+                        keccak_table.input_rlc,
+                        *offset + idx + 1,
+                        data_rlc,
+                    );
+                    assign_advice_custom(
+                        region,
+                        keccak_table.output_rlc,
+                        *offset + idx + 1,
+                        hash_rlc,
+                    );
+                    let byte_idx = chunk_idx * variant.rate() + round * NUM_BYTES_PER_WORD + idx;
+                    if round < num_words_to_absorb && byte_idx < bytes.len() {
+                        data_rlc =
+                            data_rlc * challenge + Value::known(F::from(bytes[byte_idx] as u64));
+                    }
+                    if let Some(byte) = squeezed.get(round * NUM_BYTES_PER_WORD + idx) {
+                        hash_rlc = hash_rlc * challenge + Value::known(F::from(*byte as u64));
+                    }
+                }
+            }
+            let input_rlc = assign_advice_custom(region, keccak_table.input_rlc, *offset, data_rlc);
+            let output_rlc =
+                assign_advice_custom(region, keccak_table.output_rlc, *offset, hash_rlc);
+            if round == NUM_ROUNDS {
+                input_rlcs.push(input_rlc);
+                output_rlcs.push(output_rlc);
+            }
+
+            *offset += num_rows_per_round;
+        }
+    }
+}
+
 /// Witness generation in `FirstPhase` for a keccak hash digest without
 /// computing RLCs, which are deferred to `SecondPhase`.
 pub fn keccak_phase0<F: Field>(
@@ -1641,34 +2285,91 @@ pub fn keccak_phase0<F: Field>(
     squeeze_digests: &mut Vec<[F; NUM_WORDS_TO_SQUEEZE]>,
     bytes: &[u8],
 ) {
-    let mut bits = into_bits(bytes);
+    let mut squeeze_words = Vec::new();
+    assign_keccak_phase0(rows, &mut squeeze_words, bytes, None);
+    squeeze_digests.extend(squeeze_words.into_iter().map(|words| words.try_into().unwrap()));
+}
+
+/// Witness generation in `FirstPhase` for a hash digest of type `variant` without
+/// computing RLCs, which are deferred to `SecondPhase`.
+///
+/// The rows are in the layout of `KeccakCircuitConfig::new_with_domain_separation`. The output bytes squeezed
+/// after each keccak_f are pushed to `squeezed_bytes`, empty for the keccak_f's before the last padded block.
+pub fn keccak_phase0_with_variant<F: Field>(
+    rows: &mut Vec<KeccakRow<F>>,
+    squeezed_bytes: &mut Vec<Vec<u8>>,
+    bytes: &[u8],
+    variant: KeccakVariant,
+) {
+    let mut squeeze_words = Vec::new();
+    assign_keccak_phase0(rows, &mut squeeze_words, bytes, Some(variant));
+    squeezed_bytes.extend(
+        squeeze_words.into_iter().zip(variant.num_bytes_squeezed(bytes.len())).map(
+            |(words, num_bytes)| {
+                words
+                    .into_iter()
+                    .flat_map(|word| to_bytes::value(&unpack(word)))
+                    .take(num_bytes)
+                    .collect()
+            },
+        ),
+    );
+}
+
+// `variant` is `None` for the layout without domain separation, which hashes with keccak-256.
+// Pushes the words squeezed after each keccak_f to `squeeze_words`.
+fn assign_keccak_phase0<F: Field>(
+    rows: &mut Vec<KeccakRow<F>>,
+    squeeze_words: &mut Vec<Vec<F>>,
+    bytes: &[u8],
+    variant: Option<KeccakVariant>,
+) {
     let mut s = [[F::zero(); 5]; 5];
-    let absorb_positions = get_absorb_positions();
-    let num_bytes_in_last_block = bytes.len() % RATE;
+    // With domain separation the layout absorbs and squeezes the words of the largest rate
+    let (num_words_to_absorb, num_words_to_squeeze) = if variant.is_some() {
+        (MAX_NUM_WORDS_TO_ABSORB, MAX_NUM_WORDS_TO_ABSORB)
+    } else {
+        (NUM_WORDS_TO_ABSORB, NUM_WORDS_TO_SQUEEZE)
+    };
+    let absorb_positions = get_absorb_positions(num_words_to_absorb);
+    let num_words_in_rate = variant.unwrap_or_default().num_words_to_absorb();
+    let rate = variant.unwrap_or_default().rate();
+    let num_bytes_in_last_block = bytes.len() % rate;
     let num_rows_per_round = get_num_rows_per_round();
     let two = F::from(2u64);
 
     // Padding
-    bits.push(1);
-    while (bits.len() + 1) % RATE_IN_BITS != 0 {
-        bits.push(0);
-    }
-    bits.push(1);
+    let bits = into_bits(&variant.unwrap_or_default().pad(bytes));
 
-    let chunks = bits.chunks(RATE_IN_BITS);
-    let num_chunks = chunks.len();
+    let chunks = bits.chunks(rate * NUM_BITS_PER_BYTE).collect_vec();
+    let num_absorbed = chunks.len();
+    // An output longer than the rate is squeezed from further keccak_f's
+    let num_bytes_squeezed_per_chunk =
+        variant.map(|variant| variant.num_bytes_squeezed(bytes.len()));
+    let num_chunks =
+        num_bytes_squeezed_per_chunk.as_ref().map_or(num_absorbed, |num_bytes| num_bytes.len());
+    let mut squeeze_len = 0;
 
     let mut cell_managers = Vec::with_capacity(NUM_ROUNDS + 1);
     let mut regions = Vec::with_capacity(NUM_ROUNDS + 1);
-    let mut hash_words = [F::zero(); NUM_WORDS_TO_SQUEEZE];
 
-    for (idx, chunk) in chunks.enumerate() {
-        let is_final_block = idx == num_chunks - 1;
+    for idx in 0..num_chunks {
+        // `None` for the keccak_f's that only squeeze
+        let chunk = chunks.get(idx);
+        let is_padded_block = idx + 1 == num_absorbed;
+        let is_final_block = idx + 1 == num_chunks;
+        let num_bytes_squeezed =
+            num_bytes_squeezed_per_chunk.as_ref().map_or(0, |num_bytes| num_bytes[idx]);
 
         let mut absorb_rows = Vec::new();
         // Absorb
-        for (idx, &(i, j)) in absorb_positions.iter().enumerate() {
-            let absorb = pack(&chunk[idx * 64..(idx + 1) * 64]);
+        for (word_idx, &(i, j)) in absorb_positions.iter().enumerate() {
+            let absorb = match chunk {
+                Some(chunk) if word_idx < num_words_in_rate => {
+                    pack(&chunk[word_idx * 64..(word_idx + 1) * 64])
+                }
+                _ => F::zero(),
+            };
             let from = s[i][j];
             s[i][j] = field_xor(s[i][j], absorb);
             absorb_rows.push(AbsorbData { from, absorb, result: s[i][j] });
@@ -1683,7 +2384,7 @@ pub fn keccak_phase0<F: Field>(
             let mut region = KeccakRegion::new();
 
             let mut absorb_row = AbsorbData::default();
-            if round < NUM_WORDS_TO_ABSORB {
+            if round < num_words_to_absorb {
                 absorb_row = absorb_rows[round].clone();
             }
 
@@ -1731,14 +2432,53 @@ pub fn keccak_phase0<F: Field>(
             cell_manager.start_region();
             let is_paddings =
                 input_bytes.iter().map(|_| cell_manager.query_cell_value()).collect::<Vec<_>>();
+            let variant_cells =
+                variant.map(|_| VariantCells::new(|| cell_manager.query_cell_value()));
             debug_assert_eq!(is_paddings.len(), NUM_BYTES_PER_WORD);
-            if round < NUM_WORDS_TO_ABSORB {
+            if round < num_words_in_rate && chunk.is_some() {
                 for (padding_idx, is_padding) in is_paddings.iter().enumerate() {
                     let byte_idx = round * NUM_BYTES_PER_WORD + padding_idx;
-                    let padding = is_final_block && byte_idx >= num_bytes_in_last_block;
+                    let padding = is_padded_block && byte_idx >= num_bytes_in_last_block;
                     is_padding.assign(&mut region, 0, F::from(padding));
+                    if let (Some(variant), Some(variant_cells)) = (variant, &variant_cells) {
+                        let is_first_padding =
+                            is_padded_block && byte_idx == num_bytes_in_last_block;
+                        let domain_byte = if is_first_padding { variant.domain_byte() } else { 0 };
+                        variant_cells.padding_bytes[padding_idx].assign(
+                            &mut region,
+                            0,
+                            F::from(domain_byte as u64),
+                        );
+                    }
                 }
             }
+            if let Some(variant_cells) = &variant_cells {
+                let q_rate = round < num_words_in_rate;
+                let q_absorb_rate = q_rate && chunk.is_some();
+                let is_padding_prev = q_absorb_rate
+                    && is_padded_block
+                    && round * NUM_BYTES_PER_WORD > num_bytes_in_last_block;
+                for (cell, value) in [
+                    (&variant_cells.q_rate, q_rate),
+                    (&variant_cells.q_rate_last, round + 1 == num_words_in_rate),
+                    (&variant_cells.q_absorb_rate, q_absorb_rate),
+                    (&variant_cells.is_padding_prev, is_padding_prev),
+                    (&variant_cells.has_padding, is_padded_block),
+                    (&variant_cells.is_fully_squeezed, num_bytes_squeezed == rate),
+                ] {
+                    cell.assign(&mut region, 0, F::from(value));
+                }
+                for (byte_idx, is_squeeze) in variant_cells.is_squeezes.iter().enumerate() {
+                    let is_squeezed = round * NUM_BYTES_PER_WORD + byte_idx < num_bytes_squeezed;
+                    is_squeeze.assign(&mut region, 0, F::from(is_squeezed));
+                }
+                let num_squeezed = ((round + 1) * NUM_BYTES_PER_WORD).min(num_bytes_squeezed);
+                variant_cells.squeeze_len.assign(
+                    &mut region,
+                    0,
+                    F::from((squeeze_len + num_squeezed) as u64),
+                );
+            }
             cell_manager.start_region();
 
             if round != NUM_ROUNDS {
@@ -1880,21 +2620,24 @@ pub fn keccak_phase0<F: Field>(
                 ));
             }
 
-            // The words to squeeze out: this is the hash digest as words with
-            // NUM_BYTES_PER_WORD (=8) bytes each
-            for (hash_word, a) in hash_words.iter_mut().zip(s.iter()) {
-                *hash_word = a[0];
-            }
-
             cell_managers.push(cell_manager);
             regions.push(region);
         }
 
+        // The words to squeeze out: without domain separation this is the hash digest as words with
+        // NUM_BYTES_PER_WORD (=8) bytes each, otherwise all the words within the largest rate
+        let hash_words = get_absorb_positions(num_words_to_squeeze)
+            .into_iter()
+            .map(|(i, j)| s[i][j])
+            .collect_vec();
+
         // Now that we know the state at the end of the rounds, set the squeeze data
         let num_rounds = cell_managers.len();
-        for (idx, word) in hash_words.iter().enumerate() {
-            let cell_manager = &mut cell_managers[num_rounds - 2 - idx];
-            let region = &mut regions[num_rounds - 2 - idx];
+        for (word_idx, word) in hash_words.iter().enumerate() {
+            // With domain separation word `word_idx` is squeezed on round `word_idx`
+            let round = if variant.is_some() { word_idx } else { num_rounds - 2 - word_idx };
+            let cell_manager = &mut cell_managers[round];
+            let region = &mut regions[round];
 
             cell_manager.start_region();
             let squeeze_packed = cell_manager.query_cell_value();
@@ -1905,10 +2648,15 @@ pub fn keccak_phase0<F: Field>(
             cell_manager.start_region();
             transform::value(cell_manager, region, packed, false, |v| *v, true);
         }
-        squeeze_digests.push(hash_words);
+        squeeze_words.push(hash_words);
+        squeeze_len += num_bytes_squeezed;
 
         for round in 0..NUM_ROUNDS + 1 {
             let round_cst = pack_u64(ROUND_CST[round]);
+            // The bytes absorbed up to this round, matching the "update length" constraint
+            let length = bytes
+                .len()
+                .min(idx * rate + (round + 1).min(num_words_in_rate) * NUM_BYTES_PER_WORD);
 
             for row_idx in 0..num_rows_per_round {
                 rows.push(KeccakRow {
@@ -1919,8 +2667,18 @@ pub fn keccak_phase0<F: Field>(
                     q_round_last: row_idx == 0 && round == NUM_ROUNDS,
                     q_padding: row_idx == 0 && round < NUM_WORDS_TO_ABSORB,
                     q_padding_last: row_idx == 0 && round == NUM_WORDS_TO_ABSORB - 1,
+                    q_padding_rates: [
+                        KeccakVariant::Sha3_512.num_words_to_absorb(),
+                        MAX_NUM_WORDS_TO_ABSORB,
+                    ]
+                    .map(|num_words| {
+                        [row_idx == 0 && round < num_words, row_idx == 0 && round + 1 == num_words]
+                    }),
                     round_cst,
                     is_final: is_final_block && round == NUM_ROUNDS && row_idx == 0,
+                    variant,
+                    is_squeeze_only: chunk.is_none(),
+                    length,
                     cell_values: regions[round].rows.get(row_idx).unwrap_or(&vec![]).clone(),
                 });
                 #[cfg(debug_assertions)]
@@ -1997,11 +2755,95 @@ pub fn multi_keccak_phase1<'a, 'v, F: Field>(
     (input_rlcs, output_rlcs)
 }
 
+/// Computes and assigns the input and output RLC values, where each input is hashed with its own [KeccakVariant].
+///
+/// The inputs and variants must be the same as those passed to [multi_keccak_phase0_with_variants], and
+/// `squeezed_bytes` is the output bytes it returned.
+pub fn multi_keccak_phase1_with_variants<'a, 'v, F: Field>(
+    region: &mut Region<F>,
+    keccak_table: &KeccakTable,
+    inputs: impl IntoIterator<Item = (&'a [u8], KeccakVariant)>,
+    challenge: Value<F>,
+    squeezed_bytes: Vec<Vec<u8>>,
+) -> (Vec<KeccakAssignedValue<'v, F>>, Vec<KeccakAssignedValue<'v, F>>) {
+    let mut input_rlcs = Vec::with_capacity(squeezed_bytes.len());
+    let mut output_rlcs = Vec::with_capacity(squeezed_bytes.len());
+
+    let num_rows_per_round = get_num_rows_per_round();
+    for idx in 0..num_rows_per_round {
+        [keccak_table.input_rlc, keccak_table.output_rlc]
+            .map(|column| assign_advice_custom(region, column, idx, Value::known(F::zero())));
+    }
+
+    let mut offset = num_rows_per_round;
+    let mut squeezed_bytes = squeezed_bytes.as_slice();
+    for (bytes, variant) in inputs {
+        let (squeezed, rest) = squeezed_bytes.split_at(variant.num_keccak_f(bytes.len()));
+        keccak_phase1_with_variant(
+            region,
+            keccak_table,
+            bytes,
+            variant,
+            squeezed,
+            challenge,
+            &mut input_rlcs,
+            &mut output_rlcs,
+            &mut offset,
+        );
+        squeezed_bytes = rest;
+    }
+    // The remaining keccak_f's are keccak-256 hashes of the empty input
+    for squeezed in squeezed_bytes.chunks(1) {
+        keccak_phase1_with_variant(
+            region,
+            keccak_table,
+            &[],
+            KeccakVariant::Keccak256,
+            squeezed,
+            challenge,
+            &mut input_rlcs,
+            &mut output_rlcs,
+            &mut offset,
+        );
+    }
+
+    (input_rlcs, output_rlcs)
+}
+
 /// Returns vector of KeccakRow and vector of hash digest outputs.
 pub fn multi_keccak_phase0<F: Field>(
     bytes: &[Vec<u8>],
     capacity: Option<usize>,
 ) -> (Vec<KeccakRow<F>>, Vec<[F; NUM_WORDS_TO_SQUEEZE]>) {
+    assign_multi_keccak_phase0(bytes, capacity, |rows, squeeze_digests, bytes, _| {
+        keccak_phase0(rows, squeeze_digests, bytes)
+    })
+}
+
+/// Returns vector of KeccakRow and the output bytes squeezed after each keccak_f, see
+/// [keccak_phase0_with_variant], where `bytes[i]` is hashed with `variants[i]`.
+///
+/// The rows are in the layout of `KeccakCircuitConfig::new_with_domain_separation`.
+/// Unused capacity is filled with keccak-256 hashes of the empty input.
+pub fn multi_keccak_phase0_with_variants<F: Field>(
+    bytes: &[Vec<u8>],
+    variants: &[KeccakVariant],
+    capacity: Option<usize>,
+) -> (Vec<KeccakRow<F>>, Vec<Vec<u8>>) {
+    assert_eq!(bytes.len(), variants.len(), "each input needs a keccak variant");
+    assign_multi_keccak_phase0(bytes, capacity, |rows, squeezed_bytes, bytes, idx| {
+        let variant = idx.map_or(KeccakVariant::Keccak256, |idx| variants[idx]);
+        keccak_phase0_with_variant(rows, squeezed_bytes, bytes, variant)
+    })
+}
+
+// Calls `assign(rows, squeezes, bytes, Some(idx))` for each of the inputs `bytes`, then
+// `assign(rows, squeezes, &[], None)` until the capacity is filled
+fn assign_multi_keccak_phase0<F: Field, T: Send>(
+    bytes: &[Vec<u8>],
+    capacity: Option<usize>,
+    assign: impl Fn(&mut Vec<KeccakRow<F>>, &mut Vec<T>, &[u8], Option<usize>) + Sync,
+) -> (Vec<KeccakRow<F>>, Vec<T>) {
     let num_rows_per_round = get_num_rows_per_round();
     let mut rows =
         Vec::with_capacity((1 + capacity.unwrap_or(0) * (NUM_ROUNDS + 1)) * num_rows_per_round);
@@ -2011,30 +2853,31 @@ pub fn multi_keccak_phase0<F: Field>(
     // Actual keccaks
     let artifacts = bytes
         .par_iter()
-        .map(|bytes| {
+        .enumerate()
+        .map(|(idx, bytes)| {
             let num_keccak_f = get_num_keccak_f(bytes.len());
-            let mut squeeze_digests = Vec::with_capacity(num_keccak_f);
+            let mut squeezes = Vec::with_capacity(num_keccak_f);
             let mut rows = Vec::with_capacity(num_keccak_f * (NUM_ROUNDS + 1) * num_rows_per_round);
-            keccak_phase0(&mut rows, &mut squeeze_digests, bytes);
-            (rows, squeeze_digests)
+            assign(&mut rows, &mut squeezes, bytes, Some(idx));
+            (rows, squeezes)
         })
         .collect::<Vec<_>>();
 
-    let mut squeeze_digests = Vec::with_capacity(capacity.unwrap_or(0));
-    for (rows_part, squeezes) in artifacts {
+    let mut squeezes = Vec::with_capacity(capacity.unwrap_or(0));
+    for (rows_part, squeezes_part) in artifacts {
         rows.extend(rows_part);
-        squeeze_digests.extend(squeezes);
+        squeezes.extend(squeezes_part);
     }
 
     if let Some(capacity) = capacity {
         // Pad with no data hashes to the expected capacity
         while rows.len() < (1 + capacity * (NUM_ROUNDS + 1)) * get_num_rows_per_round() {
-            keccak_phase0(&mut rows, &mut squeeze_digests, &[]);
+            assign(&mut rows, &mut squeezes, &[], None);
         }
         // Check that we are not over capacity
         if rows.len() > (1 + capacity * (NUM_ROUNDS + 1)) * get_num_rows_per_round() {
             panic!("{:?}", Error::BoundsFailure);
         }
     }
-    (rows, squeeze_digests)
+    (rows, squeezes)
 }
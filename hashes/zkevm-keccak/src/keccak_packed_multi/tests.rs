@@ -20,16 +20,17 @@ use crate::halo2_proofs::{
 };
 use rand_core::OsRng;
 
-/// KeccakCircuit
+/// KeccakCircuit, hashing with any [KeccakVariant] if `DOMAIN_SEPARATION` is set
 #[derive(Default, Clone, Debug)]
-pub struct KeccakCircuit<F: Field> {
+pub struct KeccakCircuit<F: Field, const DOMAIN_SEPARATION: bool = false> {
     inputs: Vec<Vec<u8>>,
+    variants: Vec<KeccakVariant>,
     num_rows: Option<usize>,
     _marker: PhantomData<F>,
 }
 
 #[cfg(any(feature = "test", test))]
-impl<F: Field> Circuit<F> for KeccakCircuit<F> {
+impl<F: Field, const DOMAIN_SEPARATION: bool> Circuit<F> for KeccakCircuit<F, DOMAIN_SEPARATION> {
     type Config = KeccakCircuitConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
@@ -42,7 +43,11 @@ impl<F: Field> Circuit<F> for KeccakCircuit<F> {
         meta.advice_column();
 
         let challenge = meta.challenge_usable_after(FirstPhase);
-        KeccakCircuitConfig::new(meta, challenge)
+        if DOMAIN_SEPARATION {
+            KeccakCircuitConfig::new_with_domain_separation(meta, challenge)
+        } else {
+            KeccakCircuitConfig::new(meta, challenge)
+        }
     }
 
     fn synthesize(
@@ -60,7 +65,18 @@ impl<F: Field> Circuit<F> for KeccakCircuit<F> {
                     first_pass = false;
                     return Ok(());
                 }
-                let (witness, squeeze_digests) = multi_keccak_phase0(&self.inputs, self.capacity());
+                let (witness, squeeze_digests, squeezed_bytes) = if DOMAIN_SEPARATION {
+                    let (witness, squeezed_bytes) = multi_keccak_phase0_with_variants(
+                        &self.inputs,
+                        &self.variants,
+                        self.capacity(),
+                    );
+                    (witness, vec![], squeezed_bytes)
+                } else {
+                    let (witness, squeeze_digests) =
+                        multi_keccak_phase0(&self.inputs, self.capacity());
+                    (witness, squeeze_digests, vec![])
+                };
                 config.assign(&mut region, &witness);
 
                 #[cfg(feature = "halo2-axiom")]
@@ -68,13 +84,24 @@ impl<F: Field> Circuit<F> for KeccakCircuit<F> {
                     region.next_phase();
                     challenge = region.get_challenge(config.challenge);
                 }
-                multi_keccak_phase1(
-                    &mut region,
-                    &config.keccak_table,
-                    self.inputs.iter().map(|v| v.as_slice()),
-                    challenge,
-                    squeeze_digests,
-                );
+                let inputs = self.inputs.iter().map(|v| v.as_slice());
+                if DOMAIN_SEPARATION {
+                    multi_keccak_phase1_with_variants(
+                        &mut region,
+                        &config.keccak_table,
+                        inputs.zip(self.variants.iter().copied()),
+                        challenge,
+                        squeezed_bytes,
+                    );
+                } else {
+                    multi_keccak_phase1(
+                        &mut region,
+                        &config.keccak_table,
+                        inputs,
+                        challenge,
+                        squeeze_digests,
+                    );
+                }
                 Ok(())
             },
         )?;
@@ -86,9 +113,23 @@ impl<F: Field> Circuit<F> for KeccakCircuit<F> {
 impl<F: Field> KeccakCircuit<F> {
     /// Creates a new circuit instance
     pub fn new(num_rows: Option<usize>, inputs: Vec<Vec<u8>>) -> Self {
-        KeccakCircuit { inputs, num_rows, _marker: PhantomData }
+        let variants = vec![KeccakVariant::Keccak256; inputs.len()];
+        KeccakCircuit { inputs, variants, num_rows, _marker: PhantomData }
     }
+}
+
+impl<F: Field> KeccakCircuit<F, true> {
+    /// Creates a new circuit instance hashing `inputs[i]` with `variants[i]`
+    pub fn new_with_variants(
+        num_rows: Option<usize>,
+        inputs: Vec<Vec<u8>>,
+        variants: Vec<KeccakVariant>,
+    ) -> Self {
+        KeccakCircuit { inputs, variants, num_rows, _marker: PhantomData }
+    }
+}
 
+impl<F: Field, const DOMAIN_SEPARATION: bool> KeccakCircuit<F, DOMAIN_SEPARATION> {
     /// The number of keccak_f's that can be done in this circuit
     pub fn capacity(&self) -> Option<usize> {
         // Subtract two for unusable rows
@@ -119,6 +160,130 @@ fn packed_multi_keccak_simple() {
     verify::<Fr>(k, inputs, true);
 }
 
+#[test]
+fn packed_multi_keccak_variants() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let k = 14;
+    let inputs = vec![
+        vec![],
+        vec![],
+        vec![],
+        b"abc".to_vec(),
+        b"abc".to_vec(),
+        (0u8..135).collect::<Vec<_>>(),
+        (0u8..200).collect::<Vec<_>>(),
+        vec![],
+        b"abc".to_vec(),
+        (0u8..200).collect::<Vec<_>>(),
+        vec![],
+        b"abc".to_vec(),
+        (0u8..200).collect::<Vec<_>>(),
+        vec![],
+        b"abc".to_vec(),
+    ];
+    let variants = vec![
+        KeccakVariant::Keccak256,
+        KeccakVariant::Sha3_256,
+        KeccakVariant::Shake256(32),
+        KeccakVariant::Sha3_256,
+        KeccakVariant::Shake256(32),
+        KeccakVariant::Sha3_256,
+        KeccakVariant::Shake256(32),
+        KeccakVariant::Sha3_512,
+        KeccakVariant::Sha3_512,
+        KeccakVariant::Sha3_512,
+        KeccakVariant::Shake128(32),
+        // An output that is not a multiple of the word size
+        KeccakVariant::Shake128(100),
+        // Two blocks of input and two keccak_f's of output
+        KeccakVariant::Shake128(200),
+        // Three keccak_f's of output
+        KeccakVariant::Shake256(300),
+        KeccakVariant::Shake256(1),
+    ];
+    // The empty input and "abc" digests are the FIPS 202 example values
+    let expected = [
+        "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f",
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+        "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739",
+        "fded8fd9d6551c601eeb3b7c6bc5e5cfd8aad1d015b7e9aaa9c9b9475231d5e2",
+        "4ee1ca03272b05d3bfb1e1c79a967f823b9fc5e4bb3987b1ba9e9cb5afb07a5e",
+        concat!(
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a6",
+            "15b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+        ),
+        concat!(
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e",
+            "10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+        ),
+        concat!(
+            "ea5d05f19348dd589793354793a15f37a73b4c0bb4e750b9a00757dfce2f8b65",
+            "a64191bb9b137de00feef6474cfd47abf7880efbc51614a5715df12cfe0caee3",
+        ),
+        "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26",
+        concat!(
+            "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+            "44c50af32acd3f2cdd066568706f509bc1bdde58295dae3f891a9a0fca578378",
+            "9a41f8611214ce612394df286a62d1a2252aa94db9c538956c717dc2bed4f232",
+            "a0294c85",
+        ),
+        concat!(
+            "0c4234ca1e31801ae606f8b8d8e0665c66f42a21d601c2681858a92c79ad5d69",
+            "e143c3b1393dd894e7abd5621b0d877f3573a34245e6b911f671081664a5fa53",
+            "f778886cb56bdba60b2e8d21bd5b68b2f03f7db45fab8bec05d5869227359673",
+            "93f6c99991150acb1dcbfe12e54793975742408b347feedeabfeb77f9bbc70f3",
+            "b14024309f530cc8919ed69e58b9b8ece0cf40db1b7a33d1329885e9ca4004b1",
+            "fba4bad349b3f98d635b9775fc9cb1027c1e431756302e109614ff269d8415f4",
+            "3b504fbdff98605f",
+        ),
+        concat!(
+            "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f",
+            "d75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be",
+            "141e96616fb13957692cc7edd0b45ae3dc07223c8e92937bef84bc0eab862853",
+            "349ec75546f58fb7c2775c38462c5010d846c185c15111e595522a6bcd16cf86",
+            "f3d122109e3b1fdd943b6aec468a2d621a7c06c6a957c62b54dafc3be87567d6",
+            "77231395f6147293b68ceab7a9e0c58d864e8efde4e1b9a46cbe854713672f5c",
+            "aaae314ed9083dab4b099f8e300f01b8650f1f4b1d8fcf3f3cb53fb8e9eb2ea2",
+            "03bdc970f50ae55428a91f7f53ac266b28419c3778a15fd248d339ede785fb7f",
+            "5a1aaa96d313eacc890936c173cdcd0fab882c45755feb3aed96d477ff96390b",
+            "f9a66d1368b208e21f7c10d0",
+        ),
+        "48",
+    ];
+
+    // The output of each input is squeezed after its last keccak_f's
+    let (_, squeezed_bytes) = multi_keccak_phase0_with_variants::<Fr>(&inputs, &variants, None);
+    let mut digests = Vec::new();
+    let mut squeezed_bytes = squeezed_bytes.as_slice();
+    for (bytes, variant) in inputs.iter().zip(variants.iter()) {
+        let (squeezed, rest) = squeezed_bytes.split_at(variant.num_keccak_f(bytes.len()));
+        digests.push(hex::encode(squeezed.concat()));
+        squeezed_bytes = rest;
+    }
+    assert!(squeezed_bytes.is_empty());
+    assert_eq!(digests, expected);
+
+    let circuit = KeccakCircuit::new_with_variants(Some(2usize.pow(k)), inputs, variants);
+    let prover = MockProver::<Fr>::run(k, &circuit, vec![]).unwrap();
+    prover.assert_satisfied();
+}
+
+#[test]
+fn packed_multi_keccak_legacy_layout() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    KeccakCircuit::<Fr>::configure(&mut meta);
+    let mut meta_with_variants = ConstraintSystem::<Fr>::default();
+    KeccakCircuit::<Fr, true>::configure(&mut meta_with_variants);
+
+    // Without domain separation there are no variant columns or gates
+    assert!(meta.gates().iter().all(|gate| !gate.name().starts_with("keccak variant")));
+    assert!(meta_with_variants.gates().iter().any(|gate| gate.name() == "keccak variant"));
+    assert!(meta_with_variants.num_advice_columns() >= meta.num_advice_columns() + 2);
+}
+
 #[test]
 fn packed_multi_keccak_prover() {
     let _ = env_logger::builder().is_test(true).try_init();
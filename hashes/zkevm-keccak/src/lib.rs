@@ -3,8 +3,12 @@
 
 use halo2_base::halo2_proofs;
 
+/// Keccak chip for hashing bytes assigned in a `GateThreadBuilder`
+pub mod keccak_chip;
 /// Keccak packed multi
 pub mod keccak_packed_multi;
+/// Merkle-Patricia trie proofs
+pub mod mpt;
 /// Util
 pub mod util;
 
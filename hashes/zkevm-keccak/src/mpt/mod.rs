@@ -0,0 +1,444 @@
+//! Proofs of inclusion and exclusion of keys in Ethereum Merkle-Patricia tries (MPTs), such as the state trie of
+//! accounts and the storage tries of slots, against the Keccak256 hash of the root of the trie.
+//!
+//! A proof is the list of the RLP encodings of the nodes on the path of the key, from the root. Each node is a branch
+//! (a list of 16 references to children, one per nibble, and an empty value), an extension (a list of a hex-prefix
+//! encoded path and a reference to a branch) or a leaf (a list of a hex-prefix encoded path and a value), and each
+//! node after the root is referenced by the Keccak256 hash of its encoding in the previous node.
+
+use crate::{keccak_chip::KeccakChip, util::eth_types::Field};
+use halo2_base::{
+    gates::{GateChip, GateInstructions, RangeChip, RangeInstructions},
+    rlc::RlcChip,
+    rlp::{max_rlp_encoding_len, native_rlp_list_items, RlpArrayTraceWitness, RlpChip},
+    utils::bit_length,
+    AssignedValue, Context,
+    QuantumCell::{Constant, Existing, Witness},
+};
+use std::iter;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of nibbles of a key, i.e. of a Keccak256 hash.
+pub const KEY_NIBBLE_LEN: usize = 64;
+/// The maximum length of the hex-prefix encoded path of a leaf or extension: a flag byte and 32 bytes of nibbles.
+pub const MAX_PATH_LEN: usize = 33;
+/// The maximum lengths of the items of a branch: the hashes of 16 children and an empty value.
+pub const BRANCH_MAX_FIELD_LENS: [usize; 17] =
+    [32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 32, 0];
+/// The maximum length of the encoding of a branch, i.e. [max_rlp_encoding_len] of `16 * 33 + 1`.
+pub const MAX_BRANCH_LEN: usize = 532;
+
+/// The parameters of the proofs of a [MptChip], fixed at keygen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MptParams {
+    /// The maximum number of nodes of a proof.
+    pub max_depth: usize,
+    /// The maximum length of a value: 33 bytes for the RLP encoding of a storage slot, and 110 bytes for the RLP
+    /// encoding of an account.
+    pub max_value_len: usize,
+}
+
+impl MptParams {
+    /// The maximum lengths of the items of a leaf or extension: the hex-prefix encoded path, and the value or the hash
+    /// of the child.
+    pub fn pair_max_field_lens(&self) -> [usize; 2] {
+        [MAX_PATH_LEN, self.max_value_len.max(32)]
+    }
+
+    /// The maximum length of the encoding of a leaf or extension.
+    pub fn max_pair_len(&self) -> usize {
+        let max_payload_len =
+            self.pair_max_field_lens().iter().map(|len| max_rlp_encoding_len(*len)).sum();
+        max_rlp_encoding_len(max_payload_len)
+    }
+
+    /// The maximum length of the encoding of a node, to which the nodes of a proof are padded.
+    pub fn max_node_len(&self) -> usize {
+        MAX_BRANCH_LEN.max(self.max_pair_len())
+    }
+}
+
+/// A proof of inclusion or exclusion of a key in a trie, with bytes assigned in phase 0.
+#[derive(Clone, Debug)]
+pub struct MptProof<F: Field> {
+    /// The 32 bytes of the key: the hash of an address in the state trie, and of a slot in a storage trie.
+    pub key_bytes: Vec<AssignedValue<F>>,
+    /// The value of the key, padded to [MptParams::max_value_len] bytes. Ignored for exclusion proofs.
+    pub value_bytes: Vec<AssignedValue<F>>,
+    /// The length of the value.
+    pub value_len: AssignedValue<F>,
+    /// The 32 bytes of the hash of the root of the trie.
+    pub root_hash_bytes: Vec<AssignedValue<F>>,
+    /// The encodings of the nodes from the root, each padded to [MptParams::max_node_len] bytes, and padded with
+    /// arbitrary nodes to [MptParams::max_depth] nodes.
+    pub nodes: Vec<Vec<AssignedValue<F>>>,
+    /// The number of nodes of the proof.
+    pub depth: AssignedValue<F>,
+    /// 1 for a proof of exclusion of the key and 0 for a proof of inclusion.
+    pub slot_is_empty: AssignedValue<F>,
+}
+
+/// A node of a [MptProof] decomposed in phase 0, see [MptChip::parse_mpt_proof_phase0].
+#[derive(Clone, Debug)]
+pub struct MptNodeWitness<F: Field> {
+    /// The decomposition of the node as a branch, or of an empty branch if it is not a branch of the proof.
+    pub branch: RlpArrayTraceWitness<F>,
+    /// The decomposition of the node as a leaf or extension, or of a dummy extension if it is not a leaf or extension
+    /// of the proof.
+    pub pair: RlpArrayTraceWitness<F>,
+    /// 1 if the node is a leaf or extension of the proof.
+    pub is_pair: AssignedValue<F>,
+    /// 1 if the node is in the proof and is not the last node.
+    pub is_internal: AssignedValue<F>,
+    /// 1 if the node is the last node of the proof.
+    pub is_last: AssignedValue<F>,
+    /// The nibbles of the path of `pair`, padded to [KEY_NIBBLE_LEN] nibbles.
+    pub path_nibbles: Vec<AssignedValue<F>>,
+    /// The number of nibbles of the path of `pair`.
+    pub path_len: AssignedValue<F>,
+    /// The number of nibbles of the key consumed by the previous nodes.
+    pub key_idx: AssignedValue<F>,
+}
+
+/// A [MptProof] decomposed in phase 0, to be completed by [MptChip::parse_mpt_proof_phase1].
+#[derive(Clone, Debug)]
+pub struct MptProofWitness<F: Field> {
+    /// The proof.
+    pub proof: MptProof<F>,
+    /// The [KEY_NIBBLE_LEN] nibbles of the key, high nibble first.
+    pub key_nibbles: Vec<AssignedValue<F>>,
+    /// The decompositions of the nodes.
+    pub nodes: Vec<MptNodeWitness<F>>,
+}
+
+/// Encoding of a branch without children, decoded in place of nodes which are not branches of the proof.
+fn dummy_branch() -> Vec<u8> {
+    iter::once(0xd1).chain([0x80; 17]).collect()
+}
+
+/// Encoding of an extension with the empty path and a zero hash, decoded in place of nodes which are not leaves or
+/// extensions of the proof.
+fn dummy_extension() -> Vec<u8> {
+    [0xe2, 0x00, 0xa0].into_iter().chain([0; 32]).collect()
+}
+
+/// Chip for proofs of inclusion and exclusion of keys in Merkle-Patricia tries.
+///
+/// A proof is verified in two phases: [Self::parse_mpt_proof_phase0] decodes the nodes, constrains the hashes linking
+/// them with a [KeccakChip] and follows the key through the branches, and [Self::parse_mpt_proof_phase1] matches the
+/// paths of the leaves and extensions with the key and the value of the leaf with RLCs.
+///
+/// Assumes the trie is not empty, and that the encodings of all nodes after the root have at least 32 bytes, so that
+/// they are referenced by their hashes rather than inlined. This holds for the state and storage tries, whose keys
+/// are hashes.
+#[derive(Clone, Debug)]
+pub struct MptChip<'a, F: Field> {
+    range: &'a RangeChip<F>,
+    params: MptParams,
+}
+
+impl<'a, F: Field> MptChip<'a, F> {
+    /// Construct a MptChip for proofs with the given parameters.
+    pub fn new(range: &'a RangeChip<F>, params: MptParams) -> Self {
+        Self { range, params }
+    }
+
+    /// Returns the underlying range chip.
+    pub fn range(&self) -> &RangeChip<F> {
+        self.range
+    }
+
+    /// Returns the underlying gate chip.
+    pub fn gate(&self) -> &GateChip<F> {
+        self.range.gate()
+    }
+
+    /// Returns the parameters of the proofs.
+    pub fn params(&self) -> MptParams {
+        self.params
+    }
+
+    /// Constrains and returns the high and low nibbles of `byte`, which constrains `byte` to be a byte.
+    fn byte_to_nibbles(
+        &self,
+        ctx: &mut Context<F>,
+        byte: AssignedValue<F>,
+    ) -> [AssignedValue<F>; 2] {
+        let value = byte.value().get_lower_32();
+        let (lo, hi) = (F::from((value & 0xf) as u64), F::from((value >> 4) as u64));
+        ctx.assign_region([Witness(lo), Constant(F::from(16)), Witness(hi), Existing(byte)], [0]);
+        let (lo, hi) = (ctx.get(-4), ctx.get(-2));
+        self.range.range_check(ctx, lo, 4);
+        self.range.range_check(ctx, hi, 4);
+        [hi, lo]
+    }
+
+    /// Returns the first `len` bytes of `node` if `use_node`, and of `dummy` padded with zeros otherwise.
+    fn select_node(
+        &self,
+        ctx: &mut Context<F>,
+        node: &[AssignedValue<F>],
+        dummy: &[u8],
+        len: usize,
+        use_node: AssignedValue<F>,
+    ) -> Vec<AssignedValue<F>> {
+        let dummy = dummy.iter().copied().chain(iter::repeat(0));
+        node[..len]
+            .iter()
+            .zip(dummy)
+            .map(|(byte, dummy)| {
+                self.gate().select(ctx, *byte, Constant(F::from(dummy as u64)), use_node)
+            })
+            .collect()
+    }
+
+    /// Constrains `a` to be zero if `condition` is 1.
+    fn assert_zero_if(
+        &self,
+        ctx: &mut Context<F>,
+        condition: AssignedValue<F>,
+        a: AssignedValue<F>,
+    ) {
+        let prod = self.gate().mul(ctx, condition, a);
+        self.gate().assert_is_const(ctx, &prod, &F::zero());
+    }
+
+    /// Decomposes the nodes of `proof` and constrains the structure of the proof, except for the matching of the paths
+    /// of leaves and extensions with the key and the value of the leaf, which are constrained by
+    /// [Self::parse_mpt_proof_phase1].
+    ///
+    /// Constrains:
+    /// * `1 <= depth <= max_depth` and the bytes of the key;
+    /// * the root to be the hash of the first node, and each node of the proof to be referenced by the hash of its
+    /// encoding in the previous node, at the next nibble of the key for branches;
+    /// * every node but the last to be a branch or extension, and the branches to be at most at nibble 63 of the key;
+    /// * for inclusion, the last node to be a leaf whose path ends the key;
+    /// * for exclusion, the last node to be a leaf or extension, or a branch without a child at the next nibble.
+    ///
+    /// Queries `keccak` for the hash of every node, each taking the keccak_f permutations of [MptParams::max_node_len]
+    /// bytes. Costs the decompositions of every node both as a branch and as a leaf or extension, see
+    /// [RlpChip::decompose_rlp_array_phase0].
+    pub fn parse_mpt_proof_phase0(
+        &self,
+        ctx: &mut Context<F>,
+        keccak: &mut KeccakChip<F>,
+        proof: MptProof<F>,
+    ) -> MptProofWitness<F> {
+        let max_depth = self.params.max_depth;
+        let max_node_len = self.params.max_node_len();
+        assert!(max_depth > 0);
+        assert_eq!(proof.key_bytes.len(), 32);
+        assert_eq!(proof.root_hash_bytes.len(), 32);
+        assert_eq!(proof.value_bytes.len(), self.params.max_value_len);
+        assert_eq!(proof.nodes.len(), max_depth);
+        assert!(proof.nodes.iter().all(|node| node.len() == max_node_len));
+        let (gate, range, rlp) = (self.gate(), self.range, RlpChip::new(self.range));
+
+        gate.assert_bit(ctx, proof.slot_is_empty);
+        let key_nibbles = proof
+            .key_bytes
+            .iter()
+            .flat_map(|byte| self.byte_to_nibbles(ctx, *byte))
+            .collect::<Vec<_>>();
+
+        let last_idx = gate.sub(ctx, proof.depth, Constant(F::one()));
+        range.check_less_than_safe(ctx, last_idx, max_depth as u64);
+        let is_last = gate.idx_to_indicator(ctx, last_idx, max_depth);
+
+        // each node consumes at most `KEY_NIBBLE_LEN + 1` nibbles, once the path lengths are range checked in phase 1
+        let key_idx_bits = bit_length((max_depth * (KEY_NIBBLE_LEN + 1)) as u64);
+        let (dummy_branch, dummy_extension) = (dummy_branch(), dummy_extension());
+        let mut key_idx = ctx.load_zero();
+        let mut in_proof = ctx.load_constant(F::one());
+        let mut nodes = Vec::with_capacity(max_depth);
+        let mut hashes = Vec::with_capacity(max_depth);
+        let mut refs = Vec::with_capacity(max_depth);
+        for (node, is_last) in proof.nodes.iter().zip(is_last) {
+            let bytes =
+                node.iter().map(|byte| byte.value().get_lower_32() as u8).collect::<Vec<_>>();
+            let is_pair_val =
+                matches!(native_rlp_list_items(&bytes), Some(items) if items.len() == 2);
+            let is_pair = ctx.load_witness(F::from(is_pair_val as u64));
+            gate.assert_bit(ctx, is_pair);
+            // the nodes after the last one are decoded as dummies
+            let is_pair = gate.mul(ctx, is_pair, in_proof);
+            let is_branch = gate.sub(ctx, in_proof, is_pair);
+            let is_internal = gate.sub(ctx, in_proof, is_last);
+
+            let branch = self.select_node(ctx, node, &dummy_branch, MAX_BRANCH_LEN, is_branch);
+            let branch = rlp.decompose_rlp_array_phase0(ctx, branch, &BRANCH_MAX_FIELD_LENS, false);
+            let pair_len = self.params.max_pair_len();
+            let pair = self.select_node(ctx, node, &dummy_extension, pair_len, is_pair);
+            let pair_max_field_lens = self.params.pair_max_field_lens();
+            let pair = rlp.decompose_rlp_array_phase0(ctx, pair, &pair_max_field_lens, false);
+
+            let node_len = gate.select(ctx, pair.rlp_len, branch.rlp_len, is_pair);
+            hashes.push(keccak.keccak_var_len(ctx, node.clone(), node_len));
+
+            // the hex-prefix encoded path: the high nibble of the first byte is 2 * is_leaf + is_odd, and the low one
+            // is the first nibble of the path if it is odd and 0 otherwise
+            let path = &pair.fields[0];
+            let path_nibbles = path
+                .field_cells
+                .iter()
+                .map(|byte| self.byte_to_nibbles(ctx, *byte))
+                .collect::<Vec<_>>();
+            let [flag, first] = path_nibbles[0];
+            let flag_bits = gate.num_to_bits(ctx, flag, 2);
+            let (is_odd, is_leaf) = (flag_bits[0], flag_bits[1]);
+            let padding = gate.mul_not(ctx, is_odd, first);
+            gate.assert_is_const(ctx, &padding, &F::zero());
+            let rest = path_nibbles[1..].iter().flatten().copied().collect::<Vec<_>>();
+            let path_nibbles = (0..KEY_NIBBLE_LEN)
+                .map(|i| {
+                    let odd = if i == 0 { first } else { rest[i - 1] };
+                    gate.select(ctx, odd, rest[i], is_odd)
+                })
+                .collect();
+            // an empty encoded path makes `path_len` negative, which fails the range check in phase 1
+            let path_len = gate.mul_add(ctx, path.field_len, Constant(F::from(2)), is_odd);
+            let path_len = gate.sub(ctx, path_len, Constant(F::from(2)));
+            let is_leaf = gate.mul(ctx, is_pair, is_leaf);
+            self.assert_zero_if(ctx, is_internal, is_leaf);
+
+            // the child of a branch at the next nibble of the key
+            let key_idx_in_range = range.is_less_than(
+                ctx,
+                key_idx,
+                Constant(F::from(KEY_NIBBLE_LEN as u64)),
+                key_idx_bits,
+            );
+            let key_idx_out_of_range = gate.not(ctx, key_idx_in_range);
+            self.assert_zero_if(ctx, is_branch, key_idx_out_of_range);
+            let nibble = gate.select_from_idx(ctx, key_nibbles.iter().copied(), key_idx);
+            let indicator = gate.idx_to_indicator(ctx, nibble, 16);
+            let children = &branch.fields[..16];
+            let child_len = gate.select_by_indicator(
+                ctx,
+                children.iter().map(|child| child.field_len),
+                indicator.iter().copied(),
+            );
+            let child = (0..32)
+                .map(|i| {
+                    let bytes = children.iter().map(|child| child.field_cells[i]);
+                    gate.select_by_indicator(ctx, bytes, indicator.iter().copied())
+                })
+                .collect::<Vec<_>>();
+
+            // the reference to the next node is the child of a branch or the second item of an extension
+            let ext_ref = &pair.fields[1];
+            let ref_len = gate.select(ctx, ext_ref.field_len, child_len, is_pair);
+            let ref_bytes = child
+                .iter()
+                .zip(&ext_ref.field_cells)
+                .map(|(child, ext_ref)| gate.select(ctx, *ext_ref, *child, is_pair))
+                .collect::<Vec<_>>();
+            refs.push((ref_bytes, ref_len));
+
+            // inclusion ends at a leaf whose path ends the key, and exclusion at a branch ends at an empty child
+            let is_excl = gate.mul(ctx, is_last, proof.slot_is_empty);
+            let is_incl = gate.sub(ctx, is_last, is_excl);
+            let not_leaf = gate.not(ctx, is_leaf);
+            self.assert_zero_if(ctx, is_incl, not_leaf);
+            let key_end = gate.add(ctx, key_idx, path_len);
+            let key_end_diff = gate.sub(ctx, key_end, Constant(F::from(KEY_NIBBLE_LEN as u64)));
+            self.assert_zero_if(ctx, is_incl, key_end_diff);
+            let is_branch_excl = gate.mul(ctx, is_excl, is_branch);
+            self.assert_zero_if(ctx, is_branch_excl, child_len);
+
+            nodes.push(MptNodeWitness {
+                branch,
+                pair,
+                is_pair,
+                is_internal,
+                is_last,
+                path_nibbles,
+                path_len,
+                key_idx,
+            });
+            let consumed = gate.select(ctx, path_len, Constant(F::one()), is_pair);
+            key_idx = gate.add(ctx, key_idx, consumed);
+            in_proof = is_internal;
+        }
+
+        for (hash_byte, root_byte) in hashes[0].iter().zip(&proof.root_hash_bytes) {
+            ctx.constrain_equal(hash_byte, root_byte);
+        }
+        for ((node, (ref_bytes, ref_len)), hash) in nodes.iter().zip(&refs).zip(&hashes[1..]) {
+            let ref_len_diff = gate.sub(ctx, *ref_len, Constant(F::from(32)));
+            self.assert_zero_if(ctx, node.is_internal, ref_len_diff);
+            for (ref_byte, hash_byte) in ref_bytes.iter().zip(hash) {
+                let diff = gate.sub(ctx, *ref_byte, *hash_byte);
+                self.assert_zero_if(ctx, node.is_internal, diff);
+            }
+        }
+
+        MptProofWitness { proof, key_nibbles, nodes }
+    }
+
+    /// Completes the verification of the proof of `witness`, see [Self::parse_mpt_proof_phase0].
+    ///
+    /// Constrains the RLP decompositions of the nodes, the paths of extensions before the last node and of the leaf
+    /// of an inclusion proof to be the next nibbles of the key, the value of the leaf of an inclusion proof to be
+    /// `value_bytes[..value_len]`, and the path of the last leaf or extension of an exclusion proof not to be the next
+    /// nibbles of the key.
+    ///
+    /// `rlc` must use a challenge generated after phase 0. Costs the RLP decompositions of every node both as a branch
+    /// and as a leaf or extension, see [RlpChip::decompose_rlp_array_phase1], and the RLCs of the key and the paths.
+    pub fn parse_mpt_proof_phase1(
+        &self,
+        ctx: &mut Context<F>,
+        rlc: &mut RlcChip<F>,
+        witness: MptProofWitness<F>,
+    ) {
+        let (gate, range, rlp) = (self.gate(), self.range, RlpChip::new(self.range));
+        let path_len_bits = bit_length(KEY_NIBBLE_LEN as u64);
+        let key_idx_bits = bit_length((self.params.max_depth * (KEY_NIBBLE_LEN + 1)) as u64);
+        rlc.load_rlc_cache(ctx, path_len_bits);
+
+        // `key_prefixes[i]` is the RLC of the first `i` nibbles of the key
+        let key_prefixes = iter::once(Constant(F::zero()))
+            .chain(
+                rlc.rlc_prefixes(ctx, witness.key_nibbles.iter().copied())
+                    .into_iter()
+                    .map(Existing),
+            )
+            .collect::<Vec<_>>();
+        let proof = witness.proof;
+        let value = rlc.compute_rlc(ctx, proof.value_bytes.iter().copied(), proof.value_len);
+        let is_incl = gate.not(ctx, proof.slot_is_empty);
+
+        for node in witness.nodes {
+            rlp.decompose_rlp_array_phase1(ctx, rlc, node.branch);
+            let pair = rlp.decompose_rlp_array_phase1(ctx, rlc, node.pair);
+
+            // the path matches the key if rlc(key[..key_idx + path_len]) = rlc(key[..key_idx]) * gamma^path_len +
+            // rlc(path), which also constrains `path_len <= KEY_NIBBLE_LEN`
+            let path = rlc.compute_rlc(ctx, node.path_nibbles.iter().copied(), node.path_len);
+            let key_end = gate.add(ctx, node.key_idx, node.path_len);
+            let max_key_end = Constant(F::from(KEY_NIBBLE_LEN as u64 + 1));
+            let key_end_in_range = range.is_less_than(ctx, key_end, max_key_end, key_idx_bits);
+            let key_start_rlc = gate.select_from_idx(ctx, key_prefixes.clone(), node.key_idx);
+            let key_end_rlc = gate.select_from_idx(ctx, key_prefixes.clone(), key_end);
+            let gamma_pow = rlc.rlc_pow(ctx, node.path_len, path_len_bits);
+            let expected = gate.mul_add(ctx, key_start_rlc, gamma_pow, path.rlc_val);
+            let is_match = gate.is_equal(ctx, expected, key_end_rlc);
+            let is_match = gate.and(ctx, is_match, key_end_in_range);
+            let not_match = gate.not(ctx, is_match);
+
+            let is_internal_ext = gate.mul(ctx, node.is_internal, node.is_pair);
+            let is_incl_leaf = gate.mul(ctx, node.is_last, is_incl);
+            let must_match = gate.add(ctx, is_internal_ext, is_incl_leaf);
+            self.assert_zero_if(ctx, must_match, not_match);
+            let is_excl = gate.mul(ctx, node.is_last, proof.slot_is_empty);
+            let is_excl_pair = gate.mul(ctx, is_excl, node.is_pair);
+            self.assert_zero_if(ctx, is_excl_pair, is_match);
+
+            let value_match = rlc.is_equal(ctx, &pair.field_rlcs[1], &value);
+            let value_mismatch = gate.not(ctx, value_match);
+            self.assert_zero_if(ctx, is_incl_leaf, value_mismatch);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use super::*;
+use crate::{halo2_proofs::halo2curves::bn256::Fr, keccak_chip::KeccakCircuitBuilder};
+use ethers_core::utils::keccak256;
+use halo2_base::{
+    gates::builder::GateThreadBuilder,
+    utils::testing::{
+        mock_verify,
+        rlp::{encode_bytes, encode_list},
+    },
+};
+use serde_json::Value;
+use std::{env::set_var, fs::File};
+
+const K: usize = 14;
+const LOOKUP_BITS: usize = 8;
+const PARAMS: MptParams = MptParams { max_depth: 4, max_value_len: 80 };
+/// Enough for the `eth_getProof` responses in `testdata`, whose account proofs have up to 9 nodes.
+const ETH_PARAMS: MptParams = MptParams { max_depth: 9, max_value_len: 110 };
+const ETH_K: usize = 15;
+/// The state roots the `eth_getProof` responses in `testdata` were queried at, by file name.
+const STATE_ROOTS: [(&str, &str); 2] = [
+    ("account_proof.json", "471374b211dfc7de94e14882f279b2cf6b66eefc10f4215fffcb077f8a59d97a"),
+    ("storage_proof.json", "57e6e864257daf9d96aaca31edd0cfe4e3892f09061e727c57ab56197dd59287"),
+];
+
+fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = 2 * is_leaf as u8 + (nibbles.len() % 2) as u8;
+    let prefix = if nibbles.len() % 2 == 1 { vec![flag] } else { vec![flag, 0] };
+    let nibbles = [prefix, nibbles.to_vec()].concat();
+    nibbles.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect()
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|byte| [byte >> 4, byte & 0xf]).collect()
+}
+
+/// Returns the number of nibbles shared by the keys of `items` from nibble `depth`.
+fn common_prefix_len(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> usize {
+    (depth..KEY_NIBBLE_LEN)
+        .take_while(|i| items.iter().all(|(key, _)| key[*i] == items[0].0[*i]))
+        .count()
+}
+
+/// Returns the encoding of the node of the trie of `items`, with keys as nibbles, below the first `depth` nibbles.
+/// Children are always referenced by hashes.
+fn encode_node(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    if items.len() == 1 {
+        let (key, value) = &items[0];
+        return encode_list(&[hex_prefix(&key[depth..], true), value.clone()]);
+    }
+    let prefix_len = common_prefix_len(items, depth);
+    if prefix_len > 0 {
+        let path = hex_prefix(&items[0].0[depth..depth + prefix_len], false);
+        let child = encode_node(items, depth + prefix_len);
+        return encode_list(&[path, keccak256(child).to_vec()]);
+    }
+    let mut children = (0..16)
+        .map(|nibble| {
+            let group =
+                items.iter().filter(|(key, _)| key[depth] == nibble).cloned().collect::<Vec<_>>();
+            if group.is_empty() {
+                vec![]
+            } else {
+                keccak256(encode_node(&group, depth + 1)).to_vec()
+            }
+        })
+        .collect::<Vec<_>>();
+    children.push(vec![]);
+    encode_list(&children)
+}
+
+/// Returns the nodes of the proof of `key` in the trie of `items`, ending at the node where the path of the key ends
+/// or leaves the trie.
+fn mpt_proof(items: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> Vec<Vec<u8>> {
+    let mut items = items.to_vec();
+    let mut depth = 0;
+    let mut proof = vec![];
+    loop {
+        proof.push(encode_node(&items, depth));
+        if items.len() == 1 {
+            return proof;
+        }
+        let prefix_len = common_prefix_len(&items, depth);
+        if prefix_len > 0 {
+            if key[depth..depth + prefix_len] != items[0].0[depth..depth + prefix_len] {
+                return proof;
+            }
+            depth += prefix_len;
+        } else {
+            items.retain(|(item_key, _)| item_key[depth] == key[depth]);
+            if items.is_empty() {
+                return proof;
+            }
+            depth += 1;
+        }
+    }
+}
+
+/// Returns a key starting with `prefix` and continued with the hash of `prefix`.
+fn key(prefix: &[u8]) -> [u8; 32] {
+    let mut key = keccak256(prefix);
+    key[..prefix.len()].copy_from_slice(prefix);
+    key
+}
+
+/// The trie of keys starting with `0x1234`, `0x1235` and `0x50`: a branch at the root with a leaf at nibble 5, and an
+/// extension by `[2, 3]` at nibble 1 to a branch with leaves at nibbles 4 and 5.
+fn test_trie() -> Vec<(Vec<u8>, Vec<u8>)> {
+    let account = encode_list(&[vec![1], vec![0xde; 8], vec![0x56; 32], vec![0x78; 32]]);
+    [
+        (key(&[0x12, 0x34]), encode_bytes(&[0x01, 0x02])),
+        (key(&[0x12, 0x35]), vec![0x2a]),
+        (key(&[0x50]), account),
+    ]
+    .into_iter()
+    .map(|(key, value)| (to_nibbles(&key), value))
+    .collect()
+}
+
+fn load_bytes(ctx: &mut Context<Fr>, bytes: &[u8], len: usize) -> Vec<AssignedValue<Fr>> {
+    assert!(bytes.len() <= len);
+    let bytes = bytes.iter().copied().chain(iter::repeat(0)).take(len);
+    ctx.assign_witnesses(bytes.map(|byte| Fr::from(byte as u64)))
+}
+
+fn verify_mpt_proof(
+    nodes: &[Vec<u8>],
+    key: [u8; 32],
+    value: &[u8],
+    root_hash: [u8; 32],
+    slot_is_empty: bool,
+) -> bool {
+    verify_mpt_proof_with_params(PARAMS, K, nodes, key, value, root_hash, slot_is_empty)
+}
+
+fn verify_mpt_proof_with_params(
+    params: MptParams,
+    k: usize,
+    nodes: &[Vec<u8>],
+    key: [u8; 32],
+    value: &[u8],
+    root_hash: [u8; 32],
+    slot_is_empty: bool,
+) -> bool {
+    set_var("LOOKUP_BITS", LOOKUP_BITS.to_string());
+    let mut builder = GateThreadBuilder::mock();
+    let mut keccak = KeccakChip::new();
+    let ctx = builder.main(0);
+    let max_node_len = params.max_node_len();
+    let proof = MptProof {
+        key_bytes: load_bytes(ctx, &key, 32),
+        value_bytes: load_bytes(ctx, value, params.max_value_len),
+        value_len: ctx.load_witness(Fr::from(value.len() as u64)),
+        root_hash_bytes: load_bytes(ctx, &root_hash, 32),
+        nodes: (0..params.max_depth)
+            .map(|i| load_bytes(ctx, nodes.get(i).map_or(&[][..], |node| node), max_node_len))
+            .collect(),
+        depth: ctx.load_witness(Fr::from(nodes.len() as u64)),
+        slot_is_empty: ctx.load_witness(Fr::from(slot_is_empty as u64)),
+    };
+    let range = RangeChip::default(LOOKUP_BITS);
+    let witness = MptChip::new(&range, params).parse_mpt_proof_phase0(ctx, &mut keccak, proof);
+    builder.register_phase1_callback(move |builder, gamma| {
+        let range = RangeChip::default(LOOKUP_BITS);
+        let mut rlc = RlcChip::new(&range, gamma);
+        let chip = MptChip::new(&range, params);
+        chip.parse_mpt_proof_phase1(builder.main(1), &mut rlc, witness.clone());
+    });
+    mock_verify(k, &KeccakCircuitBuilder::mock(builder, keccak)).is_ok()
+}
+
+/// Reads the `eth_getProof` response `testdata/{name}`.
+fn read_response(name: &str) -> Value {
+    let file = File::open(format!("src/mpt/testdata/{name}"))
+        .unwrap_or_else(|e| panic!("Path {name} does not exist: {e:?}"));
+    serde_json::from_reader(file).unwrap()
+}
+
+fn hex_bytes(value: &Value) -> Vec<u8> {
+    hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+}
+
+fn hex_hash(value: &Value) -> [u8; 32] {
+    hex_bytes(value).try_into().unwrap()
+}
+
+/// Returns the big-endian bytes without leading zeros of the quantity `value`, as in the RLP encoding of an account.
+fn quantity_bytes(value: &Value) -> Vec<u8> {
+    let value = u128::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap();
+    value.to_be_bytes().into_iter().skip_while(|byte| *byte == 0).collect()
+}
+
+/// Returns the proof of the account of an `eth_getProof` response: its nodes, its key and the RLP encoding of the
+/// account `[nonce, balance, storageHash, codeHash]`.
+fn account_proof(response: &Value) -> (Vec<Vec<u8>>, [u8; 32], Vec<u8>) {
+    let nodes = response["accountProof"].as_array().unwrap().iter().map(hex_bytes).collect();
+    let key = keccak256(hex_bytes(&response["address"]));
+    let account = encode_list(&[
+        quantity_bytes(&response["nonce"]),
+        quantity_bytes(&response["balance"]),
+        hex_bytes(&response["storageHash"]),
+        hex_bytes(&response["codeHash"]),
+    ]);
+    (nodes, key, account)
+}
+
+/// Verifies the proof of `key` in the test trie, with the value of the key in the trie if it is included.
+fn verify_test_proof(key: [u8; 32], slot_is_empty: bool) -> bool {
+    let items = test_trie();
+    let root_hash = keccak256(encode_node(&items, 0));
+    let nibbles = to_nibbles(&key);
+    let value = items
+        .iter()
+        .find(|(item_key, _)| *item_key == nibbles)
+        .map_or(vec![], |item| item.1.clone());
+    verify_mpt_proof(&mpt_proof(&items, &nibbles), key, &value, root_hash, slot_is_empty)
+}
+
+#[test]
+fn test_mpt_inclusion() {
+    // through the extension
+    assert!(verify_test_proof(key(&[0x12, 0x34]), false));
+    // leaf with a long value at the root branch
+    assert!(verify_test_proof(key(&[0x50]), false));
+}
+
+#[test]
+fn test_mpt_exclusion_at_branch() {
+    assert!(verify_test_proof(key(&[0x12, 0x36]), true));
+    assert!(verify_test_proof(key(&[0x70]), true));
+}
+
+#[test]
+fn test_mpt_exclusion_at_extension() {
+    assert!(verify_test_proof(key(&[0x13]), true));
+}
+
+#[test]
+fn test_mpt_exclusion_at_leaf() {
+    assert!(verify_test_proof(key(&[0x50, 0x01]), true));
+}
+
+#[test]
+fn test_mpt_wrong_inclusion() {
+    assert!(!verify_test_proof(key(&[0x12, 0x36]), false));
+    assert!(!verify_test_proof(key(&[0x50, 0x01]), false));
+}
+
+#[test]
+fn test_mpt_wrong_exclusion() {
+    assert!(!verify_test_proof(key(&[0x12, 0x35]), true));
+}
+
+#[test]
+fn test_mpt_wrong_value() {
+    let items = test_trie();
+    let root_hash = keccak256(encode_node(&items, 0));
+    let key = key(&[0x12, 0x35]);
+    let nodes = mpt_proof(&items, &to_nibbles(&key));
+    assert!(!verify_mpt_proof(&nodes, key, &[0x2b], root_hash, false));
+}
+
+#[test]
+fn test_mpt_wrong_root() {
+    let items = test_trie();
+    let mut root_hash = keccak256(encode_node(&items, 0));
+    root_hash[0] ^= 1;
+    let key = key(&[0x12, 0x34]);
+    let nodes = mpt_proof(&items, &to_nibbles(&key));
+    assert!(!verify_mpt_proof(&nodes, key, &encode_bytes(&[0x01, 0x02]), root_hash, false));
+}
+
+#[test]
+fn test_mpt_truncated_proof() {
+    let items = test_trie();
+    let root_hash = keccak256(encode_node(&items, 0));
+    let key = key(&[0x12, 0x34]);
+    let nodes = mpt_proof(&items, &to_nibbles(&key));
+    // ending at the extension is neither an inclusion nor an exclusion proof
+    assert!(!verify_mpt_proof(&nodes[..2], key, &[], root_hash, true));
+}
+
+#[test]
+fn test_mpt_eth_get_proof_account() {
+    for (name, state_root) in STATE_ROOTS {
+        let (nodes, key, account) = account_proof(&read_response(name));
+        let state_root = hex::decode(state_root).unwrap().try_into().unwrap();
+        assert!(verify_mpt_proof_with_params(
+            ETH_PARAMS, ETH_K, &nodes, key, &account, state_root, false
+        ));
+    }
+}
+
+#[test]
+fn test_mpt_eth_get_proof_wrong_account() {
+    let (name, state_root) = STATE_ROOTS[0];
+    let (nodes, key, mut account) = account_proof(&read_response(name));
+    let state_root = hex::decode(state_root).unwrap().try_into().unwrap();
+    // the nonce of the account is 1
+    account[2] = 2;
+    assert!(!verify_mpt_proof_with_params(
+        ETH_PARAMS, ETH_K, &nodes, key, &account, state_root, false
+    ));
+}
+
+#[test]
+fn test_mpt_eth_get_proof_storage() {
+    // slot 0 is empty, so this is a proof of exclusion ending at a branch
+    let response = read_response("storage_proof.json");
+    let storage_hash = hex_hash(&response["storageHash"]);
+    let storage_proof = &response["storageProof"][0];
+    assert_eq!(quantity_bytes(&storage_proof["value"]), Vec::<u8>::new());
+    let nodes =
+        storage_proof["proof"].as_array().unwrap().iter().map(hex_bytes).collect::<Vec<_>>();
+    let key = keccak256(hex_hash(&storage_proof["key"]));
+    assert!(verify_mpt_proof_with_params(ETH_PARAMS, ETH_K, &nodes, key, &[], storage_hash, true));
+    assert!(!verify_mpt_proof_with_params(
+        ETH_PARAMS,
+        ETH_K,
+        &nodes,
+        key,
+        &[0x01],
+        storage_hash,
+        false
+    ));
+}
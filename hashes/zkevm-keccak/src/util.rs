@@ -27,6 +27,128 @@ pub const ABSORB_WIDTH_PER_ROW: usize = NUM_BITS_PER_WORD;
 pub const ABSORB_WIDTH_PER_ROW_BYTES: usize = ABSORB_WIDTH_PER_ROW / NUM_BITS_PER_BYTE;
 pub const RATE: usize = NUM_WORDS_TO_ABSORB * NUM_BYTES_PER_WORD;
 pub const RATE_IN_BITS: usize = RATE * NUM_BITS_PER_BYTE;
+
+/// The maximum number of words absorbed per keccak_f, for the rate of 1344 bits of SHAKE128.
+pub const MAX_NUM_WORDS_TO_ABSORB: usize = 21;
+
+/// The members of the Keccak sponge family supported by the packed multi-keccak circuit.
+///
+/// They differ in the domain separation bits that are prepended to the `pad10*1` padding (FIPS 202, Section 6 and
+/// Appendix B.2), in the rate, i.e. the number of words absorbed and squeezed per keccak_f, and in the length of the
+/// output. An output longer than the rate is squeezed from further keccak_f's which do not absorb any input.
+///
+/// The variants are only available in a config created with `KeccakCircuitConfig::new_with_domain_separation`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeccakVariant {
+    /// Ethereum's keccak-256, i.e. the original Keccak padding without domain separation.
+    #[default]
+    Keccak256,
+    /// FIPS 202 SHA3-256.
+    Sha3_256,
+    /// FIPS 202 SHA3-512, with a rate of 576 bits.
+    Sha3_512,
+    /// FIPS 202 SHAKE128 with an output of the given positive number of bytes, with a rate of 1344 bits.
+    Shake128(usize),
+    /// FIPS 202 SHAKE256 with an output of the given positive number of bytes.
+    Shake256(usize),
+}
+
+impl KeccakVariant {
+    /// The first padding byte: the domain separation bits followed by the first bit of `pad10*1`.
+    pub fn domain_byte(&self) -> u8 {
+        match self {
+            Self::Keccak256 => 0x01,
+            Self::Sha3_256 | Self::Sha3_512 => 0x06,
+            Self::Shake128(_) | Self::Shake256(_) => 0x1f,
+        }
+    }
+
+    /// The number of words absorbed or squeezed per keccak_f.
+    pub fn num_words_to_absorb(&self) -> usize {
+        match self {
+            Self::Sha3_512 => 9,
+            Self::Shake128(_) => MAX_NUM_WORDS_TO_ABSORB,
+            _ => NUM_WORDS_TO_ABSORB,
+        }
+    }
+
+    /// The rate in bytes.
+    pub fn rate(&self) -> usize {
+        self.num_words_to_absorb() * NUM_BYTES_PER_WORD
+    }
+
+    /// The number of output bytes.
+    pub fn output_len(&self) -> usize {
+        match self {
+            Self::Keccak256 | Self::Sha3_256 => NUM_BYTES_TO_SQUEEZE,
+            Self::Sha3_512 => 2 * NUM_BYTES_TO_SQUEEZE,
+            Self::Shake128(len) | Self::Shake256(len) => {
+                assert!(*len > 0, "SHAKE needs a positive output length");
+                *len
+            }
+        }
+    }
+
+    /// The value the input RLC of a hash starts from, binding the variant and the output length into `input_rlc`.
+    ///
+    /// It is `0` for [KeccakVariant::Keccak256], so existing consumers of the keccak table are unaffected,
+    /// and otherwise a multiple of 256, which can never be the leading coefficient of an RLC of bytes.
+    pub fn input_rlc_init(&self) -> u64 {
+        let [_, _, is_rate_576, is_rate_1344] = self.flags();
+        let shake_output_len = match self {
+            Self::Shake128(_) | Self::Shake256(_) => self.output_len() as u64,
+            _ => 0,
+        };
+        (self.domain_byte() as u64 - 1
+            + 32 * is_rate_576 as u64
+            + 64 * is_rate_1344 as u64
+            + 128 * shake_output_len)
+            * 256
+    }
+
+    /// Returns `[is_sha3, is_shake, is_rate_576, is_rate_1344]`, the flags assigned in the circuit for this variant.
+    pub fn flags(&self) -> [bool; 4] {
+        [
+            matches!(self, Self::Sha3_256 | Self::Sha3_512),
+            matches!(self, Self::Shake128(_) | Self::Shake256(_)),
+            *self == Self::Sha3_512,
+            matches!(self, Self::Shake128(_)),
+        ]
+    }
+
+    /// Pads `bytes` to a multiple of the rate.
+    pub fn pad(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut padded = bytes.to_vec();
+        padded.push(self.domain_byte());
+        while padded.len() % self.rate() != 0 {
+            padded.push(0);
+        }
+        *padded.last_mut().unwrap() |= 0x80;
+        padded
+    }
+
+    /// The number of keccak_f's to hash `byte_length` bytes: one per block of the padded input, and one more per
+    /// `rate` bytes of output after the first.
+    pub fn num_keccak_f(&self, byte_length: usize) -> usize {
+        byte_length / self.rate() + (self.output_len() + self.rate() - 1) / self.rate()
+    }
+
+    /// The number of output bytes squeezed after each keccak_f to hash `byte_length` bytes.
+    pub fn num_bytes_squeezed(&self, byte_length: usize) -> Vec<usize> {
+        let num_absorbed = byte_length / self.rate() + 1;
+        let mut output_len = self.output_len();
+        (0..self.num_keccak_f(byte_length))
+            .map(|idx| {
+                if idx + 1 < num_absorbed {
+                    return 0;
+                }
+                let num_bytes = output_len.min(self.rate());
+                output_len -= num_bytes;
+                num_bytes
+            })
+            .collect()
+    }
+}
 // pub(crate) const THETA_C_WIDTH: usize = 5 * NUM_BITS_PER_WORD;
 pub(crate) const RHO_MATRIX: [[usize; 5]; 5] = [
     [0, 36, 3, 41, 18],
@@ -136,12 +258,12 @@ pub mod scatter {
     }
 }
 
-/// The words that absorb data
-pub fn get_absorb_positions() -> Vec<(usize, usize)> {
+/// The words that absorb data, for `num_words` words absorbed per keccak_f
+pub fn get_absorb_positions(num_words: usize) -> Vec<(usize, usize)> {
     let mut absorb_positions = Vec::new();
     for j in 0..5 {
         for i in 0..5 {
-            if i + j * 5 < 17 {
+            if i + j * 5 < num_words {
                 absorb_positions.push((i, j));
             }
         }